@@ -0,0 +1,889 @@
+//! Periodic announcement of devices and services on the network.
+
+use std::cmp;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::io;
+use std::net::IpAddr;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use {SSDPError, SSDPResult};
+use error::MsgError;
+use bootid::BootIdManager;
+use header::names;
+use message::{DeviceAnnouncement, NotifyMessage};
+use net::{self, InterfaceFilter};
+
+/// Function used to send announcements, swapped out when testing.
+type SendFn = Arc<Fn(&mut NotifyMessage) -> SSDPResult<()> + Send + Sync>;
+/// Function used to find the local interface addresses, swapped out when testing.
+type AddrsFn = Arc<Fn() -> io::Result<Vec<IpAddr>> + Send + Sync>;
+
+/// Default number of additional times each announcement is sent.
+const DEFAULT_REPEAT_COUNT: u32 = 1;
+/// Default upper bound on the random spacing between repeated announcements.
+const DEFAULT_REPEAT_SPACING_MS: u64 = 100;
+/// Default range, as a percentage of the max-age, at which to re-announce.
+const DEFAULT_REANNOUNCE_MIN_PERCENT: u32 = 50;
+const DEFAULT_REANNOUNCE_MAX_PERCENT: u32 = 75;
+
+/// Identifies an advertisement registered with an `Advertiser`.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct AdvertisementId(usize);
+
+struct Entry {
+    id: usize,
+    message: NotifyMessage,
+    max_age: Duration,
+    repeats_left: u32,
+    next_due: Instant,
+}
+
+impl Entry {
+    /// Schedule the next transmission after the announcement was sent at now.
+    fn reschedule(&mut self, now: Instant, schedule: &Schedule) {
+        if self.repeats_left > 0 {
+            self.repeats_left -= 1;
+            self.next_due = now + random_between(Duration::from_millis(0), schedule.repeat_spacing);
+        } else {
+            self.repeats_left = schedule.repeat_count;
+            self.next_due = now + schedule.reannounce_delay(self.max_age);
+        }
+    }
+}
+
+/// Timing of repeated announcements and periodic re-announcements.
+#[derive(Copy, Clone)]
+struct Schedule {
+    repeat_count: u32,
+    repeat_spacing: Duration,
+    reannounce_min_percent: u32,
+    reannounce_max_percent: u32,
+}
+
+impl Schedule {
+    fn reannounce_delay(&self, max_age: Duration) -> Duration {
+        let max_age_ms = duration_millis(max_age);
+
+        random_between(Duration::from_millis(max_age_ms * self.reannounce_min_percent as u64 / 100),
+                       Duration::from_millis(max_age_ms * self.reannounce_max_percent as u64 / 100))
+    }
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Schedule {
+            repeat_count: DEFAULT_REPEAT_COUNT,
+            repeat_spacing: Duration::from_millis(DEFAULT_REPEAT_SPACING_MS),
+            reannounce_min_percent: DEFAULT_REANNOUNCE_MIN_PERCENT,
+            reannounce_max_percent: DEFAULT_REANNOUNCE_MAX_PERCENT,
+        }
+    }
+}
+
+/// Periodic polling of the local interface addresses for network changes.
+struct Watch {
+    interval: Duration,
+    next_check: Instant,
+    known: Option<Vec<IpAddr>>,
+    addrs: AddrsFn,
+}
+
+impl Watch {
+    /// Record the local addresses found at now, returning those that were not
+    /// known before.
+    ///
+    /// The first addresses recorded are taken as the baseline.
+    fn update(&mut self, result: io::Result<Vec<IpAddr>>, now: Instant) -> Vec<IpAddr> {
+        self.next_check = now + self.interval;
+
+        let current = match result {
+            Ok(n) => n,
+            Err(err) => {
+                warn!("Advertiser failed to find local interface addresses: {}", err);
+                return Vec::new();
+            }
+        };
+
+        let added = match self.known {
+            Some(ref known) => {
+                for ip in known.iter().filter(|ip| !current.contains(ip)) {
+                    debug!("Local interface address {} was removed, it can not send byebyes", ip);
+                }
+
+                current.iter().filter(|ip| !known.contains(ip)).cloned().collect()
+            }
+            None => Vec::new(),
+        };
+        self.known = Some(current);
+
+        added
+    }
+}
+
+struct State {
+    entries: Vec<Entry>,
+    byebyes: Vec<NotifyMessage>,
+    next_id: usize,
+    schedule: Schedule,
+    boot_ids: Option<Arc<BootIdManager>>,
+    interfaces: Option<InterfaceFilter>,
+    watch: Option<Watch>,
+    shutdown: bool,
+}
+
+impl State {
+    /// Stamp the current boot instance and configuration number onto the message
+    /// and select the interfaces it is sent from.
+    fn prepare(&self, message: &mut NotifyMessage) {
+        if let Some(ref boot_ids) = self.boot_ids {
+            boot_ids.stamp(message);
+        }
+        if let Some(ref interfaces) = self.interfaces {
+            message.set_interfaces(interfaces.clone());
+        }
+    }
+
+    /// Announcements for every advertisement, sent only from those of the added
+    /// local addresses that the advertisement is selected to be sent from.
+    fn added_announcements(&self, added: &[IpAddr]) -> Vec<NotifyMessage> {
+        let mut messages = Vec::new();
+
+        for entry in &self.entries {
+            let mut message = entry.message.clone();
+            self.prepare(&mut message);
+
+            let ips = added.iter().filter(|ip| message.interfaces().matches(ip)).cloned().collect::<Vec<_>>();
+            if !ips.is_empty() {
+                message.set_interfaces(InterfaceFilter::Addrs(ips));
+                messages.push(message);
+            }
+        }
+
+        messages
+    }
+}
+
+struct Shared {
+    state: Mutex<State>,
+    wakeup: Condvar,
+}
+
+impl Shared {
+    fn lock<'a>(&'a self) -> MutexGuard<'a, State> {
+        // Announcements are sent without the lock held, recover from poisoning
+        match self.state.lock() {
+            Ok(n) => n,
+            Err(err) => err.into_inner(),
+        }
+    }
+}
+
+/// Multicasts `ssdp:alive` announcements for registered advertisements, first
+/// when they are added and then periodically from a background thread before
+/// their CACHE-CONTROL max-age expires.
+///
+/// Stopping or dropping the Advertiser stops the background thread and multicasts
+/// an `ssdp:byebye` for every advertisement that is still registered.
+pub struct Advertiser {
+    shared: Arc<Shared>,
+    send: SendFn,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Advertiser {
+    /// Construct a new Advertiser, starting its background thread.
+    pub fn new() -> Advertiser {
+        Advertiser::with_sender(Arc::new(|message: &mut NotifyMessage| message.multicast()))
+    }
+
+    fn with_sender(send: SendFn) -> Advertiser {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                entries: Vec::new(),
+                byebyes: Vec::new(),
+                next_id: 0,
+                schedule: Schedule::default(),
+                boot_ids: None,
+                interfaces: None,
+                watch: None,
+                shutdown: false,
+            }),
+            wakeup: Condvar::new(),
+        });
+
+        let thread_shared = shared.clone();
+        let thread_send = send.clone();
+        let thread = thread::spawn(move || run_advertiser(thread_shared, thread_send));
+
+        Advertiser {
+            shared: shared,
+            send: send,
+            thread: Some(thread),
+        }
+    }
+
+    /// Register an advertisement, multicasting it immediately.
+    ///
+    /// The message must carry a CACHE-CONTROL max-age, it will be repeated and
+    /// re-announced in the background, as configured, until it is removed.
+    pub fn add(&self, message: NotifyMessage) -> SSDPResult<AdvertisementId> {
+        let max_age = try!(announce_max_age(&message));
+
+        let mut first_message = message.clone();
+        self.shared.lock().prepare(&mut first_message);
+        try!((self.send)(&mut first_message));
+
+        let mut state = self.shared.lock();
+        let id = state.next_id;
+        state.next_id += 1;
+
+        let mut entry = Entry {
+            id: id,
+            message: message,
+            max_age: max_age,
+            repeats_left: state.schedule.repeat_count,
+            next_due: Instant::now(),
+        };
+        entry.reschedule(Instant::now(), &state.schedule);
+
+        state.entries.push(entry);
+        self.shared.wakeup.notify_one();
+
+        Ok(AdvertisementId(id))
+    }
+
+    /// Send each announcement an additional count times, waiting a random duration
+    /// of up to spacing in between each transmission, since UDP packets may be lost.
+    ///
+    /// Defaults to one additional transmission within 100 milliseconds.
+    pub fn set_repeat(&self, count: u32, spacing: Duration) {
+        let mut state = self.shared.lock();
+
+        state.schedule.repeat_count = count;
+        state.schedule.repeat_spacing = spacing;
+    }
+
+    /// Re-announce advertisements after a random duration between the given
+    /// percentages of their max-age, so that devices announcing on the same
+    /// network do not synchronize their multicasts.
+    ///
+    /// Percentages are capped at 100 and the minimum at the maximum. Defaults to
+    /// between 50 and 75 percent of the max-age.
+    pub fn set_reannounce_range(&self, min_percent: u32, max_percent: u32) {
+        let max_percent = cmp::min(max_percent, 100);
+        let min_percent = cmp::min(min_percent, max_percent);
+
+        let mut state = self.shared.lock();
+        state.schedule.reannounce_min_percent = min_percent;
+        state.schedule.reannounce_max_percent = max_percent;
+    }
+
+    /// Stamp the BOOTID.UPNP.ORG and CONFIGID.UPNP.ORG headers from the manager
+    /// onto every announcement and byebye sent from now on.
+    ///
+    /// Passing None will send the messages with whatever headers they were added with.
+    pub fn set_boot_id_manager(&self, opt_boot_ids: Option<Arc<BootIdManager>>) {
+        self.shared.lock().boot_ids = opt_boot_ids;
+    }
+
+    /// Send every announcement and byebye from the selected local network
+    /// interfaces only, overriding the selection of the messages themselves.
+    ///
+    /// Passing None will send the messages from the interfaces they were added with.
+    pub fn set_interfaces(&self, opt_interfaces: Option<InterfaceFilter>) {
+        self.shared.lock().interfaces = opt_interfaces;
+    }
+
+    /// Poll the local network interfaces every interval and immediately announce
+    /// every advertisement from any address that appears, so that devices which
+    /// resume from sleep or roam between networks are rediscovered by control points.
+    ///
+    /// No byebye is sent from addresses that disappear. An address is only missing
+    /// from the local interfaces once the system has removed it, after which a
+    /// socket can no longer be bound to it. Control points drop the advertisements
+    /// seen through it once their max-age expires. Passing None stops watching,
+    /// which is the default.
+    pub fn set_network_watch(&self, opt_interval: Option<Duration>) {
+        self.set_watch(opt_interval, Arc::new(local_ips));
+    }
+
+    fn set_watch(&self, opt_interval: Option<Duration>, addrs: AddrsFn) {
+        let mut state = self.shared.lock();
+
+        state.watch = opt_interval.map(|interval| {
+            Watch {
+                interval: interval,
+                next_check: Instant::now(),
+                known: None,
+                addrs: addrs,
+            }
+        });
+        self.shared.wakeup.notify_one();
+    }
+
+    /// Announce that the device is switching to its next boot instance, such as
+    /// when the address of an advertised interface changes on a multi-homed device.
+    ///
+    /// An `ssdp:update` is multicast for every advertisement with the current and
+    /// next boot instance, after which the manager is incremented and every
+    /// advertisement is re-announced with the new boot instance, which is returned.
+    pub fn update_boot_id(&self) -> SSDPResult<u32> {
+        let (boot_ids, messages) = {
+            let state = self.shared.lock();
+            let boot_ids = match state.boot_ids {
+                Some(ref n) => n.clone(),
+                None => return Err(SSDPError::Other(Box::new(MsgError::new("No BootIdManager Set")))),
+            };
+
+            (boot_ids, state.entries.iter().map(|entry| entry.message.clone()).collect::<Vec<_>>())
+        };
+
+        let (boot_id, next_boot_id) = (boot_ids.boot_id(), boot_ids.next_boot_id());
+        for message in messages {
+            let mut update = try!(message.to_update(boot_id, next_boot_id));
+            self.shared.lock().prepare(&mut update);
+
+            try!((self.send)(&mut update));
+        }
+        let new_boot_id = try!(boot_ids.increment());
+
+        let mut state = self.shared.lock();
+        let (now, repeat_count) = (Instant::now(), state.schedule.repeat_count);
+        for entry in &mut state.entries {
+            entry.repeats_left = repeat_count;
+            entry.next_due = now;
+        }
+        self.shared.wakeup.notify_one();
+
+        Ok(new_boot_id)
+    }
+
+    /// Register every announcement required for the device, multicasting them
+    /// immediately as a batch.
+    ///
+    /// If any announcement fails to send, those already registered are removed.
+    pub fn add_device(&self, device: &DeviceAnnouncement) -> SSDPResult<Vec<AdvertisementId>> {
+        let messages = try!(device.alive_messages());
+
+        let mut ids = Vec::with_capacity(messages.len());
+        for message in messages {
+            match self.add(message) {
+                Ok(id) => ids.push(id),
+                Err(err) => {
+                    for id in ids {
+                        self.remove(id);
+                    }
+                    return Err(err);
+                }
+            }
+        }
+
+        Ok(ids)
+    }
+
+    /// Stop re-announcing the advertisement and multicast its `ssdp:byebye`.
+    ///
+    /// The byebye is sent from the background thread, after any announcement of
+    /// the advertisement that is already being sent, so that control points do
+    /// not see the advertisement come back. Returns false if the advertisement
+    /// was not registered.
+    pub fn remove(&self, id: AdvertisementId) -> bool {
+        let mut state = self.shared.lock();
+
+        let entry = match state.entries.iter().position(|entry| entry.id == id.0) {
+            Some(index) => state.entries.remove(index),
+            None => return false,
+        };
+
+        match entry.message.to_byebye() {
+            Ok(byebye) => {
+                state.byebyes.push(byebye);
+                self.shared.wakeup.notify_one();
+            }
+            Err(err) => warn!("Advertiser failed to build byebye: {}", err),
+        }
+
+        true
+    }
+
+    /// Stop the background thread and multicast an `ssdp:byebye` for every
+    /// advertisement that is still registered.
+    ///
+    /// Returns the first error encountered, after attempting to send every byebye.
+    pub fn stop(mut self) -> SSDPResult<()> {
+        self.shutdown();
+
+        self.byebye_all()
+    }
+
+    fn shutdown(&mut self) {
+        self.shared.lock().shutdown = true;
+        self.shared.wakeup.notify_one();
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+
+    /// Send the byebyes of removed advertisements that the background thread did
+    /// not get to, followed by a byebye for every advertisement still registered.
+    fn byebye_all(&self) -> SSDPResult<()> {
+        let (byebyes, entries) = {
+            let mut state = self.shared.lock();
+
+            (state.byebyes.drain(..).collect::<Vec<_>>(), state.entries.drain(..).collect::<Vec<_>>())
+        };
+
+        let mut result = Ok(());
+        for byebye in byebyes {
+            if let Err(err) = self.send_byebye(byebye) {
+                if result.is_ok() {
+                    result = Err(err);
+                }
+            }
+        }
+        for entry in entries {
+            if let Err(err) = entry.message.to_byebye().and_then(|byebye| self.send_byebye(byebye)) {
+                if result.is_ok() {
+                    result = Err(err);
+                }
+            }
+        }
+
+        result
+    }
+
+    fn send_byebye(&self, mut byebye: NotifyMessage) -> SSDPResult<()> {
+        self.shared.lock().prepare(&mut byebye);
+
+        (self.send)(&mut byebye)
+    }
+
+    /// Number of advertisements currently registered.
+    pub fn len(&self) -> usize {
+        self.shared.lock().entries.len()
+    }
+
+    /// Whether or not no advertisements are currently registered.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for Advertiser {
+    fn default() -> Self {
+        Advertiser::new()
+    }
+}
+
+impl Drop for Advertiser {
+    fn drop(&mut self) {
+        self.shutdown();
+
+        if let Err(err) = self.byebye_all() {
+            warn!("Advertiser failed to send byebye: {}", err);
+        }
+    }
+}
+
+/// Get the duration for which the announcement is valid.
+fn announce_max_age(message: &NotifyMessage) -> SSDPResult<Duration> {
+    match message.max_age() {
+        Some(n) if n == Duration::from_secs(0) => {
+            Err(SSDPError::InvalidHeader(names::CACHE_CONTROL, "Max Age Of Zero Can Not Be Announced"))
+        }
+        Some(n) => Ok(n),
+        None => Err(SSDPError::MissingHeader(names::CACHE_CONTROL)),
+    }
+}
+
+/// Random duration, with millisecond precision, between min and max inclusive.
+pub fn random_between(min: Duration, max: Duration) -> Duration {
+    let min_ms = duration_millis(min);
+    let max_ms = cmp::max(duration_millis(max), min_ms);
+
+    // Each RandomState is seeded with different keys, which is enough for jitter
+    let random = RandomState::new().build_hasher().finish();
+
+    Duration::from_millis(min_ms + random % (max_ms - min_ms + 1))
+}
+
+fn duration_millis(duration: Duration) -> u64 {
+    duration.as_secs() * 1000 + (duration.subsec_nanos() / 1_000_000) as u64
+}
+
+/// Addresses of the local network interfaces that announcements can be sent from.
+fn local_ips() -> io::Result<Vec<IpAddr>> {
+    let addrs = try!(net::local_addrs());
+
+    Ok(addrs.into_iter().map(|addr| addr.ip()).filter(|ip| !ip.is_loopback()).collect())
+}
+
+/// Re-announce advertisements as they come due until the advertiser is shutdown.
+fn run_advertiser(shared: Arc<Shared>, send: SendFn) {
+    let mut state = shared.lock();
+
+    loop {
+        if state.shutdown {
+            return;
+        }
+
+        let opt_addrs = match state.watch {
+            Some(ref watch) if watch.next_check <= Instant::now() => Some(watch.addrs.clone()),
+            _ => None,
+        };
+        let mut added = Vec::new();
+        if let Some(addrs) = opt_addrs {
+            // Finding the addresses can take a while, don't block registration while it happens
+            drop(state);
+            let result = addrs();
+
+            state = shared.lock();
+            if let Some(ref mut watch) = state.watch {
+                added = watch.update(result, Instant::now());
+            }
+        }
+
+        let now = Instant::now();
+        let schedule = state.schedule;
+        let mut due = Vec::new();
+        for entry in &mut state.entries {
+            if entry.next_due <= now {
+                due.push(entry.message.clone());
+                entry.reschedule(now, &schedule);
+            }
+        }
+        // Byebyes of removed advertisements go out after any of their announcements
+        due.extend(state.byebyes.drain(..));
+        for message in &mut due {
+            state.prepare(message);
+        }
+        if !added.is_empty() {
+            debug!("Announcing on added local interface addresses {:?}", added);
+            due.extend(state.added_announcements(&added));
+        }
+
+        if !due.is_empty() {
+            // Sending can take a while, don't block registration while it happens
+            drop(state);
+            for mut message in due {
+                if let Err(err) = send(&mut message) {
+                    warn!("Advertiser failed to send announcement: {}", err);
+                }
+            }
+
+            state = shared.lock();
+            continue;
+        }
+
+        let opt_next_check = state.watch.as_ref().map(|watch| watch.next_check);
+        let opt_next_due = state.entries.iter().map(|entry| entry.next_due).chain(opt_next_check).min();
+        state = match opt_next_due {
+            Some(next_due) => {
+                match shared.wakeup.wait_timeout(state, next_due.saturating_duration_since(now)) {
+                    Ok((n, _)) => n,
+                    Err(err) => err.into_inner().0,
+                }
+            }
+            None => {
+                match shared.wakeup.wait(state) {
+                    Ok(n) => n,
+                    Err(err) => err.into_inner(),
+                }
+            }
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp;
+    use std::sync::{Arc, Mutex};
+    use std::sync::mpsc::{self, Receiver};
+    use std::time::{Duration, Instant};
+
+    use FieldMap;
+    use bootid::{BootIdManager, MemoryStorage};
+    use header::{HeaderRef, HeaderMut, BootID, CacheControl, CacheDirective, NTS, USN};
+    use message::{DeviceAnnouncement, NotifyMessage};
+    use net::InterfaceFilter;
+    use super::{Advertiser, AddrsFn, random_between};
+
+    /// Advertiser that sends what the record function returns for every message
+    /// to the channel instead of multicasting the message, without repeats.
+    fn channel_advertiser<T, F>(record: F) -> (Advertiser, Receiver<T>)
+        where T: Send + 'static,
+              F: Fn(&NotifyMessage) -> T + Send + Sync + 'static
+    {
+        let (send, recv) = mpsc::channel();
+        let send = Mutex::new(send);
+
+        let advertiser = Advertiser::with_sender(Arc::new(move |message: &mut NotifyMessage| {
+            let _ = send.lock().unwrap().send(record(message));
+            Ok(())
+        }));
+        advertiser.set_repeat(0, Duration::from_millis(0));
+
+        (advertiser, recv)
+    }
+
+    fn recording_advertiser() -> (Advertiser, Receiver<NTS>) {
+        channel_advertiser(|message| message.get::<NTS>().unwrap())
+    }
+
+    /// Next message sent from the background thread, failing if none is sent
+    /// well within the time that any of the tests schedule messages for.
+    fn recv_sent<T>(sent: &Receiver<T>) -> T {
+        sent.recv_timeout(Duration::from_secs(5)).unwrap()
+    }
+
+    /// Local addresses that are found at each check of the network watch, repeating
+    /// the last ones, along with a channel receiving the number of each check.
+    ///
+    /// Once check n is received, everything sent for check n - 1 has been sent.
+    fn scripted_addrs(script: Vec<Vec<&str>>) -> (AddrsFn, Receiver<usize>) {
+        let script = script.into_iter()
+            .map(|addrs| addrs.into_iter().map(|addr| addr.parse().unwrap()).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let (send, recv) = mpsc::channel();
+        let checks = Mutex::new((0, send));
+
+        let addrs: AddrsFn = Arc::new(move || {
+            let mut checks = checks.lock().unwrap();
+            checks.0 += 1;
+            let _ = checks.1.send(checks.0);
+
+            Ok(script[cmp::min(checks.0, script.len()) - 1].clone())
+        });
+
+        (addrs, recv)
+    }
+
+    /// Wait for the network watch to reach check n.
+    fn wait_for_check(checks: &Receiver<usize>, n: usize) {
+        while recv_sent(checks) < n {}
+    }
+
+    fn alive_message(max_age: u32) -> NotifyMessage {
+        NotifyMessage::alive()
+            .notification_type(FieldMap::upnp("rootdevice"))
+            .usn(USN(FieldMap::uuid("device"), Some(FieldMap::upnp("rootdevice"))))
+            .location("http://192.168.0.2:80/desc.xml")
+            .max_age(max_age)
+            .server("Linux/3.0 UPnP/1.0 Test/1.0")
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn positive_announce_on_add() {
+        let (advertiser, sent) = recording_advertiser();
+
+        advertiser.add(alive_message(1800)).unwrap();
+
+        assert_eq!(sent.try_iter().collect::<Vec<_>>(), vec![NTS::Alive]);
+        assert_eq!(advertiser.len(), 1);
+    }
+
+    #[test]
+    fn positive_reannounce() {
+        let (advertiser, sent) = recording_advertiser();
+
+        advertiser.add(alive_message(1)).unwrap();
+
+        assert_eq!(recv_sent(&sent), NTS::Alive);
+        assert_eq!(recv_sent(&sent), NTS::Alive);
+    }
+
+    #[test]
+    fn positive_repeat_announce() {
+        let (advertiser, sent) = recording_advertiser();
+        advertiser.set_repeat(2, Duration::from_millis(50));
+
+        advertiser.add(alive_message(1800)).unwrap();
+
+        assert_eq!((recv_sent(&sent), recv_sent(&sent), recv_sent(&sent)),
+                   (NTS::Alive, NTS::Alive, NTS::Alive));
+        advertiser.stop().unwrap();
+        assert_eq!(sent.try_iter().collect::<Vec<_>>(), vec![NTS::ByeBye]);
+    }
+
+    #[test]
+    fn positive_reannounce_range() {
+        let (advertiser, sent) = channel_advertiser(|_| Instant::now());
+        advertiser.set_reannounce_range(90, 100);
+
+        advertiser.add(alive_message(1)).unwrap();
+        let announced = recv_sent(&sent);
+
+        assert!(recv_sent(&sent) - announced >= Duration::from_millis(900));
+    }
+
+    #[test]
+    fn positive_random_between() {
+        for _ in 0..100 {
+            let duration = random_between(Duration::from_millis(10), Duration::from_millis(20));
+
+            assert!(duration >= Duration::from_millis(10) && duration <= Duration::from_millis(20));
+        }
+        assert_eq!(random_between(Duration::from_millis(5), Duration::from_millis(5)),
+                   Duration::from_millis(5));
+    }
+
+    #[test]
+    fn positive_stamp_boot_id() {
+        let (advertiser, sent) = channel_advertiser(|message| message.get::<BootID>());
+
+        let boot_ids = Arc::new(BootIdManager::new(MemoryStorage::new(), 0).unwrap());
+        advertiser.set_boot_id_manager(Some(boot_ids.clone()));
+
+        let id = advertiser.add(alive_message(1800)).unwrap();
+        boot_ids.increment().unwrap();
+        advertiser.remove(id);
+
+        assert_eq!((recv_sent(&sent), recv_sent(&sent)), (Some(BootID(1)), Some(BootID(2))));
+    }
+
+    #[test]
+    fn positive_update_boot_id() {
+        let (advertiser, sent) = channel_advertiser(|message| {
+            (message.get::<NTS>().unwrap(), message.get::<BootID>())
+        });
+        advertiser.set_boot_id_manager(Some(Arc::new(BootIdManager::new(MemoryStorage::new(), 0).unwrap())));
+
+        advertiser.add(alive_message(1800)).unwrap();
+        assert_eq!(advertiser.update_boot_id().unwrap(), 2);
+
+        assert_eq!(recv_sent(&sent), (NTS::Alive, Some(BootID(1))));
+        assert_eq!(recv_sent(&sent), (NTS::Update, Some(BootID(1))));
+        assert_eq!(recv_sent(&sent), (NTS::Alive, Some(BootID(2))));
+    }
+
+    #[test]
+    fn negative_update_boot_id_without_manager() {
+        let (advertiser, sent) = recording_advertiser();
+
+        advertiser.add(alive_message(1800)).unwrap();
+
+        assert!(advertiser.update_boot_id().is_err());
+        assert_eq!(sent.try_iter().collect::<Vec<_>>(), vec![NTS::Alive]);
+    }
+
+    #[test]
+    fn positive_set_interfaces() {
+        let (advertiser, sent) = channel_advertiser(|message| message.interfaces().clone());
+
+        let id = advertiser.add(alive_message(1800)).unwrap();
+        advertiser.set_interfaces(Some(InterfaceFilter::Names(vec!["br0".to_owned()])));
+        advertiser.remove(id);
+
+        assert_eq!((recv_sent(&sent), recv_sent(&sent)),
+                   (InterfaceFilter::All, InterfaceFilter::Names(vec!["br0".to_owned()])));
+    }
+
+    #[test]
+    fn positive_network_watch_announces_added_addrs() {
+        let (advertiser, sent) = channel_advertiser(|message| message.interfaces().clone());
+        advertiser.add(alive_message(1800)).unwrap();
+
+        let (addrs, checks) = scripted_addrs(vec![vec!["192.168.0.2"], vec!["10.0.0.2"]]);
+        advertiser.set_watch(Some(Duration::from_millis(20)), addrs);
+        wait_for_check(&checks, 3);
+
+        assert_eq!(sent.try_iter().collect::<Vec<_>>(),
+                   vec![InterfaceFilter::All, InterfaceFilter::Addrs(vec!["10.0.0.2".parse().unwrap()])]);
+    }
+
+    #[test]
+    fn negative_network_watch_skips_unselected_addrs() {
+        let (advertiser, sent) = recording_advertiser();
+        advertiser.set_interfaces(Some(InterfaceFilter::Addrs(vec!["192.168.0.2".parse().unwrap()])));
+        advertiser.add(alive_message(1800)).unwrap();
+
+        let (addrs, checks) = scripted_addrs(vec![vec![], vec!["10.0.0.2"]]);
+        advertiser.set_watch(Some(Duration::from_millis(20)), addrs);
+        wait_for_check(&checks, 3);
+
+        assert_eq!(sent.try_iter().collect::<Vec<_>>(), vec![NTS::Alive]);
+    }
+
+    #[test]
+    fn positive_remove_sends_byebye() {
+        let (advertiser, sent) = recording_advertiser();
+
+        let id = advertiser.add(alive_message(1)).unwrap();
+        assert!(advertiser.remove(id));
+        assert!(!advertiser.remove(id));
+
+        assert_eq!((recv_sent(&sent), recv_sent(&sent)), (NTS::Alive, NTS::ByeBye));
+        assert!(advertiser.is_empty());
+    }
+
+    #[test]
+    fn positive_remove_byebye_after_announcements() {
+        let (advertiser, sent) = recording_advertiser();
+        // Announcements are sent back to back from the background thread
+        advertiser.set_repeat(u32::max_value(), Duration::from_millis(0));
+
+        let id = advertiser.add(alive_message(1800)).unwrap();
+        assert_eq!((recv_sent(&sent), recv_sent(&sent)), (NTS::Alive, NTS::Alive));
+        advertiser.remove(id);
+        advertiser.stop().unwrap();
+
+        let sent = sent.try_iter().collect::<Vec<_>>();
+        assert_eq!(sent.last(), Some(&NTS::ByeBye));
+        assert_eq!(sent.iter().filter(|&nts| *nts == NTS::ByeBye).count(), 1);
+    }
+
+    #[test]
+    fn negative_missing_max_age() {
+        let (advertiser, sent) = recording_advertiser();
+
+        let mut message = alive_message(1800);
+        message.set(CacheControl(vec![CacheDirective::NoCache]));
+
+        assert!(advertiser.add(message).is_err());
+        assert!(sent.try_recv().is_err());
+    }
+
+    #[test]
+    fn positive_add_device() {
+        let (advertiser, sent) = recording_advertiser();
+
+        let device = DeviceAnnouncement::new("root", FieldMap::urn("schemas-upnp-org:device:Basic:1"))
+            .service(FieldMap::urn("schemas-upnp-org:service:Dimming:1"))
+            .location("http://192.168.0.2:80/desc.xml")
+            .max_age(1800)
+            .server("Linux/3.0 UPnP/1.0 Test/1.0");
+        let ids = advertiser.add_device(&device).unwrap();
+
+        assert_eq!(ids.len(), 4);
+        assert_eq!(sent.try_iter().count(), 4);
+        assert_eq!(advertiser.len(), 4);
+    }
+
+    #[test]
+    fn positive_byebye_on_drop() {
+        let (advertiser, sent) = recording_advertiser();
+
+        advertiser.add(alive_message(1800)).unwrap();
+        drop(advertiser);
+
+        assert_eq!(sent.try_iter().collect::<Vec<_>>(), vec![NTS::Alive, NTS::ByeBye]);
+    }
+
+    #[test]
+    fn positive_byebye_on_stop() {
+        let (advertiser, sent) = recording_advertiser();
+
+        advertiser.add(alive_message(1800)).unwrap();
+        advertiser.add(alive_message(1800)).unwrap();
+        advertiser.stop().unwrap();
+
+        assert_eq!(sent.try_iter().collect::<Vec<_>>(),
+                   vec![NTS::Alive, NTS::Alive, NTS::ByeBye, NTS::ByeBye]);
+    }
+}
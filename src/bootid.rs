@@ -0,0 +1,265 @@
+//! Lifecycle management of the `BOOTID.UPNP.ORG` and `CONFIGID.UPNP.ORG` headers.
+
+use std::fs::File;
+use std::io::{Read, Write};
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+
+use {SSDPError, SSDPResult};
+use header::{HeaderMut, BootID, ConfigID, BOOTID_MAX};
+
+/// Storage used to persist the boot instance of a device across restarts.
+pub trait BootIdStorage: Send {
+    /// Load the boot instance that was last stored, if any.
+    fn load(&mut self) -> SSDPResult<Option<u32>>;
+
+    /// Store the current boot instance.
+    fn store(&mut self, boot_id: u32) -> SSDPResult<()>;
+}
+
+/// Storage that only persists the boot instance for the life of the process.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStorage {
+    boot_id: Option<u32>,
+}
+
+impl MemoryStorage {
+    /// Construct a new MemoryStorage with no boot instance stored.
+    pub fn new() -> MemoryStorage {
+        MemoryStorage::default()
+    }
+}
+
+impl BootIdStorage for MemoryStorage {
+    fn load(&mut self) -> SSDPResult<Option<u32>> {
+        Ok(self.boot_id)
+    }
+
+    fn store(&mut self, boot_id: u32) -> SSDPResult<()> {
+        self.boot_id = Some(boot_id);
+
+        Ok(())
+    }
+}
+
+/// Storage that persists the boot instance, as a decimal number, to a file.
+#[derive(Debug, Clone)]
+pub struct FileStorage {
+    path: PathBuf,
+}
+
+impl FileStorage {
+    /// Construct a new FileStorage backed by the file at the given path.
+    ///
+    /// The file does not need to exist until the first boot instance is stored.
+    pub fn new<P>(path: P) -> FileStorage
+        where P: Into<PathBuf>
+    {
+        FileStorage { path: path.into() }
+    }
+}
+
+impl BootIdStorage for FileStorage {
+    fn load(&mut self) -> SSDPResult<Option<u32>> {
+        let mut file = match File::open(&self.path) {
+            Ok(n) => n,
+            Err(ref err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(SSDPError::from(err)),
+        };
+
+        let mut contents = String::new();
+        try!(file.read_to_string(&mut contents));
+
+        Ok(Some(try!(contents.trim().parse::<u32>())))
+    }
+
+    fn store(&mut self, boot_id: u32) -> SSDPResult<()> {
+        let mut file = try!(File::create(&self.path));
+
+        try!(file.write_all(boot_id.to_string().as_bytes()));
+
+        Ok(())
+    }
+}
+
+struct State {
+    boot_id: u32,
+    config_id: u32,
+    storage: Box<BootIdStorage>,
+}
+
+/// Tracks the boot instance and configuration number of a root device, so that
+/// they can be stamped onto every outgoing NOTIFY and search response.
+///
+/// The boot instance is incremented, and persisted, each time a manager is
+/// constructed, which should happen once per boot of the device.
+pub struct BootIdManager {
+    state: Mutex<State>,
+}
+
+impl BootIdManager {
+    /// Construct a new BootIdManager, incrementing the boot instance loaded from
+    /// the storage (starting at 1 if none was stored) and persisting the result.
+    pub fn new<S>(mut storage: S, config_id: u32) -> SSDPResult<BootIdManager>
+        where S: BootIdStorage + 'static
+    {
+        try!(validate_config_id(config_id));
+
+        let boot_id = match try!(storage.load()) {
+            Some(n) => next_boot_id(n),
+            None => 1,
+        };
+        try!(storage.store(boot_id));
+
+        Ok(BootIdManager {
+            state: Mutex::new(State {
+                boot_id: boot_id,
+                config_id: config_id,
+                storage: Box::new(storage),
+            }),
+        })
+    }
+
+    fn lock<'a>(&'a self) -> MutexGuard<'a, State> {
+        // Storage errors are returned rather than panicking, recover from poisoning
+        match self.state.lock() {
+            Ok(n) => n,
+            Err(err) => err.into_inner(),
+        }
+    }
+
+    /// Current boot instance of the device.
+    pub fn boot_id(&self) -> u32 {
+        self.lock().boot_id
+    }
+
+    /// Boot instance that the device will use after its next increment.
+    pub fn next_boot_id(&self) -> u32 {
+        next_boot_id(self.boot_id())
+    }
+
+    /// Current configuration number of the device description.
+    pub fn config_id(&self) -> u32 {
+        self.lock().config_id
+    }
+
+    /// Set the configuration number, which should change whenever the device or
+    /// service descriptions change.
+    pub fn set_config_id(&self, config_id: u32) -> SSDPResult<()> {
+        try!(validate_config_id(config_id));
+
+        self.lock().config_id = config_id;
+        Ok(())
+    }
+
+    /// Increment and persist the boot instance, returning the new value.
+    ///
+    /// The boot instance is only updated if it was successfully stored.
+    pub fn increment(&self) -> SSDPResult<u32> {
+        let mut state = self.lock();
+        let boot_id = next_boot_id(state.boot_id);
+
+        try!(state.storage.store(boot_id));
+        state.boot_id = boot_id;
+
+        Ok(boot_id)
+    }
+
+    /// Set the BOOTID.UPNP.ORG and CONFIGID.UPNP.ORG headers on the message.
+    pub fn stamp<T>(&self, message: &mut T)
+        where T: HeaderMut
+    {
+        let (boot_id, config_id) = {
+            let state = self.lock();
+            (state.boot_id, state.config_id)
+        };
+
+        message.set(BootID(boot_id));
+        message.set(ConfigID(config_id));
+    }
+}
+
+/// Boot instance following the given one, wrapping back to zero past the 31 bit maximum.
+fn next_boot_id(boot_id: u32) -> u32 {
+    if boot_id >= BOOTID_MAX {
+        0
+    } else {
+        boot_id + 1
+    }
+}
+
+fn validate_config_id(config_id: u32) -> SSDPResult<()> {
+    ConfigID::new(config_id).map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+
+    use header::{HeaderRef, BootID, ConfigID, BOOTID_MAX};
+    use message::NotifyMessage;
+    use super::{BootIdManager, BootIdStorage, FileStorage, MemoryStorage};
+
+    #[test]
+    fn positive_first_boot() {
+        let manager = BootIdManager::new(MemoryStorage::new(), 0).unwrap();
+
+        assert_eq!(manager.boot_id(), 1);
+        assert_eq!(manager.next_boot_id(), 2);
+    }
+
+    #[test]
+    fn positive_increment_on_reboot() {
+        let mut storage = MemoryStorage::new();
+        storage.store(41).unwrap();
+
+        let manager = BootIdManager::new(storage, 0).unwrap();
+        assert_eq!(manager.boot_id(), 42);
+
+        assert_eq!(manager.increment().unwrap(), 43);
+        assert_eq!(manager.boot_id(), 43);
+    }
+
+    #[test]
+    fn positive_boot_id_wraps() {
+        let mut storage = MemoryStorage::new();
+        storage.store(BOOTID_MAX).unwrap();
+
+        let manager = BootIdManager::new(storage, 0).unwrap();
+
+        assert_eq!(manager.boot_id(), 0);
+    }
+
+    #[test]
+    fn positive_file_storage() {
+        let path = env::temp_dir().join(format!("ssdp-bootid-test-{}", ::std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(BootIdManager::new(FileStorage::new(path.clone()), 0).unwrap().boot_id(), 1);
+        assert_eq!(BootIdManager::new(FileStorage::new(path.clone()), 0).unwrap().boot_id(), 2);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn positive_stamp() {
+        let manager = BootIdManager::new(MemoryStorage::new(), 7).unwrap();
+        let mut message = NotifyMessage::new();
+
+        manager.stamp(&mut message);
+
+        assert_eq!(message.get::<BootID>(), Some(BootID(1)));
+        assert_eq!(message.get::<ConfigID>(), Some(ConfigID(7)));
+    }
+
+    #[test]
+    fn negative_reserved_config_id() {
+        assert!(BootIdManager::new(MemoryStorage::new(), 16_777_216).is_err());
+
+        let manager = BootIdManager::new(MemoryStorage::new(), 0).unwrap();
+        assert!(manager.set_config_id(16_777_216).is_err());
+        assert_eq!(manager.config_id(), 0);
+    }
+}
@@ -0,0 +1,675 @@
+//! Validation of messages against the rules of the `UPnP` Device Architecture.
+//!
+//! Every check reports all of the violations it finds instead of stopping at the
+//! first, so that certification tooling can show everything a device gets wrong.
+//! Rules that the standard states with MUST or REQUIRED are reported as errors,
+//! while those it states with SHOULD or RECOMMENDED are reported as warnings.
+
+use std::fmt::{self, Display, Formatter};
+
+use {FieldMap, SSDPError};
+use header::{self, Header, HeaderFormat, HeaderRef, names, BootID, ConfigID, Location, ManValidity,
+             NextBootID, ProductTokens, SearchPort, CPFN, MX, NT, NTS, ST, USN, MX_HEADER_UDA11_MAX};
+use message::{AnyMessage, NotifyMessage, SearchRequest, SearchResponse};
+
+/// Max-age below which announcements expire sooner than the standard recommends.
+const MIN_RECOMMENDED_MAX_AGE: u64 = 1800;
+
+/// Value of the "upnp" field that announces a root device.
+const ROOT_DEVICE_VALUE: &'static str = "rootdevice";
+
+/// Part of a URN field that marks it as a device type.
+const DEVICE_URN_KIND: &'static str = ":device:";
+
+/// Enumerates the versions of the `UPnP` Device Architecture that messages can be
+/// validated against.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum UdaVersion {
+    /// `UPnP` Device Architecture 1.0.
+    Uda10,
+    /// `UPnP` Device Architecture 1.1, which requires BOOTID.UPNP.ORG and
+    /// CONFIGID.UPNP.ORG and introduces `ssdp:update`.
+    Uda11,
+    /// `UPnP` Device Architecture 2.0, which additionally requires CPFN.UPNP.ORG
+    /// on multicast search requests.
+    Uda20,
+}
+
+impl UdaVersion {
+    /// Version that the `UPnP` token of SERVER and USER-AGENT headers should carry.
+    pub fn upnp_token(&self) -> (u8, u8) {
+        match *self {
+            UdaVersion::Uda10 => (1, 0),
+            UdaVersion::Uda11 => (1, 1),
+            UdaVersion::Uda20 => (2, 0),
+        }
+    }
+
+    fn requires_boot_ids(&self) -> bool {
+        *self != UdaVersion::Uda10
+    }
+}
+
+/// Enumerates how serious a violation is.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Severity {
+    /// Breaks a rule that the standard states with MUST or REQUIRED.
+    Error,
+    /// Breaks a rule that the standard states with SHOULD or RECOMMENDED.
+    Warning,
+}
+
+/// Enumerates the kinds of violations.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum ViolationKind {
+    /// A header that is required is missing.
+    MissingHeader,
+    /// A header is present but its value is malformed or out of range.
+    InvalidHeader,
+    /// The message as a whole is not allowed, such as an `ssdp:update` in `UPnP` 1.0.
+    InvalidMessage,
+    /// Headers that have to agree with each other do not.
+    Inconsistent,
+    /// A message that a set of announcements has to include is missing.
+    MissingAnnouncement,
+}
+
+/// A single rule that a message, or set of messages, breaks.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Violation {
+    kind: ViolationKind,
+    severity: Severity,
+    header: Option<&'static str>,
+    detail: String,
+}
+
+impl Violation {
+    fn new<S>(kind: ViolationKind, severity: Severity, header: Option<&'static str>, detail: S) -> Violation
+        where S: Into<String>
+    {
+        Violation {
+            kind: kind,
+            severity: severity,
+            header: header,
+            detail: detail.into(),
+        }
+    }
+
+    fn error<S>(kind: ViolationKind, header: &'static str, detail: S) -> Violation
+        where S: Into<String>
+    {
+        Violation::new(kind, Severity::Error, Some(header), detail)
+    }
+
+    fn warning<S>(kind: ViolationKind, header: &'static str, detail: S) -> Violation
+        where S: Into<String>
+    {
+        Violation::new(kind, Severity::Warning, Some(header), detail)
+    }
+
+    /// Kind of rule that is broken.
+    pub fn kind(&self) -> ViolationKind {
+        self.kind
+    }
+
+    /// How serious the violation is.
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    /// Name of the header that the violation concerns, if it concerns a single header.
+    pub fn header(&self) -> Option<&'static str> {
+        self.header
+    }
+
+    /// Description of the violation.
+    pub fn detail(&self) -> &str {
+        &self.detail
+    }
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        try!(write!(f, "{:?}: {:?}", self.severity, self.kind));
+        if let Some(header) = self.header {
+            try!(write!(f, " ({})", header));
+        }
+
+        write!(f, ": {}", self.detail)
+    }
+}
+
+/// Validate a message of any type, see `check_notify`, `check_search_request`
+/// and `check_search_response`.
+pub fn check_message(message: &AnyMessage, version: UdaVersion) -> Vec<Violation> {
+    match *message {
+        AnyMessage::Notify(ref n) => check_notify(n, version),
+        AnyMessage::Search(ref n) => check_search_request(n, version),
+        AnyMessage::Response(ref n) => check_search_response(n, version),
+    }
+}
+
+/// Validate the headers of an `ssdp:alive`, `ssdp:byebye` or `ssdp:update` notify
+/// message, including that the USN agrees with the NT header.
+pub fn check_notify(message: &NotifyMessage, version: UdaVersion) -> Vec<Violation> {
+    let mut violations = Vec::new();
+
+    check_multicast_host(message, message.has_multicast_host(), &mut violations);
+    let opt_nt = required::<NT, _>(message, &mut violations);
+    let opt_nts = required::<NTS, _>(message, &mut violations);
+    check_usn(message, opt_nt.as_ref().map(|nt| &nt.0), &mut violations);
+
+    match opt_nts {
+        Some(NTS::Alive) => {
+            check_max_age(message, &mut violations);
+            check_location(message, &mut violations);
+            check_product_tokens(message, names::SERVER, true, version, &mut violations);
+            optional::<SearchPort, _>(message, &mut violations);
+            check_boot_ids(message, version, &mut violations);
+        }
+        Some(NTS::ByeBye) => {
+            check_boot_ids(message, version, &mut violations);
+        }
+        Some(NTS::Update) => {
+            if !version.requires_boot_ids() {
+                violations.push(Violation::error(ViolationKind::InvalidMessage,
+                                                 names::NTS,
+                                                 "ssdp:update Is Not Defined In UPnP 1.0"));
+            }
+
+            check_location(message, &mut violations);
+            optional::<SearchPort, _>(message, &mut violations);
+            let opt_boot_id = check_boot_ids(message, version, &mut violations);
+            let opt_next_boot_id = required::<NextBootID, _>(message, &mut violations);
+
+            match (opt_boot_id, opt_next_boot_id) {
+                (Some(boot_id), Some(next_boot_id)) if boot_id.0 == next_boot_id.0 => {
+                    violations.push(Violation::error(ViolationKind::Inconsistent,
+                                                     names::NEXTBOOTID,
+                                                     "Same As BOOTID.UPNP.ORG"));
+                }
+                _ => (),
+            }
+        }
+        None => (),
+    }
+
+    violations
+}
+
+/// Validate the headers of a search request, including the MX and CPFN.UPNP.ORG
+/// headers required of multicast searches.
+pub fn check_search_request(request: &SearchRequest, version: UdaVersion) -> Vec<Violation> {
+    let mut violations = Vec::new();
+
+    if request.get_raw(names::HOST).is_none() {
+        violations.push(Violation::error(ViolationKind::MissingHeader, names::HOST, "Missing"));
+    }
+
+    match request.man_validity() {
+        ManValidity::Valid => (),
+        ManValidity::Unquoted => {
+            violations.push(Violation::error(ViolationKind::InvalidHeader,
+                                             names::MAN,
+                                             "Expected A Quoted Value"));
+        }
+        ManValidity::Invalid if request.get_raw(names::MAN).is_none() => {
+            violations.push(Violation::error(ViolationKind::MissingHeader, names::MAN, "Missing"));
+        }
+        ManValidity::Invalid => {
+            violations.push(Violation::error(ViolationKind::InvalidHeader,
+                                             names::MAN,
+                                             "Expected \"ssdp:discover\""));
+        }
+    }
+
+    required::<ST, _>(request, &mut violations);
+
+    if request.has_multicast_host() {
+        let opt_mx = required::<MX, _>(request, &mut violations);
+        if version != UdaVersion::Uda10 && opt_mx.map_or(false, |mx| mx.0 > MX_HEADER_UDA11_MAX) {
+            violations.push(Violation::warning(ViolationKind::InvalidHeader,
+                                               names::MX,
+                                               "Above The UPnP 1.1 Maximum Of 5"));
+        }
+
+        if version == UdaVersion::Uda20 {
+            required::<CPFN, _>(request, &mut violations);
+        }
+    }
+
+    check_product_tokens(request, names::USER_AGENT, false, version, &mut violations);
+
+    violations
+}
+
+/// Validate the status and headers of a search response, including that the USN
+/// agrees with the ST header.
+pub fn check_search_response(response: &SearchResponse, version: UdaVersion) -> Vec<Violation> {
+    let mut violations = Vec::new();
+
+    if response.status_code() != 200 {
+        violations.push(Violation::new(ViolationKind::InvalidMessage,
+                                       Severity::Error,
+                                       None,
+                                       format!("Status Code {} Instead Of 200", response.status_code())));
+    }
+
+    check_max_age(response, &mut violations);
+    if !response.has_ext() {
+        violations.push(Violation::error(ViolationKind::MissingHeader, names::EXT, "Missing"));
+    }
+    check_location(response, &mut violations);
+    check_product_tokens(response, names::SERVER, true, version, &mut violations);
+
+    match required::<ST, _>(response, &mut violations) {
+        Some(ST::Target(field)) => check_usn(response, Some(&field), &mut violations),
+        _ => check_usn(response, None, &mut violations),
+    }
+
+    if response.get_raw(names::DATE).is_none() {
+        violations.push(Violation::warning(ViolationKind::MissingHeader, names::DATE, "Missing"));
+    }
+
+    optional::<SearchPort, _>(response, &mut violations);
+    check_boot_ids(response, version, &mut violations);
+
+    violations
+}
+
+/// Validate that the `ssdp:alive` messages of a device make up a complete set of
+/// announcements.
+///
+/// The set has to include a `upnp:rootdevice` announcement, and for every device
+/// a `uuid:{uuid}` announcement and one for its device type. Announcements of the
+/// same device have to share their LOCATION, and in `UPnP` 1.1 and later every
+/// announcement has to carry the same BOOTID.UPNP.ORG and CONFIGID.UPNP.ORG.
+/// Messages that are not `ssdp:alive` are ignored.
+pub fn check_announce_set(messages: &[NotifyMessage], version: UdaVersion) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    let alive = messages.iter().filter(|message| message.nts() == Some(NTS::Alive)).collect::<Vec<_>>();
+
+    let mut devices: Vec<(String, Vec<FieldMap>, Option<&str>)> = Vec::new();
+    for message in &alive {
+        let (opt_nt, opt_usn) = (message.get::<NT>(), message.get::<USN>());
+        let (nt, uuid) = match (opt_nt, opt_usn.as_ref().and_then(|usn| usn.device_uuid())) {
+            (Some(nt), Some(uuid)) => (nt.0, uuid.to_owned()),
+            _ => continue,
+        };
+        let location = message.get_str(names::LOCATION);
+
+        match devices.iter().position(|&(ref device_uuid, _, _)| *device_uuid == uuid) {
+            Some(index) => {
+                let device = &mut devices[index];
+                if device.2 != location {
+                    violations.push(Violation::error(ViolationKind::Inconsistent,
+                                                     names::LOCATION,
+                                                     format!("Differs Between Announcements Of uuid:{}",
+                                                             uuid)));
+                }
+                device.1.push(nt);
+            }
+            None => devices.push((uuid, vec![nt], location)),
+        }
+    }
+
+    let is_root = |nt: &FieldMap| *nt == FieldMap::UPnP(ROOT_DEVICE_VALUE.to_owned());
+    if !devices.iter().any(|&(_, ref nts, _)| nts.iter().any(&is_root)) {
+        violations.push(Violation::new(ViolationKind::MissingAnnouncement,
+                                       Severity::Error,
+                                       None,
+                                       "Missing upnp:rootdevice Announcement"));
+    }
+
+    for &(ref uuid, ref nts, _) in &devices {
+        if !nts.iter().any(|nt| *nt == FieldMap::UUID(uuid.clone())) {
+            violations.push(Violation::new(ViolationKind::MissingAnnouncement,
+                                           Severity::Error,
+                                           None,
+                                           format!("Missing uuid:{} Announcement", uuid)));
+        }
+
+        let is_device_type = |nt: &FieldMap| match *nt {
+            FieldMap::URN(ref urn) => urn.contains(DEVICE_URN_KIND),
+            _ => false,
+        };
+        if !nts.iter().any(is_device_type) {
+            violations.push(Violation::new(ViolationKind::MissingAnnouncement,
+                                           Severity::Error,
+                                           None,
+                                           format!("Missing Device Type Announcement Of uuid:{}", uuid)));
+        }
+    }
+
+    if version.requires_boot_ids() {
+        if !all_equal(alive.iter().map(|message| message.boot_id())) {
+            violations.push(Violation::error(ViolationKind::Inconsistent,
+                                             names::BOOTID,
+                                             "Differs Between Announcements"));
+        }
+        if !all_equal(alive.iter().map(|message| message.config_id())) {
+            violations.push(Violation::error(ViolationKind::Inconsistent,
+                                             names::CONFIGID,
+                                             "Differs Between Announcements"));
+        }
+    }
+
+    violations
+}
+
+/// Parse a header that is required, reporting it if it is missing or invalid.
+fn required<H, T>(message: &T, violations: &mut Vec<Violation>) -> Option<H>
+    where H: Header + HeaderFormat,
+          T: HeaderRef
+{
+    if message.get_raw(H::header_name()).is_none() {
+        violations.push(Violation::error(ViolationKind::MissingHeader, H::header_name(), "Missing"));
+
+        None
+    } else {
+        optional(message, violations)
+    }
+}
+
+/// Parse a header that is optional, reporting it if it is present but invalid.
+fn optional<H, T>(message: &T, violations: &mut Vec<Violation>) -> Option<H>
+    where H: Header + HeaderFormat,
+          T: HeaderRef
+{
+    let raw = match message.get_raw(H::header_name()) {
+        Some(n) => n,
+        None => return None,
+    };
+
+    match H::parse_header(raw) {
+        Ok(n) => Some(n),
+        Err(err) => {
+            let detail = match err {
+                SSDPError::InvalidHeader(_, detail) => detail.to_owned(),
+                other => other.to_string(),
+            };
+            violations.push(Violation::error(ViolationKind::InvalidHeader, H::header_name(), detail));
+
+            None
+        }
+    }
+}
+
+fn check_multicast_host<T>(message: &T, is_multicast: bool, violations: &mut Vec<Violation>)
+    where T: HeaderRef
+{
+    if message.get_raw(names::HOST).is_none() {
+        violations.push(Violation::error(ViolationKind::MissingHeader, names::HOST, "Missing"));
+    } else if !is_multicast {
+        violations.push(Violation::error(ViolationKind::InvalidHeader,
+                                         names::HOST,
+                                         "Expected The SSDP Multicast Address And Port"));
+    }
+}
+
+fn check_max_age<T>(message: &T, violations: &mut Vec<Violation>)
+    where T: HeaderRef
+{
+    match header::max_age(message) {
+        Some(max_age) if max_age.as_secs() < MIN_RECOMMENDED_MAX_AGE => {
+            violations.push(Violation::warning(ViolationKind::InvalidHeader,
+                                               names::CACHE_CONTROL,
+                                               "max-age Below 1800 Seconds"));
+        }
+        Some(_) => (),
+        None if message.get_raw(names::CACHE_CONTROL).is_none() => {
+            violations.push(Violation::error(ViolationKind::MissingHeader, names::CACHE_CONTROL, "Missing"));
+        }
+        None => {
+            violations.push(Violation::error(ViolationKind::InvalidHeader,
+                                             names::CACHE_CONTROL,
+                                             "Missing max-age Directive"));
+        }
+    }
+}
+
+fn check_location<T>(message: &T, violations: &mut Vec<Violation>)
+    where T: HeaderRef
+{
+    let location = match required::<Location, _>(message, violations) {
+        Some(n) => n.0.trim().to_ascii_lowercase(),
+        None => return,
+    };
+
+    if !location.starts_with("http://") && !location.starts_with("https://") {
+        violations.push(Violation::error(ViolationKind::InvalidHeader,
+                                         names::LOCATION,
+                                         "Not An Absolute HTTP URL"));
+    }
+}
+
+/// Validate the SERVER or USER-AGENT header, which has to carry a `UPnP` version token.
+fn check_product_tokens<T>(message: &T,
+                           name: &'static str,
+                           is_required: bool,
+                           version: UdaVersion,
+                           violations: &mut Vec<Violation>)
+    where T: HeaderRef
+{
+    let value = match message.get_str(name) {
+        Some(n) => n,
+        None if is_required => {
+            violations.push(Violation::error(ViolationKind::MissingHeader, name, "Missing"));
+            return;
+        }
+        None => return,
+    };
+
+    match value.parse::<ProductTokens>() {
+        Ok(ref tokens) if tokens.upnp() != version.upnp_token() => {
+            let (major, minor) = tokens.upnp();
+
+            violations.push(Violation::warning(ViolationKind::InvalidHeader,
+                                               name,
+                                               format!("UPnP/{}.{} Token Does Not Match The Version",
+                                                       major,
+                                                       minor)));
+        }
+        Ok(_) => (),
+        Err(_) => {
+            violations.push(Violation::error(ViolationKind::InvalidHeader,
+                                             name,
+                                             "Expected OS/version UPnP/version product/version"));
+        }
+    }
+}
+
+/// Validate the USN header, and that it names the target of the NT or ST header.
+fn check_usn<T>(message: &T, opt_target: Option<&FieldMap>, violations: &mut Vec<Violation>)
+    where T: HeaderRef
+{
+    let usn = match required::<USN, _>(message, violations) {
+        Some(n) => n,
+        None => return,
+    };
+
+    if usn.device_uuid().is_none() {
+        violations.push(Violation::error(ViolationKind::InvalidHeader, names::USN, "Expected A UUID Field"));
+        return;
+    }
+
+    let matches = match opt_target {
+        Some(&FieldMap::UUID(_)) => usn.1.is_none() && Some(&usn.0) == opt_target,
+        Some(target) => usn.1.as_ref() == Some(target),
+        None => true,
+    };
+
+    if !matches {
+        violations.push(Violation::error(ViolationKind::Inconsistent,
+                                         names::USN,
+                                         "Does Not Name The Notification Or Search Target"));
+    }
+}
+
+/// Validate the BOOTID.UPNP.ORG and CONFIGID.UPNP.ORG headers, which are required
+/// in `UPnP` 1.1 and later, returning the boot instance.
+fn check_boot_ids<T>(message: &T, version: UdaVersion, violations: &mut Vec<Violation>) -> Option<BootID>
+    where T: HeaderRef
+{
+    let (opt_boot_id, opt_config_id) = if version.requires_boot_ids() {
+        (required::<BootID, _>(message, violations), required::<ConfigID, _>(message, violations))
+    } else {
+        (optional::<BootID, _>(message, violations), optional::<ConfigID, _>(message, violations))
+    };
+
+    if opt_config_id.map_or(false, |config_id| config_id.is_reserved()) {
+        violations.push(Violation::error(ViolationKind::InvalidHeader,
+                                         names::CONFIGID,
+                                         "Value Is Reserved (Above 16777215)"));
+    }
+
+    opt_boot_id
+}
+
+fn all_equal<I, T>(mut values: I) -> bool
+    where I: Iterator<Item = T>,
+          T: PartialEq
+{
+    match values.next() {
+        Some(first) => values.all(|value| value == first),
+        None => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use header::{names, HeaderMut};
+    use message::{NotifyMessage, SearchRequest, SearchResponse};
+    use receiver::FromRawSSDP;
+    use super::{check_announce_set, check_notify, check_search_request, check_search_response, Severity,
+                UdaVersion, Violation, ViolationKind};
+
+    fn alive(nt: &str, usn: &str, boot_id: u32) -> NotifyMessage {
+        let raw = format!("NOTIFY * HTTP/1.1\r\n\
+                           HOST: 239.255.255.250:1900\r\n\
+                           CACHE-CONTROL: max-age=1800\r\n\
+                           LOCATION: http://192.168.1.2/desc.xml\r\n\
+                           NT: {}\r\n\
+                           NTS: ssdp:alive\r\n\
+                           SERVER: Linux/5.0 UPnP/1.1 product/1.0\r\n\
+                           USN: {}\r\n\
+                           BOOTID.UPNP.ORG: {}\r\n\
+                           CONFIGID.UPNP.ORG: 1\r\n\r\n",
+                          nt,
+                          usn,
+                          boot_id);
+
+        NotifyMessage::raw_ssdp(raw.as_bytes()).unwrap()
+    }
+
+    fn has(violations: &[Violation], kind: ViolationKind, header: &str) -> bool {
+        violations.iter().any(|violation| violation.kind() == kind && violation.header() == Some(header))
+    }
+
+    #[test]
+    fn positive_valid_alive() {
+        let message = alive("upnp:rootdevice", "uuid:device::upnp:rootdevice", 1);
+
+        assert_eq!(check_notify(&message, UdaVersion::Uda11), vec![]);
+    }
+
+    #[test]
+    fn positive_boot_ids_only_required_since_uda11() {
+        let mut message = alive("uuid:device", "uuid:device", 1);
+        message.remove_raw(names::BOOTID);
+        message.remove_raw(names::CONFIGID);
+
+        assert!(has(&check_notify(&message, UdaVersion::Uda11), ViolationKind::MissingHeader, names::BOOTID));
+        assert!(check_notify(&message, UdaVersion::Uda10)
+            .iter()
+            .all(|violation| violation.severity() == Severity::Warning));
+    }
+
+    #[test]
+    fn negative_usn_does_not_match_nt() {
+        let message = alive("upnp:rootdevice", "uuid:device", 1);
+
+        assert!(has(&check_notify(&message, UdaVersion::Uda11), ViolationKind::Inconsistent, names::USN));
+    }
+
+    #[test]
+    fn negative_update_in_uda10() {
+        let message = NotifyMessage::raw_ssdp(b"NOTIFY * HTTP/1.1\r\n\
+                                                HOST: 239.255.255.250:1900\r\n\
+                                                LOCATION: http://192.168.1.2/desc.xml\r\n\
+                                                NT: uuid:device\r\n\
+                                                NTS: ssdp:update\r\n\
+                                                USN: uuid:device\r\n\
+                                                BOOTID.UPNP.ORG: 1\r\n\
+                                                NEXTBOOTID.UPNP.ORG: 1\r\n\r\n")
+            .unwrap();
+
+        let violations = check_notify(&message, UdaVersion::Uda10);
+        assert!(has(&violations, ViolationKind::InvalidMessage, names::NTS));
+        assert!(has(&violations, ViolationKind::Inconsistent, names::NEXTBOOTID));
+    }
+
+    #[test]
+    fn negative_search_request() {
+        let request = SearchRequest::raw_ssdp(b"M-SEARCH * HTTP/1.1\r\n\
+                                                HOST: 239.255.255.250:1900\r\n\
+                                                MAN: ssdp:discover\r\n\
+                                                MX: 10\r\n\
+                                                ST: ssdp:all\r\n\r\n")
+            .unwrap();
+
+        let violations = check_search_request(&request, UdaVersion::Uda20);
+        assert!(has(&violations, ViolationKind::InvalidHeader, names::MAN));
+        assert!(has(&violations, ViolationKind::InvalidHeader, names::MX));
+        assert!(has(&violations, ViolationKind::MissingHeader, names::CPFN));
+        assert_eq!(check_search_request(&request, UdaVersion::Uda10).len(), 1);
+    }
+
+    #[test]
+    fn negative_search_response() {
+        let response = SearchResponse::raw_ssdp(b"HTTP/1.1 200 OK\r\n\
+                                                  CACHE-CONTROL: max-age=60\r\n\
+                                                  LOCATION: /desc.xml\r\n\
+                                                  SERVER: product/1.0\r\n\
+                                                  ST: upnp:rootdevice\r\n\
+                                                  USN: uuid:device\r\n\r\n")
+            .unwrap();
+
+        let violations = check_search_response(&response, UdaVersion::Uda10);
+        for &(kind, header) in &[(ViolationKind::InvalidHeader, names::CACHE_CONTROL),
+                                 (ViolationKind::MissingHeader, names::EXT),
+                                 (ViolationKind::InvalidHeader, names::LOCATION),
+                                 (ViolationKind::InvalidHeader, names::SERVER),
+                                 (ViolationKind::Inconsistent, names::USN),
+                                 (ViolationKind::MissingHeader, names::DATE)] {
+            assert!(has(&violations, kind, header), "Expected {:?} {}", kind, header);
+        }
+    }
+
+    #[test]
+    fn positive_complete_announce_set() {
+        let messages = vec![alive("upnp:rootdevice", "uuid:device::upnp:rootdevice", 1),
+                            alive("uuid:device", "uuid:device", 1),
+                            alive("urn:schemas-upnp-org:device:MediaServer:1",
+                                  "uuid:device::urn:schemas-upnp-org:device:MediaServer:1",
+                                  1)];
+
+        assert_eq!(check_announce_set(&messages, UdaVersion::Uda11), vec![]);
+    }
+
+    #[test]
+    fn negative_incomplete_announce_set() {
+        let messages = vec![alive("upnp:rootdevice", "uuid:device::upnp:rootdevice", 1),
+                            alive("urn:schemas-upnp-org:service:ContentDirectory:1",
+                                  "uuid:device::urn:schemas-upnp-org:service:ContentDirectory:1",
+                                  2)];
+
+        let violations = check_announce_set(&messages, UdaVersion::Uda11);
+        assert_eq!(violations.iter().filter(|v| v.kind() == ViolationKind::MissingAnnouncement).count(),
+                   2);
+        assert!(has(&violations, ViolationKind::Inconsistent, names::BOOTID));
+        assert!(!has(&check_announce_set(&messages, UdaVersion::Uda10),
+                     ViolationKind::Inconsistent,
+                     names::BOOTID));
+    }
+}
@@ -0,0 +1,358 @@
+//! Higher level view of the devices and services found on the network.
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+use SSDPError;
+use header::{self, HeaderRef, Location, SearchPort, Server, NT, ST, USN};
+use message::{self, SearchResponse, NotifyMessage};
+use receiver::ReceiveInfo;
+use target::SearchTarget;
+
+/// Device or service found on the network, either in response to a search or
+/// through a notify message that it sent.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct DiscoveredDevice {
+    uuid: String,
+    target: Option<SearchTarget>,
+    location: String,
+    server: Option<String>,
+    max_age: Option<Duration>,
+    search_port: Option<u16>,
+    source_addr: SocketAddr,
+    interface: Option<IpAddr>,
+}
+
+impl DiscoveredDevice {
+    /// Unique identifier of the device, taken from the USN header.
+    pub fn uuid(&self) -> &str {
+        &self.uuid
+    }
+
+    /// Device or service type that was advertised, if it is a standard target.
+    pub fn target(&self) -> Option<&SearchTarget> {
+        self.target.as_ref()
+    }
+
+    /// URL for the description of the device or service.
+    pub fn location(&self) -> &str {
+        &self.location
+    }
+
+    /// Operating system, `UPnP` version and product of the device.
+    pub fn server(&self) -> Option<&str> {
+        self.server.as_ref().map(|server| &server[..])
+    }
+
+    /// Duration that the advertisement is valid for.
+    pub fn max_age(&self) -> Option<Duration> {
+        self.max_age
+    }
+
+    /// Port that the device accepts unicast search requests on, if it advertised one.
+    pub fn search_port(&self) -> Option<u16> {
+        self.search_port
+    }
+
+    /// Address that unicast search requests for the device should be sent to.
+    ///
+    /// Uses the advertised `SEARCHPORT.UPNP.ORG` port, or the standard port of 1900
+    /// if the device did not advertise one.
+    pub fn search_addr(&self) -> SocketAddr {
+        let port = self.search_port.unwrap_or(message::UPNP_MULTICAST_PORT);
+
+        SocketAddr::new(self.source_addr.ip(), port)
+    }
+
+    /// Address that the message was sent from.
+    pub fn source_addr(&self) -> SocketAddr {
+        self.source_addr
+    }
+
+    /// Address of the local interface that the message was received on, if known.
+    ///
+    /// Taken from the receive metadata of the message when it is available.
+    pub fn interface(&self) -> Option<IpAddr> {
+        self.interface
+    }
+
+    /// Set the address of the local interface that the message was received on.
+    pub fn set_interface(&mut self, opt_interface: Option<IpAddr>) {
+        self.interface = opt_interface;
+    }
+
+    fn from_message<T>(message: &T,
+                       opt_target: Option<ST>,
+                       opt_info: Option<&ReceiveInfo>,
+                       source_addr: SocketAddr)
+                       -> Result<DiscoveredDevice, SSDPError>
+        where T: HeaderRef
+    {
+        let uuid = match message.get::<USN>() {
+            Some(usn) => {
+                match usn.device_uuid() {
+                    Some(n) => n.to_owned(),
+                    None => return Err(SSDPError::InvalidHeader("USN", "Missing UUID Field")),
+                }
+            }
+            None => return Err(SSDPError::MissingHeader("USN")),
+        };
+
+        let location = match message.get::<Location>() {
+            Some(n) => n.0.clone(),
+            None => return Err(SSDPError::MissingHeader("LOCATION")),
+        };
+
+        let max_age = header::max_age(&message);
+
+        Ok(DiscoveredDevice {
+            uuid: uuid,
+            target: opt_target.as_ref().and_then(SearchTarget::from_st),
+            location: location,
+            server: message.get::<Server>().map(|server| server.0.clone()),
+            max_age: max_age,
+            search_port: message.get::<SearchPort>().map(|search_port| search_port.0),
+            source_addr: source_addr,
+            interface: opt_info.map(|info| info.local_addr().ip()).filter(|ip| !ip.is_unspecified()),
+        })
+    }
+}
+
+impl TryFrom<(SearchResponse, SocketAddr)> for DiscoveredDevice {
+    type Error = SSDPError;
+
+    fn try_from((response, source_addr): (SearchResponse, SocketAddr)) -> Result<Self, SSDPError> {
+        let opt_target = response.get::<ST>();
+
+        DiscoveredDevice::from_message(&response, opt_target, response.receive_info(), source_addr)
+    }
+}
+
+/// Notify messages announcing that a device is leaving the network do not carry
+/// a LOCATION header and will fail to convert.
+impl TryFrom<(NotifyMessage, SocketAddr)> for DiscoveredDevice {
+    type Error = SSDPError;
+
+    fn try_from((notify, source_addr): (NotifyMessage, SocketAddr)) -> Result<Self, SSDPError> {
+        let opt_target = notify.get::<NT>().map(|nt| ST::Target(nt.0.clone()));
+
+        DiscoveredDevice::from_message(&notify, opt_target, notify.receive_info(), source_addr)
+    }
+}
+
+/// All of the targets advertised by a single physical device, identified by the
+/// UUID in the USN header.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct DeviceGroup {
+    uuid: String,
+    location: String,
+    source_addr: SocketAddr,
+    targets: Vec<SearchTarget>,
+}
+
+impl DeviceGroup {
+    /// Unique identifier of the device.
+    pub fn uuid(&self) -> &str {
+        &self.uuid
+    }
+
+    /// URL for the description of the device, from the first advertisement seen.
+    pub fn location(&self) -> &str {
+        &self.location
+    }
+
+    /// Address that the first advertisement seen was sent from.
+    pub fn source_addr(&self) -> SocketAddr {
+        self.source_addr
+    }
+
+    /// Standard targets advertised by the device, in the order they were first seen.
+    pub fn targets(&self) -> &[SearchTarget] {
+        &self.targets
+    }
+
+    /// Service types advertised by the device.
+    pub fn services(&self) -> Vec<&SearchTarget> {
+        self.targets
+            .iter()
+            .filter(|target| match **target {
+                SearchTarget::ServiceUrn { .. } => true,
+                _ => false,
+            })
+            .collect()
+    }
+
+    /// Whether or not the device advertised itself as a root device.
+    pub fn is_root_device(&self) -> bool {
+        self.targets.contains(&SearchTarget::RootDevice)
+    }
+}
+
+/// Group discovered devices by UUID, producing one entry per physical device in
+/// the order that the devices were first seen.
+pub fn group_by_device<I>(devices: I) -> Vec<DeviceGroup>
+    where I: IntoIterator<Item = DiscoveredDevice>
+{
+    let mut groups: Vec<DeviceGroup> = Vec::new();
+    let mut indices = HashMap::new();
+
+    for device in devices {
+        let index = *indices.entry(device.uuid.clone()).or_insert_with(|| {
+            groups.push(DeviceGroup {
+                uuid: device.uuid.clone(),
+                location: device.location.clone(),
+                source_addr: device.source_addr,
+                targets: Vec::new(),
+            });
+
+            groups.len() - 1
+        });
+
+        if let Some(target) = device.target {
+            if !groups[index].targets.contains(&target) {
+                groups[index].targets.push(target);
+            }
+        }
+    }
+
+    groups
+}
+
+/// Group search responses by device UUID, see `group_by_device`.
+///
+/// Responses that can not be converted into a `DiscoveredDevice` are skipped.
+pub fn group_responses<I>(responses: I) -> Vec<DeviceGroup>
+    where I: IntoIterator<Item = (SearchResponse, SocketAddr)>
+{
+    group_by_device(responses.into_iter().filter_map(|response| DiscoveredDevice::try_from(response).ok()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+    use std::net::SocketAddr;
+    use std::time::Duration;
+
+    use message::{SearchResponse, NotifyMessage};
+    use receiver::FromRawSSDP;
+    use target::SearchTarget;
+    use super::{DiscoveredDevice, group_responses};
+
+    fn source_addr() -> SocketAddr {
+        "192.168.0.2:1900".parse().unwrap()
+    }
+
+    #[test]
+    fn positive_from_search_response() {
+        let response = SearchResponse::raw_ssdp(b"HTTP/1.1 200 OK\r\n\
+                                                  CACHE-CONTROL: max-age=1800\r\n\
+                                                  LOCATION: http://192.168.0.2:80/desc.xml\r\n\
+                                                  SERVER: Linux/3.0 UPnP/1.0 Test/1.0\r\n\
+                                                  ST: upnp:rootdevice\r\n\
+                                                  USN: uuid:device::upnp:rootdevice\r\n\r\n")
+            .unwrap();
+
+        let device = DiscoveredDevice::try_from((response, source_addr())).unwrap();
+
+        assert_eq!(device.uuid(), "device");
+        assert_eq!(device.target(), Some(&SearchTarget::RootDevice));
+        assert_eq!(device.location(), "http://192.168.0.2:80/desc.xml");
+        assert_eq!(device.server(), Some("Linux/3.0 UPnP/1.0 Test/1.0"));
+        assert_eq!(device.max_age(), Some(Duration::from_secs(1800)));
+        assert_eq!(device.source_addr(), source_addr());
+        assert_eq!(device.interface(), None);
+    }
+
+    #[test]
+    fn positive_from_notify_message() {
+        let notify = NotifyMessage::raw_ssdp(b"NOTIFY * HTTP/1.1\r\n\
+                                               HOST: 239.255.255.250:1900\r\n\
+                                               LOCATION: http://192.168.0.2:80/desc.xml\r\n\
+                                               NT: urn:schemas-upnp-org:device:MediaServer:1\r\n\
+                                               NTS: ssdp:alive\r\n\
+                                               USN: uuid:device\r\n\r\n")
+            .unwrap();
+
+        let device = DiscoveredDevice::try_from((notify, source_addr())).unwrap();
+
+        assert_eq!(device.uuid(), "device");
+        assert_eq!(device.target(), Some(&SearchTarget::device("MediaServer", 1)));
+        assert_eq!(device.search_addr(), source_addr());
+    }
+
+    #[test]
+    fn positive_advertised_search_port() {
+        let notify = NotifyMessage::raw_ssdp(b"NOTIFY * HTTP/1.1\r\n\
+                                               HOST: 239.255.255.250:1900\r\n\
+                                               LOCATION: http://192.168.0.2:80/desc.xml\r\n\
+                                               NT: upnp:rootdevice\r\n\
+                                               NTS: ssdp:alive\r\n\
+                                               SEARCHPORT.UPNP.ORG: 50000\r\n\
+                                               USN: uuid:device::upnp:rootdevice\r\n\r\n")
+            .unwrap();
+
+        let device = DiscoveredDevice::try_from((notify, source_addr())).unwrap();
+
+        assert_eq!(device.search_port(), Some(50000));
+        assert_eq!(device.search_addr(), "192.168.0.2:50000".parse().unwrap());
+    }
+
+    fn response(st: &str, usn: &str) -> (SearchResponse, SocketAddr) {
+        let raw = format!("HTTP/1.1 200 OK\r\n\
+                           LOCATION: http://192.168.0.2:80/desc.xml\r\n\
+                           ST: {}\r\n\
+                           USN: {}\r\n\r\n",
+                          st,
+                          usn);
+
+        (SearchResponse::raw_ssdp(raw.as_bytes()).unwrap(), source_addr())
+    }
+
+    #[test]
+    fn positive_group_responses() {
+        let responses = vec![response("upnp:rootdevice", "uuid:first::upnp:rootdevice"),
+                             response("urn:schemas-upnp-org:service:ContentDirectory:1",
+                                      "uuid:first::urn:schemas-upnp-org:service:ContentDirectory:1"),
+                             response("uuid:second", "uuid:second"),
+                             response("upnp:rootdevice", "uuid:first::upnp:rootdevice")];
+
+        let groups = group_responses(responses);
+
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].uuid(), "first");
+        assert_eq!(groups[0].targets(),
+                   &[SearchTarget::RootDevice, SearchTarget::service("ContentDirectory", 1)][..]);
+        assert_eq!(groups[0].services(), vec![&SearchTarget::service("ContentDirectory", 1)]);
+        assert!(groups[0].is_root_device());
+        assert_eq!(groups[1].uuid(), "second");
+        assert!(!groups[1].is_root_device());
+    }
+
+    #[test]
+    fn negative_group_responses_skips_invalid() {
+        let responses = vec![response("upnp:rootdevice", "upnp:rootdevice")];
+
+        assert!(group_responses(responses).is_empty());
+    }
+
+    #[test]
+    fn negative_missing_location() {
+        let response = SearchResponse::raw_ssdp(b"HTTP/1.1 200 OK\r\n\
+                                                  USN: uuid:device\r\n\r\n")
+            .unwrap();
+
+        assert!(DiscoveredDevice::try_from((response, source_addr())).is_err());
+    }
+
+    #[test]
+    fn negative_missing_uuid() {
+        let response = SearchResponse::raw_ssdp(b"HTTP/1.1 200 OK\r\n\
+                                                  LOCATION: http://192.168.0.2:80/desc.xml\r\n\
+                                                  USN: upnp:rootdevice\r\n\r\n")
+            .unwrap();
+
+        assert!(DiscoveredDevice::try_from((response, source_addr())).is_err());
+    }
+}
@@ -1,6 +1,7 @@
 use std::convert::From;
 use std::error::Error;
 use std::fmt::{self, Display, Formatter};
+use std::io;
 
 /// Result that can return a T or an `SSDPError`.
 pub type SSDPResult<T> = Result<T, SSDPError>;
@@ -67,6 +68,21 @@ impl<T> From<T> for SSDPError
     }
 }
 
+impl From<SSDPError> for io::Error {
+    /// Unwrap io errors, any other error is reported as invalid data.
+    fn from(err: SSDPError) -> io::Error {
+        match err {
+            SSDPError::Other(err) => {
+                match err.downcast::<io::Error>() {
+                    Ok(err) => *err,
+                    Err(err) => io::Error::new(io::ErrorKind::Other, err.to_string()),
+                }
+            }
+            err => io::Error::new(io::ErrorKind::InvalidData, err.to_string()),
+        }
+    }
+}
+
 /// Basic type implementing the Error trait.
 #[derive(Debug)]
 pub struct MsgError {
@@ -94,8 +110,9 @@ impl Display for MsgError {
 #[cfg(test)]
 mod tests {
     use std::error::Error;
+    use std::io;
 
-    use super::MsgError;
+    use super::{MsgError, SSDPError};
 
     #[test]
     fn positive_msg_error_display() {
@@ -110,4 +127,12 @@ mod tests {
 
         assert_eq!(error.description(), "Something Happened");
     }
+
+    #[test]
+    fn positive_io_error_from_ssdp_error() {
+        let io_err = io::Error::new(io::ErrorKind::AddrInUse, "In Use");
+
+        assert_eq!(io::Error::from(SSDPError::from(io_err)).kind(), io::ErrorKind::AddrInUse);
+        assert_eq!(io::Error::from(SSDPError::InvalidHttpVersion).kind(), io::ErrorKind::InvalidData);
+    }
 }
@@ -7,6 +7,9 @@ use std::fmt::{Display, Error, Formatter};
 use std::result::Result;
 use std::borrow::Cow;
 
+#[cfg(feature = "uuid")]
+use uuid::Uuid;
+
 /// Separator character for a `FieldMap` and it's value.
 pub const PAIR_SEPARATOR: char = ':';
 
@@ -42,7 +45,8 @@ impl FieldMap {
     /// Breaks a field up into a single key and single value which are
     /// separated by a colon and neither of which are empty.
     ///
-    /// Separation will occur at the first colon encountered.
+    /// Separation will occur at the first colon encountered. The known keys are
+    /// matched regardless of case, so "UUID:" and "uuid:" are the same field.
     pub fn parse_bytes(field: &[u8]) -> Option<Self> {
         let split_index = match field.iter().position(|&b| b == PAIR_SEPARATOR as u8) {
             Some(n) => n,
@@ -87,6 +91,30 @@ impl FieldMap {
     pub fn unknown<'a, S: Into<Cow<'a, str>>, S2: Into<Cow<'a, str>>>(key: S, value: S2) -> Self {
         FieldMap::Unknown(key.into().into_owned(), value.into().into_owned())
     }
+
+    /// Construct a "uuid" field holding the UUID in its lower case, hyphenated form.
+    #[cfg(feature = "uuid")]
+    pub fn from_uuid(uuid: Uuid) -> Self {
+        FieldMap::UUID(uuid.hyphenated().to_string())
+    }
+
+    /// Parse the value of a "uuid" field as a `Uuid`.
+    ///
+    /// Returns None if this is not a "uuid" field or its value is not a valid UUID.
+    #[cfg(feature = "uuid")]
+    pub fn to_uuid(&self) -> Option<Uuid> {
+        match *self {
+            FieldMap::UUID(ref n) => Uuid::parse_str(n).ok(),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "uuid")]
+impl From<Uuid> for FieldMap {
+    fn from(uuid: Uuid) -> FieldMap {
+        FieldMap::from_uuid(uuid)
+    }
 }
 
 impl Display for FieldMap {
@@ -117,17 +145,17 @@ impl Display for FieldMap {
 
 /// Returns the header field value if the key matches the uuid key, else returns None.
 fn matches_uuid_key(key: &str) -> bool {
-    UUID_PREFIX == key
+    UUID_PREFIX.eq_ignore_ascii_case(key)
 }
 
 /// Returns the header field value if the key matches the urn key, else returns None.
 fn matches_urn_key(key: &str) -> bool {
-    URN_PREFIX == key
+    URN_PREFIX.eq_ignore_ascii_case(key)
 }
 
 /// Returns the header field value if the key matches the upnp key, else returns None.
 fn matches_upnp_key(key: &str) -> bool {
-    UPNP_PREFIX == key
+    UPNP_PREFIX.eq_ignore_ascii_case(key)
 }
 
 #[cfg(test)]
@@ -166,12 +194,36 @@ mod tests {
         assert_eq!(urn_pair, FieldMap::urn("some_value"));
     }
 
+    #[test]
+    fn positive_key_case_insensitive() {
+        assert_eq!(FieldMap::new("UUID:some_value").unwrap(), FieldMap::uuid("some_value"));
+        assert_eq!(FieldMap::new("Urn:some_value").unwrap(), FieldMap::urn("some_value"));
+        assert_eq!(FieldMap::new("UPnP:some_value").unwrap(), FieldMap::upnp("some_value"));
+    }
+
     #[test]
     fn positive_unknown() {
         let unknown_pair = FieldMap::new("some_key:some_value").unwrap();
         assert_eq!(unknown_pair, FieldMap::unknown("some_key", "some_value"));
     }
 
+    #[test]
+    #[cfg(feature = "uuid")]
+    fn positive_uuid_round_trip() {
+        let uuid = "2FAC1234-31F8-11B4-A222-08002B34C003".parse::<::uuid::Uuid>().unwrap();
+        let field = FieldMap::from_uuid(uuid);
+
+        assert_eq!(field, FieldMap::uuid("2fac1234-31f8-11b4-a222-08002b34c003"));
+        assert_eq!(field.to_uuid(), Some(uuid));
+    }
+
+    #[test]
+    #[cfg(feature = "uuid")]
+    fn negative_malformed_uuid() {
+        assert_eq!(FieldMap::uuid("device-UUID").to_uuid(), None);
+        assert_eq!(FieldMap::urn("2fac1234-31f8-11b4-a222-08002b34c003").to_uuid(), None);
+    }
+
     #[test]
     #[should_panic]
     fn negative_no_colon() {
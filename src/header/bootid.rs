@@ -1,22 +1,37 @@
 use std::fmt::{Formatter, Result};
 
-use hyper::error::{self, Error};
-use hyper::header::{HeaderFormat, Header};
+use {SSDPError, SSDPResult};
+use header::{HeaderFormat, Header, names};
 
-const BOOTID_HEADER_NAME: &'static str = "BOOTID.UPNP.ORG";
+const BOOTID_HEADER_NAME: &'static str = names::BOOTID;
+
+/// Largest boot instance allowed by the `UPnP` 1.1 standard, a 31 bit integer.
+pub const BOOTID_MAX: u32 = 0x7FFF_FFFF;
 
 /// Represents a header used to denote the boot instance of a root device.
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub struct BootID(pub u32);
 
+impl BootID {
+    /// Construct a BOOTID.UPNP.ORG header, validating that the boot instance
+    /// fits in 31 bits.
+    pub fn new(boot_id: u32) -> SSDPResult<BootID> {
+        if boot_id > BOOTID_MAX {
+            Err(SSDPError::InvalidHeader(BOOTID_HEADER_NAME, "Out Of Range"))
+        } else {
+            Ok(BootID(boot_id))
+        }
+    }
+}
+
 impl Header for BootID {
     fn header_name() -> &'static str {
         BOOTID_HEADER_NAME
     }
 
-    fn parse_header(raw: &[Vec<u8>]) -> error::Result<Self> {
+    fn parse_header(raw: &[Vec<u8>]) -> SSDPResult<Self> {
         if raw.len() != 1 {
-            return Err(Error::Header);
+            return Err(SSDPError::InvalidHeader(BOOTID_HEADER_NAME, "Expected A Single Value"));
         }
 
         let cow_str = String::from_utf8_lossy(&raw[0][..]);
@@ -24,12 +39,12 @@ impl Header for BootID {
         // Value needs to be a 31 bit non-negative integer, so convert to i32
         let value = match i32::from_str_radix(&*cow_str, 10) {
             Ok(n) => n,
-            Err(_) => return Err(Error::Header),
+            Err(_) => return Err(SSDPError::InvalidHeader(BOOTID_HEADER_NAME, "Not A Number")),
         };
 
         // Check if value is negative, then convert to u32
         if value.is_negative() {
-            Err(Error::Header)
+            Err(SSDPError::InvalidHeader(BOOTID_HEADER_NAME, "Out Of Range"))
         } else {
             Ok(BootID(value as u32))
         }
@@ -46,9 +61,9 @@ impl HeaderFormat for BootID {
 
 #[cfg(test)]
 mod tests {
-    use hyper::header::Header;
+    use header::Header;
 
-    use super::BootID;
+    use super::{BootID, BOOTID_MAX};
 
     #[test]
     fn positive_bootid() {
@@ -85,6 +100,16 @@ mod tests {
         BootID::parse_header(bootid_header_value).unwrap();
     }
 
+    #[test]
+    fn positive_new_upper_bound() {
+        assert_eq!(BootID::new(BOOTID_MAX).unwrap(), BootID(2147483647));
+    }
+
+    #[test]
+    fn negative_new_overflow() {
+        assert!(BootID::new(BOOTID_MAX + 1).is_err());
+    }
+
     #[test]
     #[should_panic]
     fn negative_overflow() {
@@ -0,0 +1,128 @@
+use std::fmt::{Formatter, Result};
+
+use {SSDPError, SSDPResult};
+use header::{HeaderFormat, Header, names};
+
+const CACHE_CONTROL_HEADER_NAME: &'static str = names::CACHE_CONTROL;
+
+/// Represents a header used to specify how long an advertisement is valid for.
+///
+/// See `header::max_age` for reading the max-age directive from devices that
+/// format the header loosely.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct CacheControl(pub Vec<CacheDirective>);
+
+/// Enumerates the directives of a CACHE-CONTROL header.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum CacheDirective {
+    /// The "max-age" directive with the number of seconds.
+    MaxAge(u32),
+    /// The "no-cache" directive.
+    NoCache,
+    /// The "no-store" directive.
+    NoStore,
+    /// Any other directive, with its value if it has one.
+    Extension(String, Option<String>),
+}
+
+impl CacheDirective {
+    fn parse(directive: &str) -> Option<CacheDirective> {
+        let mut parts = directive.splitn(2, '=');
+        let name = parts.next().unwrap_or("").trim();
+        let opt_value = parts.next().map(|value| value.trim().trim_matches('"'));
+
+        match (name.to_lowercase().as_str(), opt_value) {
+            ("", _) => None,
+            ("max-age", Some(value)) => value.parse().ok().map(CacheDirective::MaxAge),
+            ("no-cache", None) => Some(CacheDirective::NoCache),
+            ("no-store", None) => Some(CacheDirective::NoStore),
+            (_, opt_value) => Some(CacheDirective::Extension(name.to_owned(), opt_value.map(str::to_owned))),
+        }
+    }
+
+    fn fmt(&self, fmt: &mut Formatter) -> Result {
+        match *self {
+            CacheDirective::MaxAge(n) => fmt.write_fmt(format_args!("max-age={}", n)),
+            CacheDirective::NoCache => fmt.write_str("no-cache"),
+            CacheDirective::NoStore => fmt.write_str("no-store"),
+            CacheDirective::Extension(ref name, None) => fmt.write_str(name),
+            CacheDirective::Extension(ref name, Some(ref value)) => {
+                fmt.write_fmt(format_args!("{}={}", name, value))
+            }
+        }
+    }
+}
+
+impl Header for CacheControl {
+    fn header_name() -> &'static str {
+        CACHE_CONTROL_HEADER_NAME
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> SSDPResult<Self> {
+        let mut directives = Vec::new();
+
+        for line in raw {
+            for directive in String::from_utf8_lossy(line).split(',').filter(|n| !n.trim().is_empty()) {
+                match CacheDirective::parse(directive) {
+                    Some(n) => directives.push(n),
+                    None => {
+                        return Err(SSDPError::InvalidHeader(CACHE_CONTROL_HEADER_NAME, "Invalid Directive"));
+                    }
+                }
+            }
+        }
+
+        if directives.is_empty() {
+            Err(SSDPError::InvalidHeader(CACHE_CONTROL_HEADER_NAME, "No Directives"))
+        } else {
+            Ok(CacheControl(directives))
+        }
+    }
+}
+
+impl HeaderFormat for CacheControl {
+    fn fmt_header(&self, fmt: &mut Formatter) -> Result {
+        for (index, directive) in self.0.iter().enumerate() {
+            if index != 0 {
+                try!(fmt.write_str(", "));
+            }
+            try!(directive.fmt(fmt));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use header::Header;
+
+    use super::{CacheControl, CacheDirective};
+
+    #[test]
+    fn positive_max_age() {
+        let cache_control_header_value = &[b"max-age=1800"[..].to_vec()];
+
+        assert_eq!(CacheControl::parse_header(cache_control_header_value).unwrap(),
+                   CacheControl(vec![CacheDirective::MaxAge(1800)]));
+    }
+
+    #[test]
+    fn positive_multiple_directives() {
+        let cache_control_header_value = &[b"no-cache, max-age = \"900\""[..].to_vec(),
+                                           b"x-test=1"[..].to_vec()];
+
+        assert_eq!(CacheControl::parse_header(cache_control_header_value).unwrap(),
+                   CacheControl(vec![CacheDirective::NoCache,
+                                     CacheDirective::MaxAge(900),
+                                     CacheDirective::Extension("x-test".to_owned(), Some("1".to_owned()))]));
+    }
+
+    #[test]
+    #[should_panic]
+    fn negative_invalid_max_age() {
+        let cache_control_header_value = &[b"max-age=forever"[..].to_vec()];
+
+        CacheControl::parse_header(cache_control_header_value).unwrap();
+    }
+}
@@ -1,22 +1,44 @@
 use std::fmt::{Formatter, Result};
 
-use hyper::error::{self, Error};
-use hyper::header::{HeaderFormat, Header};
+use {SSDPError, SSDPResult};
+use header::{HeaderFormat, Header, names};
 
-const CONFIGID_HEADER_NAME: &'static str = "CONFIGID.UPNP.ORG";
+const CONFIGID_HEADER_NAME: &'static str = names::CONFIGID;
+
+/// Largest configuration number that devices may use in the `UPnP` 1.1 standard,
+/// values above are reserved.
+pub const CONFIGID_MAX: u32 = 16_777_215;
 
 /// Represents a header used to denote the configuration of a device's DDD.
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub struct ConfigID(pub u32);
 
+impl ConfigID {
+    /// Construct a CONFIGID.UPNP.ORG header, validating that the configuration
+    /// number is not one of the reserved values.
+    pub fn new(config_id: u32) -> SSDPResult<ConfigID> {
+        if config_id > CONFIGID_MAX {
+            Err(SSDPError::InvalidHeader(CONFIGID_HEADER_NAME, "Value Is Reserved (Above 16777215)"))
+        } else {
+            Ok(ConfigID(config_id))
+        }
+    }
+
+    /// Whether or not the configuration number is one of the values reserved
+    /// for future use, which are still accepted when parsing.
+    pub fn is_reserved(&self) -> bool {
+        self.0 > CONFIGID_MAX
+    }
+}
+
 impl Header for ConfigID {
     fn header_name() -> &'static str {
         CONFIGID_HEADER_NAME
     }
 
-    fn parse_header(raw: &[Vec<u8>]) -> error::Result<Self> {
+    fn parse_header(raw: &[Vec<u8>]) -> SSDPResult<Self> {
         if raw.len() != 1 {
-            return Err(Error::Header);
+            return Err(SSDPError::InvalidHeader(CONFIGID_HEADER_NAME, "Expected A Single Value"));
         }
 
         let cow_str = String::from_utf8_lossy(&raw[0][..]);
@@ -24,7 +46,7 @@ impl Header for ConfigID {
         // Value needs to be a 31 bit non-negative integer, so convert to i32
         let value = match i32::from_str_radix(&*cow_str, 10) {
             Ok(n) => n,
-            Err(_) => return Err(Error::Header),
+            Err(_) => return Err(SSDPError::InvalidHeader(CONFIGID_HEADER_NAME, "Not A Number")),
         };
 
         // UPnP 1.1 spec says higher numbers are reserved for future use by the
@@ -34,7 +56,7 @@ impl Header for ConfigID {
 
         // Check if value is negative, then convert to u32
         if value.is_negative() {
-            Err(Error::Header)
+            Err(SSDPError::InvalidHeader(CONFIGID_HEADER_NAME, "Out Of Range"))
         } else {
             Ok(ConfigID(value as u32))
         }
@@ -51,9 +73,9 @@ impl HeaderFormat for ConfigID {
 
 #[cfg(test)]
 mod tests {
-    use hyper::header::Header;
+    use header::Header;
 
-    use super::ConfigID;
+    use super::{ConfigID, CONFIGID_MAX};
 
     #[test]
     fn positive_configid() {
@@ -66,7 +88,19 @@ mod tests {
     fn positive_reserved() {
         let configid_header_value = &[b"20720000"[..].to_vec()];
 
-        ConfigID::parse_header(configid_header_value).unwrap();
+        assert!(ConfigID::parse_header(configid_header_value).unwrap().is_reserved());
+    }
+
+    #[test]
+    fn positive_new_upper_bound() {
+        let config_id = ConfigID::new(CONFIGID_MAX).unwrap();
+
+        assert!(!config_id.is_reserved());
+    }
+
+    #[test]
+    fn negative_new_reserved() {
+        assert!(ConfigID::new(CONFIGID_MAX + 1).is_err());
     }
 
     #[test]
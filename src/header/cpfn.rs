@@ -0,0 +1,70 @@
+use std::fmt::{Formatter, Result};
+
+use {SSDPError, SSDPResult};
+use header::{HeaderFormat, Header, names};
+
+const CPFN_HEADER_NAME: &'static str = names::CPFN;
+
+/// Represents a header used to specify the friendly name of the control point
+/// sending a search request.
+///
+/// Devices implementing the `UPnP` 2.0 standard may use this to identify the
+/// control point to users.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct CPFN(pub String);
+
+impl Header for CPFN {
+    fn header_name() -> &'static str {
+        CPFN_HEADER_NAME
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> SSDPResult<Self> {
+        if raw.len() != 1 || raw[0].is_empty() {
+            return Err(SSDPError::InvalidHeader(CPFN_HEADER_NAME, "Expected A Single Non Empty Value"));
+        }
+
+        match String::from_utf8(raw[0].clone()) {
+            Ok(n) => Ok(CPFN(n)),
+            Err(_) => Err(SSDPError::InvalidHeader(CPFN_HEADER_NAME, "Invalid Value")),
+        }
+    }
+}
+
+impl HeaderFormat for CPFN {
+    fn fmt_header(&self, fmt: &mut Formatter) -> Result {
+        try!(fmt.write_str(&self.0));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use header::Header;
+
+    use super::CPFN;
+
+    #[test]
+    fn positive_cpfn() {
+        let cpfn_header_value = &[b"Living Room Remote"[..].to_vec()];
+
+        assert_eq!(CPFN::parse_header(cpfn_header_value).unwrap(),
+                   CPFN("Living Room Remote".to_owned()));
+    }
+
+    #[test]
+    #[should_panic]
+    fn negative_empty() {
+        let cpfn_header_value = &[b""[..].to_vec()];
+
+        CPFN::parse_header(cpfn_header_value).unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn negative_invalid_utf8() {
+        let cpfn_header_value = &[b"Remote\x80"[..].to_vec()];
+
+        CPFN::parse_header(cpfn_header_value).unwrap();
+    }
+}
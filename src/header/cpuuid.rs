@@ -0,0 +1,70 @@
+use std::fmt::{Formatter, Result};
+
+use {SSDPError, SSDPResult};
+use header::{HeaderFormat, Header, names};
+
+const CPUUID_HEADER_NAME: &'static str = names::CPUUID;
+
+/// Represents a header used to specify the UUID of the control point sending a
+/// search request.
+///
+/// Devices implementing the `UPnP` 2.0 standard may use this alongside the
+/// `CPFN` header to tell control points with the same friendly name apart.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct CPUUID(pub String);
+
+impl Header for CPUUID {
+    fn header_name() -> &'static str {
+        CPUUID_HEADER_NAME
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> SSDPResult<Self> {
+        if raw.len() != 1 || raw[0].is_empty() {
+            return Err(SSDPError::InvalidHeader(CPUUID_HEADER_NAME, "Expected A Single Non Empty Value"));
+        }
+
+        match String::from_utf8(raw[0].clone()) {
+            Ok(n) => Ok(CPUUID(n)),
+            Err(_) => Err(SSDPError::InvalidHeader(CPUUID_HEADER_NAME, "Invalid Value")),
+        }
+    }
+}
+
+impl HeaderFormat for CPUUID {
+    fn fmt_header(&self, fmt: &mut Formatter) -> Result {
+        try!(fmt.write_str(&self.0));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use header::Header;
+
+    use super::CPUUID;
+
+    #[test]
+    fn positive_cpuuid() {
+        let cpuuid_header_value = &[b"2fac1234-31f8-11b4-a222-08002b34c003"[..].to_vec()];
+
+        assert_eq!(CPUUID::parse_header(cpuuid_header_value).unwrap(),
+                   CPUUID("2fac1234-31f8-11b4-a222-08002b34c003".to_owned()));
+    }
+
+    #[test]
+    #[should_panic]
+    fn negative_empty() {
+        let cpuuid_header_value = &[b""[..].to_vec()];
+
+        CPUUID::parse_header(cpuuid_header_value).unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn negative_multiple_values() {
+        let cpuuid_header_value = &[b"first"[..].to_vec(), b"second"[..].to_vec()];
+
+        CPUUID::parse_header(cpuuid_header_value).unwrap();
+    }
+}
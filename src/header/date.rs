@@ -0,0 +1,87 @@
+use std::fmt::{Formatter, Result};
+
+use time::{self, Tm};
+
+use {SSDPError, SSDPResult};
+use header::{HeaderFormat, Header, names};
+
+const DATE_HEADER_NAME: &'static str = names::DATE;
+
+/// Formats accepted for an HTTP date, the preferred RFC 1123 format followed
+/// by the obsolete RFC 850 and asctime formats.
+const HTTP_DATE_FORMATS: [&'static str; 3] = ["%a, %d %b %Y %T %Z", "%A, %d-%b-%y %T %Z", "%c"];
+
+/// Represents a point in time as formatted in HTTP headers.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct HttpDate(pub Tm);
+
+impl HttpDate {
+    /// Parse an HTTP date in any of the formats that recipients are required to accept.
+    pub fn parse(value: &str) -> Option<HttpDate> {
+        HTTP_DATE_FORMATS.iter()
+            .filter_map(|format| time::strptime(value, format).ok())
+            .next()
+            .map(HttpDate)
+    }
+}
+
+/// Represents a header used to specify when a search response was generated.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct Date(pub HttpDate);
+
+impl Header for Date {
+    fn header_name() -> &'static str {
+        DATE_HEADER_NAME
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> SSDPResult<Self> {
+        if raw.len() != 1 {
+            return Err(SSDPError::InvalidHeader(DATE_HEADER_NAME, "Expected A Single Value"));
+        }
+
+        match HttpDate::parse(String::from_utf8_lossy(&raw[0][..]).trim()) {
+            Some(n) => Ok(Date(n)),
+            None => Err(SSDPError::InvalidHeader(DATE_HEADER_NAME, "Not An HTTP Date")),
+        }
+    }
+}
+
+impl HeaderFormat for Date {
+    fn fmt_header(&self, fmt: &mut Formatter) -> Result {
+        try!(fmt.write_fmt(format_args!("{}", (self.0).0.to_utc().rfc822())));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use header::{Header, HeaderFormatter};
+
+    use super::Date;
+
+    #[test]
+    fn positive_rfc1123() {
+        let date_header_value = &[b"Sun, 06 Nov 1994 08:49:37 GMT"[..].to_vec()];
+        let date = Date::parse_header(date_header_value).unwrap();
+
+        assert_eq!(HeaderFormatter(&date).to_string(), "Sun, 06 Nov 1994 08:49:37 GMT");
+    }
+
+    #[test]
+    fn positive_obsolete_formats() {
+        let rfc850 = Date::parse_header(&[b"Sunday, 06-Nov-94 08:49:37 GMT"[..].to_vec()]).unwrap();
+        let asctime = Date::parse_header(&[b"Sun Nov  6 08:49:37 1994"[..].to_vec()]).unwrap();
+
+        assert_eq!(((rfc850.0).0.tm_mday, (rfc850.0).0.tm_hour), (6, 8));
+        assert_eq!(((asctime.0).0.tm_mday, (asctime.0).0.tm_hour), (6, 8));
+    }
+
+    #[test]
+    #[should_panic]
+    fn negative_not_a_date() {
+        let date_header_value = &[b"yesterday"[..].to_vec()];
+
+        Date::parse_header(date_header_value).unwrap();
+    }
+}
@@ -0,0 +1,129 @@
+use std::borrow::Cow;
+
+use header::{HeaderRef, HeaderMut};
+
+/// View into a single header field of a header structure, which may or may not be present.
+#[derive(Debug)]
+pub struct HeaderEntry<'a, T: 'a> {
+    headers: &'a mut T,
+    name: Cow<'static, str>,
+}
+
+impl<'a, T> HeaderEntry<'a, T>
+    where T: HeaderRef + HeaderMut
+{
+    /// Construct a new HeaderEntry for the named header field.
+    pub fn new(headers: &'a mut T, name: Cow<'static, str>) -> HeaderEntry<'a, T> {
+        HeaderEntry {
+            headers: headers,
+            name: name,
+        }
+    }
+
+    /// Name of the header field.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Whether or not the header field is present.
+    pub fn is_occupied(&self) -> bool {
+        self.get_raw().is_some()
+    }
+
+    /// View the raw bytes of the header field if it is present.
+    pub fn get_raw(&self) -> Option<&[Vec<u8>]> {
+        self.headers.get_raw(&self.name)
+    }
+
+    /// Set the header field to the raw bytes if it is not already present,
+    /// returning the raw bytes of the header field.
+    pub fn or_insert_raw(self, default: Vec<Vec<u8>>) -> &'a [Vec<u8>] {
+        let headers = self.headers;
+        if headers.get_raw(&self.name).is_none() {
+            headers.set_raw(self.name.clone(), default);
+        }
+
+        let headers: &'a T = headers;
+        headers.get_raw(&self.name).unwrap()
+    }
+
+    /// Modify the raw bytes of the header field in place if it is present.
+    pub fn and_modify<F>(self, modify: F) -> Self
+        where F: FnOnce(&mut Vec<Vec<u8>>)
+    {
+        let opt_value = self.get_raw().map(|value| value.to_vec());
+        if let Some(mut value) = opt_value {
+            modify(&mut value);
+            self.headers.set_raw(self.name.clone(), value);
+        }
+
+        self
+    }
+
+    /// Replace the header field with the raw bytes, returning the previous raw bytes if it was present.
+    pub fn insert_raw(self, value: Vec<Vec<u8>>) -> Option<Vec<Vec<u8>>> {
+        let opt_previous = self.get_raw().map(|value| value.to_vec());
+        self.headers.set_raw(self.name, value);
+
+        opt_previous
+    }
+
+    /// Remove the header field, returning its raw bytes if it was present.
+    pub fn remove(self) -> Option<Vec<Vec<u8>>> {
+        let opt_previous = self.get_raw().map(|value| value.to_vec());
+        self.headers.remove_raw(&self.name);
+
+        opt_previous
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use header::{HeaderMap, HeaderRef, HeaderMut};
+
+    #[test]
+    fn positive_or_insert_raw() {
+        let mut headers = HeaderMap::new();
+
+        assert_eq!(headers.entry("NT").or_insert_raw(vec![b"upnp:rootdevice".to_vec()]),
+                   &[b"upnp:rootdevice".to_vec()][..]);
+        assert_eq!(headers.entry("nt").or_insert_raw(vec![b"ssdp:all".to_vec()]),
+                   &[b"upnp:rootdevice".to_vec()][..]);
+    }
+
+    #[test]
+    fn positive_and_modify() {
+        let mut headers = HeaderMap::new();
+        headers.set_raw("SERVER", vec![b"Linux/5.0".to_vec()]);
+
+        headers.entry("SERVER").and_modify(|value| value[0].extend_from_slice(b" UPnP/1.1"));
+        headers.entry("ST").and_modify(|value| value.clear());
+
+        assert_eq!(headers.get_raw("SERVER").unwrap(), &[b"Linux/5.0 UPnP/1.1".to_vec()][..]);
+        assert!(headers.get_raw("ST").is_none());
+    }
+
+    #[test]
+    fn positive_insert_and_remove() {
+        let mut headers = HeaderMap::new();
+
+        assert_eq!(headers.entry("X-EXTRA").insert_raw(vec![b"1".to_vec()]), None);
+        assert_eq!(headers.entry("X-EXTRA").insert_raw(vec![b"2".to_vec()]), Some(vec![b"1".to_vec()]));
+        assert_eq!(headers.entry("X-EXTRA").remove(), Some(vec![b"2".to_vec()]));
+        assert!(!headers.entry("X-EXTRA").is_occupied());
+    }
+
+    #[test]
+    fn positive_iter_headers_in_order() {
+        let mut headers = HeaderMap::new();
+        headers.set_raw("ST", vec![b"ssdp:all".to_vec()]);
+        headers.set_raw("X-EXTRA", vec![b"1".to_vec()]);
+        headers.set_raw("MX", vec![b"3".to_vec()]);
+        assert!(HeaderMut::remove_raw(&mut headers, "MX"));
+        assert!(!HeaderMut::remove_raw(&mut headers, "MX"));
+
+        let names = headers.iter_headers().map(|view| view.name().to_owned()).collect::<Vec<_>>();
+
+        assert_eq!(names, vec!["ST", "X-EXTRA"]);
+    }
+}
@@ -0,0 +1,112 @@
+use std::fmt::{self, Debug, Formatter};
+
+use {SSDPError, SSDPResult};
+use header::{HeaderFormat, Header};
+
+/// Trait for defining a typed vendor extension header, such as `hue-bridgeid`
+/// or `X-SONOS-*`, outside of this crate.
+///
+/// Wrapping the type in an `Extension` lets it be read and written through
+/// `HeaderRef::get` and `HeaderMut::set` like the built in headers.
+pub trait ExtensionHeader: Clone + Debug + Send + Sync + 'static {
+    /// Name of the header field, matched regardless of case.
+    fn header_name() -> &'static str;
+
+    /// Parse the value of the header field, with surrounding whitespace removed.
+    fn parse_value(value: &str) -> Option<Self>;
+
+    /// Format the value of the header field.
+    fn fmt_value(&self, fmt: &mut Formatter) -> fmt::Result;
+}
+
+/// Represents a vendor extension header defined through `ExtensionHeader`.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Extension<T>(pub T);
+
+impl<T> Header for Extension<T>
+    where T: ExtensionHeader
+{
+    fn header_name() -> &'static str {
+        T::header_name()
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> SSDPResult<Self> {
+        if raw.len() != 1 {
+            return Err(SSDPError::InvalidHeader(T::header_name(), "Expected A Single Value"));
+        }
+
+        let cow_str = String::from_utf8_lossy(&raw[0][..]);
+
+        match T::parse_value(cow_str.trim()) {
+            Some(n) => Ok(Extension(n)),
+            None => Err(SSDPError::InvalidHeader(T::header_name(), "Invalid Value")),
+        }
+    }
+}
+
+impl<T> HeaderFormat for Extension<T>
+    where T: ExtensionHeader
+{
+    fn fmt_header(&self, fmt: &mut Formatter) -> fmt::Result {
+        self.0.fmt_value(fmt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::{self, Formatter};
+
+    use header::{Header, HeaderMap, HeaderMut, HeaderRef};
+    use super::{Extension, ExtensionHeader};
+
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    struct BridgeId(String);
+
+    impl ExtensionHeader for BridgeId {
+        fn header_name() -> &'static str {
+            "hue-bridgeid"
+        }
+
+        fn parse_value(value: &str) -> Option<Self> {
+            if value.len() == 16 && value.chars().all(|c| c.is_ascii_hexdigit()) {
+                Some(BridgeId(value.to_uppercase()))
+            } else {
+                None
+            }
+        }
+
+        fn fmt_value(&self, fmt: &mut Formatter) -> fmt::Result {
+            fmt.write_str(&self.0)
+        }
+    }
+
+    #[test]
+    fn positive_extension() {
+        let bridge_id_header_value = &[b" 001788fffe4a2b3c"[..].to_vec()];
+
+        assert_eq!(Extension::<BridgeId>::parse_header(bridge_id_header_value).unwrap(),
+                   Extension(BridgeId("001788FFFE4A2B3C".to_owned())));
+    }
+
+    #[test]
+    fn positive_extension_through_headers() {
+        let mut headers = HeaderMap::new();
+        headers.set(Extension(BridgeId("001788FFFE4A2B3C".to_owned())));
+
+        assert_eq!(headers.get_raw("HUE-BRIDGEID").unwrap(),
+                   &[b"001788FFFE4A2B3C".to_vec()][..]);
+
+        headers.set_raw("hue-bridgeid", vec![b"001788fffe000000".to_vec()]);
+
+        assert_eq!(headers.get::<Extension<BridgeId>>(),
+                   Some(Extension(BridgeId("001788FFFE000000".to_owned()))));
+    }
+
+    #[test]
+    #[should_panic]
+    fn negative_invalid_value() {
+        let bridge_id_header_value = &[b"not-a-bridge-id"[..].to_vec()];
+
+        Extension::<BridgeId>::parse_header(bridge_id_header_value).unwrap();
+    }
+}
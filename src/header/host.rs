@@ -0,0 +1,98 @@
+use std::fmt::{Formatter, Result};
+
+use {SSDPError, SSDPResult};
+use header::{HeaderFormat, Header, names};
+
+const HOST_HEADER_NAME: &'static str = names::HOST;
+
+/// Represents a header used to specify the address and port a message was sent to.
+///
+/// IPv6 hostnames keep their brackets, as in "[FF02::C]".
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Host {
+    /// Hostname or IP address.
+    pub hostname: String,
+    /// Port, if one was given.
+    pub port: Option<u16>,
+}
+
+impl Header for Host {
+    fn header_name() -> &'static str {
+        HOST_HEADER_NAME
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> SSDPResult<Self> {
+        if raw.len() != 1 {
+            return Err(SSDPError::InvalidHeader(HOST_HEADER_NAME, "Expected A Single Value"));
+        }
+
+        let cow_str = String::from_utf8_lossy(&raw[0][..]);
+        let value = cow_str.trim();
+
+        // The last colon separates the port, unless it is within an IPv6 address
+        let (hostname, port) = match value.rfind(':') {
+            Some(index) if !value[index..].contains(']') => {
+                match value[index + 1..].parse::<u16>() {
+                    Ok(port) => (&value[..index], Some(port)),
+                    Err(_) => return Err(SSDPError::InvalidHeader(HOST_HEADER_NAME, "Invalid Port")),
+                }
+            }
+            _ => (value, None),
+        };
+
+        if hostname.is_empty() || hostname.starts_with('[') != hostname.ends_with(']') {
+            return Err(SSDPError::InvalidHeader(HOST_HEADER_NAME, "Invalid Hostname"));
+        }
+
+        Ok(Host {
+            hostname: hostname.to_owned(),
+            port: port,
+        })
+    }
+}
+
+impl HeaderFormat for Host {
+    fn fmt_header(&self, fmt: &mut Formatter) -> Result {
+        try!(fmt.write_str(&self.hostname));
+        if let Some(port) = self.port {
+            try!(fmt.write_fmt(format_args!(":{}", port)));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use header::Header;
+
+    use super::Host;
+
+    #[test]
+    fn positive_ipv4_host() {
+        let host_header_value = &[b"239.255.255.250:1900"[..].to_vec()];
+
+        assert_eq!(Host::parse_header(host_header_value).unwrap(),
+                   Host {
+                       hostname: "239.255.255.250".to_owned(),
+                       port: Some(1900),
+                   });
+    }
+
+    #[test]
+    fn positive_ipv6_host() {
+        let with_port = Host::parse_header(&[b"[FF02::C]:1900"[..].to_vec()]).unwrap();
+        let without_port = Host::parse_header(&[b"[FF02::C]"[..].to_vec()]).unwrap();
+
+        assert_eq!((&with_port.hostname[..], with_port.port), ("[FF02::C]", Some(1900)));
+        assert_eq!((&without_port.hostname[..], without_port.port), ("[FF02::C]", None));
+    }
+
+    #[test]
+    #[should_panic]
+    fn negative_invalid_port() {
+        let host_header_value = &[b"239.255.255.250:port"[..].to_vec()];
+
+        Host::parse_header(host_header_value).unwrap();
+    }
+}
@@ -0,0 +1,93 @@
+//! Conversions between SSDP message headers and the `http` crate's `HeaderMap`.
+
+use http::header::{HeaderName, HeaderValue};
+use http::HeaderMap as HttpHeaderMap;
+
+use header::{HeaderMap, HeaderMut, HeaderRef};
+
+/// Copy the header fields into an `http::HeaderMap`, one value per line of each field.
+///
+/// Fields whose name or value the `http` crate rejects are skipped.
+pub fn to_http<T: HeaderRef + ?Sized>(headers: &T) -> HttpHeaderMap {
+    let mut http_headers = HttpHeaderMap::new();
+
+    for view in headers.iter_headers() {
+        let name = match HeaderName::from_bytes(view.name().as_bytes()) {
+            Ok(n) => n,
+            Err(_) => {
+                debug!("Skipping header {:?} not allowed by the http crate", view.name());
+                continue;
+            }
+        };
+
+        for line in view.raw() {
+            match HeaderValue::from_bytes(line) {
+                Ok(value) => {
+                    http_headers.append(name.clone(), value);
+                }
+                Err(_) => debug!("Skipping value of header {} not allowed by the http crate", name),
+            }
+        }
+    }
+
+    http_headers
+}
+
+/// Set the header fields from an `http::HeaderMap`, replacing any fields of the same name.
+pub fn extend_from_http<T: HeaderMut + ?Sized>(headers: &mut T, http_headers: &HttpHeaderMap) {
+    for name in http_headers.keys() {
+        let raw = http_headers.get_all(name).iter().map(|value| value.as_bytes().to_vec()).collect();
+
+        headers.set_raw(name.as_str().to_owned(), raw);
+    }
+}
+
+impl<'a> From<&'a HeaderMap> for HttpHeaderMap {
+    fn from(headers: &'a HeaderMap) -> HttpHeaderMap {
+        to_http(headers)
+    }
+}
+
+impl<'a> From<&'a HttpHeaderMap> for HeaderMap {
+    fn from(http_headers: &'a HttpHeaderMap) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        extend_from_http(&mut headers, http_headers);
+
+        headers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http::HeaderMap as HttpHeaderMap;
+
+    use header::{HeaderMap, HeaderRef, HeaderMut, Location, ST};
+
+    #[test]
+    fn positive_to_http() {
+        let mut headers = HeaderMap::new();
+        headers.set(ST::All);
+        headers.set(Location("http://192.168.0.2/desc.xml".to_owned()));
+        headers.set_raw("X-BAD NAME", vec![b"1".to_vec()]);
+
+        let http_headers = HttpHeaderMap::from(&headers);
+
+        assert_eq!(http_headers.len(), 2);
+        assert_eq!(http_headers["st"], "ssdp:all");
+        assert_eq!(http_headers["location"], "http://192.168.0.2/desc.xml");
+    }
+
+    #[test]
+    fn positive_from_http() {
+        let mut http_headers = HttpHeaderMap::new();
+        http_headers.insert("st", "ssdp:all".parse().unwrap());
+        http_headers.append("cache-control", "no-cache".parse().unwrap());
+        http_headers.append("cache-control", "max-age=1800".parse().unwrap());
+
+        let headers = HeaderMap::from(&http_headers);
+
+        assert_eq!(headers.get::<ST>(), Some(ST::All));
+        assert_eq!(headers.get_raw("CACHE-CONTROL").unwrap(),
+                   &[b"no-cache".to_vec(), b"max-age=1800".to_vec()][..]);
+    }
+}
@@ -0,0 +1,58 @@
+use std::fmt::{Formatter, Result};
+
+use {SSDPError, SSDPResult};
+use header::{HeaderFormat, Header, names};
+
+const LOCATION_HEADER_NAME: &'static str = names::LOCATION;
+
+/// Represents a header used to specify the URL of the description of a device.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Location(pub String);
+
+impl Header for Location {
+    fn header_name() -> &'static str {
+        LOCATION_HEADER_NAME
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> SSDPResult<Self> {
+        if raw.len() != 1 {
+            return Err(SSDPError::InvalidHeader(LOCATION_HEADER_NAME, "Expected A Single Value"));
+        }
+
+        match String::from_utf8(raw[0].clone()) {
+            Ok(n) => Ok(Location(n.trim().to_owned())),
+            Err(_) => Err(SSDPError::InvalidHeader(LOCATION_HEADER_NAME, "Not Valid UTF-8")),
+        }
+    }
+}
+
+impl HeaderFormat for Location {
+    fn fmt_header(&self, fmt: &mut Formatter) -> Result {
+        try!(fmt.write_str(&self.0));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use header::Header;
+
+    use super::Location;
+
+    #[test]
+    fn positive_location() {
+        let location_header_value = &[b"http://192.168.0.2:49152/desc.xml"[..].to_vec()];
+
+        assert_eq!(Location::parse_header(location_header_value).unwrap(),
+                   Location("http://192.168.0.2:49152/desc.xml".to_owned()));
+    }
+
+    #[test]
+    #[should_panic]
+    fn negative_invalid_utf8() {
+        let location_header_value = &[b"http://192.168.0.2:49152/desc.xml\x80"[..].to_vec()];
+
+        Location::parse_header(location_header_value).unwrap();
+    }
+}
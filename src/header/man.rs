@@ -1,10 +1,49 @@
 use std::fmt::{Formatter, Result};
 
-use hyper::error::{self, Error};
-use hyper::header::{HeaderFormat, Header};
+use {SSDPError, SSDPResult};
+use header::{HeaderFormat, Header, names};
 
-const MAN_HEADER_NAME: &'static str = "MAN";
+const MAN_HEADER_NAME: &'static str = names::MAN;
 const MAN_HEADER_VALUE: &'static str = "\"ssdp:discover\"";
+const MAN_HEADER_UNQUOTED_VALUE: &'static str = "ssdp:discover";
+
+/// Outcome of validating the MAN header of a received search request.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum ManValidity {
+    /// Exactly `"ssdp:discover"`, as the specification requires.
+    Valid,
+    /// `ssdp:discover` without the quotes, which some control points send.
+    Unquoted,
+    /// Missing, repeated or any other value.
+    Invalid,
+}
+
+impl ManValidity {
+    /// Validate the raw MAN header values, if the header was present.
+    pub fn from_raw(opt_raw: Option<&[Vec<u8>]>) -> ManValidity {
+        let raw = match opt_raw {
+            Some(n) if n.len() == 1 => &n[0][..],
+            _ => return ManValidity::Invalid,
+        };
+
+        if raw == MAN_HEADER_VALUE.as_bytes() {
+            ManValidity::Valid
+        } else if raw == MAN_HEADER_UNQUOTED_VALUE.as_bytes() {
+            ManValidity::Unquoted
+        } else {
+            ManValidity::Invalid
+        }
+    }
+
+    /// Whether or not the header is acceptable, in strict mode only if it is exactly valid.
+    pub fn is_acceptable(&self, strict: bool) -> bool {
+        match *self {
+            ManValidity::Valid => true,
+            ManValidity::Unquoted => !strict,
+            ManValidity::Invalid => false,
+        }
+    }
+}
 
 /// Represents a header used to specify HTTP extension.
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
@@ -15,15 +54,15 @@ impl Header for Man {
         MAN_HEADER_NAME
     }
 
-    fn parse_header(raw: &[Vec<u8>]) -> error::Result<Self> {
+    fn parse_header(raw: &[Vec<u8>]) -> SSDPResult<Self> {
         if raw.len() != 1 {
-            return Err(Error::Header);
+            return Err(SSDPError::InvalidHeader(MAN_HEADER_NAME, "Expected A Single Value"));
         }
 
         let man_bytes = MAN_HEADER_VALUE.as_bytes();
         match &raw[0][..] {
             n if n == man_bytes => Ok(Man),
-            _ => Err(Error::Header),
+            _ => Err(SSDPError::InvalidHeader(MAN_HEADER_NAME, "Invalid Value")),
         }
     }
 }
@@ -38,9 +77,9 @@ impl HeaderFormat for Man {
 
 #[cfg(test)]
 mod tests {
-    use hyper::header::Header;
+    use header::Header;
 
-    use super::Man;
+    use super::{Man, ManValidity};
 
     #[test]
     fn positive_man() {
@@ -64,4 +103,29 @@ mod tests {
 
         Man::parse_header(missing_quotes_man_header).unwrap();
     }
+
+    #[test]
+    fn positive_validity_strict() {
+        let validity = ManValidity::from_raw(Some(&[b"\"ssdp:discover\""[..].to_vec()]));
+
+        assert_eq!(validity, ManValidity::Valid);
+        assert!(validity.is_acceptable(true));
+    }
+
+    #[test]
+    fn positive_validity_unquoted() {
+        let validity = ManValidity::from_raw(Some(&[b"ssdp:discover"[..].to_vec()]));
+
+        assert_eq!(validity, ManValidity::Unquoted);
+        assert!(!validity.is_acceptable(true));
+        assert!(validity.is_acceptable(false));
+    }
+
+    #[test]
+    fn negative_validity_invalid() {
+        assert_eq!(ManValidity::from_raw(None), ManValidity::Invalid);
+        assert_eq!(ManValidity::from_raw(Some(&[b"\"ssdp:alive\""[..].to_vec()])),
+                   ManValidity::Invalid);
+        assert!(!ManValidity::Invalid.is_acceptable(false));
+    }
 }
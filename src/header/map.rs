@@ -0,0 +1,302 @@
+use std::borrow::Cow;
+use std::fmt::{self, Debug, Formatter};
+use std::slice;
+
+#[cfg(feature = "serde")]
+use serde::de::{Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+#[cfg(feature = "serde")]
+use serde::ser::{Serialize, SerializeMap, Serializer};
+
+/// Case-insensitive map of header fields to their raw values, which keeps the
+/// fields in the order they were set or received.
+#[derive(Clone, PartialEq, Eq, Default)]
+pub struct HeaderMap {
+    fields: Vec<(Cow<'static, str>, Vec<Vec<u8>>)>,
+}
+
+impl HeaderMap {
+    /// Construct a new, empty HeaderMap.
+    pub fn new() -> HeaderMap {
+        HeaderMap { fields: Vec::new() }
+    }
+
+    /// View the raw bytes of the header field with the given name if it exists.
+    pub fn get_raw(&self, name: &str) -> Option<&[Vec<u8>]> {
+        self.position(name).map(|index| &self.fields[index].1[..])
+    }
+
+    /// Set the header field with the given name to the raw bytes.
+    ///
+    /// A field that is already present keeps its position and the case of its name.
+    pub fn set_raw<K>(&mut self, name: K, value: Vec<Vec<u8>>)
+        where K: Into<Cow<'static, str>>
+    {
+        let name = name.into();
+
+        match self.position(&name) {
+            Some(index) => self.fields[index].1 = value,
+            None => self.fields.push((name, value)),
+        }
+    }
+
+    /// Append a value to the header field with the given name, adding the field
+    /// if it is not already present.
+    pub fn append_raw<K>(&mut self, name: K, value: Vec<u8>)
+        where K: Into<Cow<'static, str>>
+    {
+        let name = name.into();
+
+        match self.position(&name) {
+            Some(index) => self.fields[index].1.push(value),
+            None => self.fields.push((name, vec![value])),
+        }
+    }
+
+    /// Remove the header field with the given name, returning whether or not it was present.
+    pub fn remove_raw(&mut self, name: &str) -> bool {
+        match self.position(name) {
+            Some(index) => {
+                self.fields.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Iterate over the header fields in order.
+    pub fn iter(&self) -> HeadersItems {
+        HeadersItems { inner: self.fields.iter() }
+    }
+
+    /// Number of header fields.
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    /// Whether or not there are no header fields.
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.fields.iter().position(|&(ref field_name, _)| field_name.eq_ignore_ascii_case(name))
+    }
+}
+
+impl Debug for HeaderMap {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter().map(|view| (view.name(), view.value()))).finish()
+    }
+}
+
+/// Iterator over the header fields of a `HeaderMap`.
+#[derive(Clone)]
+pub struct HeadersItems<'a> {
+    inner: slice::Iter<'a, (Cow<'static, str>, Vec<Vec<u8>>)>,
+}
+
+impl<'a> Iterator for HeadersItems<'a> {
+    type Item = HeaderView<'a>;
+
+    fn next(&mut self) -> Option<HeaderView<'a>> {
+        self.inner.next().map(|&(ref name, ref raw)| {
+            HeaderView {
+                name: name,
+                raw: raw,
+            }
+        })
+    }
+}
+
+/// View of a single header field of a `HeaderMap`.
+#[derive(Copy, Clone, Debug)]
+pub struct HeaderView<'a> {
+    name: &'a str,
+    raw: &'a [Vec<u8>],
+}
+
+impl<'a> HeaderView<'a> {
+    /// Name of the header field, in the case it was set or received with.
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    /// Raw bytes of the header field, one entry per line it appeared on.
+    pub fn raw(&self) -> &'a [Vec<u8>] {
+        self.raw
+    }
+
+    /// Value of the header field as a string, with multiple lines joined by commas.
+    pub fn value_string(&self) -> String {
+        self.value().into_owned()
+    }
+
+    /// Value of the header field as a string like `value_string`, borrowed from the
+    /// map when the field appeared on a single line of valid UTF-8.
+    pub fn value(&self) -> Cow<'a, str> {
+        if self.raw.len() == 1 {
+            return String::from_utf8_lossy(&self.raw[0]);
+        }
+
+        Cow::Owned(self.raw
+            .iter()
+            .map(|line| String::from_utf8_lossy(line))
+            .collect::<Vec<_>>()
+            .join(", "))
+    }
+}
+
+/// Serializes as a map of field names to values, where fields that appeared on
+/// multiple lines have a sequence of values.
+#[cfg(feature = "serde")]
+impl Serialize for HeaderMap {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = try!(serializer.serialize_map(Some(self.len())));
+
+        for view in self.iter() {
+            let values = view.raw().iter().map(|line| String::from_utf8_lossy(line)).collect::<Vec<_>>();
+
+            if values.len() == 1 {
+                try!(map.serialize_entry(view.name(), &values[0]));
+            } else {
+                try!(map.serialize_entry(view.name(), &values));
+            }
+        }
+
+        map.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for HeaderMap {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<HeaderMap, D::Error> {
+        deserializer.deserialize_map(HeaderMapVisitor)
+    }
+}
+
+#[cfg(feature = "serde")]
+struct HeaderMapVisitor;
+
+#[cfg(feature = "serde")]
+impl<'de> Visitor<'de> for HeaderMapVisitor {
+    type Value = HeaderMap;
+
+    fn expecting(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str("a map of header names to a value or sequence of values")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<HeaderMap, A::Error> {
+        let mut headers = HeaderMap::new();
+
+        while let Some((name, RawValue(raw))) = try!(access.next_entry::<String, RawValue>()) {
+            headers.set_raw(name, raw);
+        }
+
+        Ok(headers)
+    }
+}
+
+/// Raw value of a deserialized header field, from either a string or a sequence of strings.
+#[cfg(feature = "serde")]
+struct RawValue(Vec<Vec<u8>>);
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for RawValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<RawValue, D::Error> {
+        deserializer.deserialize_any(RawValueVisitor)
+    }
+}
+
+#[cfg(feature = "serde")]
+struct RawValueVisitor;
+
+#[cfg(feature = "serde")]
+impl<'de> Visitor<'de> for RawValueVisitor {
+    type Value = RawValue;
+
+    fn expecting(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str("a header value or sequence of header values")
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<RawValue, E> {
+        Ok(RawValue(vec![value.as_bytes().to_vec()]))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut access: A) -> Result<RawValue, A::Error> {
+        let mut raw = Vec::new();
+        while let Some(value) = try!(access.next_element::<String>()) {
+            raw.push(value.into_bytes());
+        }
+
+        Ok(RawValue(raw))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::HeaderMap;
+
+    #[test]
+    fn positive_case_insensitive() {
+        let mut headers = HeaderMap::new();
+        headers.set_raw("Location", vec![b"http://192.168.0.2/".to_vec()]);
+
+        for name in &["location", "Location", "LOCATION"] {
+            assert_eq!(headers.get_raw(name).unwrap(), &[b"http://192.168.0.2/".to_vec()][..]);
+        }
+    }
+
+    #[test]
+    fn positive_replace_keeps_position() {
+        let mut headers = HeaderMap::new();
+        headers.set_raw("ST", vec![b"ssdp:all".to_vec()]);
+        headers.set_raw("MX", vec![b"3".to_vec()]);
+        headers.set_raw("st", vec![b"upnp:rootdevice".to_vec()]);
+
+        let fields = headers.iter().map(|view| (view.name(), view.value_string())).collect::<Vec<_>>();
+
+        assert_eq!(fields,
+                   vec![("ST", "upnp:rootdevice".to_owned()), ("MX", "3".to_owned())]);
+    }
+
+    #[test]
+    fn positive_value_borrows_single_line() {
+        let mut headers = HeaderMap::new();
+        headers.set_raw("SERVER", vec![b"Linux/5.0 UPnP/1.1".to_vec()]);
+        headers.set_raw("X-MULTI", vec![b"1".to_vec(), b"2".to_vec()]);
+
+        let mut views = headers.iter();
+        match views.next().unwrap().value() {
+            Cow::Borrowed(value) => assert_eq!(value, "Linux/5.0 UPnP/1.1"),
+            Cow::Owned(_) => panic!("Expected A Borrowed Value"),
+        }
+        assert_eq!(views.next().unwrap().value(), "1, 2");
+    }
+
+    #[test]
+    fn positive_append_and_remove() {
+        let mut headers = HeaderMap::new();
+        headers.append_raw("CACHE-CONTROL", b"no-cache".to_vec());
+        headers.append_raw("Cache-Control", b"max-age=1800".to_vec());
+
+        assert_eq!(headers.iter().next().unwrap().value_string(), "no-cache, max-age=1800");
+        assert!(headers.remove_raw("cache-control"));
+        assert!(!headers.remove_raw("cache-control"));
+        assert!(headers.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn positive_serde_round_trip() {
+        let mut headers = HeaderMap::new();
+        headers.set_raw("ST", vec![b"ssdp:all".to_vec()]);
+        headers.set_raw("X-MULTI", vec![b"1".to_vec(), b"2".to_vec()]);
+
+        let json = ::serde_json::to_string(&headers).unwrap();
+        assert_eq!(json, r#"{"ST":"ssdp:all","X-MULTI":["1","2"]}"#);
+
+        assert_eq!(::serde_json::from_str::<HeaderMap>(&json).unwrap(), headers);
+    }
+}
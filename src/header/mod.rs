@@ -1,52 +1,214 @@
 //! Headers and primitives for parsing headers within SSDP requests.
 //!
 //! This module combines abstractions at both the HTTPU/HTTPMU layer and SSDP
-//! layer in order to provide a cleaner interface for extending the underlying
-//! HTTP parsing library.
+//! layer, with typed headers parsed from and formatted into a case-insensitive
+//! `HeaderMap` of raw header fields.
 
 use std::borrow::Cow;
-use std::fmt::Debug;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::str;
+use std::time::Duration;
 
-use hyper::header::{Headers, Header, HeaderFormat};
+#[cfg(feature = "http")]
+use http::HeaderMap as HttpHeaderMap;
+#[cfg(feature = "url")]
+use url::Url;
+
+use SSDPResult;
+#[cfg(feature = "url")]
+use SSDPError;
 
 mod bootid;
+mod cachecontrol;
 mod configid;
+mod cpfn;
+mod cpuuid;
+mod date;
+mod entry;
+mod extension;
+mod host;
+#[cfg(feature = "http")]
+mod interop;
+mod location;
 mod man;
+mod map;
 mod mx;
+pub mod names;
+mod nextbootid;
+mod nls;
 mod nt;
 mod nts;
+mod product;
 mod searchport;
 mod securelocation;
+mod server;
 mod st;
+mod tcpport;
+mod useragent;
 mod usn;
 
-pub use self::bootid::BootID;
-pub use self::configid::ConfigID;
-pub use self::man::Man;
-pub use self::mx::MX;
+pub use self::bootid::{BootID, BOOTID_MAX};
+pub use self::cachecontrol::{CacheControl, CacheDirective};
+pub use self::configid::{ConfigID, CONFIGID_MAX};
+pub use self::cpfn::CPFN;
+pub use self::cpuuid::CPUUID;
+pub use self::date::{Date, HttpDate};
+pub use self::entry::HeaderEntry;
+pub use self::extension::{Extension, ExtensionHeader};
+pub use self::host::Host;
+pub use self::location::Location;
+pub use self::man::{Man, ManValidity};
+pub use self::map::{HeaderMap, HeadersItems, HeaderView};
+pub use self::mx::{MX, MX_HEADER_MIN, MX_HEADER_MAX, MX_HEADER_UDA11_MAX};
+pub use self::nextbootid::NextBootID;
+pub use self::nls::NLS;
 pub use self::nt::NT;
 pub use self::nts::NTS;
+pub use self::product::ProductTokens;
 pub use self::searchport::SearchPort;
 pub use self::securelocation::SecureLocation;
+pub use self::server::Server;
 pub use self::st::ST;
+pub use self::tcpport::TcpPort;
+pub use self::useragent::UserAgent;
 pub use self::usn::USN;
 
-// Re-exports
-pub use hyper::header::{Location, Server, CacheControl, CacheDirective};
+/// Name of the CACHE-CONTROL directive holding the number of seconds a message is valid for.
+const MAX_AGE_DIRECTIVE: &'static str = "max-age";
+
+/// Get the max-age directive of the CACHE-CONTROL header as a `Duration`.
+///
+/// Tolerates extra directives, whitespace around the "=", differences in case
+/// and quoted values, which devices commonly send.
+pub fn max_age<T: HeaderRef + ?Sized>(headers: &T) -> Option<Duration> {
+    let raw = match headers.get_raw(names::CACHE_CONTROL) {
+        Some(n) => n,
+        None => return None,
+    };
+
+    raw.iter()
+        .flat_map(|line| String::from_utf8_lossy(line).split(',').map(str::to_owned).collect::<Vec<_>>())
+        .filter_map(|directive| parse_max_age(&directive))
+        .next()
+}
+
+/// View the value of a header field holding a single string, such as LOCATION
+/// or SERVER, without copying it.
+pub fn get_str<'a, T: HeaderRef + ?Sized>(headers: &'a T, name: &str) -> Option<&'a str> {
+    match headers.get_raw(name) {
+        Some(raw) if raw.len() == 1 => str::from_utf8(&raw[0]).ok().map(str::trim),
+        _ => None,
+    }
+}
+
+/// View the Network Location Signature in the 01-NLS header, falling back to
+/// an NLS header without the namespace prefix, without copying it.
+pub fn nls<T: HeaderRef + ?Sized>(headers: &T) -> Option<&str> {
+    headers.get_str(names::NLS)
+        .or_else(|| headers.get_str(names::UNPREFIXED_NLS))
+        .and_then(|value| if nls::is_signature(value) { Some(value) } else { None })
+}
+
+/// Get the LOCATION header as an absolute URL.
+///
+/// Relative URLs and values that are not URLs at all are rejected.
+#[cfg(feature = "url")]
+pub fn location_url<T: HeaderRef + ?Sized>(headers: &T) -> SSDPResult<Url> {
+    let location = match headers.get::<Location>() {
+        Some(n) => n,
+        None => return Err(SSDPError::MissingHeader(Location::header_name())),
+    };
+
+    Url::parse(&location.0)
+        .map_err(|_| SSDPError::InvalidHeader(Location::header_name(), "Not An Absolute URL"))
+}
+
+/// Get the LOCATION header as an absolute URL like `location_url`, additionally
+/// rejecting URLs that are not http or https or that are missing a host.
+#[cfg(feature = "url")]
+pub fn strict_location_url<T: HeaderRef + ?Sized>(headers: &T) -> SSDPResult<Url> {
+    let url = try!(location_url(headers));
+
+    match url.scheme() {
+        "http" | "https" if url.host().is_some() => Ok(url),
+        _ => Err(SSDPError::InvalidHeader(Location::header_name(), "Not An HTTP(S) URL")),
+    }
+}
+
+/// Parse a single max-age directive into a `Duration`.
+fn parse_max_age(directive: &str) -> Option<Duration> {
+    let mut parts = directive.splitn(2, '=');
+
+    let name = parts.next().unwrap_or("").trim();
+    if !name.eq_ignore_ascii_case(MAX_AGE_DIRECTIVE) {
+        return None;
+    }
+
+    let value = parts.next().unwrap_or("").trim().trim_matches('"');
+
+    value.parse::<u64>().ok().map(Duration::from_secs)
+}
+
+/// Trait for a typed header that can be parsed from the raw values of its header field.
+pub trait Header: Clone + Debug {
+    /// Name of the header field, matched regardless of case.
+    fn header_name() -> &'static str;
+
+    /// Parse the header from the raw bytes of each line of its header field.
+    fn parse_header(raw: &[Vec<u8>]) -> SSDPResult<Self>;
+}
+
+/// Trait for a typed header that can be formatted as the value of its header field.
+pub trait HeaderFormat: Debug {
+    /// Format the header as the value of its header field.
+    fn fmt_header(&self, fmt: &mut Formatter) -> fmt::Result;
+}
+
+/// Displays a typed header as the value of its header field.
+pub struct HeaderFormatter<'a, H: 'a>(pub &'a H);
+
+impl<'a, H> Display for HeaderFormatter<'a, H>
+    where H: HeaderFormat
+{
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        self.0.fmt_header(fmt)
+    }
+}
 
 /// Trait for viewing the contents of a header structure.
+///
+/// Raw and string views borrow from the stored header fields, while typed headers
+/// are parsed into owned values on each call to `get`.
 pub trait HeaderRef: Debug {
-    /// View a reference to a header field if it exists.
-    fn get<H>(&self) -> Option<&H> where H: Header + HeaderFormat;
+    /// Parse a header field into its typed header if it exists and is valid.
+    fn get<H>(&self) -> Option<H> where H: Header + HeaderFormat;
 
     /// View a reference to the raw bytes of a header field if it exists.
     fn get_raw(&self, name: &str) -> Option<&[Vec<u8>]>;
+
+    /// View the value of a header field holding a single string, such as LOCATION
+    /// or SERVER, without copying or parsing it.
+    ///
+    /// Prefer this over `get` in hot paths where only the text of a field is needed.
+    fn get_str(&self, name: &str) -> Option<&str> {
+        get_str(self, name)
+    }
+
+    /// Iterate over all of the header fields, in the order they were set or received.
+    fn iter_headers(&self) -> HeadersItems;
+
+    /// Copy the header fields into an `http::HeaderMap`, skipping fields that
+    /// the `http` crate does not allow.
+    #[cfg(feature = "http")]
+    fn to_http_headers(&self) -> HttpHeaderMap {
+        interop::to_http(self)
+    }
 }
 
 impl<'a, T: ?Sized> HeaderRef for &'a T
     where T: HeaderRef
 {
-    fn get<H>(&self) -> Option<&H>
+    fn get<H>(&self) -> Option<H>
         where H: Header + HeaderFormat
     {
         HeaderRef::get::<H>(*self)
@@ -55,12 +217,16 @@ impl<'a, T: ?Sized> HeaderRef for &'a T
     fn get_raw(&self, name: &str) -> Option<&[Vec<u8>]> {
         HeaderRef::get_raw(*self, name)
     }
+
+    fn iter_headers(&self) -> HeadersItems {
+        HeaderRef::iter_headers(*self)
+    }
 }
 
 impl<'a, T: ?Sized> HeaderRef for &'a mut T
     where T: HeaderRef
 {
-    fn get<H>(&self) -> Option<&H>
+    fn get<H>(&self) -> Option<H>
         where H: Header + HeaderFormat
     {
         HeaderRef::get::<H>(*self)
@@ -69,17 +235,25 @@ impl<'a, T: ?Sized> HeaderRef for &'a mut T
     fn get_raw(&self, name: &str) -> Option<&[Vec<u8>]> {
         HeaderRef::get_raw(*self, name)
     }
+
+    fn iter_headers(&self) -> HeadersItems {
+        HeaderRef::iter_headers(*self)
+    }
 }
 
-impl HeaderRef for Headers {
-    fn get<H>(&self) -> Option<&H>
+impl HeaderRef for HeaderMap {
+    fn get<H>(&self) -> Option<H>
         where H: Header + HeaderFormat
     {
-        Headers::get::<H>(self)
+        HeaderMap::get_raw(self, H::header_name()).and_then(|raw| H::parse_header(raw).ok())
     }
 
     fn get_raw(&self, name: &str) -> Option<&[Vec<u8>]> {
-        Headers::get_raw(self, name)
+        HeaderMap::get_raw(self, name)
+    }
+
+    fn iter_headers(&self) -> HeadersItems {
+        HeaderMap::iter(self)
     }
 }
 
@@ -90,6 +264,26 @@ pub trait HeaderMut: Debug {
 
     /// Set a header to the given raw bytes.
     fn set_raw<K>(&mut self, name: K, value: Vec<Vec<u8>>) where K: Into<Cow<'static, str>> + Debug;
+
+    /// Remove a header, returning whether or not it was present.
+    fn remove<H>(&mut self) -> bool where H: Header + HeaderFormat;
+
+    /// Remove a header by name, returning whether or not it was present.
+    fn remove_raw(&mut self, name: &str) -> bool;
+
+    /// Set the header fields from an `http::HeaderMap`, replacing any fields of the same name.
+    #[cfg(feature = "http")]
+    fn extend_from_http_headers(&mut self, http_headers: &HttpHeaderMap) {
+        interop::extend_from_http(self, http_headers)
+    }
+
+    /// Get the entry for the header field with the given name, for in place manipulation.
+    fn entry<K>(&mut self, name: K) -> HeaderEntry<Self>
+        where K: Into<Cow<'static, str>>,
+              Self: HeaderRef + Sized
+    {
+        HeaderEntry::new(self, name.into())
+    }
 }
 
 impl<'a, T: ?Sized> HeaderMut for &'a mut T
@@ -106,74 +300,113 @@ impl<'a, T: ?Sized> HeaderMut for &'a mut T
     {
         HeaderMut::set_raw(*self, name, value)
     }
+
+    fn remove<H>(&mut self) -> bool
+        where H: Header + HeaderFormat
+    {
+        HeaderMut::remove::<H>(*self)
+    }
+
+    fn remove_raw(&mut self, name: &str) -> bool {
+        HeaderMut::remove_raw(*self, name)
+    }
 }
 
-impl HeaderMut for Headers {
+impl HeaderMut for HeaderMap {
     fn set<H>(&mut self, value: H)
         where H: Header + HeaderFormat
     {
-        Headers::set(self, value)
+        let value_string = HeaderFormatter(&value).to_string();
+
+        HeaderMap::set_raw(self, H::header_name(), vec![value_string.into_bytes()])
     }
 
     fn set_raw<K>(&mut self, name: K, value: Vec<Vec<u8>>)
         where K: Into<Cow<'static, str>> + Debug
     {
-        Headers::set_raw(self, name, value)
-    }
-}
-
-// #[cfg(test)]
-// pub mod mock {
-// use std::any::{Any};
-// use std::borrow::{ToOwned};
-// use std::clone::{Clone};
-// use std::collections::{HashMap};
-//
-// use hyper::header::{Header, HeaderFormat};
-//
-// use ssdp::header::{HeaderView};
-//
-// #[derive(Debug)]
-// pub struct MockHeaderView {
-// map: HashMap<&'static str, (Box<Any>, [Vec<u8>; 1])>
-// }
-//
-// impl MockHeaderView {
-// pub fn new() -> MockHeaderView {
-// MockHeaderView{ map: HashMap::new() }
-// }
-//
-// pub fn insert<H>(&mut self, value: &str) where H: Header + HeaderFormat {
-// let header_bytes = [value.to_owned().into_bytes()];
-//
-// let header = match H::parse_header(&header_bytes[..]) {
-// Some(n) => n,
-// None    => panic!("Failed To Parse value As Header!!!")
-// };
-//
-// self.map.insert(H::header_name(), (Box::new(header), header_bytes));
-// }
-// }
-//
-// impl Clone for MockHeaderView {
-// fn clone(&self) -> MockHeaderView {
-// panic!("Can Not Clone A MockHeaderView")
-// }
-// }
-//
-// impl HeaderView for MockHeaderView {
-// fn view<H>(&self) -> Option<&H> where H: Header + HeaderFormat {
-// match self.map.get(H::header_name()) {
-// Some(&(ref header, _)) => header.downcast_ref::<H>(),
-// None => None
-// }
-// }
-//
-// fn view_raw(&self, name: &str) -> Option<&[Vec<u8>]> {
-// match self.map.get(name) {
-// Some(&(_, ref header_bytes)) => Some(header_bytes),
-// None => None
-// }
-// }
-// }
-// }
+        HeaderMap::set_raw(self, name, value)
+    }
+
+    fn remove<H>(&mut self) -> bool
+        where H: Header + HeaderFormat
+    {
+        HeaderMap::remove_raw(self, H::header_name())
+    }
+
+    fn remove_raw(&mut self, name: &str) -> bool {
+        HeaderMap::remove_raw(self, name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{HeaderMap, HeaderRef};
+
+    fn max_age(value: &str) -> Option<Duration> {
+        let mut headers = HeaderMap::new();
+        headers.set_raw("CACHE-CONTROL", vec![value.as_bytes().to_vec()]);
+
+        super::max_age(&headers)
+    }
+
+    #[test]
+    fn positive_get_str_borrows() {
+        let mut headers = HeaderMap::new();
+        headers.set_raw("LOCATION", vec![b" http://192.168.0.2/desc.xml ".to_vec()]);
+        headers.set_raw("X-MULTI", vec![b"1".to_vec(), b"2".to_vec()]);
+
+        let location = headers.get_str("location").unwrap();
+        assert_eq!(location, "http://192.168.0.2/desc.xml");
+        assert_eq!(location.as_ptr(), headers.get_raw("LOCATION").unwrap()[0][1..].as_ptr());
+        assert_eq!(headers.get_str("X-MULTI"), None);
+    }
+
+    #[test]
+    fn positive_max_age() {
+        assert_eq!(max_age("max-age=1800"), Some(Duration::from_secs(1800)));
+    }
+
+    #[test]
+    fn positive_max_age_extra_directives() {
+        assert_eq!(max_age("no-cache, max-age=1800, private"), Some(Duration::from_secs(1800)));
+    }
+
+    #[test]
+    fn positive_max_age_whitespace_case_and_quotes() {
+        assert_eq!(max_age("MAX-AGE = 1800"), Some(Duration::from_secs(1800)));
+        assert_eq!(max_age("max-age=\"1800\""), Some(Duration::from_secs(1800)));
+    }
+
+    #[test]
+    #[cfg(feature = "url")]
+    fn positive_location_url() {
+        let mut headers = HeaderMap::new();
+        headers.set_raw("LOCATION", vec![b"http://192.168.0.2:80/desc.xml".to_vec()]);
+
+        assert_eq!(super::strict_location_url(&headers).unwrap().as_str(),
+                   "http://192.168.0.2/desc.xml");
+    }
+
+    #[test]
+    #[cfg(feature = "url")]
+    fn negative_location_url() {
+        let mut headers = HeaderMap::new();
+        assert!(super::location_url(&headers).is_err());
+
+        headers.set_raw("LOCATION", vec![b"/desc.xml".to_vec()]);
+        assert!(super::location_url(&headers).is_err());
+
+        headers.set_raw("LOCATION", vec![b"ftp://192.168.0.2/desc.xml".to_vec()]);
+        assert!(super::location_url(&headers).is_ok());
+        assert!(super::strict_location_url(&headers).is_err());
+    }
+
+    #[test]
+    fn negative_max_age_missing() {
+        assert_eq!(max_age("no-cache"), None);
+        assert_eq!(max_age("max-age="), None);
+        assert_eq!(max_age("max-age=soon"), None);
+    }
+}
@@ -1,11 +1,9 @@
 use std::fmt::{Formatter, Result};
 
-use hyper::error::{self, Error};
-use hyper::header::{HeaderFormat, Header};
+use {SSDPError, SSDPResult};
+use header::{HeaderFormat, Header, names};
 
-use {SSDPResult, SSDPError};
-
-const MX_HEADER_NAME: &'static str = "MX";
+const MX_HEADER_NAME: &'static str = names::MX;
 
 /// Minimum wait time specified in the `UPnP` 1.0 standard.
 pub const MX_HEADER_MIN: u8 = 1;
@@ -13,6 +11,9 @@ pub const MX_HEADER_MIN: u8 = 1;
 /// Maximum wait time specified in the `UPnP` 1.0 standard.
 pub const MX_HEADER_MAX: u8 = 120;
 
+/// Maximum wait time specified in the `UPnP` 1.1 standard.
+pub const MX_HEADER_UDA11_MAX: u8 = 5;
+
 /// Represents a header used to specify the maximum time that devices should wait
 /// before sending a response.
 ///
@@ -32,6 +33,16 @@ impl MX {
             Ok(MX(wait_bound))
         }
     }
+
+    /// Construct an MX header, validating the wait bound against the tighter
+    /// range specified in the `UPnP` 1.1 standard.
+    pub fn strict(wait_bound: u8) -> SSDPResult<MX> {
+        if !(MX_HEADER_MIN..=MX_HEADER_UDA11_MAX).contains(&wait_bound) {
+            Err(SSDPError::InvalidHeader(MX_HEADER_NAME, "Supplied Wait Bound Is Out Of UPnP 1.1 Bounds"))
+        } else {
+            Ok(MX(wait_bound))
+        }
+    }
 }
 
 impl Header for MX {
@@ -39,16 +50,16 @@ impl Header for MX {
         MX_HEADER_NAME
     }
 
-    fn parse_header(raw: &[Vec<u8>]) -> error::Result<Self> {
+    fn parse_header(raw: &[Vec<u8>]) -> SSDPResult<Self> {
         if raw.len() != 1 {
-            return Err(Error::Header);
+            return Err(SSDPError::InvalidHeader(MX_HEADER_NAME, "Expected A Single Value"));
         }
 
         let cow_string = String::from_utf8_lossy(&raw[0][..]);
 
         match u8::from_str_radix(&cow_string, 10) {
             Ok(n) if n >= MX_HEADER_MIN && n <= MX_HEADER_MAX => Ok(MX(n)),
-            _ => Err(Error::Header),
+            _ => Err(SSDPError::InvalidHeader(MX_HEADER_NAME, "Invalid Value")),
         }
     }
 }
@@ -63,7 +74,7 @@ impl HeaderFormat for MX {
 
 #[cfg(test)]
 mod tests {
-    use hyper::header::Header;
+    use header::Header;
 
     use super::MX;
 
@@ -72,7 +83,7 @@ mod tests {
         let mx_lower_header = &[b"1"[..].to_vec()];
 
         match MX::parse_header(mx_lower_header) {
-            Ok(n) if n == MX(1) => (),
+            Ok(MX(1)) => (),
             _ => panic!("Failed To Accept 1 As MX Value"),
         };
     }
@@ -82,7 +93,7 @@ mod tests {
         let mx_inner_header = &[b"5"[..].to_vec()];
 
         match MX::parse_header(mx_inner_header) {
-            Ok(n) if n == MX(5) => (),
+            Ok(MX(5)) => (),
             _ => panic!("Failed To Accept 5 As MX Value"),
         };
     }
@@ -92,7 +103,7 @@ mod tests {
         let mx_upper_header = &[b"120"[..].to_vec()];
 
         match MX::parse_header(mx_upper_header) {
-            Ok(n) if n == MX(120) => (),
+            Ok(MX(120)) => (),
             _ => panic!("Failed To Accept 120 As MX Value"),
         };
     }
@@ -121,6 +132,17 @@ mod tests {
         MX::parse_header(mx_too_high_header).unwrap();
     }
 
+    #[test]
+    fn positive_strict_upper_bound() {
+        assert_eq!(MX::strict(5).unwrap(), MX(5));
+    }
+
+    #[test]
+    fn negative_strict_too_high_bound() {
+        assert!(MX::strict(6).is_err());
+        assert!(MX::new(6).is_ok());
+    }
+
     #[test]
     #[should_panic]
     fn negative_zero_bound() {
@@ -0,0 +1,83 @@
+//! Names of the standard SSDP header fields, for use with the raw `get_raw` and
+//! `set_raw` API.
+//!
+//! Header names are matched case-insensitively, these are the canonical forms
+//! used when sending messages.
+
+/// Name of the HOST header.
+pub const HOST: &'static str = "HOST";
+/// Name of the ST header, holding the search target.
+pub const ST: &'static str = "ST";
+/// Name of the NT header, holding the notification type.
+pub const NT: &'static str = "NT";
+/// Name of the NTS header, holding the notification sub type.
+pub const NTS: &'static str = "NTS";
+/// Name of the USN header, holding the unique service name.
+pub const USN: &'static str = "USN";
+/// Name of the MX header, holding the maximum wait time for responses.
+pub const MX: &'static str = "MX";
+/// Name of the MAN header, holding the mandatory extension of a search.
+pub const MAN: &'static str = "MAN";
+/// Name of the EXT header, confirming that the MAN header was understood.
+pub const EXT: &'static str = "EXT";
+/// Name of the CACHE-CONTROL header, holding the max-age of an announcement.
+pub const CACHE_CONTROL: &'static str = "CACHE-CONTROL";
+/// Name of the LOCATION header, holding the URL of the device description.
+pub const LOCATION: &'static str = "LOCATION";
+/// Name of the SERVER header, holding the product tokens of a device.
+pub const SERVER: &'static str = "SERVER";
+/// Name of the USER-AGENT header, holding the product tokens of a control point.
+pub const USER_AGENT: &'static str = "USER-AGENT";
+/// Name of the DATE header.
+pub const DATE: &'static str = "DATE";
+/// Name of the BOOTID.UPNP.ORG header.
+pub const BOOTID: &'static str = "BOOTID.UPNP.ORG";
+/// Name of the NEXTBOOTID.UPNP.ORG header.
+pub const NEXTBOOTID: &'static str = "NEXTBOOTID.UPNP.ORG";
+/// Name of the CONFIGID.UPNP.ORG header.
+pub const CONFIGID: &'static str = "CONFIGID.UPNP.ORG";
+/// Name of the SEARCHPORT.UPNP.ORG header.
+pub const SEARCHPORT: &'static str = "SEARCHPORT.UPNP.ORG";
+/// Name of the SECURELOCATION.UPNP.ORG header.
+pub const SECURELOCATION: &'static str = "SECURELOCATION.UPNP.ORG";
+/// Name of the TCPPORT.UPNP.ORG header.
+pub const TCPPORT: &'static str = "TCPPORT.UPNP.ORG";
+/// Name of the 01-NLS header, holding the Network Location Signature sent by Windows.
+pub const NLS: &'static str = "01-NLS";
+/// Name of the NLS header, as sent by devices that leave out the namespace prefix.
+pub const UNPREFIXED_NLS: &'static str = "NLS";
+/// Name of the CPFN.UPNP.ORG header, holding the friendly name of a control point.
+pub const CPFN: &'static str = "CPFN.UPNP.ORG";
+/// Name of the CPUUID.UPNP.ORG header, holding the UUID of a control point.
+pub const CPUUID: &'static str = "CPUUID.UPNP.ORG";
+
+#[cfg(test)]
+mod tests {
+    use header::{Header, BootID, CacheControl, ConfigID, CPFN, CPUUID, Date, Host, Location, Man, MX,
+                 NextBootID, NLS, NT, NTS, SearchPort, SecureLocation, Server, TcpPort, UserAgent, ST, USN};
+
+    #[test]
+    fn positive_typed_header_names() {
+        assert_eq!(super::HOST, Host::header_name());
+        assert_eq!(super::ST, ST::header_name());
+        assert_eq!(super::NT, NT::header_name());
+        assert_eq!(super::NTS, NTS::header_name());
+        assert_eq!(super::USN, USN::header_name());
+        assert_eq!(super::MX, MX::header_name());
+        assert_eq!(super::MAN, Man::header_name());
+        assert_eq!(super::CACHE_CONTROL, CacheControl::header_name());
+        assert_eq!(super::LOCATION, Location::header_name());
+        assert_eq!(super::SERVER, Server::header_name());
+        assert_eq!(super::USER_AGENT, UserAgent::header_name());
+        assert_eq!(super::DATE, Date::header_name());
+        assert_eq!(super::BOOTID, BootID::header_name());
+        assert_eq!(super::NEXTBOOTID, NextBootID::header_name());
+        assert_eq!(super::CONFIGID, ConfigID::header_name());
+        assert_eq!(super::SEARCHPORT, SearchPort::header_name());
+        assert_eq!(super::SECURELOCATION, SecureLocation::header_name());
+        assert_eq!(super::TCPPORT, TcpPort::header_name());
+        assert_eq!(super::NLS, NLS::header_name());
+        assert_eq!(super::CPFN, CPFN::header_name());
+        assert_eq!(super::CPUUID, CPUUID::header_name());
+    }
+}
@@ -0,0 +1,84 @@
+use std::fmt::{Formatter, Result};
+
+use {SSDPError, SSDPResult};
+use header::{HeaderFormat, Header, names};
+
+const NEXTBOOTID_HEADER_NAME: &'static str = names::NEXTBOOTID;
+
+/// Represents a header used to denote the boot instance that a root device will
+/// use after sending an `ssdp:update` message.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct NextBootID(pub u32);
+
+impl Header for NextBootID {
+    fn header_name() -> &'static str {
+        NEXTBOOTID_HEADER_NAME
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> SSDPResult<Self> {
+        if raw.len() != 1 {
+            return Err(SSDPError::InvalidHeader(NEXTBOOTID_HEADER_NAME, "Expected A Single Value"));
+        }
+
+        let cow_str = String::from_utf8_lossy(&raw[0][..]);
+
+        // Value needs to be a 31 bit non-negative integer, so convert to i32
+        let value = match i32::from_str_radix(&*cow_str, 10) {
+            Ok(n) => n,
+            Err(_) => return Err(SSDPError::InvalidHeader(NEXTBOOTID_HEADER_NAME, "Not A Number")),
+        };
+
+        // Check if value is negative, then convert to u32
+        if value.is_negative() {
+            Err(SSDPError::InvalidHeader(NEXTBOOTID_HEADER_NAME, "Out Of Range"))
+        } else {
+            Ok(NextBootID(value as u32))
+        }
+    }
+}
+
+impl HeaderFormat for NextBootID {
+    fn fmt_header(&self, fmt: &mut Formatter) -> Result {
+        try!(fmt.write_fmt(format_args!("{}", self.0)));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use header::Header;
+
+    use super::NextBootID;
+
+    #[test]
+    fn positive_nextbootid() {
+        let nextbootid_header_value = &[b"1216907401"[..].to_vec()];
+
+        assert_eq!(NextBootID::parse_header(nextbootid_header_value).unwrap(),
+                   NextBootID(1216907401));
+    }
+
+    #[test]
+    fn positive_upper_bound() {
+        let nextbootid_header_value = &[b"2147483647"[..].to_vec()];
+
+        NextBootID::parse_header(nextbootid_header_value).unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn negative_overflow() {
+        let nextbootid_header_value = &[b"2290649224"[..].to_vec()];
+
+        NextBootID::parse_header(nextbootid_header_value).unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn negative_negative() {
+        let nextbootid_header_value = &[b"-1"[..].to_vec()];
+
+        NextBootID::parse_header(nextbootid_header_value).unwrap();
+    }
+}
@@ -0,0 +1,80 @@
+use std::fmt::{Formatter, Result};
+
+use {SSDPError, SSDPResult};
+use header::{HeaderFormat, Header, names};
+
+const NLS_HEADER_NAME: &'static str = names::NLS;
+
+/// Represents a header used by Windows to carry the Network Location Signature
+/// of the announcing device, a hexadecimal identifier that changes whenever the
+/// device reconnects to the network.
+///
+/// Sent as "01-NLS", in the namespace declared by the accompanying OPT header,
+/// although some devices send it as a plain "NLS" header.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct NLS(pub String);
+
+impl Header for NLS {
+    fn header_name() -> &'static str {
+        NLS_HEADER_NAME
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> SSDPResult<Self> {
+        if raw.len() != 1 {
+            return Err(SSDPError::InvalidHeader(NLS_HEADER_NAME, "Expected A Single Value"));
+        }
+
+        let cow_str = String::from_utf8_lossy(&raw[0][..]);
+        let value = cow_str.trim();
+
+        if !is_signature(value) {
+            Err(SSDPError::InvalidHeader(NLS_HEADER_NAME, "Expected A Hexadecimal Value"))
+        } else {
+            Ok(NLS(value.to_owned()))
+        }
+    }
+}
+
+impl HeaderFormat for NLS {
+    fn fmt_header(&self, fmt: &mut Formatter) -> Result {
+        try!(fmt.write_str(&self.0));
+
+        Ok(())
+    }
+}
+
+/// Whether or not the value is a valid Network Location Signature, a non empty
+/// hexadecimal string.
+pub fn is_signature(value: &str) -> bool {
+    !value.is_empty() && value.bytes().all(|byte| byte.is_ascii_hexdigit())
+}
+
+#[cfg(test)]
+mod tests {
+    use header::Header;
+
+    use super::NLS;
+
+    #[test]
+    fn positive_nls() {
+        let nls_header_value = &[b"c3a16b8e9fd4a0f0d6b3e4b84b1e97ae"[..].to_vec()];
+
+        NLS::parse_header(nls_header_value).unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn negative_empty() {
+        let nls_header_value = &[b""[..].to_vec()];
+
+        NLS::parse_header(nls_header_value).unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn negative_not_hex() {
+        let nls_header_value = &[b"not-a-signature"[..].to_vec()];
+
+        NLS::parse_header(nls_header_value).unwrap();
+    }
+}
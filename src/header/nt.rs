@@ -1,11 +1,16 @@
 use std::fmt::{Formatter, Display, Result};
 
-use hyper::error::{self, Error};
-use hyper::header::{HeaderFormat, Header};
+#[cfg(feature = "uuid")]
+use uuid::Uuid;
+
+use {SSDPError, SSDPResult};
+use header::{HeaderFormat, Header, names};
 
 use FieldMap;
+use header::ST;
+use target::SearchTarget;
 
-const NT_HEADER_NAME: &'static str = "NT";
+const NT_HEADER_NAME: &'static str = names::NT;
 
 /// Represents a header used to specify a notification type.
 ///
@@ -17,6 +22,31 @@ impl NT {
     pub fn new(field: FieldMap) -> NT {
         NT(field)
     }
+
+    /// Construct a notification type for the device with the UUID.
+    #[cfg(feature = "uuid")]
+    pub fn from_uuid(uuid: Uuid) -> NT {
+        NT(FieldMap::from_uuid(uuid))
+    }
+
+    /// UUID of the device parsed as a `Uuid`, validating that the header holds a
+    /// "uuid" field with a well formed UUID.
+    #[cfg(feature = "uuid")]
+    pub fn uuid(&self) -> SSDPResult<Uuid> {
+        match self.0 {
+            FieldMap::UUID(_) => {
+                self.0.to_uuid().ok_or(SSDPError::InvalidHeader(NT_HEADER_NAME, "Invalid UUID"))
+            }
+            _ => Err(SSDPError::InvalidHeader(NT_HEADER_NAME, "Expected A UUID Field")),
+        }
+    }
+
+    /// Notification type as a `SearchTarget`, the same domain as search targets.
+    ///
+    /// Returns None if the header does not hold a target defined by the standard.
+    pub fn target(&self) -> Option<SearchTarget> {
+        SearchTarget::from_st(&ST::Target(self.0.clone()))
+    }
 }
 
 impl Header for NT {
@@ -24,14 +54,14 @@ impl Header for NT {
         NT_HEADER_NAME
     }
 
-    fn parse_header(raw: &[Vec<u8>]) -> error::Result<Self> {
+    fn parse_header(raw: &[Vec<u8>]) -> SSDPResult<Self> {
         if raw.len() != 1 {
-            return Err(Error::Header);
+            return Err(SSDPError::InvalidHeader(NT_HEADER_NAME, "Expected A Single Value"));
         }
 
         match FieldMap::parse_bytes(&raw[0][..]) {
             Some(n) => Ok(NT(n)),
-            None => Err(Error::Header),
+            None => Err(SSDPError::InvalidHeader(NT_HEADER_NAME, "Invalid Value")),
         }
     }
 }
@@ -46,10 +76,28 @@ impl HeaderFormat for NT {
 
 #[cfg(test)]
 mod tests {
-    use hyper::header::Header;
+    use header::Header;
 
     use super::NT;
-    use FieldMap::{UPnP, UUID, URN, Unknown};
+    use FieldMap::{self, UPnP, UUID, URN, Unknown};
+    use target::SearchTarget;
+
+    #[test]
+    #[cfg(feature = "uuid")]
+    fn positive_from_uuid() {
+        let uuid = "2fac1234-31f8-11b4-a222-08002b34c003".parse::<::uuid::Uuid>().unwrap();
+
+        assert_eq!(NT::from_uuid(uuid).uuid().unwrap(), uuid);
+        assert!(NT(FieldMap::upnp("rootdevice")).uuid().is_err());
+    }
+
+    #[test]
+    fn positive_target() {
+        assert_eq!(NT(FieldMap::upnp("rootdevice")).target(), Some(SearchTarget::RootDevice));
+        assert_eq!(NT(FieldMap::urn("schemas-upnp-org:device:MediaServer:1")).target(),
+                   Some(SearchTarget::device("MediaServer", 1)));
+        assert_eq!(NT(FieldMap::upnp("other")).target(), None);
+    }
 
     #[test]
     fn positive_uuid() {
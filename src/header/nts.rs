@@ -1,9 +1,9 @@
 use std::fmt::{Formatter, Result};
 
-use hyper::error::{self, Error};
-use hyper::header::{HeaderFormat, Header};
+use {SSDPError, SSDPResult};
+use header::{HeaderFormat, Header, names};
 
-const NTS_HEADER_NAME: &'static str = "NTS";
+const NTS_HEADER_NAME: &'static str = names::NTS;
 
 const ALIVE_HEADER: &'static str = "ssdp:alive";
 const UPDATE_HEADER: &'static str = "ssdp:update";
@@ -28,9 +28,9 @@ impl Header for NTS {
         NTS_HEADER_NAME
     }
 
-    fn parse_header(raw: &[Vec<u8>]) -> error::Result<Self> {
+    fn parse_header(raw: &[Vec<u8>]) -> SSDPResult<Self> {
         if raw.len() != 1 {
-            return Err(Error::Header);
+            return Err(SSDPError::InvalidHeader(NTS_HEADER_NAME, "Expected A Single Value"));
         }
 
         if &raw[0][..] == ALIVE_HEADER.as_bytes() {
@@ -40,7 +40,7 @@ impl Header for NTS {
         } else if &raw[0][..] == BYEBYE_HEADER.as_bytes() {
             Ok(NTS::ByeBye)
         } else {
-            Err(Error::Header)
+            Err(SSDPError::InvalidHeader(NTS_HEADER_NAME, "Invalid Value"))
         }
     }
 }
@@ -59,7 +59,7 @@ impl HeaderFormat for NTS {
 
 #[cfg(test)]
 mod tests {
-    use hyper::header::Header;
+    use header::Header;
 
     use super::NTS;
 
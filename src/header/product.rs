@@ -0,0 +1,240 @@
+use std::env;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use SSDPError;
+
+/// Name of the product used by default, this crate.
+const DEFAULT_PRODUCT_NAME: &'static str = "ssdp";
+
+/// Version of the product used by default, this crate.
+const DEFAULT_PRODUCT_VERSION: &'static str = env!("CARGO_PKG_VERSION");
+
+/// Version of the operating system used by default, since it cannot be queried portably.
+const DEFAULT_OS_VERSION: &'static str = "unknown";
+
+/// Name of the token holding the version of the `UPnP` standard.
+const UPNP_TOKEN_NAME: &'static str = "UPnP";
+
+/// Represents the product tokens sent in USER-AGENT and SERVER headers, in the
+/// `OS/version UPnP/1.1 product/version` format specified in the `UPnP` 1.1 standard.
+///
+/// Some devices ignore searches with a missing or malformed USER-AGENT header.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct ProductTokens {
+    os_name: String,
+    os_version: String,
+    upnp_major: u8,
+    upnp_minor: u8,
+    product_name: String,
+    product_version: String,
+}
+
+impl ProductTokens {
+    /// Construct ProductTokens for the given product, running on the host
+    /// operating system and implementing `UPnP` 1.1.
+    pub fn new<N, V>(product_name: N, product_version: V) -> ProductTokens
+        where N: Into<String>,
+              V: Into<String>
+    {
+        ProductTokens {
+            os_name: env::consts::OS.to_owned(),
+            os_version: DEFAULT_OS_VERSION.to_owned(),
+            upnp_major: 1,
+            upnp_minor: 1,
+            product_name: product_name.into(),
+            product_version: product_version.into(),
+        }
+    }
+
+    /// Set the operating system name and version, in place of the host operating
+    /// system name and an unknown version.
+    pub fn os<N, V>(mut self, name: N, version: V) -> ProductTokens
+        where N: Into<String>,
+              V: Into<String>
+    {
+        self.os_name = name.into();
+        self.os_version = version.into();
+        self
+    }
+
+    /// Set the version of the `UPnP` standard implemented.
+    pub fn upnp_version(mut self, major: u8, minor: u8) -> ProductTokens {
+        self.upnp_major = major;
+        self.upnp_minor = minor;
+        self
+    }
+
+    /// Name of the operating system, such as "Linux".
+    pub fn os_name(&self) -> &str {
+        &self.os_name
+    }
+
+    /// Version of the operating system, empty if it was not given.
+    pub fn os_version(&self) -> &str {
+        &self.os_version
+    }
+
+    /// Major and minor version of the `UPnP` standard implemented.
+    pub fn upnp(&self) -> (u8, u8) {
+        (self.upnp_major, self.upnp_minor)
+    }
+
+    /// Name of the product, such as the firmware of a device.
+    pub fn product_name(&self) -> &str {
+        &self.product_name
+    }
+
+    /// Version of the product, empty if it was not given.
+    pub fn product_version(&self) -> &str {
+        &self.product_version
+    }
+}
+
+impl Default for ProductTokens {
+    fn default() -> Self {
+        ProductTokens::new(DEFAULT_PRODUCT_NAME, DEFAULT_PRODUCT_VERSION)
+    }
+}
+
+impl Display for ProductTokens {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_fmt(format_args!("{}/{} UPnP/{}.{} {}/{}",
+                                 self.os_name,
+                                 self.os_version,
+                                 self.upnp_major,
+                                 self.upnp_minor,
+                                 self.product_name,
+                                 self.product_version))
+    }
+}
+
+impl FromStr for ProductTokens {
+    type Err = SSDPError;
+
+    /// Parse product tokens from a SERVER or USER-AGENT header value.
+    ///
+    /// The tokens are split around the `UPnP/major.minor` token, so the operating
+    /// system and product may contain spaces, and commas between the tokens are
+    /// tolerated since devices commonly send them.
+    fn from_str(s: &str) -> Result<ProductTokens, SSDPError> {
+        let tokens: Vec<&str> = s.split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|token| !token.is_empty())
+            .collect();
+
+        let opt_upnp = tokens.iter().enumerate().filter_map(|(index, token)| {
+            let (name, version) = split_version(token);
+
+            if name.eq_ignore_ascii_case(UPNP_TOKEN_NAME) {
+                parse_upnp_version(version).map(|upnp| (index, upnp))
+            } else {
+                None
+            }
+        }).next();
+
+        let (index, (upnp_major, upnp_minor)) = match opt_upnp {
+            Some(n) => n,
+            None => return Err(SSDPError::InvalidHeader("SERVER", "Missing UPnP Version Token")),
+        };
+
+        let os = tokens[..index].join(" ");
+        let product = tokens[index + 1..].join(" ");
+        let (os_name, os_version) = split_version(&os);
+        let (product_name, product_version) = split_version(&product);
+
+        Ok(ProductTokens {
+            os_name: os_name.to_owned(),
+            os_version: os_version.to_owned(),
+            upnp_major: upnp_major,
+            upnp_minor: upnp_minor,
+            product_name: product_name.to_owned(),
+            product_version: product_version.to_owned(),
+        })
+    }
+}
+
+/// Split a token into its name and version at the last "/".
+fn split_version(token: &str) -> (&str, &str) {
+    match token.rfind('/') {
+        Some(index) => (&token[..index], &token[index + 1..]),
+        None => (token, ""),
+    }
+}
+
+/// Parse a "major.minor" version.
+fn parse_upnp_version(version: &str) -> Option<(u8, u8)> {
+    let mut parts = version.splitn(2, '.');
+
+    match (parts.next().map(str::parse), parts.next().map(str::parse)) {
+        (Some(Ok(major)), Some(Ok(minor))) => Some((major, minor)),
+        _ => None,
+    }
+}
+
+impl From<ProductTokens> for String {
+    fn from(tokens: ProductTokens) -> String {
+        tokens.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::ProductTokens;
+
+    #[test]
+    fn positive_product_tokens() {
+        let tokens = ProductTokens::new("Player", "2.1").os("Linux", "5.10");
+
+        assert_eq!(tokens.to_string(), "Linux/5.10 UPnP/1.1 Player/2.1");
+    }
+
+    #[test]
+    fn positive_upnp_version() {
+        let tokens = ProductTokens::new("Player", "2.1").os("Linux", "5.10").upnp_version(2, 0);
+
+        assert_eq!(tokens.to_string(), "Linux/5.10 UPnP/2.0 Player/2.1");
+    }
+
+    #[test]
+    fn positive_parse_product_tokens() {
+        let tokens: ProductTokens = "Linux/3.0 UPnP/1.0 Test/1.0".parse().unwrap();
+
+        assert_eq!(tokens.os_name(), "Linux");
+        assert_eq!(tokens.os_version(), "3.0");
+        assert_eq!(tokens.upnp(), (1, 0));
+        assert_eq!(tokens.product_name(), "Test");
+        assert_eq!(tokens.product_version(), "1.0");
+    }
+
+    #[test]
+    fn positive_parse_commas_and_spaces() {
+        let tokens: ProductTokens = "Linux, UPnP/1.0, Portable SDK for UPnP devices/1.6.19".parse().unwrap();
+
+        assert_eq!(tokens.os_name(), "Linux");
+        assert_eq!(tokens.os_version(), "");
+        assert_eq!(tokens.product_name(), "Portable SDK for UPnP devices");
+        assert_eq!(tokens.product_version(), "1.6.19");
+    }
+
+    #[test]
+    fn positive_parse_round_trip() {
+        let tokens = ProductTokens::new("Player", "2.1").os("Linux", "5.10");
+
+        assert_eq!(tokens.to_string().parse::<ProductTokens>().unwrap(), tokens);
+    }
+
+    #[test]
+    fn negative_parse_missing_upnp() {
+        assert!("Linux/3.0 Test/1.0".parse::<ProductTokens>().is_err());
+        assert!("Linux/3.0 UPnP/one Test/1.0".parse::<ProductTokens>().is_err());
+    }
+
+    #[test]
+    fn positive_default_product_tokens() {
+        let expected = format!("{}/unknown UPnP/1.1 ssdp/{}", env::consts::OS, env!("CARGO_PKG_VERSION"));
+
+        assert_eq!(ProductTokens::default().to_string(), expected);
+    }
+}
@@ -1,9 +1,9 @@
 use std::fmt::{Formatter, Result};
 
-use hyper::error::{self, Error};
-use hyper::header::{HeaderFormat, Header};
+use {SSDPError, SSDPResult};
+use header::{HeaderFormat, Header, names};
 
-const SEARCHPORT_HEADER_NAME: &'static str = "SEARCHPORT.UPNP.ORG";
+const SEARCHPORT_HEADER_NAME: &'static str = names::SEARCHPORT;
 
 pub const SEARCHPORT_MIN_VALUE: u16 = 49152;
 
@@ -19,22 +19,22 @@ impl Header for SearchPort {
         SEARCHPORT_HEADER_NAME
     }
 
-    fn parse_header(raw: &[Vec<u8>]) -> error::Result<Self> {
+    fn parse_header(raw: &[Vec<u8>]) -> SSDPResult<Self> {
         if raw.len() != 1 {
-            return Err(Error::Header);
+            return Err(SSDPError::InvalidHeader(SEARCHPORT_HEADER_NAME, "Expected A Single Value"));
         }
 
         let cow_str = String::from_utf8_lossy(&raw[0][..]);
 
         let value = match u16::from_str_radix(&*cow_str, 10) {
             Ok(n) => n,
-            Err(_) => return Err(Error::Header),
+            Err(_) => return Err(SSDPError::InvalidHeader(SEARCHPORT_HEADER_NAME, "Not A Number")),
         };
 
         if value >= SEARCHPORT_MIN_VALUE {
             Ok(SearchPort(value))
         } else {
-            Err(Error::Header)
+            Err(SSDPError::InvalidHeader(SEARCHPORT_HEADER_NAME, "Out Of Range"))
         }
     }
 }
@@ -49,7 +49,7 @@ impl HeaderFormat for SearchPort {
 
 #[cfg(test)]
 mod tests {
-    use hyper::header::Header;
+    use header::Header;
 
     use super::SearchPort;
 
@@ -1,9 +1,9 @@
 use std::fmt::{Formatter, Result};
 
-use hyper::error::{self, Error};
-use hyper::header::{HeaderFormat, Header};
+use {SSDPError, SSDPResult};
+use header::{HeaderFormat, Header, names};
 
-const SECURELOCATION_HEADER_NAME: &'static str = "SECURELOCATION.UPNP.ORG";
+const SECURELOCATION_HEADER_NAME: &'static str = names::SECURELOCATION;
 
 /// Represents a header used to specify a secure url for a device's DDD.
 ///
@@ -16,16 +16,17 @@ impl Header for SecureLocation {
         SECURELOCATION_HEADER_NAME
     }
 
-    fn parse_header(raw: &[Vec<u8>]) -> error::Result<Self> {
+    fn parse_header(raw: &[Vec<u8>]) -> SSDPResult<Self> {
         if raw.len() != 1 || raw[0].is_empty() {
-            return Err(Error::Header);
+            return Err(SSDPError::InvalidHeader(SECURELOCATION_HEADER_NAME,
+                                                "Expected A Single Non Empty Value"));
         }
 
         let owned_bytes = raw[0].clone();
 
         match String::from_utf8(owned_bytes) {
             Ok(n) => Ok(SecureLocation(n)),
-            Err(_) => Err(Error::Header),
+            Err(_) => Err(SSDPError::InvalidHeader(SECURELOCATION_HEADER_NAME, "Invalid Value")),
         }
     }
 }
@@ -40,7 +41,7 @@ impl HeaderFormat for SecureLocation {
 
 #[cfg(test)]
 mod tests {
-    use hyper::header::Header;
+    use header::Header;
 
     use super::SecureLocation;
 
@@ -0,0 +1,59 @@
+use std::fmt::{Formatter, Result};
+
+use {SSDPError, SSDPResult};
+use header::{HeaderFormat, Header, names};
+
+const SERVER_HEADER_NAME: &'static str = names::SERVER;
+
+/// Represents a header used to specify the OS, `UPnP` version and product of a device,
+/// see `ProductTokens` for the parsed form.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Server(pub String);
+
+impl Header for Server {
+    fn header_name() -> &'static str {
+        SERVER_HEADER_NAME
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> SSDPResult<Self> {
+        if raw.len() != 1 {
+            return Err(SSDPError::InvalidHeader(SERVER_HEADER_NAME, "Expected A Single Value"));
+        }
+
+        match String::from_utf8(raw[0].clone()) {
+            Ok(n) => Ok(Server(n.trim().to_owned())),
+            Err(_) => Err(SSDPError::InvalidHeader(SERVER_HEADER_NAME, "Not Valid UTF-8")),
+        }
+    }
+}
+
+impl HeaderFormat for Server {
+    fn fmt_header(&self, fmt: &mut Formatter) -> Result {
+        try!(fmt.write_str(&self.0));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use header::Header;
+
+    use super::Server;
+
+    #[test]
+    fn positive_server() {
+        let server_header_value = &[b"Linux/5.10 UPnP/1.1 Test/1.0"[..].to_vec()];
+
+        assert_eq!(Server::parse_header(server_header_value).unwrap(),
+                   Server("Linux/5.10 UPnP/1.1 Test/1.0".to_owned()));
+    }
+
+    #[test]
+    #[should_panic]
+    fn negative_invalid_utf8() {
+        let server_header_value = &[b"Linux/5.10 UPnP/1.1 Test/1.0\x80"[..].to_vec()];
+
+        Server::parse_header(server_header_value).unwrap();
+    }
+}
@@ -1,11 +1,11 @@
 use std::fmt::{Formatter, Display, Result};
 
-use hyper::error::{self, Error};
-use hyper::header::{HeaderFormat, Header};
+use {SSDPError, SSDPResult};
+use header::{HeaderFormat, Header, names};
 
 use FieldMap;
 
-const ST_HEADER_NAME: &'static str = "ST";
+const ST_HEADER_NAME: &'static str = names::ST;
 
 const ST_ALL_VALUE: &'static str = "ssdp:all";
 
@@ -21,15 +21,17 @@ impl Header for ST {
         ST_HEADER_NAME
     }
 
-    fn parse_header(raw: &[Vec<u8>]) -> error::Result<Self> {
+    fn parse_header(raw: &[Vec<u8>]) -> SSDPResult<Self> {
         if raw.len() != 1 {
-            return Err(Error::Header);
+            return Err(SSDPError::InvalidHeader(ST_HEADER_NAME, "Expected A Single Value"));
         }
 
         if &raw[0][..] == ST_ALL_VALUE.as_bytes() {
             Ok(ST::All)
         } else {
-            FieldMap::parse_bytes(&raw[0][..]).map(ST::Target).ok_or(Error::Header)
+            FieldMap::parse_bytes(&raw[0][..])
+                .map(ST::Target)
+                .ok_or(SSDPError::InvalidHeader(ST_HEADER_NAME, "Invalid Value"))
         }
     }
 }
@@ -47,7 +49,7 @@ impl HeaderFormat for ST {
 
 #[cfg(test)]
 mod tests {
-    use hyper::header::Header;
+    use header::Header;
 
     use FieldMap;
     use super::ST;
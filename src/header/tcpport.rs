@@ -0,0 +1,83 @@
+use std::fmt::{Formatter, Result};
+
+use {SSDPError, SSDPResult};
+use header::{HeaderFormat, Header, names};
+
+const TCPPORT_HEADER_NAME: &'static str = names::TCPPORT;
+
+/// Represents a header used to specify the TCP port that a device or control
+/// point accepts `UPnP` Device Protection connections on.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct TcpPort(pub u16);
+
+impl Header for TcpPort {
+    fn header_name() -> &'static str {
+        TCPPORT_HEADER_NAME
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> SSDPResult<Self> {
+        if raw.len() != 1 {
+            return Err(SSDPError::InvalidHeader(TCPPORT_HEADER_NAME, "Expected A Single Value"));
+        }
+
+        let cow_str = String::from_utf8_lossy(&raw[0][..]);
+
+        match u16::from_str_radix(&*cow_str, 10) {
+            Ok(0) | Err(_) => Err(SSDPError::InvalidHeader(TCPPORT_HEADER_NAME, "Invalid Value")),
+            Ok(n) => Ok(TcpPort(n)),
+        }
+    }
+}
+
+impl HeaderFormat for TcpPort {
+    fn fmt_header(&self, fmt: &mut Formatter) -> Result {
+        try!(fmt.write_fmt(format_args!("{}", self.0)));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use header::Header;
+
+    use super::TcpPort;
+
+    #[test]
+    fn positive_tcpport() {
+        let tcpport_header_value = &[b"443"[..].to_vec()];
+
+        assert_eq!(TcpPort::parse_header(tcpport_header_value).unwrap(), TcpPort(443));
+    }
+
+    #[test]
+    fn positive_upper_bound() {
+        let tcpport_header_value = &[b"65535"[..].to_vec()];
+
+        TcpPort::parse_header(tcpport_header_value).unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn negative_zero() {
+        let tcpport_header_value = &[b"0"[..].to_vec()];
+
+        TcpPort::parse_header(tcpport_header_value).unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn negative_out_of_range() {
+        let tcpport_header_value = &[b"65536"[..].to_vec()];
+
+        TcpPort::parse_header(tcpport_header_value).unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn negative_nan() {
+        let tcpport_header_value = &[b"443a"[..].to_vec()];
+
+        TcpPort::parse_header(tcpport_header_value).unwrap();
+    }
+}
@@ -0,0 +1,59 @@
+use std::fmt::{Formatter, Result};
+
+use {SSDPError, SSDPResult};
+use header::{HeaderFormat, Header, names};
+
+const USER_AGENT_HEADER_NAME: &'static str = names::USER_AGENT;
+
+/// Represents a header used to specify the OS, `UPnP` version and product of the
+/// control point sending a search request.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct UserAgent(pub String);
+
+impl Header for UserAgent {
+    fn header_name() -> &'static str {
+        USER_AGENT_HEADER_NAME
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> SSDPResult<Self> {
+        if raw.len() != 1 {
+            return Err(SSDPError::InvalidHeader(USER_AGENT_HEADER_NAME, "Expected A Single Value"));
+        }
+
+        match String::from_utf8(raw[0].clone()) {
+            Ok(n) => Ok(UserAgent(n.trim().to_owned())),
+            Err(_) => Err(SSDPError::InvalidHeader(USER_AGENT_HEADER_NAME, "Not Valid UTF-8")),
+        }
+    }
+}
+
+impl HeaderFormat for UserAgent {
+    fn fmt_header(&self, fmt: &mut Formatter) -> Result {
+        try!(fmt.write_str(&self.0));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use header::Header;
+
+    use super::UserAgent;
+
+    #[test]
+    fn positive_useragent() {
+        let useragent_header_value = &[b"Linux/5.10 UPnP/1.1 Remote/1.0"[..].to_vec()];
+
+        assert_eq!(UserAgent::parse_header(useragent_header_value).unwrap(),
+                   UserAgent("Linux/5.10 UPnP/1.1 Remote/1.0".to_owned()));
+    }
+
+    #[test]
+    #[should_panic]
+    fn negative_invalid_utf8() {
+        let useragent_header_value = &[b"Linux/5.10 UPnP/1.1 Remote/1.0\x80"[..].to_vec()];
+
+        UserAgent::parse_header(useragent_header_value).unwrap();
+    }
+}
@@ -1,12 +1,17 @@
 use std::fmt::{Formatter, Display, Result};
 
-use hyper::error::{self, Error};
-use hyper::header::{HeaderFormat, Header};
+#[cfg(feature = "uuid")]
+use uuid::Uuid;
+
+use {SSDPError, SSDPResult};
+use header::{HeaderFormat, Header, names};
 
 use FieldMap;
 use field;
+use header::ST;
+use target::SearchTarget;
 
-const USN_HEADER_NAME: &'static str = "USN";
+const USN_HEADER_NAME: &'static str = names::USN;
 
 /// Separator for multiple key/values in header fields.
 const FIELD_PAIR_SEPARATOR: &'static str = "::";
@@ -21,6 +26,43 @@ impl USN {
     pub fn new(field: FieldMap, opt_field: Option<FieldMap>) -> USN {
         USN(field, opt_field)
     }
+
+    /// Construct a USN for the device with the UUID, followed by the target field if provided.
+    #[cfg(feature = "uuid")]
+    pub fn from_uuid(uuid: Uuid, opt_field: Option<FieldMap>) -> USN {
+        USN(FieldMap::from_uuid(uuid), opt_field)
+    }
+
+    /// UUID of the device, if the first field is a "uuid" field.
+    pub fn device_uuid(&self) -> Option<&str> {
+        match self.0 {
+            FieldMap::UUID(ref n) => Some(n),
+            _ => None,
+        }
+    }
+
+    /// UUID of the device parsed as a `Uuid`, validating that the first field is
+    /// a "uuid" field holding a well formed UUID.
+    #[cfg(feature = "uuid")]
+    pub fn uuid(&self) -> SSDPResult<Uuid> {
+        match self.0 {
+            FieldMap::UUID(_) => {
+                self.0.to_uuid().ok_or(SSDPError::InvalidHeader(USN_HEADER_NAME, "Invalid UUID"))
+            }
+            _ => Err(SSDPError::InvalidHeader(USN_HEADER_NAME, "Expected A UUID Field")),
+        }
+    }
+
+    /// Target that the USN identifies, which is the device itself when the USN
+    /// only holds a "uuid" field, or the target after the "::" separator otherwise.
+    ///
+    /// Returns None if the target is not a target defined by the standard.
+    pub fn target(&self) -> Option<SearchTarget> {
+        match self.1 {
+            Some(ref n) => SearchTarget::from_st(&ST::Target(n.clone())),
+            None => self.device_uuid().map(|uuid| SearchTarget::Uuid(uuid.to_owned())),
+        }
+    }
 }
 
 impl Header for USN {
@@ -28,20 +70,20 @@ impl Header for USN {
         USN_HEADER_NAME
     }
 
-    fn parse_header(raw: &[Vec<u8>]) -> error::Result<Self> {
+    fn parse_header(raw: &[Vec<u8>]) -> SSDPResult<Self> {
         if raw.len() != 1 {
-            return Err(Error::Header);
+            return Err(SSDPError::InvalidHeader(USN_HEADER_NAME, "Expected A Single Value"));
         }
 
         let (first, second) = match partition_pairs(raw[0][..].iter()) {
             Some((n, Some(u))) => (FieldMap::parse_bytes(&n[..]), FieldMap::parse_bytes(&u[..])),
             Some((n, None)) => (FieldMap::parse_bytes(&n[..]), None),
-            None => return Err(Error::Header),
+            None => return Err(SSDPError::InvalidHeader(USN_HEADER_NAME, "Invalid Value")),
         };
 
         match first {
             Some(n) => Ok(USN(n, second)),
-            None => Err(Error::Header),
+            None => Err(SSDPError::InvalidHeader(USN_HEADER_NAME, "Invalid Value")),
         }
     }
 }
@@ -100,10 +142,30 @@ fn partition_pairs<'a, I>(header_iter: I) -> Option<(Vec<u8>, Option<Vec<u8>>)>
 
 #[cfg(test)]
 mod tests {
-    use hyper::header::Header;
+    use header::Header;
 
     use super::USN;
     use FieldMap::{UPnP, UUID, URN, Unknown};
+    use target::SearchTarget;
+
+    #[test]
+    #[cfg(feature = "uuid")]
+    fn positive_from_uuid() {
+        let uuid = "2fac1234-31f8-11b4-a222-08002b34c003".parse::<::uuid::Uuid>().unwrap();
+        let usn = USN::from_uuid(uuid, Some(UPnP("rootdevice".to_owned())));
+
+        assert_eq!(::header::HeaderFormatter(&usn).to_string(),
+                   "uuid:2fac1234-31f8-11b4-a222-08002b34c003::upnp:rootdevice");
+        assert_eq!(usn.uuid().unwrap(), uuid);
+    }
+
+    #[test]
+    #[cfg(feature = "uuid")]
+    fn negative_malformed_uuid() {
+        let header = &["uuid:device-UUID::upnp:rootdevice".to_string().into_bytes()];
+
+        assert!(USN::parse_header(header).unwrap().uuid().is_err());
+    }
 
     #[test]
     fn positive_double_pair() {
@@ -163,6 +225,40 @@ mod tests {
         assert!(second.is_none());
     }
 
+    #[test]
+    fn positive_decompose_root_device() {
+        let usn = USN::parse_header(&[b"uuid:device-UUID::upnp:rootdevice".to_vec()]).unwrap();
+
+        assert_eq!(usn.device_uuid(), Some("device-UUID"));
+        assert_eq!(usn.target(), Some(SearchTarget::RootDevice));
+    }
+
+    #[test]
+    fn positive_decompose_urn() {
+        let usn = USN::parse_header(&[b"uuid:device-UUID::urn:schemas-upnp-org:service:ContentDirectory:1"
+                                           .to_vec()])
+            .unwrap();
+
+        assert_eq!(usn.device_uuid(), Some("device-UUID"));
+        assert_eq!(usn.target(), Some(SearchTarget::service("ContentDirectory", 1)));
+    }
+
+    #[test]
+    fn positive_decompose_uuid_only() {
+        let usn = USN::parse_header(&[b"uuid:device-UUID".to_vec()]).unwrap();
+
+        assert_eq!(usn.device_uuid(), Some("device-UUID"));
+        assert_eq!(usn.target(), Some(SearchTarget::Uuid("device-UUID".to_owned())));
+    }
+
+    #[test]
+    fn negative_decompose_no_uuid() {
+        let usn = USN::parse_header(&[b"urn:device-URN::some-key:value".to_vec()]).unwrap();
+
+        assert_eq!(usn.device_uuid(), None);
+        assert_eq!(usn.target(), None);
+    }
+
     #[test]
     #[should_panic]
     fn negative_empty() {
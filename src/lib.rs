@@ -8,22 +8,74 @@
 //! discover each other. SSDP can most commonly be found in devices that implement
 //! `UPnP` as it is used as the discovery mechanism for that standard.
 
-extern crate hyper;
 #[macro_use]
 extern crate log;
 extern crate time;
 #[cfg(not(windows))]
 extern crate ifaces;
+#[cfg(not(windows))]
+extern crate libc;
 extern crate net2;
+#[cfg(any(feature = "tokio", all(test, feature = "futures")))]
+extern crate tokio;
+#[cfg(feature = "async-io")]
+extern crate async_io;
+#[cfg(any(feature = "tokio", feature = "async-io", feature = "futures"))]
+extern crate futures_core;
+#[cfg(feature = "futures")]
+extern crate futures_channel;
+#[cfg(feature = "http")]
+extern crate http;
+#[cfg(feature = "mio")]
+extern crate mio;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_json;
+#[cfg(feature = "url")]
+extern crate url;
+#[cfg(feature = "uuid")]
+extern crate uuid;
 
+mod advertiser;
+mod bootid;
+mod device;
 mod error;
 mod field;
 mod net;
+mod queue;
 mod receiver;
+mod responder;
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+mod stream;
+mod target;
 
+pub mod compliance;
 pub mod header;
 pub mod message;
 
+pub use advertiser::{Advertiser, AdvertisementId};
+pub use bootid::{BootIdManager, BootIdStorage, FileStorage, MemoryStorage};
+pub use device::{DiscoveredDevice, DeviceGroup, group_by_device, group_responses};
 pub use error::{SSDPError, SSDPResult};
 pub use field::FieldMap;
-pub use receiver::{SSDPReceiver, SSDPIter};
+pub use message::{check_alive, parse_message, parse_message_with_mode, parse_message_with_quirks, search};
+pub use net::{InterfaceFilter, LocalSubnet, RawSocketHandle, SocketHook, SocketReuse, interface_index_by_name,
+              local_subnets};
+pub use queue::OverflowPolicy;
+pub use responder::{Responder, ResponseId, RateLimit};
+pub use target::{SearchTarget, target_matches};
+pub use receiver::{SSDPReceiver, SSDPIter, SSDPRefIter, ListenHandle, ReceiveError, ReceiveInfo, DedupKey,
+                    MalformedPacket, ReceiveHandlers};
+#[cfg(feature = "futures")]
+pub use receiver::SSDPReceiverStream;
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+pub use stream::SSDPStream;
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+pub use net::runtime::Runtime;
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+pub use net::packet::PacketDst;
+#[cfg(feature = "tokio")]
+pub use net::runtime::Tokio;
+#[cfg(feature = "async-io")]
+pub use net::runtime::AsyncIo;
@@ -0,0 +1,320 @@
+use FieldMap;
+use SSDPResult;
+use header::{ST, USN};
+use message::notify::{NotifyMessage, AliveBuilder};
+use message::search::SearchResponse;
+
+/// Device and its distinct service types, identified by the device UUID.
+#[derive(Debug, Clone)]
+struct AnnouncedDevice {
+    uuid: String,
+    device_type: FieldMap,
+    service_types: Vec<FieldMap>,
+}
+
+impl AnnouncedDevice {
+    fn new(uuid: String, device_type: FieldMap) -> AnnouncedDevice {
+        AnnouncedDevice {
+            uuid: uuid,
+            device_type: device_type,
+            service_types: Vec::new(),
+        }
+    }
+
+    fn add_service(&mut self, service_type: FieldMap) {
+        if !self.service_types.contains(&service_type) {
+            self.service_types.push(service_type);
+        }
+    }
+}
+
+/// Description of a root device, its embedded devices and their services, used
+/// to generate the full set of `NotifyMessage`s required by the `UPnP` standard
+/// as well as the `SearchResponse`s answering searches for the device.
+///
+/// The root device is announced three times (upnp:rootdevice, its uuid and its
+/// device type), each embedded device twice (its uuid and its device type) and
+/// each distinct service type of a device once.
+#[derive(Debug, Clone)]
+pub struct DeviceAnnouncement {
+    root: AnnouncedDevice,
+    embedded: Vec<AnnouncedDevice>,
+    location: Option<String>,
+    max_age: Option<u32>,
+    server: Option<String>,
+    secure_location: Option<String>,
+    tcp_port: Option<u16>,
+}
+
+impl DeviceAnnouncement {
+    /// Construct a new DeviceAnnouncement for the root device with the given uuid,
+    /// without the "uuid:" prefix, and device type (ex: urn:schemas-upnp-org:device:MediaServer:1).
+    pub fn new<S>(uuid: S, device_type: FieldMap) -> DeviceAnnouncement
+        where S: Into<String>
+    {
+        DeviceAnnouncement {
+            root: AnnouncedDevice::new(uuid.into(), device_type),
+            embedded: Vec::new(),
+            location: None,
+            max_age: None,
+            server: None,
+            secure_location: None,
+            tcp_port: None,
+        }
+    }
+
+    /// Add a service type provided by the root device.
+    pub fn service(mut self, service_type: FieldMap) -> DeviceAnnouncement {
+        self.root.add_service(service_type);
+        self
+    }
+
+    /// Add an embedded device along with the service types it provides.
+    pub fn embedded_device<S, I>(mut self,
+                                 uuid: S,
+                                 device_type: FieldMap,
+                                 service_types: I)
+                                 -> DeviceAnnouncement
+        where S: Into<String>,
+              I: IntoIterator<Item = FieldMap>
+    {
+        let mut device = AnnouncedDevice::new(uuid.into(), device_type);
+        for service_type in service_types {
+            device.add_service(service_type);
+        }
+
+        self.embedded.push(device);
+        self
+    }
+
+    /// Set the URL for the description of the root device.
+    ///
+    /// The URL may contain the `LOCATION_BIND_ADDR` placeholder, which is replaced
+    /// with the address of each local interface the message is sent from.
+    pub fn location<S>(mut self, location: S) -> DeviceAnnouncement
+        where S: Into<String>
+    {
+        self.location = Some(location.into());
+        self
+    }
+
+    /// Set the number of seconds that the announcements are valid for.
+    pub fn max_age(mut self, seconds: u32) -> DeviceAnnouncement {
+        self.max_age = Some(seconds);
+        self
+    }
+
+    /// Set the operating system, `UPnP` version and product of the device.
+    pub fn server<S>(mut self, server: S) -> DeviceAnnouncement
+        where S: Into<String>
+    {
+        self.server = Some(server.into());
+        self
+    }
+
+    /// Set the HTTPS URL for the description of the root device, for `UPnP`
+    /// Device Protection aware control points.
+    pub fn secure_location<S>(mut self, secure_location: S) -> DeviceAnnouncement
+        where S: Into<String>
+    {
+        self.secure_location = Some(secure_location.into());
+        self
+    }
+
+    /// Set the TCP port that the device accepts `UPnP` Device Protection connections on.
+    pub fn tcp_port(mut self, port: u16) -> DeviceAnnouncement {
+        self.tcp_port = Some(port);
+        self
+    }
+
+    /// Pairs of notification types and unique service names that are announced.
+    fn notifications(&self) -> Vec<(FieldMap, USN)> {
+        let mut notifications = Vec::new();
+
+        let root_uuid = FieldMap::uuid(&self.root.uuid[..]);
+        notifications.push((FieldMap::upnp("rootdevice"),
+                            USN(root_uuid.clone(), Some(FieldMap::upnp("rootdevice")))));
+
+        for device in Some(&self.root).into_iter().chain(self.embedded.iter()) {
+            let uuid = FieldMap::uuid(&device.uuid[..]);
+
+            notifications.push((uuid.clone(), USN(uuid.clone(), None)));
+            notifications.push((device.device_type.clone(),
+                                USN(uuid.clone(), Some(device.device_type.clone()))));
+            for service_type in &device.service_types {
+                notifications.push((service_type.clone(), USN(uuid.clone(), Some(service_type.clone()))));
+            }
+        }
+
+        notifications
+    }
+
+    /// Validate the headers and construct the `ssdp:alive` NotifyMessages.
+    pub fn alive_messages(&self) -> SSDPResult<Vec<NotifyMessage>> {
+        let mut template = AliveBuilder::new();
+        if let Some(ref location) = self.location {
+            template = template.location(&location[..]);
+        }
+        if let Some(max_age) = self.max_age {
+            template = template.max_age(max_age);
+        }
+        if let Some(ref server) = self.server {
+            template = template.server(&server[..]);
+        }
+        if let Some(ref secure_location) = self.secure_location {
+            template = template.secure_location(&secure_location[..]);
+        }
+        if let Some(tcp_port) = self.tcp_port {
+            template = template.tcp_port(tcp_port);
+        }
+
+        self.notifications()
+            .into_iter()
+            .map(|(notification_type, usn)| {
+                template.clone().notification_type(notification_type).usn(usn).build()
+            })
+            .collect()
+    }
+
+    /// Construct the SearchResponses that answer a search for the given target.
+    ///
+    /// Responses are generated from the matching `ssdp:alive` announcements, so
+    /// their USN, LOCATION and CACHE-CONTROL headers always agree. A search for
+    /// "ssdp:all" is answered once per announcement with the announced target,
+    /// any other search is answered with the searched target.
+    pub fn search_responses(&self, search_target: &ST) -> SSDPResult<Vec<SearchResponse>> {
+        let mut responses = Vec::new();
+
+        for message in try!(self.alive_messages()) {
+            if let Some(response) = try!(message.respond_to(search_target)) {
+                responses.push(response);
+            }
+        }
+
+        Ok(responses)
+    }
+
+    /// Multicast every `ssdp:alive` announcement on all interfaces.
+    pub fn multicast(&self) -> SSDPResult<()> {
+        for mut message in try!(self.alive_messages()) {
+            try!(message.multicast());
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use FieldMap;
+    use header::{HeaderRef, Location, NT, NTS, ST, USN};
+    use super::DeviceAnnouncement;
+
+    fn announcement() -> DeviceAnnouncement {
+        DeviceAnnouncement::new("root", FieldMap::urn("schemas-upnp-org:device:MediaServer:1"))
+            .service(FieldMap::urn("schemas-upnp-org:service:ContentDirectory:1"))
+            .service(FieldMap::urn("schemas-upnp-org:service:ConnectionManager:1"))
+            .embedded_device("embedded",
+                             FieldMap::urn("schemas-upnp-org:device:Printer:1"),
+                             vec![FieldMap::urn("schemas-upnp-org:service:PrintBasic:1")])
+            .location("http://192.168.0.2:80/desc.xml")
+            .max_age(1800)
+            .server("Linux/3.0 UPnP/1.0 Test/1.0")
+    }
+
+    #[test]
+    fn positive_alive_messages() {
+        let messages = announcement().alive_messages().unwrap();
+
+        let pairs = messages.iter()
+            .map(|message| (message.get::<NT>().unwrap().0.clone(), message.get::<USN>().unwrap().clone()))
+            .collect::<Vec<_>>();
+
+        let root = FieldMap::uuid("root");
+        let embedded = FieldMap::uuid("embedded");
+        let media_server = FieldMap::urn("schemas-upnp-org:device:MediaServer:1");
+        let content_directory = FieldMap::urn("schemas-upnp-org:service:ContentDirectory:1");
+        let connection_manager = FieldMap::urn("schemas-upnp-org:service:ConnectionManager:1");
+        let printer = FieldMap::urn("schemas-upnp-org:device:Printer:1");
+        let print_basic = FieldMap::urn("schemas-upnp-org:service:PrintBasic:1");
+
+        assert_eq!(pairs,
+                   vec![(FieldMap::upnp("rootdevice"), USN(root.clone(), Some(FieldMap::upnp("rootdevice")))),
+                        (root.clone(), USN(root.clone(), None)),
+                        (media_server.clone(), USN(root.clone(), Some(media_server))),
+                        (content_directory.clone(), USN(root.clone(), Some(content_directory))),
+                        (connection_manager.clone(), USN(root.clone(), Some(connection_manager))),
+                        (embedded.clone(), USN(embedded.clone(), None)),
+                        (printer.clone(), USN(embedded.clone(), Some(printer))),
+                        (print_basic.clone(), USN(embedded.clone(), Some(print_basic)))]);
+        assert!(messages.iter().all(|message| message.get::<NTS>() == Some(NTS::Alive)));
+    }
+
+    #[test]
+    fn positive_duplicate_service_type() {
+        let messages = announcement()
+            .service(FieldMap::urn("schemas-upnp-org:service:ContentDirectory:1"))
+            .alive_messages()
+            .unwrap();
+
+        assert_eq!(messages.len(), 8);
+    }
+
+    #[test]
+    fn positive_search_responses_all() {
+        let responses = announcement().search_responses(&ST::All).unwrap();
+        let messages = announcement().alive_messages().unwrap();
+
+        assert_eq!(responses.len(), messages.len());
+        for (response, message) in responses.iter().zip(messages.iter()) {
+            assert_eq!(response.get::<ST>(), Some(ST::Target(message.get::<NT>().unwrap().0.clone())));
+            assert_eq!(response.get::<USN>(), message.get::<USN>());
+            assert_eq!(response.get::<Location>(), message.get::<Location>());
+        }
+    }
+
+    #[test]
+    fn positive_search_responses_target() {
+        let root_device = ST::Target(FieldMap::upnp("rootdevice"));
+        let responses = announcement().search_responses(&root_device).unwrap();
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0].get::<ST>(), Some(root_device));
+
+        let embedded = ST::Target(FieldMap::uuid("embedded"));
+        let responses = announcement().search_responses(&embedded).unwrap();
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0].get::<USN>(), Some(USN(FieldMap::uuid("embedded"), None)));
+    }
+
+    #[test]
+    fn positive_search_responses_older_version() {
+        let searched = ST::Target(FieldMap::urn("schemas-upnp-org:service:ContentDirectory:1"));
+        let announcement = announcement()
+            .service(FieldMap::urn("schemas-upnp-org:service:RenderingControl:2"));
+
+        let newer = ST::Target(FieldMap::urn("schemas-upnp-org:service:RenderingControl:3"));
+        assert_eq!(announcement.search_responses(&searched).unwrap().len(), 1);
+        assert!(announcement.search_responses(&newer).unwrap().is_empty());
+
+        let older = ST::Target(FieldMap::urn("schemas-upnp-org:service:RenderingControl:1"));
+        let responses = announcement.search_responses(&older).unwrap();
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0].get::<ST>(), Some(older));
+    }
+
+    #[test]
+    fn negative_search_responses_unknown_target() {
+        let searched = ST::Target(FieldMap::uuid("unknown"));
+
+        assert!(announcement().search_responses(&searched).unwrap().is_empty());
+    }
+
+    #[test]
+    fn negative_missing_location() {
+        let announcement = DeviceAnnouncement::new("root", FieldMap::urn("schemas-upnp-org:device:Basic:1"))
+            .max_age(1800)
+            .server("Linux/3.0 UPnP/1.0 Test/1.0");
+
+        assert!(announcement.alive_messages().is_err());
+    }
+}
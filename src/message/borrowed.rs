@@ -0,0 +1,555 @@
+use std::borrow::Cow;
+use std::slice;
+use std::str;
+
+use {SSDPError, SSDPResult};
+use header::{CacheControl, Header, HeaderFormat, Host, Man};
+use message::{MessageType, ParseMode, Quirk, Quirks};
+use message::monitor::AnyMessage;
+use message::search::EXT_HEADER_NAME;
+use message::ssdp::{NOTIFY_METHOD, SEARCH_METHOD, VALID_RESPONSE_CODE};
+use receiver::FromRawSSDP;
+
+/// HTTP Version Required Of SSDP Messages
+const HTTP_VERSION: &'static str = "HTTP/1.1";
+
+/// Request URI Required Of SSDP Requests
+const REQUEST_URI: &'static str = "*";
+
+/// MAN Header Value Sent Without Quotes By Some Control Points
+const MAN_UNQUOTED_VALUE: &'static [u8] = b"ssdp:discover";
+
+/// View of an SSDP message of any type that borrows its header fields from the
+/// bytes it was parsed from instead of copying them.
+///
+/// Header values are only copied when parsed as typed headers, when they were
+/// folded over multiple lines or when the view is converted to an owned message,
+/// which makes this suited to inspecting and filtering large numbers of messages,
+/// such as on a busy network monitor.
+#[derive(Clone, Debug)]
+pub struct MessageRef<'a> {
+    message_type: MessageType,
+    status: Option<(u16, &'a str)>,
+    fields: Vec<(&'a str, Cow<'a, [u8]>)>,
+    bytes: &'a [u8],
+}
+
+impl<'a> MessageRef<'a> {
+    /// Parse a view of the SSDP message in the bytes of a packet, tolerating every quirk.
+    ///
+    /// The same checks are made as when parsing an owned message.
+    pub fn parse(bytes: &'a [u8]) -> SSDPResult<MessageRef<'a>> {
+        MessageRef::parse_with_quirks(bytes, Quirks::all())
+    }
+
+    /// Parse a view of the SSDP message in the bytes of a packet using the given mode.
+    pub fn parse_with_mode(bytes: &'a [u8], mode: ParseMode) -> SSDPResult<MessageRef<'a>> {
+        MessageRef::parse_with_quirks(bytes, mode.quirks())
+    }
+
+    /// Parse a view of the SSDP message in the bytes of a packet, tolerating only
+    /// the given quirks.
+    pub fn parse_with_quirks(bytes: &'a [u8], quirks: Quirks) -> SSDPResult<MessageRef<'a>> {
+        let invalid_http = || SSDPError::InvalidHttp(bytes.to_owned());
+        let mut lines = Lines { remaining: bytes };
+
+        let start_line = try!(lines.next()
+            .and_then(|(line, ending)| if is_tolerated(ending, quirks) { Some(line) } else { None })
+            .and_then(|line| str::from_utf8(line).ok())
+            .ok_or_else(&invalid_http));
+        let (message_type, status) = try!(parse_start_line(start_line, bytes, quirks));
+
+        let mut fields: Vec<(&'a str, Cow<'a, [u8]>)> = Vec::new();
+        loop {
+            let (line, ending) = match lines.next() {
+                Some(n) => n,
+                None if quirks.is_enabled(Quirk::MissingFinalCrlf) => break,
+                None => return Err(invalid_http()),
+            };
+
+            if !is_tolerated(ending, quirks) {
+                return Err(invalid_http());
+            } else if line.is_empty() {
+                break;
+            }
+
+            if is_whitespace(line[0]) {
+                let previous = match fields.last_mut() {
+                    Some(&mut (_, ref mut value)) if quirks.is_enabled(Quirk::FoldedLines) => value,
+                    _ => return Err(invalid_http()),
+                };
+
+                let continuation = trim_bytes(line);
+                if !continuation.is_empty() {
+                    let value = previous.to_mut();
+                    if !value.is_empty() {
+                        value.push(b' ');
+                    }
+                    value.extend_from_slice(continuation);
+                }
+            } else {
+                let space_before_colon = quirks.is_enabled(Quirk::SpaceBeforeColon);
+                let (name, value) = try!(parse_field(line, space_before_colon).ok_or_else(&invalid_http));
+
+                fields.push((name, Cow::Borrowed(value)));
+            }
+        }
+
+        let message = MessageRef {
+            message_type: message_type,
+            status: status,
+            fields: fields,
+            bytes: bytes,
+        };
+
+        try!(message.validate(quirks));
+
+        Ok(message)
+    }
+
+    /// Validate the header fields that have to be present, or that real devices are
+    /// known to format incorrectly.
+    fn validate(&self, quirks: Quirks) -> SSDPResult<()> {
+        if self.message_type != MessageType::Response {
+            let raw = self.get_all_raw(Host::header_name()).map(|value| value.to_vec()).collect::<Vec<_>>();
+            if raw.is_empty() {
+                return Err(SSDPError::MissingHeader(Host::header_name()));
+            }
+
+            try!(Host::parse_header(&raw[..]));
+        }
+
+        if self.message_type == MessageType::Response && !quirks.is_enabled(Quirk::MissingExt) &&
+           self.get_raw(EXT_HEADER_NAME).is_none() {
+            return Err(SSDPError::MissingHeader(EXT_HEADER_NAME));
+        }
+
+        if self.message_type == MessageType::Search && !quirks.is_enabled(Quirk::UnquotedMan) &&
+           self.get_raw(Man::header_name()) == Some(MAN_UNQUOTED_VALUE) {
+            return Err(SSDPError::InvalidHeader(Man::header_name(), "Expected A Quoted Value"));
+        }
+
+        if !quirks.is_enabled(Quirk::SpacedCacheControl) &&
+           self.get_all_raw(CacheControl::header_name()).any(is_spaced_cache_control) {
+            return Err(SSDPError::InvalidHeader(CacheControl::header_name(),
+                                                "Whitespace Around Directive Value"));
+        }
+
+        Ok(())
+    }
+
+    /// Get the type of this message.
+    pub fn message_type(&self) -> MessageType {
+        self.message_type
+    }
+
+    /// Status code of the status line, if this message is a search response.
+    pub fn status_code(&self) -> Option<u16> {
+        self.status.map(|(code, _)| code)
+    }
+
+    /// Reason phrase of the status line, if this message is a search response.
+    ///
+    /// The reason phrase is empty if the status line did not have one.
+    pub fn reason_phrase(&self) -> Option<&'a str> {
+        self.status.map(|(_, reason)| reason)
+    }
+
+    /// Bytes that this message was parsed from.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// View the bytes of the first line of the header field with the given name, if it exists.
+    pub fn get_raw(&self, name: &str) -> Option<&[u8]> {
+        self.fields
+            .iter()
+            .find(|&&(field_name, _)| field_name.eq_ignore_ascii_case(name))
+            .map(|&(_, ref value)| &value[..])
+    }
+
+    /// View the first line of the header field with the given name as a string with
+    /// surrounding whitespace removed, if it exists and is valid UTF-8.
+    pub fn get_str(&self, name: &str) -> Option<&str> {
+        self.get_raw(name).and_then(|value| str::from_utf8(value).ok()).map(str::trim)
+    }
+
+    /// Iterate over the bytes of every line of the header field with the given name.
+    pub fn get_all_raw<'b>(&'b self, name: &'b str) -> FieldValues<'b, 'a> {
+        FieldValues {
+            inner: self.fields.iter(),
+            name: name,
+        }
+    }
+
+    /// Parse the typed header from the lines of its header field, if it is present and valid.
+    pub fn get<H>(&self) -> Option<H>
+        where H: Header + HeaderFormat
+    {
+        let raw = self.get_all_raw(H::header_name()).map(|value| value.to_vec()).collect::<Vec<_>>();
+
+        if raw.is_empty() {
+            None
+        } else {
+            H::parse_header(&raw[..]).ok()
+        }
+    }
+
+    /// Iterate over the names and values of the header fields, one entry per line
+    /// in the order they were received.
+    pub fn iter<'b>(&'b self) -> Fields<'b, 'a> {
+        Fields { inner: self.fields.iter() }
+    }
+
+    /// Convert this view into an owned message.
+    pub fn to_message(&self) -> SSDPResult<AnyMessage> {
+        AnyMessage::raw_ssdp(self.bytes)
+    }
+}
+
+/// Iterator over the header fields of a `MessageRef`.
+#[derive(Clone)]
+pub struct Fields<'b, 'a: 'b> {
+    inner: slice::Iter<'b, (&'a str, Cow<'a, [u8]>)>,
+}
+
+impl<'b, 'a> Iterator for Fields<'b, 'a> {
+    type Item = (&'a str, &'b [u8]);
+
+    fn next(&mut self) -> Option<(&'a str, &'b [u8])> {
+        self.inner.next().map(|&(name, ref value)| (name, &value[..]))
+    }
+}
+
+/// Iterator over the lines of a single header field of a `MessageRef`.
+#[derive(Clone)]
+pub struct FieldValues<'b, 'a: 'b> {
+    inner: slice::Iter<'b, (&'a str, Cow<'a, [u8]>)>,
+    name: &'b str,
+}
+
+impl<'b, 'a> Iterator for FieldValues<'b, 'a> {
+    type Item = &'b [u8];
+
+    fn next(&mut self) -> Option<&'b [u8]> {
+        let name = self.name;
+
+        self.inner
+            .by_ref()
+            .find(|&&(field_name, _)| field_name.eq_ignore_ascii_case(name))
+            .map(|&(_, ref value)| &value[..])
+    }
+}
+
+/// Enumerates how a line of a message ended.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum LineEnding {
+    CrLf,
+    Lf,
+    Missing,
+}
+
+/// Whether or not lines with the ending are tolerated.
+fn is_tolerated(ending: LineEnding, quirks: Quirks) -> bool {
+    match ending {
+        LineEnding::CrLf => true,
+        LineEnding::Lf => quirks.is_enabled(Quirk::BareLineFeed),
+        LineEnding::Missing => quirks.is_enabled(Quirk::MissingFinalCrlf),
+    }
+}
+
+/// Iterator over the lines of a message, along with how each line ended.
+struct Lines<'a> {
+    remaining: &'a [u8],
+}
+
+impl<'a> Iterator for Lines<'a> {
+    type Item = (&'a [u8], LineEnding);
+
+    fn next(&mut self) -> Option<(&'a [u8], LineEnding)> {
+        let end = match self.remaining.iter().position(|&byte| byte == b'\n') {
+            Some(n) => n,
+            None if self.remaining.is_empty() => return None,
+            None => {
+                let line = self.remaining;
+                self.remaining = &[];
+
+                return Some((line, LineEnding::Missing));
+            }
+        };
+
+        let line = &self.remaining[..end];
+        self.remaining = &self.remaining[end + 1..];
+
+        if line.last() == Some(&b'\r') {
+            Some((&line[..line.len() - 1], LineEnding::CrLf))
+        } else {
+            Some((line, LineEnding::Lf))
+        }
+    }
+}
+
+/// Parse the type of message from its request or status line, along with the
+/// status code and reason phrase of a status line.
+fn parse_start_line<'a>(line: &'a str,
+                        bytes: &[u8],
+                        quirks: Quirks)
+                        -> SSDPResult<(MessageType, Option<(u16, &'a str)>)> {
+    let parts = line.splitn(3, ' ').collect::<Vec<_>>();
+    let is_status = parts[0] == HTTP_VERSION;
+    let without_reason = is_status && parts.len() == 2 && quirks.is_enabled(Quirk::MissingReasonPhrase);
+
+    if parts.len() != 3 && !without_reason {
+        return Err(SSDPError::InvalidHttp(bytes.to_owned()));
+    }
+
+    if is_status {
+        let code = try!(parts[1].parse::<u16>().map_err(|_| SSDPError::InvalidHttp(bytes.to_owned())));
+
+        if code != VALID_RESPONSE_CODE && !quirks.is_enabled(Quirk::NonOkStatus) {
+            return Err(SSDPError::ResponseCode(code));
+        }
+
+        let reason = parts.get(2).map_or("", |reason| *reason);
+
+        return Ok((MessageType::Response, Some((code, reason))));
+    }
+
+    if !parts[2].starts_with("HTTP/") {
+        return Err(SSDPError::InvalidHttp(bytes.to_owned()));
+    } else if parts[2] != HTTP_VERSION {
+        return Err(SSDPError::InvalidHttpVersion);
+    }
+
+    if parts[1] != REQUEST_URI {
+        return Err(SSDPError::InvalidUri(parts[1].to_owned()));
+    }
+
+    let method_matches = |method: &str| if quirks.is_enabled(Quirk::LowercaseMethod) {
+        parts[0].eq_ignore_ascii_case(method)
+    } else {
+        parts[0] == method
+    };
+
+    if method_matches(NOTIFY_METHOD) {
+        Ok((MessageType::Notify, None))
+    } else if method_matches(SEARCH_METHOD) {
+        Ok((MessageType::Search, None))
+    } else {
+        Err(SSDPError::InvalidMethod(parts[0].to_owned()))
+    }
+}
+
+/// Parse the name and value of a header field line, with whitespace around the value removed.
+///
+/// Whitespace between the name and the colon is removed as well if it is tolerated.
+fn parse_field(line: &[u8], space_before_colon: bool) -> Option<(&str, &[u8])> {
+    let colon = match line.iter().position(|&byte| byte == b':') {
+        Some(n) => n,
+        None => return None,
+    };
+
+    let raw_name = if space_before_colon { trim_bytes(&line[..colon]) } else { &line[..colon] };
+    let name = match str::from_utf8(raw_name) {
+        Ok(name) if !name.is_empty() && !name.bytes().any(|byte| byte <= b' ' || byte >= 0x7F) => name,
+        _ => return None,
+    };
+
+    Some((name, trim_bytes(&line[colon + 1..])))
+}
+
+/// Whether or not a line of a CACHE-CONTROL header has whitespace around the "=" of a directive.
+fn is_spaced_cache_control(value: &[u8]) -> bool {
+    value.windows(2).any(|pair| {
+        (pair[0] == b'=' && is_whitespace(pair[1])) || (is_whitespace(pair[0]) && pair[1] == b'=')
+    })
+}
+
+/// Whether or not the byte is a space or a tab.
+fn is_whitespace(byte: u8) -> bool {
+    byte == b' ' || byte == b'\t'
+}
+
+/// Remove leading and trailing spaces and tabs from the bytes.
+fn trim_bytes(mut bytes: &[u8]) -> &[u8] {
+    while let Some((&first, rest)) = bytes.split_first() {
+        if !is_whitespace(first) {
+            break;
+        }
+        bytes = rest;
+    }
+
+    while let Some((&last, rest)) = bytes.split_last() {
+        if !is_whitespace(last) {
+            break;
+        }
+        bytes = rest;
+    }
+
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use SSDPError;
+    use header::NTS;
+    use message::{MessageType, ParseMode, Quirk, Quirks};
+    use super::MessageRef;
+
+    const NOTIFY: &'static [u8] = b"NOTIFY * HTTP/1.1\r\n\
+                                    HOST: 239.255.255.250:1900\r\n\
+                                    NTS: ssdp:alive\r\n\
+                                    USN:  uuid:device-UUID::upnp:rootdevice \r\n\
+                                    X-Multi: 1\r\n\
+                                    x-multi: 2\r\n\r\n";
+
+    #[test]
+    fn positive_borrowed_fields() {
+        let message = MessageRef::parse(NOTIFY).unwrap();
+
+        assert_eq!(message.message_type(), MessageType::Notify);
+        assert_eq!(message.get_str("usn"), Some("uuid:device-UUID::upnp:rootdevice"));
+        assert_eq!(message.get_all_raw("X-MULTI").collect::<Vec<_>>(), vec![&b"1"[..], &b"2"[..]]);
+        assert_eq!(message.iter().count(), 5);
+        assert_eq!(message.get::<NTS>(), Some(NTS::Alive));
+    }
+
+    #[test]
+    fn positive_to_message() {
+        let message = MessageRef::parse(NOTIFY).unwrap().to_message().unwrap();
+
+        assert_eq!(message.message_type(), MessageType::Notify);
+    }
+
+    #[test]
+    fn positive_response_without_host() {
+        let message = MessageRef::parse(b"HTTP/1.1 200 OK\nST: ssdp:all\n\n").unwrap();
+
+        assert_eq!(message.message_type(), MessageType::Response);
+        assert_eq!(message.get_str("ST"), Some("ssdp:all"));
+    }
+
+    #[test]
+    fn positive_lenient_status_without_reason() {
+        let message = MessageRef::parse(b"HTTP/1.1 200\r\n\r\n").unwrap();
+
+        assert_eq!(message.message_type(), MessageType::Response);
+        assert_eq!(message.reason_phrase(), Some(""));
+        assert!(message.to_message().is_ok());
+        assert!(MessageRef::parse_with_mode(b"HTTP/1.1 200\r\n\r\n", ParseMode::Strict).is_err());
+    }
+
+    #[test]
+    fn positive_lenient_non_ok_status() {
+        let raw_message = b"HTTP/1.1 404 Not Found\r\nEXT:\r\n\r\n";
+        let message = MessageRef::parse(raw_message).unwrap();
+
+        assert_eq!(message.status_code(), Some(404));
+        assert_eq!(message.reason_phrase(), Some("Not Found"));
+
+        match MessageRef::parse_with_mode(raw_message, ParseMode::Strict) {
+            Err(SSDPError::ResponseCode(404)) => (),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn positive_strict_exact_message() {
+        assert!(MessageRef::parse_with_mode(NOTIFY, ParseMode::Strict).is_ok());
+    }
+
+    #[test]
+    fn negative_strict_bare_lf() {
+        let raw_message = b"NOTIFY * HTTP/1.1\r\nHOST: 239.255.255.250:1900\nNTS: ssdp:alive\r\n\r\n";
+
+        assert!(MessageRef::parse(raw_message).is_ok());
+        assert!(MessageRef::parse_with_mode(raw_message, ParseMode::Strict).is_err());
+    }
+
+    #[test]
+    fn negative_invalid_host() {
+        let raw_message = b"M-SEARCH * HTTP/1.1\r\nHOST: 239.255.255.250:port\r\n\r\n";
+
+        match MessageRef::parse(raw_message) {
+            Err(SSDPError::InvalidHeader("HOST", _)) => (),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn positive_lenient_folded_lines() {
+        let raw_message = b"NOTIFY * HTTP/1.1\r\nHOST: 239.255.255.250:1900\r\n\
+                            SERVER: Linux/2.6\r\n UPnP/1.0\r\n\tproduct/1.0\r\nNTS: ssdp:alive\r\n\r\n";
+        let message = MessageRef::parse(raw_message).unwrap();
+
+        assert_eq!(message.get_str("SERVER"), Some("Linux/2.6 UPnP/1.0 product/1.0"));
+        assert_eq!(message.get::<NTS>(), Some(NTS::Alive));
+        assert!(MessageRef::parse_with_mode(raw_message, ParseMode::Strict).is_err());
+    }
+
+    #[test]
+    fn positive_lenient_space_before_colon() {
+        let raw_message = b"M-SEARCH * HTTP/1.1\r\nHOST : 239.255.255.250:1900\r\nST\t:ssdp:all\r\n\r\n";
+        let message = MessageRef::parse(raw_message).unwrap();
+
+        assert_eq!(message.get_str("ST"), Some("ssdp:all"));
+        assert!(MessageRef::parse_with_mode(raw_message, ParseMode::Strict).is_err());
+    }
+
+    #[test]
+    fn negative_continuation_without_field() {
+        assert!(MessageRef::parse(b"NOTIFY * HTTP/1.1\r\n folded\r\nHOST: a\r\n\r\n").is_err());
+    }
+
+    #[test]
+    fn negative_missing_host() {
+        match MessageRef::parse(b"M-SEARCH * HTTP/1.1\r\nST: ssdp:all\r\n\r\n") {
+            Err(SSDPError::MissingHeader("HOST")) => (),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn negative_invalid_start_line() {
+        for raw in &[&b"NOTIFY / HTTP/1.1\r\nHOST: a\r\n\r\n"[..],
+                     &b"NOTIFY * HTTP/2.0\r\nHOST: a\r\n\r\n"[..],
+                     &b"GET * HTTP/1.1\r\nHOST: a\r\n\r\n"[..],
+                     &b"garbage\r\n\r\n"[..]] {
+            assert!(MessageRef::parse(raw).is_err());
+        }
+    }
+
+    #[test]
+    fn positive_lenient_missing_final_crlf() {
+        for raw_message in &[&b"NOTIFY * HTTP/1.1\r\nHOST: a\r\n"[..], &b"NOTIFY * HTTP/1.1\r\nHOST: a"[..]] {
+            assert_eq!(MessageRef::parse(raw_message).unwrap().get_str("HOST"), Some("a"));
+            assert!(MessageRef::parse_with_quirks(raw_message, Quirks::all().without(Quirk::MissingFinalCrlf))
+                .is_err());
+        }
+    }
+
+    #[test]
+    fn positive_lenient_lowercase_method() {
+        let raw_message = b"m-search * HTTP/1.1\r\nHOST: 239.255.255.250:1900\r\n\r\n";
+
+        assert_eq!(MessageRef::parse(raw_message).unwrap().message_type(), MessageType::Search);
+        assert!(MessageRef::parse_with_quirks(raw_message, Quirks::all().without(Quirk::LowercaseMethod))
+            .is_err());
+    }
+
+    #[test]
+    fn positive_unquoted_man_quirk() {
+        let raw_message = b"M-SEARCH * HTTP/1.1\r\nHOST: 239.255.255.250:1900\r\nMAN: ssdp:discover\r\n\r\n";
+
+        assert!(MessageRef::parse_with_quirks(raw_message, Quirks::none().with(Quirk::UnquotedMan)).is_ok());
+        match MessageRef::parse_with_mode(raw_message, ParseMode::Strict) {
+            Err(SSDPError::InvalidHeader("MAN", _)) => (),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn negative_field_without_colon() {
+        assert!(MessageRef::parse(b"NOTIFY * HTTP/1.1\r\nHOST a\r\n\r\n").is_err());
+    }
+}
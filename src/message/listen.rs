@@ -1,50 +1,580 @@
-use std::net::{SocketAddr, IpAddr};
+use std::io;
+use std::net::{SocketAddr, IpAddr, UdpSocket};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use net2::UdpSocketExt;
 
 use error::SSDPResult;
-use message;
-use receiver::{SSDPReceiver, FromRawSSDP};
-use net;
+use message::{self, Ipv6Scope, Quirks};
+use queue::OverflowPolicy;
+use receiver::{self, SSDPReceiver, FromRawSSDP, ListenHandle, ReceiveHandlers};
+use net::{self, SocketHook, SocketReuse};
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+use net::runtime::Runtime;
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+use stream::SSDPStream;
+
+/// Interval at which interface watch threads check if they have been shutdown.
+const WATCH_POLL_INTERVAL_MS: u64 = 100;
+
+/// Options for listening for messages with `Listen::listen_with_options`.
+///
+/// By default, the standard multicast groups are joined on the standard port
+/// on all local network interfaces.
+#[derive(Clone, Debug)]
+pub struct ListenOptions {
+    port: u16,
+    groups: Vec<IpAddr>,
+    reuse: SocketReuse,
+    hook: Option<SocketHook>,
+    recv_buffer: Option<usize>,
+    device: Option<String>,
+    watch_interval: Option<Duration>,
+    capacity: Option<usize>,
+    policy: OverflowPolicy,
+    handlers: ReceiveHandlers,
+    quirks: Quirks,
+}
+
+impl ListenOptions {
+    /// Construct ListenOptions with the defaults.
+    pub fn new() -> ListenOptions {
+        ListenOptions {
+            port: message::UPNP_MULTICAST_PORT,
+            groups: standard_groups().to_vec(),
+            reuse: SocketReuse::default(),
+            hook: None,
+            recv_buffer: None,
+            device: None,
+            watch_interval: None,
+            capacity: None,
+            policy: OverflowPolicy::Block,
+            handlers: ReceiveHandlers::new(),
+            quirks: Quirks::default(),
+        }
+    }
+
+    /// Listen on a custom port.
+    pub fn port(mut self, port: u16) -> ListenOptions {
+        self.port = port;
+        self
+    }
+
+    /// Join the given multicast groups in place of the standard ones.
+    ///
+    /// Each group is joined on every local interface of the same ip version.
+    pub fn groups(mut self, groups: Vec<IpAddr>) -> ListenOptions {
+        self.groups = groups;
+        self
+    }
+
+    /// Join the IPv6 SSDP groups of each of the given scopes in place of only the
+    /// link-local one, along with the standard IPv4 group.
+    pub fn scopes(mut self, scopes: &[Ipv6Scope]) -> ListenOptions {
+        self.groups = vec![IpAddr::V4(message::UPNP_MULTICAST_IPV4_ADDR.parse().unwrap())];
+        self.groups.extend(scopes.iter().map(|scope| IpAddr::V6(scope.addr())));
+        self
+    }
+
+    /// Bind the sockets with the given reuse options.
+    ///
+    /// By default, listeners set both `SO_REUSEADDR` and `SO_REUSEPORT` so that
+    /// they can coexist with other SSDP stacks on the same host.
+    pub fn reuse(mut self, reuse: SocketReuse) -> ListenOptions {
+        self.reuse = reuse;
+        self
+    }
+
+    /// Invoke the hook on each socket before it is bound and joins the groups.
+    pub fn socket_hook(mut self, hook: SocketHook) -> ListenOptions {
+        self.hook = Some(hook);
+        self
+    }
+
+    /// Set the receive buffer of the sockets to size bytes with `SO_RCVBUF`.
+    ///
+    /// A larger buffer keeps bursts of messages, such as every device announcing
+    /// itself after a power outage, from being dropped before they are received.
+    /// The operating system may round or cap the size.
+    pub fn recv_buffer(mut self, size: usize) -> ListenOptions {
+        self.recv_buffer = Some(size);
+        self
+    }
+
+    /// Listen only on the local network device with the given name, such as
+    /// "eth0", binding the sockets to it with `SO_BINDTODEVICE`.
+    ///
+    /// Binding to a device is only supported on linux.
+    pub fn device<S>(mut self, device: S) -> ListenOptions
+        where S: Into<String>
+    {
+        self.device = Some(device.into());
+        self
+    }
+
+    /// Check for local network interfaces that were added or removed at the
+    /// given interval.
+    ///
+    /// The multicast groups are joined on interfaces as they come up, such as
+    /// after a Wi-Fi reconnect or a VPN connecting, and left on interfaces that
+    /// have gone away. Checking stops once the receiver is shutdown.
+    pub fn watch_interfaces(mut self, interval: Duration) -> ListenOptions {
+        self.watch_interval = Some(interval);
+        self
+    }
+
+    /// Queue at most capacity messages, applying the policy when full.
+    pub fn capacity(mut self, capacity: usize, policy: OverflowPolicy) -> ListenOptions {
+        self.capacity = Some(capacity);
+        self.policy = policy;
+        self
+    }
+
+    /// Report errors and malformed packets that the receiver threads encounter
+    /// to the handlers.
+    pub fn handlers(mut self, handlers: ReceiveHandlers) -> ListenOptions {
+        self.handlers = handlers;
+        self
+    }
+
+    /// Tolerate only the given quirks when parsing received messages, such as
+    /// `ParseMode::Strict.quirks()` for conformance tooling.
+    ///
+    /// Messages showing other quirks are reported to the handlers as malformed.
+    pub fn quirks(mut self, quirks: Quirks) -> ListenOptions {
+        self.quirks = quirks;
+        self
+    }
+}
+
+impl Default for ListenOptions {
+    fn default() -> Self {
+        ListenOptions::new()
+    }
+}
 
 pub trait Listen {
-    type Message: FromRawSSDP + Send + 'static;
+    type Message: FromRawSSDP + Clone + Send + 'static;
 
     /// Listen for messages on all local network interfaces.
+    ///
+    /// A single socket per ip version is bound to the wildcard address, which
+    /// joins the multicast group on every local network interface, so the number
+    /// of sockets and receiver threads does not grow with the number of interfaces.
     fn listen() -> SSDPResult<SSDPReceiver<Self::Message>> {
         Self::listen_on_port(message::UPNP_MULTICAST_PORT)
     }
 
-    /// Listen on any interface
-    #[cfg(linux)]
-    fn listen_any_on_port(port: u16) -> SSDPResult<SSDPReceiver<Self::Message>> {
-        // Ipv4
-        let mcast_ip = message::UPNP_MULTICAST_IPV4_ADDR.parse().unwrap();
-        let ipv4_sock = try!(net::bind_reuse(("0.0.0.0", port)));
-        try!(ipv4_sock.join_multicast_v4(&mcast_ip, &"0.0.0.0".parse().unwrap()));
+    /// Listen for messages on a custom port on all local network interfaces.
+    fn listen_on_port(port: u16) -> SSDPResult<SSDPReceiver<Self::Message>> {
+        Self::listen_with_options(&ListenOptions::new().port(port))
+    }
 
-        // Ipv6
-        let mcast_ip = message::UPNP_MULTICAST_IPV6_LINK_LOCAL_ADDR.parse().unwrap();
-        let ipv6_sock = try!(net::bind_reuse(("::", port)));
-        try!(ipv6_sock.join_multicast_v6(&mcast_ip, 0));
+    /// Listen for messages as configured by the options.
+    fn listen_with_options(options: &ListenOptions) -> SSDPResult<SSDPReceiver<Self::Message>> {
+        let sockets = try!(listen_sockets(options));
+        let opt_watch = try!(watch_memberships(&sockets, options));
 
-        let sockets = vec![ipv4_sock, ipv6_sock];
-        Ok(try!(SSDPReceiver::new(sockets, None)))
+        let receiver = try!(receiver::spawn_filtered(sockets,
+                                                     None,
+                                                     options.capacity,
+                                                     options.policy,
+                                                     None,
+                                                     options.handlers.clone(),
+                                                     options.quirks));
+        spawn_watch(opt_watch, receiver.handle());
+
+        Ok(receiver)
     }
 
-    /// Listen for messages on a custom port on all local network interfaces.
-    fn listen_on_port(port: u16) -> SSDPResult<SSDPReceiver<Self::Message>> {
-        let mut ipv4_sock = None;
-        let mut ipv6_sock = None;
+    /// Listen for messages without joining any multicast groups, returning a
+    /// `GroupMembership` to join them on chosen interfaces with.
+    ///
+    /// Only unicast messages to the port of the options are received until a group
+    /// is joined. The groups and interface watching of the options are not used.
+    fn listen_with_membership(options: &ListenOptions)
+                              -> SSDPResult<(SSDPReceiver<Self::Message>, GroupMembership)> {
+        let sockets = try!(wildcard_sockets(options));
+        if let Some(size) = options.recv_buffer {
+            try!(set_recv_buffer(&sockets, size));
+        }
+
+        let mut membership_sockets = Vec::with_capacity(sockets.len());
+        for sock in &sockets {
+            membership_sockets.push(try!(sock.try_clone()));
+        }
+
+        let receiver = try!(receiver::spawn_filtered(sockets,
+                                                     None,
+                                                     options.capacity,
+                                                     options.policy,
+                                                     None,
+                                                     options.handlers.clone(),
+                                                     options.quirks));
+
+        Ok((receiver, GroupMembership { sockets: membership_sockets }))
+    }
+
+    /// Listen for messages on all local network interfaces and invoke the
+    /// callback for every message received.
+    ///
+    /// The callback is invoked on the receiver thread(s), so it should not block
+    /// for any significant amount of time.
+    fn listen_with<F>(callback: F) -> SSDPResult<ListenHandle>
+        where F: FnMut(Self::Message, SocketAddr) + Send + 'static
+    {
+        Self::listen_with_options_callback(&ListenOptions::new(), callback)
+    }
+
+    /// Listen for messages as configured by the options and invoke the callback
+    /// for every message received.
+    ///
+    /// Messages are handed to the callback as they arrive, so the capacity of the
+    /// options is not used.
+    fn listen_with_options_callback<F>(options: &ListenOptions, callback: F) -> SSDPResult<ListenHandle>
+        where F: FnMut(Self::Message, SocketAddr) + Send + 'static
+    {
+        let sockets = try!(listen_sockets(options));
+        let opt_watch = try!(watch_memberships(&sockets, options));
+
+        let handle = try!(receiver::spawn_callback_filtered(sockets,
+                                                            None,
+                                                            None,
+                                                            options.handlers.clone(),
+                                                            options.quirks,
+                                                            callback));
+        spawn_watch(opt_watch, handle.clone());
+
+        Ok(handle)
+    }
+
+    /// Listen for messages on all local network interfaces, receiving them
+    /// asynchronously on the `Runtime` R.
+    #[cfg(any(feature = "tokio", feature = "async-io"))]
+    fn listen_async<R>() -> SSDPResult<SSDPStream<Self::Message, R>>
+        where R: Runtime
+    {
+        Self::listen_async_with_options(&ListenOptions::new())
+    }
+
+    /// Listen for messages as configured by the options, receiving them
+    /// asynchronously on the `Runtime` R.
+    ///
+    /// Streams are not shutdown through a `ListenHandle`, so they can not watch
+    /// the local network interfaces, and there are no receiver threads to report
+    /// to the handlers of the options or to bound with their capacity.
+    #[cfg(any(feature = "tokio", feature = "async-io"))]
+    fn listen_async_with_options<R>(options: &ListenOptions) -> SSDPResult<SSDPStream<Self::Message, R>>
+        where R: Runtime
+    {
+        if options.watch_interval.is_some() {
+            let err = io::Error::new(io::ErrorKind::InvalidInput, "Streams Can Not Watch Interfaces");
+
+            return Err(err.into());
+        }
+
+        let sockets = try!(listen_sockets(options));
+
+        Ok(try!(SSDPStream::with_filter(sockets, None, None, options.quirks)))
+    }
+}
+
+/// Memberships of the sockets in the groups of the options, along with the interval
+/// to update them at, if the options watch the local network interfaces.
+fn watch_memberships(sockets: &[UdpSocket],
+                     options: &ListenOptions)
+                     -> SSDPResult<Option<(Memberships, Duration)>> {
+    let interval = match options.watch_interval {
+        Some(n) => n,
+        None => return Ok(None),
+    };
+
+    let mut watch_sockets = Vec::with_capacity(sockets.len());
+    for sock in sockets {
+        watch_sockets.push(try!(sock.try_clone()));
+    }
+
+    let mut memberships = Memberships::new(watch_sockets, options.groups.clone(), options.device.clone());
+    memberships.update(try!(local_addrs(memberships.device())));
+
+    Ok(Some((memberships, interval)))
+}
+
+/// Spawn a thread updating the memberships, if any, until the handle is shutdown.
+fn spawn_watch(opt_watch: Option<(Memberships, Duration)>, handle: ListenHandle) {
+    if let Some((memberships, interval)) = opt_watch {
+        thread::spawn(move || watch_interfaces(memberships, interval, handle));
+    }
+}
+
+/// Bind the sockets of a listener as configured by the options.
+///
+/// Sockets of listeners watching the local network interfaces join the groups
+/// once the interfaces are known, see `Memberships`.
+fn listen_sockets(options: &ListenOptions) -> SSDPResult<Vec<UdpSocket>> {
+    if options.groups.iter().any(|group| !group.is_multicast()) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Group Is Not A Multicast Address").into());
+    }
+
+    let sockets = match (options.watch_interval, &options.device) {
+        (Some(_), _) => try!(wildcard_sockets(options)),
+        (None, &Some(ref device)) => try!(device_sockets(options, device)),
+        (None, &None) => try!(group_sockets(options)),
+    };
+
+    if let Some(size) = options.recv_buffer {
+        try!(set_recv_buffer(&sockets, size));
+    }
+
+    Ok(sockets)
+}
+
+/// Set the receive buffer of each of the sockets to size bytes.
+fn set_recv_buffer(sockets: &[UdpSocket], size: usize) -> io::Result<()> {
+    for sock in sockets {
+        debug!("Setting receive buffer of {:?} to {} bytes", sock.local_addr(), size);
+        try!(sock.set_recv_buffer_size(size));
+    }
+
+    Ok(())
+}
+
+/// Standard multicast group for each ip version.
+fn standard_groups() -> [IpAddr; 2] {
+    [IpAddr::V4(message::UPNP_MULTICAST_IPV4_ADDR.parse().unwrap()),
+     IpAddr::V6(message::UPNP_MULTICAST_IPV6_LINK_LOCAL_ADDR.parse().unwrap())]
+}
+
+/// Addresses of the local network interfaces, only those of the local network
+/// device with the given name if one is given.
+fn local_addrs(opt_device: Option<&str>) -> io::Result<Vec<SocketAddr>> {
+    message::map_local(|&addr| {
+        match opt_device {
+            Some(device) if net::interface_name(&addr.ip()).map_or(true, |name| name != device) => Ok(None),
+            _ => Ok(Some(addr)),
+        }
+    })
+}
+
+/// Bind a reused socket per ip version on the port of the options without joining
+/// any multicast groups, skipping ip versions the system does not support.
+///
+/// The sockets are bound to the device of the options, if one is set.
+fn wildcard_sockets(options: &ListenOptions) -> SSDPResult<Vec<UdpSocket>> {
+    let hook = options.hook.as_ref();
+    let ipv4_sock = try!(net::bind_with_hook(("0.0.0.0", options.port), options.reuse, hook));
+
+    let sockets = match net::bind_with_hook(("::", options.port), options.reuse, hook) {
+        Ok(ipv6_sock) => vec![ipv4_sock, ipv6_sock],
+        Err(err) => {
+            debug!("Not listening on ipv6, failed to bind: {}", err);
+            vec![ipv4_sock]
+        }
+    };
+
+    if let Some(ref device) = options.device {
+        for sock in &sockets {
+            try!(net::bind_to_device(sock, device));
+        }
+    }
+
+    Ok(sockets)
+}
 
-        // Generate a list of reused sockets on the standard multicast address.
-        let addrs: Vec<SocketAddr> = try!(message::map_local(|&addr| Ok(Some(addr))));
+/// Bind a reused socket per ip version on the port of the options to the local
+/// network device, which has joined the groups of the options on that device.
+fn device_sockets(options: &ListenOptions, device: &str) -> SSDPResult<Vec<UdpSocket>> {
+    let addrs = try!(local_addrs(Some(device)));
 
+    if addrs.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Device Has No Local Addresses").into());
+    }
+
+    let sockets = try!(wildcard_sockets(options));
+
+    for addr in addrs {
+        let opt_sock = sockets.iter().find(|sock| {
+            sock.local_addr().map(|local| local.is_ipv4() == addr.is_ipv4()).unwrap_or(false)
+        });
+        let sock = match opt_sock {
+            Some(n) => n,
+            None => continue,
+        };
+
+        for group in options.groups.iter().filter(|group| group.is_ipv4() == addr.is_ipv4()) {
+            debug!("Joining multicast {} at device {} iface: {}", group, device, addr);
+            try!(net::join_multicast(sock, &addr, group));
+        }
+    }
+
+    Ok(sockets)
+}
+
+/// Memberships of the sockets of a listener in multicast groups, which are joined
+/// and left at explicitly chosen local network interfaces.
+///
+/// Interfaces are identified by their index, such as the one found by
+/// `interface_index_by_name`. IPv4 groups are joined at the first IPv4 address
+/// of the interface.
+#[derive(Debug)]
+pub struct GroupMembership {
+    sockets: Vec<UdpSocket>,
+}
+
+impl GroupMembership {
+    /// Join the multicast group at the local network interface with the given
+    /// index, or at the interface chosen by the routing table if it is 0.
+    pub fn join(&self, group: &IpAddr, iface_index: u32) -> io::Result<()> {
+        debug!("Joining multicast {} at iface index: {}", group, iface_index);
+
+        net::join_multicast_index(try!(self.socket_for(group)), group, iface_index)
+    }
+
+    /// Leave the multicast group at the local network interface with the given index.
+    pub fn leave(&self, group: &IpAddr, iface_index: u32) -> io::Result<()> {
+        debug!("Leaving multicast {} at iface index: {}", group, iface_index);
+
+        net::leave_multicast_index(try!(self.socket_for(group)), group, iface_index)
+    }
+
+    /// Join the standard SSDP multicast group of each ip version at the local
+    /// network interface with the given index.
+    ///
+    /// Groups of ip versions that the listener has no socket for are skipped.
+    pub fn join_standard(&self, iface_index: u32) -> io::Result<()> {
+        for group in standard_groups().iter() {
+            if self.socket_for(group).is_ok() {
+                try!(self.join(group, iface_index));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Socket of the same ip version as the group.
+    fn socket_for(&self, group: &IpAddr) -> io::Result<&UdpSocket> {
+        self.sockets
+            .iter()
+            .find(|sock| sock.local_addr().map(|local| local.is_ipv4() == group.is_ipv4()).unwrap_or(false))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "No Socket For The Group Ip Version"))
+    }
+}
+
+/// Multicast groups joined by a set of wildcard sockets, kept up to date with
+/// the local network interfaces, or only those of a local network device.
+struct Memberships {
+    sockets: Vec<UdpSocket>,
+    groups: Vec<IpAddr>,
+    device: Option<String>,
+    joined: Vec<SocketAddr>,
+}
+
+impl Memberships {
+    fn new(sockets: Vec<UdpSocket>, groups: Vec<IpAddr>, device: Option<String>) -> Memberships {
+        Memberships {
+            sockets: sockets,
+            groups: groups,
+            device: device,
+            joined: Vec::new(),
+        }
+    }
+
+    /// Local network device that the groups are joined on, if only one.
+    fn device(&self) -> Option<&str> {
+        self.device.as_ref().map(|device| &device[..])
+    }
+
+    /// Join the groups on interfaces that were added and leave them on
+    /// interfaces that were removed.
+    ///
+    /// Interfaces that fail to join are retried on the next update.
+    fn update(&mut self, addrs: Vec<SocketAddr>) {
+        let (kept, removed): (Vec<SocketAddr>, Vec<SocketAddr>) =
+            self.joined.drain(..).partition(|addr| addrs.contains(addr));
+
+        for addr in removed {
+            // Interface is usually gone already, taking its memberships with it
+            debug!("Leaving multicast at removed iface: {}", addr);
+            self.for_each_group(&addr, |sock, group| net::leave_multicast(sock, &addr, group));
+        }
+
+        self.joined = kept;
         for addr in addrs {
-            match addr {
-                SocketAddr::V4(_) => {
-                    let mcast_ip = message::UPNP_MULTICAST_IPV4_ADDR.parse().unwrap();
+            if self.joined.contains(&addr) {
+                continue;
+            }
+
+            debug!("Joining multicast at added iface: {}", addr);
+            if self.for_each_group(&addr, |sock, group| net::join_multicast(sock, &addr, group)) {
+                self.joined.push(addr);
+            }
+        }
+    }
+
+    /// Invoke the closure for every group of the same ip version as the address
+    /// along with the socket of that ip version, returning false if any failed.
+    fn for_each_group<F>(&self, addr: &SocketAddr, mut f: F) -> bool
+        where F: FnMut(&UdpSocket, &IpAddr) -> io::Result<()>
+    {
+        let opt_sock = self.sockets.iter().find(|sock| {
+            sock.local_addr().map(|local| local.is_ipv4() == addr.is_ipv4()).unwrap_or(false)
+        });
+        let sock = match opt_sock {
+            Some(n) => n,
+            None => return false,
+        };
+
+        let mut success = true;
+        for group in self.groups.iter().filter(|group| group.is_ipv4() == addr.is_ipv4()) {
+            if let Err(err) = f(sock, group) {
+                debug!("Failed to update multicast {} at iface {}: {}", group, addr, err);
+                success = false;
+            }
+        }
+
+        success
+    }
+}
 
+/// Update the memberships every interval until the handle is shutdown.
+fn watch_interfaces(mut memberships: Memberships, interval: Duration, handle: ListenHandle) {
+    let poll_interval = Duration::from_millis(WATCH_POLL_INTERVAL_MS);
+    let mut next_check = Instant::now() + interval;
+
+    while !handle.is_shutdown() {
+        let now = Instant::now();
+        if now < next_check {
+            thread::sleep(::std::cmp::min(next_check - now, poll_interval));
+            continue;
+        }
+
+        match local_addrs(memberships.device()) {
+            Ok(addrs) => memberships.update(addrs),
+            Err(err) => debug!("Failed to enumerate local interfaces: {}", err),
+        }
+
+        next_check = Instant::now() + interval;
+    }
+}
+
+/// Bind a socket per ip version on the port of the options which has joined each
+/// of the multicast groups of the options of that ip version on all local network
+/// interfaces.
+fn group_sockets(options: &ListenOptions) -> SSDPResult<Vec<UdpSocket>> {
+    let (port, reuse, hook) = (options.port, options.reuse, options.hook.as_ref());
+    let mut ipv4_sock = None;
+    let mut ipv6_sock = None;
+
+    // Generate a list of reused sockets on the multicast groups.
+    let addrs: Vec<SocketAddr> = try!(message::map_local(|&addr| Ok(Some(addr))));
+
+    for addr in addrs {
+        for group in &options.groups {
+            match (addr, *group) {
+                (SocketAddr::V4(_), mcast_ip @ IpAddr::V4(_)) => {
                     if ipv4_sock.is_none() {
-                        ipv4_sock = Some(try!(net::bind_reuse(("0.0.0.0", port))));
+                        ipv4_sock = Some(try!(net::bind_with_hook(("0.0.0.0", port), reuse, hook)));
                     }
 
                     let ref sock = ipv4_sock.as_ref().unwrap();
@@ -52,26 +582,141 @@ pub trait Listen {
                     debug!("Joining ipv4 multicast {} at iface: {}", mcast_ip, addr);
                     try!(net::join_multicast(&sock, &addr, &mcast_ip));
                 }
-                SocketAddr::V6(_) => {
-                    let mcast_ip = message::UPNP_MULTICAST_IPV6_LINK_LOCAL_ADDR.parse().unwrap();
-
+                (SocketAddr::V6(_), mcast_ip @ IpAddr::V6(_)) => {
                     if ipv6_sock.is_none() {
-                        ipv6_sock = Some(try!(net::bind_reuse(("::", port))));
+                        ipv6_sock = Some(try!(net::bind_with_hook(("::", port), reuse, hook)));
                     }
 
                     let ref sock = ipv6_sock.as_ref().unwrap();
 
                     debug!("Joining ipv6 multicast {} at iface: {}", mcast_ip, addr);
-                    try!(net::join_multicast(&sock, &addr, &IpAddr::V6(mcast_ip)));
+                    match net::join_multicast(&sock, &addr, &mcast_ip) {
+                        // Interfaces with several addresses are joined at their index only once
+                        Err(ref err) if err.kind() == io::ErrorKind::AddrInUse => (),
+                        result => try!(result),
+                    }
                 }
+                _ => (),
             }
         }
+    }
+
+    Ok(vec![ipv4_sock, ipv6_sock]
+        .into_iter()
+        .flatten()
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::UdpSocket;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    use net2::UdpSocketExt;
+
+    use message::NotifyListener;
+    use queue::OverflowPolicy;
+    use receiver::ReceiveHandlers;
+    use super::{device_sockets, listen_sockets, set_recv_buffer, GroupMembership, Listen, ListenOptions,
+                Memberships};
+
+    #[test]
+    fn positive_memberships_update() {
+        let sock = UdpSocket::bind("0.0.0.0:0").unwrap();
+        let mut memberships = Memberships::new(vec![sock], vec!["239.255.255.250".parse().unwrap()], None);
+        let loopback = "127.0.0.1:0".parse().unwrap();
+        let ipv6_addr = "[fe80::1]:0".parse().unwrap();
+
+        // No socket to join the ipv6 address on, it is not considered joined
+        memberships.update(vec![loopback, ipv6_addr]);
+        assert_eq!(memberships.joined, vec![loopback]);
+
+        memberships.update(vec![]);
+        assert!(memberships.joined.is_empty());
+    }
+
+    #[test]
+    fn positive_group_membership() {
+        let membership = GroupMembership { sockets: vec![UdpSocket::bind("0.0.0.0:0").unwrap()] };
+        let group = "239.255.255.250".parse().unwrap();
 
-        let sockets = vec![ipv4_sock, ipv6_sock]
-            .into_iter()
-            .flat_map(|opt_interface| opt_interface)
-            .collect();
+        membership.join(&group, 0).unwrap();
+        membership.leave(&group, 0).unwrap();
+        assert!(membership.join(&"ff02::c".parse().unwrap(), 0).is_err());
+    }
+
+    #[test]
+    fn positive_set_recv_buffer() {
+        let sockets = vec![UdpSocket::bind("127.0.0.1:0").unwrap()];
+
+        set_recv_buffer(&sockets, 65536).unwrap();
+        assert!(sockets[0].recv_buffer_size().unwrap() >= 65536);
+    }
+
+    #[test]
+    fn positive_listen_with_options() {
+        let port = UdpSocket::bind("0.0.0.0:0").unwrap().local_addr().unwrap().port();
+        let options = ListenOptions::new()
+            .port(port)
+            .recv_buffer(65536)
+            .capacity(4, OverflowPolicy::DropNewest);
+
+        let receiver = NotifyListener::listen_with_options(&options).unwrap();
+
+        let send_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        send_sock.send_to(b"NOTIFY * HTTP/1.1\r\nHOST: 239.255.255.250:1900\r\nNT: upnp:rootdevice\r\n\
+                            NTS: ssdp:alive\r\nUSN: uuid:device::upnp:rootdevice\r\n\r\n",
+                          ("127.0.0.1", port))
+            .unwrap();
+
+        let (_, src_addr) = receiver.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert_eq!(src_addr, send_sock.local_addr().unwrap());
+    }
+
+    #[test]
+    fn positive_listen_with_options_callback_handlers() {
+        let port = UdpSocket::bind("0.0.0.0:0").unwrap().local_addr().unwrap().port();
+        let (send, recv) = mpsc::channel();
+        let options = ListenOptions::new()
+            .port(port)
+            .handlers(ReceiveHandlers::new().on_malformed(move |packet| send.send(packet.source()).unwrap()));
+
+        let handle = NotifyListener::listen_with_options_callback(&options, |_, _| ()).unwrap();
+
+        let send_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        send_sock.send_to(b"NOT SSDP\r\n\r\n", ("127.0.0.1", port)).unwrap();
+
+        assert_eq!(recv.recv_timeout(Duration::from_secs(1)).unwrap(), send_sock.local_addr().unwrap());
+        handle.shutdown();
+    }
+
+    #[test]
+    fn positive_listen_with_membership_options() {
+        let options = ListenOptions::new().port(0).recv_buffer(65536);
+
+        let (receiver, membership) = NotifyListener::listen_with_membership(&options).unwrap();
+
+        membership.join_standard(0).unwrap();
+        assert!(membership.sockets[0].recv_buffer_size().unwrap() >= 65536);
+        receiver.handle().shutdown();
+    }
+
+    #[test]
+    fn negative_listen_with_options_zero_capacity() {
+        let options = ListenOptions::new().port(0).capacity(0, OverflowPolicy::Block);
+
+        assert!(NotifyListener::listen_with_options(&options).is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn negative_listen_sockets_not_multicast() {
+        listen_sockets(&ListenOptions::new().port(0).groups(vec!["192.168.1.1".parse().unwrap()])).unwrap();
+    }
 
-        Ok(try!(SSDPReceiver::new(sockets, None)))
+    #[test]
+    fn negative_device_sockets_unknown_device() {
+        assert!(device_sockets(&ListenOptions::new().port(0), "not-an-interface").is_err());
     }
 }
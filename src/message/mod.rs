@@ -1,32 +1,59 @@
 //! Messaging primitives for discovering devices and services.
 
 use std::io;
-#[cfg(windows)]
-use std::net;
-use std::net::SocketAddr;
+use std::net::{IpAddr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6, ToSocketAddrs};
+use std::str::FromStr;
 
+use header::{HeaderRef, HeaderMut, Host, Location};
+use message::ssdp::SSDPMessage;
+use net::{self, IpVersionMode, SocketHook};
 use net::connector::UdpConnector;
-use net::IpVersionMode;
+use SSDPResult;
 
+mod announce;
+mod borrowed;
+mod monitor;
 mod notify;
 mod search;
 mod ssdp;
 mod listen;
+mod quirks;
+#[cfg(feature = "serde")]
+mod serialize;
 
-pub use message::search::{SearchRequest, SearchResponse, SearchListener};
-pub use message::notify::{NotifyMessage, NotifyListener};
-pub use message::listen::Listen;
-
-#[cfg(not(windows))]
-use ifaces;
+pub use message::search::{check_alive, search, SearchRequest, SearchRequestBuilder, SearchResponse,
+                          SearchListener, SearchRequestListener, TargetedResponse};
+pub use message::announce::DeviceAnnouncement;
+pub use message::borrowed::{MessageRef, Fields, FieldValues};
+pub use message::monitor::{parse_message, parse_message_with_mode, parse_message_with_quirks, AnyMessage,
+                           MonitorListener};
+pub use message::notify::{NotifyMessage, NotifyListener, AliveBuilder, UpdateBuilder};
+pub use message::listen::{Listen, ListenOptions, GroupMembership};
+pub use message::quirks::{Quirk, Quirks, QuirksIter};
 
 /// Multicast Socket Information
 const UPNP_MULTICAST_IPV4_ADDR: &'static str = "239.255.255.250";
 const UPNP_MULTICAST_IPV6_LINK_LOCAL_ADDR: &'static str = "FF02::C";
+const UPNP_MULTICAST_IPV6_SITE_LOCAL_ADDR: &'static str = "FF05::C";
+const UPNP_MULTICAST_IPV6_ORG_LOCAL_ADDR: &'static str = "FF08::C";
 pub const UPNP_MULTICAST_PORT: u16 = 1900;
 
-/// Default TTL For Multicast
-const UPNP_MULTICAST_TTL: u32 = 2;
+/// Default TTL of multicast messages, as recommended by the `UPnP` standard.
+pub const UPNP_MULTICAST_TTL: u32 = 2;
+
+/// Default hop limit of IPv6 multicast messages sent to the link-local group,
+/// which routers never forward.
+pub const UPNP_MULTICAST_HOPS_LINK_LOCAL: u32 = 1;
+
+/// Default hop limit of IPv6 multicast messages sent to the site-local and
+/// organization-local groups, matching the IPv4 TTL.
+pub const UPNP_MULTICAST_HOPS_SITE_LOCAL: u32 = UPNP_MULTICAST_TTL;
+
+/// Placeholder in a LOCATION header that is replaced with the address of the
+/// local interface each message is sent from, such as "http://{bind_addr}:8080/desc.xml".
+///
+/// IPv6 addresses are substituted in brackets, as they appear in URLs.
+pub const LOCATION_BIND_ADDR: &'static str = "{bind_addr}";
 
 /// Enumerates different types of SSDP messages.
 #[derive(Copy, Clone, Hash, Eq, PartialEq, Debug)]
@@ -39,16 +66,269 @@ pub enum MessageType {
     Response,
 }
 
-/// Generate `UdpConnector` objects for all local `IPv4` interfaces.
-fn all_local_connectors(multicast_ttl: Option<u32>, filter: IpVersionMode) -> io::Result<Vec<UdpConnector>> {
+/// Enumerates how closely received messages have to follow the specification to be parsed.
+#[derive(Copy, Clone, Hash, Eq, PartialEq, Debug, Default)]
+pub enum ParseMode {
+    /// Accept only messages that follow the HTTPU rules of the specification exactly,
+    /// for conformance tooling, tolerating none of the `Quirk`s.
+    Strict,
+    /// Accept the deviations from the specification that real devices are known
+    /// to ship, tolerating every `Quirk`.
+    ///
+    /// Receivers parse messages with the default `Quirks` instead, which do not
+    /// tolerate responses with a status code other than 200.
+    #[default]
+    Lenient,
+}
+
+impl ParseMode {
+    /// Get the quirks that are tolerated in this mode.
+    pub fn quirks(&self) -> Quirks {
+        match *self {
+            ParseMode::Strict => Quirks::none(),
+            ParseMode::Lenient => Quirks::all(),
+        }
+    }
+}
+
+/// Enumerates the scopes of the IPv6 SSDP multicast groups.
+#[derive(Copy, Clone, Hash, Eq, PartialEq, Debug)]
+pub enum Ipv6Scope {
+    /// Link-local group `FF02::C`, the standard group.
+    LinkLocal,
+    /// Site-local group `FF05::C`.
+    SiteLocal,
+    /// Organization-local group `FF08::C`.
+    OrganizationLocal,
+}
+
+impl Ipv6Scope {
+    /// Get the SSDP multicast address for this scope.
+    pub fn addr(&self) -> Ipv6Addr {
+        let addr = match *self {
+            Ipv6Scope::LinkLocal => UPNP_MULTICAST_IPV6_LINK_LOCAL_ADDR,
+            Ipv6Scope::SiteLocal => UPNP_MULTICAST_IPV6_SITE_LOCAL_ADDR,
+            Ipv6Scope::OrganizationLocal => UPNP_MULTICAST_IPV6_ORG_LOCAL_ADDR,
+        };
+
+        addr.parse().unwrap()
+    }
+
+    /// Get the scope of an IPv6 multicast address from its scope field, such as
+    /// `SiteLocal` for `FF05::1`.
+    ///
+    /// Returns None for addresses that are not multicast or of another scope.
+    pub fn from_multicast(addr: &Ipv6Addr) -> Option<Ipv6Scope> {
+        if addr.segments()[0] & 0xff00 != 0xff00 {
+            return None;
+        }
+
+        match addr.segments()[0] & 0x000f {
+            0x2 => Some(Ipv6Scope::LinkLocal),
+            0x5 => Some(Ipv6Scope::SiteLocal),
+            0x8 => Some(Ipv6Scope::OrganizationLocal),
+            _ => None,
+        }
+    }
+
+    /// Get the default hop limit of multicasts sent to the group of this scope.
+    pub fn default_hops(&self) -> u32 {
+        match *self {
+            Ipv6Scope::LinkLocal => UPNP_MULTICAST_HOPS_LINK_LOCAL,
+            Ipv6Scope::SiteLocal |
+            Ipv6Scope::OrganizationLocal => UPNP_MULTICAST_HOPS_SITE_LOCAL,
+        }
+    }
+}
+
+/// Whether or not the HOST header names an SSDP multicast group and the standard
+/// port, as the specification requires of multicast messages.
+fn is_multicast_host<T: HeaderRef>(message: &T) -> bool {
+    let host = match message.get::<Host>() {
+        Some(n) => n,
+        None => return false,
+    };
+
+    let hostname = host.hostname.trim_matches(|c| c == '[' || c == ']');
+    let is_group = match IpAddr::from_str(hostname) {
+        Ok(IpAddr::V4(n)) => n.to_string() == UPNP_MULTICAST_IPV4_ADDR,
+        Ok(IpAddr::V6(n)) => {
+            [Ipv6Scope::LinkLocal, Ipv6Scope::SiteLocal, Ipv6Scope::OrganizationLocal]
+                .iter()
+                .any(|scope| scope.addr() == n)
+        }
+        Err(_) => false,
+    };
+
+    is_group && host.port == Some(UPNP_MULTICAST_PORT)
+}
+
+/// Get the address that multicast messages sent from the local address should be
+/// sent to, using the destination override in place of the standard address:port
+/// if one is provided.
+///
+/// Returns None if the override is for a different IP version than the local address.
+fn multicast_dst_addr(local_addr: SocketAddr,
+                      port: u16,
+                      opt_override: Option<SocketAddr>)
+                      -> SSDPResult<Option<SocketAddr>> {
+    match (local_addr, opt_override) {
+        (SocketAddr::V4(_), Some(dst @ SocketAddr::V4(_))) => Ok(Some(dst)),
+        (SocketAddr::V6(n), Some(SocketAddr::V6(mut dst))) => {
+            if dst.scope_id() == 0 {
+                dst.set_scope_id(n.scope_id());
+            }
+
+            Ok(Some(SocketAddr::V6(dst)))
+        }
+        (_, Some(_)) => Ok(None),
+        (SocketAddr::V4(_), None) => {
+            let mcast_ip = try!(FromStr::from_str(UPNP_MULTICAST_IPV4_ADDR));
+
+            Ok(Some(SocketAddr::V4(SocketAddrV4::new(mcast_ip, port))))
+        }
+        (SocketAddr::V6(n), None) => {
+            let mcast_ip = try!(FromStr::from_str(UPNP_MULTICAST_IPV6_LINK_LOCAL_ADDR));
+
+            Ok(Some(SocketAddr::V6(SocketAddrV6::new(mcast_ip, port, n.flowinfo(), n.scope_id()))))
+        }
+    }
+}
+
+/// Get the addresses that multicast messages sent from the local address should
+/// be sent to, with one address per IPv6 scope for IPv6 local addresses.
+///
+/// A destination override replaces all of the scoped addresses.
+fn scoped_multicast_dst_addrs(local_addr: SocketAddr,
+                              port: u16,
+                              opt_override: Option<SocketAddr>,
+                              scopes: &[Ipv6Scope])
+                              -> SSDPResult<Vec<SocketAddr>> {
+    match (local_addr, opt_override) {
+        (SocketAddr::V6(n), None) => {
+            Ok(scopes.iter()
+                .map(|scope| SocketAddrV6::new(scope.addr(), port, n.flowinfo(), n.scope_id()))
+                .map(SocketAddr::V6)
+                .collect())
+        }
+        _ => Ok(try!(multicast_dst_addr(local_addr, port, opt_override)).into_iter().collect()),
+    }
+}
+
+/// Get the LOCATION header that a message sent from the local address should
+/// carry, filling in the bind address placeholder.
+///
+/// Returns None if the message has no LOCATION header or it is not a template.
+fn local_location<T: HeaderRef>(message: &T, local_addr: SocketAddr) -> Option<Location> {
+    let location = match message.get::<Location>() {
+        Some(n) if n.0.contains(LOCATION_BIND_ADDR) => n,
+        _ => return None,
+    };
+
+    let bind_addr = match local_addr.ip() {
+        IpAddr::V4(n) => n.to_string(),
+        IpAddr::V6(n) => format!("[{}]", n),
+    };
+
+    Some(Location(location.0.replace(LOCATION_BIND_ADDR, &bind_addr)))
+}
+
+/// Send the message through the connector, filling in the bind address placeholder
+/// of its LOCATION header with the local address of the connector.
+fn send_local<A>(message: &SSDPMessage, conn: &mut UdpConnector, dst_addr: A) -> SSDPResult<()>
+    where A: ToSocketAddrs
+{
+    let local_addr = try!(conn.local_addr());
+
+    match local_location(message, local_addr) {
+        Some(location) => {
+            let mut local_message = message.clone();
+            local_message.set(location);
+
+            local_message.send(conn, dst_addr)
+        }
+        None => message.send(conn, dst_addr),
+    }
+}
+
+/// Apply the socket options of a message to the connectors that it is sent from.
+///
+/// Connectors are bound to the device if one is given, dropping those bound to
+/// the address of another local network interface.
+fn configure_connectors(connectors: Vec<UdpConnector>,
+                        multicast_loop: Option<bool>,
+                        device: Option<&str>,
+                        tos: Option<u8>)
+                        -> io::Result<Vec<UdpConnector>> {
+    let mut configured = Vec::with_capacity(connectors.len());
+
+    for conn in connectors {
+        if let Some(device) = device {
+            let local_ip = try!(conn.local_addr()).ip();
+            let name = net::interface_name(&local_ip);
+
+            if !local_ip.is_unspecified() && name.as_ref().map(|name| &name[..]) != Some(device) {
+                trace!("Skipping connector at {}, interface is not device {}", local_ip, device);
+                continue;
+            }
+
+            try!(conn.bind_to_device(device));
+        }
+
+        if let Some(enabled) = multicast_loop {
+            try!(conn.set_multicast_loop(enabled));
+        }
+
+        if let Some(tos) = tos {
+            try!(conn.set_tos(tos));
+        }
+
+        configured.push(conn);
+    }
+
+    Ok(configured)
+}
+
+/// Set the hop limit of the connector for multicasts to the IPv6 destination,
+/// defaulting to that of the scope of the destination.
+///
+/// Destinations of an unknown scope get the link-local default, IPv4 destinations
+/// are left untouched.
+fn set_multicast_hops(conn: &UdpConnector, dst_addr: &SocketAddr, opt_hops: Option<u32>) -> io::Result<()> {
+    let dst_ip = match *dst_addr {
+        SocketAddr::V6(n) => *n.ip(),
+        SocketAddr::V4(_) => return Ok(()),
+    };
+
+    let hops = opt_hops.unwrap_or_else(|| {
+        Ipv6Scope::from_multicast(&dst_ip).unwrap_or(Ipv6Scope::LinkLocal).default_hops()
+    });
+
+    conn.set_multicast_hops(hops)
+}
+
+/// Generate `UdpConnector` objects for all local `IPv4` and/or `IPv6` interfaces,
+/// as selected by the `IpVersionMode`, bound to the given port, or to an ephemeral
+/// port if it is 0.
+///
+/// Loopback and global `IPv6` addresses are skipped, as in `map_local`.
+fn all_local_connectors(multicast_ttl: Option<u32>,
+                        filter: IpVersionMode,
+                        port: u16,
+                        hook: Option<&SocketHook>)
+                        -> io::Result<Vec<UdpConnector>> {
     trace!("Fetching all local connectors");
     map_local(|&addr| match (&filter, addr) {
         (&IpVersionMode::V4Only, SocketAddr::V4(n)) |
         (&IpVersionMode::Any, SocketAddr::V4(n)) => {
-            Ok(Some(try!(UdpConnector::new((*n.ip(), 0), multicast_ttl))))
+            Ok(Some(try!(UdpConnector::with_hook((*n.ip(), port), multicast_ttl, hook))))
+        }
+        (&IpVersionMode::V6Only, SocketAddr::V6(mut n)) |
+        (&IpVersionMode::Any, SocketAddr::V6(mut n)) => {
+            n.set_port(port);
+
+            Ok(Some(try!(UdpConnector::with_hook(n, multicast_ttl, hook))))
         }
-        (&IpVersionMode::V6Only, SocketAddr::V6(n)) |
-        (&IpVersionMode::Any, SocketAddr::V6(n)) => Ok(Some(try!(UdpConnector::new(n, multicast_ttl)))),
         _ => Ok(None),
     })
 }
@@ -59,7 +339,7 @@ fn all_local_connectors(multicast_ttl: Option<u32>, filter: IpVersionMode) -> io
 fn map_local<F, R>(mut f: F) -> io::Result<Vec<R>>
     where F: FnMut(&SocketAddr) -> io::Result<Option<R>>
 {
-    let addrs_iter = try!(get_local_addrs());
+    let addrs_iter = try!(net::local_addrs());
 
     let mut obj_list = Vec::with_capacity(addrs_iter.len());
 
@@ -84,22 +364,115 @@ fn map_local<F, R>(mut f: F) -> io::Result<Vec<R>>
     Ok(obj_list)
 }
 
-/// Generate a list of some object R constructed from all local `Ipv4Addr` objects.
-///
-/// If any of the `SocketAddr`'s fail to resolve, this function will not return an error.
-#[cfg(windows)]
-fn get_local_addrs() -> io::Result<Vec<SocketAddr>> {
-    let host_iter = try!(net::lookup_host(""));
-    Ok(host_iter.collect())
-}
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
 
-/// Generate a list of some object R constructed from all local `Ipv4Addr` objects.
-///
-/// If any of the `SocketAddr`'s fail to resolve, this function will not return an error.
-#[cfg(not(windows))]
-fn get_local_addrs() -> io::Result<Vec<SocketAddr>> {
-    let iface_iter = try!(ifaces::Interface::get_all()).into_iter();
-    Ok(iface_iter.filter(|iface| iface.kind != ifaces::Kind::Packet)
-        .filter_map(|iface| iface.addr)
-        .collect())
+    use header::{HeaderMut, Location};
+    use super::{Ipv6Scope, NotifyMessage};
+
+    #[test]
+    fn positive_ipv6_scope_hops() {
+        assert_eq!(Ipv6Scope::from_multicast(&"ff02::c".parse().unwrap()), Some(Ipv6Scope::LinkLocal));
+        assert_eq!(Ipv6Scope::from_multicast(&"ff15::1234".parse().unwrap()), Some(Ipv6Scope::SiteLocal));
+        assert_eq!(Ipv6Scope::from_multicast(&"fe80::2".parse().unwrap()), None);
+
+        assert_eq!(Ipv6Scope::LinkLocal.default_hops(), super::UPNP_MULTICAST_HOPS_LINK_LOCAL);
+        assert_eq!(Ipv6Scope::OrganizationLocal.default_hops(), super::UPNP_MULTICAST_HOPS_SITE_LOCAL);
+    }
+
+    #[test]
+    fn positive_multicast_host() {
+        let mut message = NotifyMessage::new();
+
+        message.set_raw("HOST", vec![b"239.255.255.250:1900".to_vec()]);
+        assert!(super::is_multicast_host(&message));
+
+        message.set_raw("HOST", vec![b"[FF05::C]:1900".to_vec()]);
+        assert!(super::is_multicast_host(&message));
+    }
+
+    #[test]
+    fn negative_multicast_host_mismatch() {
+        let mut message = NotifyMessage::new();
+        assert!(!super::is_multicast_host(&message));
+
+        message.set_raw("HOST", vec![b"239.255.255.250".to_vec()]);
+        assert!(!super::is_multicast_host(&message));
+
+        message.set_raw("HOST", vec![b"192.168.0.2:1900".to_vec()]);
+        assert!(!super::is_multicast_host(&message));
+    }
+
+    #[test]
+    fn positive_local_location() {
+        let mut message = NotifyMessage::new();
+        message.set(Location("http://{bind_addr}:8080/desc.xml".to_owned()));
+
+        let v4_addr = "192.168.0.2:1900".parse().unwrap();
+        let v6_addr = "[fe80::1]:1900".parse().unwrap();
+
+        assert_eq!(super::local_location(&message, v4_addr),
+                   Some(Location("http://192.168.0.2:8080/desc.xml".to_owned())));
+        assert_eq!(super::local_location(&message, v6_addr),
+                   Some(Location("http://[fe80::1]:8080/desc.xml".to_owned())));
+    }
+
+    #[test]
+    fn negative_local_location_not_template() {
+        let mut message = NotifyMessage::new();
+        let local_addr = "192.168.0.2:1900".parse().unwrap();
+        assert!(super::local_location(&message, local_addr).is_none());
+
+        message.set(Location("http://192.168.0.3:8080/desc.xml".to_owned()));
+        assert!(super::local_location(&message, local_addr).is_none());
+    }
+
+    #[test]
+    fn positive_standard_multicast_dst_addr() {
+        let local_addr = "192.168.0.2:0".parse().unwrap();
+
+        assert_eq!(super::multicast_dst_addr(local_addr, 1900, None).unwrap(),
+                   Some("239.255.255.250:1900".parse().unwrap()));
+    }
+
+    #[test]
+    fn positive_override_multicast_dst_addr() {
+        let local_addr = "192.168.0.2:0".parse().unwrap();
+        let dst_addr = "239.255.255.251:1901".parse().unwrap();
+
+        assert_eq!(super::multicast_dst_addr(local_addr, 1900, Some(dst_addr)).unwrap(),
+                   Some(dst_addr));
+    }
+
+    #[test]
+    fn positive_override_multicast_dst_addr_scope_id() {
+        let local_addr = "[fe80::1%3]:0".parse().unwrap();
+        let dst_addr = "[ff02::c]:1901".parse().unwrap();
+
+        match super::multicast_dst_addr(local_addr, 1900, Some(dst_addr)).unwrap() {
+            Some(SocketAddr::V6(n)) => assert_eq!(n.scope_id(), 3),
+            other => panic!("Unexpected Destination {:?}", other),
+        }
+    }
+
+    #[test]
+    fn positive_scoped_multicast_dst_addrs() {
+        let v4_addr = "192.168.0.2:0".parse().unwrap();
+        let v6_addr = "[fe80::1%3]:0".parse().unwrap();
+        let scopes = [Ipv6Scope::LinkLocal, Ipv6Scope::SiteLocal];
+
+        assert_eq!(super::scoped_multicast_dst_addrs(v4_addr, 1900, None, &scopes).unwrap(),
+                   vec!["239.255.255.250:1900".parse().unwrap()]);
+        assert_eq!(super::scoped_multicast_dst_addrs(v6_addr, 1900, None, &scopes).unwrap(),
+                   vec!["[ff02::c%3]:1900".parse().unwrap(), "[ff05::c%3]:1900".parse().unwrap()]);
+    }
+
+    #[test]
+    fn negative_override_multicast_dst_addr_version() {
+        let local_addr = "192.168.0.2:0".parse().unwrap();
+        let dst_addr = "[ff02::c]:1901".parse().unwrap();
+
+        assert_eq!(super::multicast_dst_addr(local_addr, 1900, Some(dst_addr)).unwrap(), None);
+    }
 }
@@ -0,0 +1,228 @@
+use std::fmt::{self, Debug, Display, Formatter};
+use std::str::FromStr;
+
+use error::{SSDPError, SSDPResult};
+use header::{Header, HeaderFormat, HeaderRef, HeadersItems};
+use message::{MessageType, Listen, ParseMode, Quirks};
+use message::notify::NotifyMessage;
+use message::search::{SearchRequest, SearchResponse};
+use message::ssdp::SSDPMessage;
+use receiver::{FromRawSSDP, ReceiveInfo};
+
+/// Any SSDP message seen on the network, tagged with its type.
+#[derive(Debug, Clone)]
+pub enum AnyMessage {
+    /// A notify message, such as an `ssdp:alive` announcement.
+    Notify(NotifyMessage),
+    /// A search request from a control point.
+    Search(SearchRequest),
+    /// A response to a search request.
+    Response(SearchResponse),
+}
+
+impl AnyMessage {
+    /// Get the type of this message.
+    pub fn message_type(&self) -> MessageType {
+        match *self {
+            AnyMessage::Notify(_) => MessageType::Notify,
+            AnyMessage::Search(_) => MessageType::Search,
+            AnyMessage::Response(_) => MessageType::Response,
+        }
+    }
+
+    /// Serialize this message as it is written to the network.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match *self {
+            AnyMessage::Notify(ref n) => n.to_bytes(),
+            AnyMessage::Search(ref n) => n.to_bytes(),
+            AnyMessage::Response(ref n) => n.to_bytes(),
+        }
+    }
+
+    /// Get the metadata about how this message was received, if it was received.
+    pub fn receive_info(&self) -> Option<&ReceiveInfo> {
+        match *self {
+            AnyMessage::Notify(ref n) => n.receive_info(),
+            AnyMessage::Search(ref n) => n.receive_info(),
+            AnyMessage::Response(ref n) => n.receive_info(),
+        }
+    }
+}
+
+/// Parse an SSDP message of any type from the bytes of a packet, independently
+/// of the sockets used by this crate.
+pub fn parse_message(bytes: &[u8]) -> SSDPResult<AnyMessage> {
+    AnyMessage::raw_ssdp(bytes)
+}
+
+/// Parse an SSDP message of any type from the bytes of a packet using the given mode.
+///
+/// `parse_message` is equivalent to this with `ParseMode::Lenient`.
+pub fn parse_message_with_mode(bytes: &[u8], mode: ParseMode) -> SSDPResult<AnyMessage> {
+    AnyMessage::raw_ssdp_with_mode(bytes, mode)
+}
+
+/// Parse an SSDP message of any type from the bytes of a packet, tolerating only
+/// the given quirks.
+pub fn parse_message_with_quirks(bytes: &[u8], quirks: Quirks) -> SSDPResult<AnyMessage> {
+    AnyMessage::raw_ssdp_with_quirks(bytes, quirks)
+}
+
+impl Display for AnyMessage {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            AnyMessage::Notify(ref n) => Display::fmt(n, f),
+            AnyMessage::Search(ref n) => Display::fmt(n, f),
+            AnyMessage::Response(ref n) => Display::fmt(n, f),
+        }
+    }
+}
+
+impl FromStr for AnyMessage {
+    type Err = SSDPError;
+
+    fn from_str(s: &str) -> SSDPResult<AnyMessage> {
+        AnyMessage::raw_ssdp(s.as_bytes())
+    }
+}
+
+impl FromRawSSDP for AnyMessage {
+    fn raw_ssdp(bytes: &[u8]) -> SSDPResult<AnyMessage> {
+        let message_type = try!(SSDPMessage::raw_ssdp(bytes)).message_type();
+
+        match message_type {
+            MessageType::Notify => NotifyMessage::raw_ssdp(bytes).map(AnyMessage::Notify),
+            MessageType::Search => SearchRequest::raw_ssdp(bytes).map(AnyMessage::Search),
+            MessageType::Response => SearchResponse::raw_ssdp(bytes).map(AnyMessage::Response),
+        }
+    }
+
+    fn set_receive_info(&mut self, info: ReceiveInfo) {
+        match *self {
+            AnyMessage::Notify(ref mut n) => n.set_receive_info(info),
+            AnyMessage::Search(ref mut n) => n.set_receive_info(info),
+            AnyMessage::Response(ref mut n) => n.set_receive_info(info),
+        }
+    }
+}
+
+impl HeaderRef for AnyMessage {
+    fn get<H>(&self) -> Option<H>
+        where H: Header + HeaderFormat
+    {
+        match *self {
+            AnyMessage::Notify(ref n) => n.get::<H>(),
+            AnyMessage::Search(ref n) => n.get::<H>(),
+            AnyMessage::Response(ref n) => n.get::<H>(),
+        }
+    }
+
+    fn get_raw(&self, name: &str) -> Option<&[Vec<u8>]> {
+        match *self {
+            AnyMessage::Notify(ref n) => n.get_raw(name),
+            AnyMessage::Search(ref n) => n.get_raw(name),
+            AnyMessage::Response(ref n) => n.get_raw(name),
+        }
+    }
+
+    fn iter_headers(&self) -> HeadersItems {
+        match *self {
+            AnyMessage::Notify(ref n) => n.iter_headers(),
+            AnyMessage::Search(ref n) => n.iter_headers(),
+            AnyMessage::Response(ref n) => n.iter_headers(),
+        }
+    }
+}
+
+/// Monitor listener that can listen for every notify message and search request
+/// sent within the network as a single stream of `AnyMessage`s.
+///
+/// Search responses are sent via unicast to the searching control point, so they
+/// are only seen when sent to the port being listened on.
+pub struct MonitorListener;
+
+impl Listen for MonitorListener {
+    type Message = AnyMessage;
+}
+
+#[cfg(test)]
+mod tests {
+    use header::{HeaderRef, NTS, ST};
+    use message::{MessageType, ParseMode, Quirk, Quirks};
+    use receiver::FromRawSSDP;
+    use super::{parse_message, parse_message_with_mode, parse_message_with_quirks, AnyMessage};
+
+    #[test]
+    fn positive_notify() {
+        let message = AnyMessage::raw_ssdp(b"NOTIFY * HTTP/1.1\r\n\
+                                             HOST: 239.255.255.250:1900\r\n\
+                                             NTS: ssdp:alive\r\n\r\n")
+            .unwrap();
+
+        assert_eq!(message.message_type(), MessageType::Notify);
+        assert_eq!(message.get::<NTS>(), Some(NTS::Alive));
+    }
+
+    #[test]
+    fn positive_search() {
+        let message = AnyMessage::raw_ssdp(b"M-SEARCH * HTTP/1.1\r\n\
+                                             HOST: 239.255.255.250:1900\r\n\
+                                             ST: ssdp:all\r\n\r\n")
+            .unwrap();
+
+        assert_eq!(message.message_type(), MessageType::Search);
+        assert_eq!(message.get::<ST>(), Some(ST::All));
+    }
+
+    #[test]
+    fn positive_response() {
+        let message = AnyMessage::raw_ssdp(b"HTTP/1.1 200 OK\r\nST: ssdp:all\r\n\r\n").unwrap();
+
+        assert_eq!(message.message_type(), MessageType::Response);
+    }
+
+    #[test]
+    fn positive_from_str_round_trip() {
+        let raw_message = "HTTP/1.1 200 OK\r\nST: ssdp:all\r\nContent-Length: 0\r\n\r\n";
+        let message = raw_message.parse::<AnyMessage>().unwrap();
+
+        assert_eq!(message.message_type(), MessageType::Response);
+        assert_eq!(message.to_string(), raw_message);
+    }
+
+    #[test]
+    fn positive_parse_message_to_bytes() {
+        let raw_message = b"NOTIFY * HTTP/1.1\r\nHOST: 239.255.255.250:1900\r\nNTS: ssdp:byebye\r\n\
+                            Content-Length: 0\r\n\r\n";
+        let message = parse_message(raw_message).unwrap();
+
+        assert_eq!(message.get::<NTS>(), Some(NTS::ByeBye));
+        assert_eq!(&message.to_bytes()[..], &raw_message[..]);
+    }
+
+    #[test]
+    fn positive_parse_message_with_mode() {
+        let raw_message = b"M-SEARCH * HTTP/1.1\nHOST: 239.255.255.250:1900\nST: ssdp:all\n\n";
+
+        assert!(parse_message_with_mode(raw_message, ParseMode::Lenient).is_ok());
+        assert!(parse_message_with_mode(raw_message, ParseMode::Strict).is_err());
+    }
+
+    #[test]
+    fn positive_parse_message_with_quirks() {
+        let raw_message = b"HTTP/1.1 200 OK\r\nST: ssdp:all\r\nCACHE-CONTROL: max-age = 1800\r\n\r\n";
+
+        assert!(parse_message_with_quirks(raw_message, Quirks::all()).is_ok());
+        let tolerated = Quirks::none().with(Quirk::MissingExt).with(Quirk::SpacedCacheControl);
+
+        assert!(parse_message_with_quirks(raw_message, Quirks::all().without(Quirk::MissingExt)).is_err());
+        assert!(parse_message_with_quirks(raw_message, Quirks::none().with(Quirk::MissingExt)).is_err());
+        assert!(parse_message_with_quirks(raw_message, tolerated).is_ok());
+    }
+
+    #[test]
+    #[should_panic]
+    fn negative_not_ssdp() {
+        AnyMessage::raw_ssdp(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+    }
+}
@@ -1,26 +1,434 @@
 use std::borrow::Cow;
-use std::fmt::Debug;
+use std::cmp;
+use std::fmt::{self, Debug, Display, Formatter};
 use std::net::SocketAddr;
+use std::str::FromStr;
+use std::time::Duration;
 
-use hyper::header::{Header, HeaderFormat};
+#[cfg(feature = "url")]
+use url::Url;
 
-use error::{SSDPResult, MsgError};
-use header::{HeaderRef, HeaderMut};
+use FieldMap;
+use error::{SSDPResult, SSDPError, MsgError};
+use header::{self, Header, HeaderFormat, HeaderRef, HeaderMut, HeadersItems, BootID, CacheControl,
+             CacheDirective, ConfigID, Location, NextBootID, ProductTokens, SearchPort, SecureLocation,
+             Server, TcpPort, NT, NTS, ST, USN};
 use message::{self, MessageType, Listen};
+use message::search::{self, SearchResponse};
 use message::ssdp::SSDPMessage;
-use receiver::FromRawSSDP;
-use net;
+use receiver::{FromRawSSDP, ReceiveInfo};
+use net::{self, InterfaceFilter, SocketHook};
+use target::{self, SearchTarget};
 
 /// Notify message that can be sent via multicast to devices on the network.
 #[derive(Debug, Clone)]
 pub struct NotifyMessage {
     message: SSDPMessage,
+    multicast_dst: Option<SocketAddr>,
+    interfaces: InterfaceFilter,
+    multicast_ttl: u32,
+    multicast_hops: Option<u32>,
+    multicast_loop: Option<bool>,
+    device: Option<String>,
+    tos: Option<u8>,
+    socket_hook: Option<SocketHook>,
 }
 
 impl NotifyMessage {
     /// Construct a new NotifyMessage.
     pub fn new() -> Self {
-        NotifyMessage { message: SSDPMessage::new(MessageType::Notify) }
+        NotifyMessage::from_message(SSDPMessage::new(MessageType::Notify))
+    }
+
+    /// Construct a new AliveBuilder for an `ssdp:alive` announcement.
+    pub fn alive() -> AliveBuilder {
+        AliveBuilder::new()
+    }
+
+    /// Construct a new UpdateBuilder for an `ssdp:update` announcement.
+    pub fn update() -> UpdateBuilder {
+        UpdateBuilder::new()
+    }
+
+    /// Construct an `ssdp:byebye` NotifyMessage announcing that the device or
+    /// service is leaving the network.
+    pub fn byebye(notification_type: FieldMap, usn: USN) -> NotifyMessage {
+        let mut message = NotifyMessage::new();
+
+        message.set(NT(notification_type));
+        message.set(NTS::ByeBye);
+        message.set(usn);
+
+        message
+    }
+
+    /// Construct the `ssdp:byebye` NotifyMessage that withdraws this announcement.
+    ///
+    /// The NT and USN headers are copied over, as are the BOOTID.UPNP.ORG and
+    /// CONFIGID.UPNP.ORG headers if present, along with the options for sending it,
+    /// such as any multicast destination and interface selection.
+    pub fn to_byebye(&self) -> SSDPResult<NotifyMessage> {
+        let notification_type = try!(required(self.get::<NT>().map(|nt| nt.0.clone()), NT::header_name()));
+        let usn = try!(required(self.get::<USN>(), USN::header_name()));
+
+        let mut byebye = NotifyMessage::byebye(notification_type, usn);
+        if let Some(boot_id) = self.get::<BootID>() {
+            byebye.set(boot_id);
+        }
+        if let Some(config_id) = self.get::<ConfigID>() {
+            byebye.set(config_id);
+        }
+        self.copy_send_options(&mut byebye);
+
+        Ok(byebye)
+    }
+
+    /// Construct the `ssdp:update` NotifyMessage that announces this device is
+    /// switching from the boot instance to the next boot instance.
+    ///
+    /// The NT, USN and LOCATION headers are copied over, as are the CONFIGID.UPNP.ORG,
+    /// SECURELOCATION.UPNP.ORG and TCPPORT.UPNP.ORG headers if present, along with
+    /// the options for sending it, such as any multicast destination and interface
+    /// selection.
+    pub fn to_update(&self, boot_id: u32, next_boot_id: u32) -> SSDPResult<NotifyMessage> {
+        let notification_type = try!(required(self.get::<NT>().map(|nt| nt.0.clone()), NT::header_name()));
+        let usn = try!(required(self.get::<USN>(), USN::header_name()));
+        let location = try!(required(self.get::<Location>(), Location::header_name()));
+
+        let mut builder = NotifyMessage::update()
+            .notification_type(notification_type)
+            .usn(usn)
+            .location(location.0)
+            .boot_id(boot_id)
+            .next_boot_id(next_boot_id);
+        if let Some(config_id) = self.get::<ConfigID>() {
+            builder = builder.config_id(config_id.0);
+        }
+
+        let mut update = try!(builder.build());
+        if let Some(secure_location) = self.get::<SecureLocation>() {
+            update.set(secure_location.clone());
+        }
+        if let Some(tcp_port) = self.get::<TcpPort>() {
+            update.set(tcp_port);
+        }
+        self.copy_send_options(&mut update);
+
+        Ok(update)
+    }
+
+    /// Multicast the `ssdp:byebye` NotifyMessage that withdraws this announcement.
+    pub fn multicast_byebye(&self) -> SSDPResult<()> {
+        let mut byebye = try!(self.to_byebye());
+
+        byebye.multicast()
+    }
+
+    /// Construct the SearchResponse that answers a search for the given target
+    /// with this announcement.
+    ///
+    /// The USN, LOCATION, CACHE-CONTROL and SERVER headers are copied over, as are
+    /// the BOOTID.UPNP.ORG, CONFIGID.UPNP.ORG, SEARCHPORT.UPNP.ORG, SECURELOCATION.UPNP.ORG
+    /// and TCPPORT.UPNP.ORG headers if present, so that the response can not
+    /// disagree with the announcement.
+    pub fn to_response(&self, search_target: ST) -> SSDPResult<SearchResponse> {
+        let usn = try!(required(self.get::<USN>(), USN::header_name()));
+        let location = try!(required(self.get::<Location>(), Location::header_name()));
+        let cache_control = try!(required(self.get::<CacheControl>(), CacheControl::header_name()));
+        let server = try!(required(self.get::<Server>(), Server::header_name()));
+
+        let mut response = SearchResponse::new();
+        response.set(cache_control);
+        response.set_raw(search::EXT_HEADER_NAME, vec![Vec::new()]);
+        response.set(location);
+        response.set(server);
+        response.set(search_target);
+        response.set(usn);
+        if let Some(boot_id) = self.get::<BootID>() {
+            response.set(boot_id);
+        }
+        if let Some(config_id) = self.get::<ConfigID>() {
+            response.set(config_id);
+        }
+        if let Some(search_port) = self.get::<SearchPort>() {
+            response.set(search_port);
+        }
+        if let Some(secure_location) = self.get::<SecureLocation>() {
+            response.set(secure_location.clone());
+        }
+        if let Some(tcp_port) = self.get::<TcpPort>() {
+            response.set(tcp_port);
+        }
+
+        Ok(response)
+    }
+
+    /// Construct the SearchResponse that answers a search for the search target
+    /// with this announcement, or None if the announced target does not match it.
+    ///
+    /// A search for "ssdp:all" is answered with the announced target, any other
+    /// search is answered with the searched target.
+    pub fn respond_to(&self, search_target: &ST) -> SSDPResult<Option<SearchResponse>> {
+        let announced = match self.get::<NT>() {
+            Some(nt) => ST::Target(nt.0.clone()),
+            None => return Err(SSDPError::MissingHeader(NT::header_name())),
+        };
+
+        if !target::target_matches(search_target, &announced) {
+            return Ok(None);
+        }
+
+        let response_target = match *search_target {
+            ST::All => announced,
+            ref other => other.clone(),
+        };
+
+        self.to_response(response_target).map(Some)
+    }
+
+    /// Copy the options for sending this message over to the other message.
+    fn copy_send_options(&self, other: &mut NotifyMessage) {
+        other.multicast_dst = self.multicast_dst;
+        other.interfaces = self.interfaces.clone();
+        other.multicast_ttl = self.multicast_ttl;
+        other.multicast_hops = self.multicast_hops;
+        other.multicast_loop = self.multicast_loop;
+        other.device = self.device.clone();
+        other.tos = self.tos;
+        other.socket_hook = self.socket_hook.clone();
+    }
+
+    fn from_message(message: SSDPMessage) -> NotifyMessage {
+        NotifyMessage {
+            message: message,
+            multicast_dst: None,
+            interfaces: InterfaceFilter::All,
+            multicast_ttl: message::UPNP_MULTICAST_TTL,
+            multicast_hops: None,
+            multicast_loop: None,
+            device: None,
+            tos: None,
+            socket_hook: None,
+        }
+    }
+
+    /// Send multicasts of this notify message to the given address:port instead
+    /// of the standard multicast address:port, such as one for a test network.
+    ///
+    /// Only local interfaces of the same IP version as the address are sent from.
+    /// Passing None will go back to using the standard multicast address:port.
+    pub fn set_multicast_destination(&mut self, opt_addr: Option<SocketAddr>) {
+        self.multicast_dst = opt_addr;
+    }
+
+    /// Send multicasts of this notify message from the selected local network
+    /// interfaces only, instead of from every local network interface.
+    pub fn set_interfaces(&mut self, interfaces: InterfaceFilter) {
+        self.interfaces = interfaces;
+    }
+
+    /// Local network interfaces that multicasts of this notify message are sent from.
+    pub fn interfaces(&self) -> &InterfaceFilter {
+        &self.interfaces
+    }
+
+    /// Set the TTL of multicasts of this notify message sent from IPv4 interfaces,
+    /// such as 4 or more for networks where SSDP has to cross several routers.
+    ///
+    /// Defaults to `UPNP_MULTICAST_TTL`, the value recommended by the `UPnP` standard.
+    pub fn set_multicast_ttl(&mut self, ttl: u32) {
+        self.multicast_ttl = ttl;
+    }
+
+    /// TTL of multicasts of this notify message sent from IPv4 interfaces.
+    pub fn multicast_ttl(&self) -> u32 {
+        self.multicast_ttl
+    }
+
+    /// Set the hop limit of multicasts of this notify message sent from IPv6 interfaces,
+    /// the IPv6 equivalent of the TTL.
+    ///
+    /// Passing None will go back to the default of the scope of each group,
+    /// `UPNP_MULTICAST_HOPS_LINK_LOCAL` or `UPNP_MULTICAST_HOPS_SITE_LOCAL`.
+    pub fn set_multicast_hops(&mut self, opt_hops: Option<u32>) {
+        self.multicast_hops = opt_hops;
+    }
+
+    /// Hop limit of multicasts of this notify message sent from IPv6 interfaces, or None
+    /// if the default of the scope of each group is used.
+    pub fn multicast_hops(&self) -> Option<u32> {
+        self.multicast_hops
+    }
+
+    /// Enable or disable the looping back of multicasts of this notify message to sockets
+    /// on the local host, such as to reach a stack running on the same host.
+    ///
+    /// Passing None will go back to using the default of the operating system.
+    pub fn set_multicast_loop(&mut self, opt_enabled: Option<bool>) {
+        self.multicast_loop = opt_enabled;
+    }
+
+    /// Whether or not multicasts of this notify message are looped back to the local host,
+    /// or None if the default of the operating system is used.
+    pub fn multicast_loop(&self) -> Option<bool> {
+        self.multicast_loop
+    }
+
+    /// Send multicasts of this notify message only through the local network device with
+    /// the given name, such as "eth0", binding the sockets to it with `SO_BINDTODEVICE`.
+    ///
+    /// Binding to a device is only supported on linux, sending fails on other systems.
+    /// Passing None will go back to sending through every local network device.
+    pub fn set_device(&mut self, opt_device: Option<String>) {
+        self.device = opt_device;
+    }
+
+    /// Local network device that multicasts of this notify message are sent through, if any.
+    pub fn device(&self) -> Option<&str> {
+        self.device.as_ref().map(|device| &device[..])
+    }
+
+    /// Set the TOS byte, or IPv6 traffic class, of packets of this notify message so that
+    /// QoS policies can classify them, such as 0xB8 for the DSCP value 46.
+    ///
+    /// The DSCP value is the upper six bits of the byte. Passing None will go
+    /// back to using the default of the operating system.
+    pub fn set_tos(&mut self, opt_tos: Option<u8>) {
+        self.tos = opt_tos;
+    }
+
+    /// TOS byte of packets of this notify message, or None if the default of the
+    /// operating system is used.
+    pub fn tos(&self) -> Option<u8> {
+        self.tos
+    }
+
+    /// Set the hook that is invoked on each socket that this notify message is sent from,
+    /// before the socket is bound, to set options that are not otherwise exposed.
+    ///
+    /// Passing None will remove any previously set hook.
+    pub fn set_socket_hook(&mut self, opt_hook: Option<SocketHook>) {
+        self.socket_hook = opt_hook;
+    }
+
+    /// Hook that is invoked on each socket that this notify message is sent from, if any.
+    pub fn socket_hook(&self) -> Option<&SocketHook> {
+        self.socket_hook.as_ref()
+    }
+
+    /// Serialize this message as it is written to the network.
+    ///
+    /// A missing HOST header is left out, as it is only filled in from the
+    /// destination address when the message is sent.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.message.to_bytes()
+    }
+
+    /// Get the metadata about how this message was received, if it was received.
+    pub fn receive_info(&self) -> Option<&ReceiveInfo> {
+        self.message.receive_info()
+    }
+
+    /// Whether or not the HOST header names an SSDP multicast group and port, as it
+    /// must for a notify message received by multicast.
+    pub fn has_multicast_host(&self) -> bool {
+        message::is_multicast_host(self)
+    }
+
+    /// URL for the description of the device or service, which must be absolute.
+    #[cfg(feature = "url")]
+    pub fn location_url(&self) -> SSDPResult<Url> {
+        header::location_url(self)
+    }
+
+    /// URL for the description of the device or service, which must be an
+    /// absolute http or https URL.
+    #[cfg(feature = "url")]
+    pub fn strict_location_url(&self) -> SSDPResult<Url> {
+        header::strict_location_url(self)
+    }
+
+    /// Operating system, `UPnP` version and product of the notifying device.
+    pub fn server(&self) -> Option<&str> {
+        self.get_str(Server::header_name())
+    }
+
+    /// Operating system, `UPnP` version and product of the notifying device,
+    /// split into their tokens.
+    ///
+    /// Returns None if there is no SERVER header or it has no `UPnP` version token.
+    pub fn server_tokens(&self) -> Option<ProductTokens> {
+        self.server().and_then(|server| server.parse().ok())
+    }
+
+    /// Notification type of this message, if it is a standard target.
+    pub fn nt(&self) -> Option<SearchTarget> {
+        self.get::<NT>().and_then(|nt| nt.target())
+    }
+
+    /// Notification sub type of this message, if it is a standard one.
+    pub fn nts(&self) -> Option<NTS> {
+        self.get::<NTS>()
+    }
+
+    /// Whether or not this message is an `ssdp:update` announcing a new boot instance.
+    pub fn is_update(&self) -> bool {
+        self.nts() == Some(NTS::Update)
+    }
+
+    /// Boot instance of the notifying device, from the BOOTID.UPNP.ORG header.
+    pub fn boot_id(&self) -> Option<u32> {
+        self.get::<BootID>().map(|boot_id| boot_id.0)
+    }
+
+    /// Boot instance that the notifying device will use after an `ssdp:update`,
+    /// from the NEXTBOOTID.UPNP.ORG header.
+    pub fn next_boot_id(&self) -> Option<u32> {
+        self.get::<NextBootID>().map(|next_boot_id| next_boot_id.0)
+    }
+
+    /// Configuration number of the notifying device's description, from the
+    /// CONFIGID.UPNP.ORG header.
+    pub fn config_id(&self) -> Option<u32> {
+        self.get::<ConfigID>().map(|config_id| config_id.0)
+    }
+
+    /// Port that the notifying device accepts unicast search requests on, if it
+    /// is not the standard port.
+    pub fn search_port(&self) -> Option<u16> {
+        self.get::<SearchPort>().map(|search_port| search_port.0)
+    }
+
+    /// Network Location Signature sent by Windows notifying devices, which changes
+    /// whenever the device reconnects to the network.
+    pub fn nls(&self) -> Option<&str> {
+        header::nls(self)
+    }
+
+    /// HTTPS URL for the description of the notifying device, for `UPnP` Device
+    /// Protection aware control points.
+    pub fn secure_location(&self) -> Option<&str> {
+        self.get_str(SecureLocation::header_name())
+    }
+
+    /// TCP port that the notifying device accepts `UPnP` Device Protection connections on.
+    pub fn tcp_port(&self) -> Option<u16> {
+        self.get::<TcpPort>().map(|tcp_port| tcp_port.0)
+    }
+
+    /// Duration that this announcement is valid for.
+    pub fn max_age(&self) -> Option<Duration> {
+        header::max_age(self)
+    }
+
+    /// Set the duration that this announcement is valid for as the CACHE-CONTROL
+    /// max-age directive, replacing any existing CACHE-CONTROL header.
+    ///
+    /// The duration is truncated to whole seconds.
+    pub fn set_max_age(&mut self, max_age: Duration) {
+        let seconds = cmp::min(max_age.as_secs(), u32::MAX as u64) as u32;
+
+        self.set(CacheControl(vec![CacheDirective::MaxAge(seconds)]));
     }
 
     /// Send this notify message to the standard multicast address:port.
@@ -29,23 +437,38 @@ impl NotifyMessage {
     }
 
     /// Send this notify message to the standard multicast address but a custom port.
+    ///
+    /// The port is ignored if a multicast destination override has been set.
     pub fn multicast_with_port(&mut self, port: u16) -> SSDPResult<()> {
-        let mcast_ttl = Some(message::UPNP_MULTICAST_TTL);
+        let mcast_ttl = Some(self.multicast_ttl);
 
-        let mut connectors = try!(message::all_local_connectors(mcast_ttl, net::IpVersionMode::Any));
+        let connectors = try!(message::all_local_connectors(mcast_ttl,
+                                                            net::IpVersionMode::Any,
+                                                            0,
+                                                            self.socket_hook()));
+        let mut connectors = try!(message::configure_connectors(connectors,
+                                                                self.multicast_loop,
+                                                                self.device(),
+                                                                self.tos));
 
         // Send On All Connectors
         for conn in &mut connectors {
-            match try!(conn.local_addr()) {
-                SocketAddr::V4(n) => {
-                    let mcast_addr = (message::UPNP_MULTICAST_IPV4_ADDR, port);
-                    debug!("Sending ipv4 multicast through {} to {:?}", n, mcast_addr);
-                    try!(self.message.send(conn, &mcast_addr));
+            let local_addr = try!(conn.local_addr());
+
+            if !self.interfaces.matches(&local_addr.ip()) {
+                trace!("Skipping multicast through {}, interface is not selected", local_addr);
+                continue;
+            }
+
+            match try!(message::multicast_dst_addr(local_addr, port, self.multicast_dst)) {
+                Some(mcast_addr) => {
+                    debug!("Sending multicast through {} to {}", local_addr, mcast_addr);
+                    try!(message::set_multicast_hops(conn, &mcast_addr, self.multicast_hops));
+                    try!(message::send_local(&self.message, conn, mcast_addr));
                 }
-                SocketAddr::V6(n) => {
-                    let mcast_addr = (message::UPNP_MULTICAST_IPV6_LINK_LOCAL_ADDR, port);
-                    debug!("Sending Ipv6 multicast through {} to {:?}", n, mcast_addr);
-                    try!(self.message.send(conn, &mcast_addr));
+                None => {
+                    trace!("Skipping multicast through {}, destination is another IP version",
+                           local_addr)
                 }
             }
         }
@@ -60,6 +483,20 @@ impl Default for NotifyMessage {
     }
 }
 
+impl Display for NotifyMessage {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        Display::fmt(&self.message, f)
+    }
+}
+
+impl FromStr for NotifyMessage {
+    type Err = SSDPError;
+
+    fn from_str(s: &str) -> SSDPResult<NotifyMessage> {
+        NotifyMessage::raw_ssdp(s.as_bytes())
+    }
+}
+
 impl FromRawSSDP for NotifyMessage {
     fn raw_ssdp(bytes: &[u8]) -> SSDPResult<NotifyMessage> {
         let message = try!(SSDPMessage::raw_ssdp(bytes));
@@ -67,13 +504,17 @@ impl FromRawSSDP for NotifyMessage {
         if message.message_type() != MessageType::Notify {
             try!(Err(MsgError::new("SSDP Message Received Is Not A NotifyMessage")))
         } else {
-            Ok(NotifyMessage { message: message })
+            Ok(NotifyMessage::from_message(message))
         }
     }
+
+    fn set_receive_info(&mut self, info: ReceiveInfo) {
+        self.message.set_receive_info(info)
+    }
 }
 
 impl HeaderRef for NotifyMessage {
-    fn get<H>(&self) -> Option<&H>
+    fn get<H>(&self) -> Option<H>
         where H: Header + HeaderFormat
     {
         self.message.get::<H>()
@@ -82,6 +523,10 @@ impl HeaderRef for NotifyMessage {
     fn get_raw(&self, name: &str) -> Option<&[Vec<u8>]> {
         self.message.get_raw(name)
     }
+
+    fn iter_headers(&self) -> HeadersItems {
+        self.message.iter_headers()
+    }
 }
 
 impl HeaderMut for NotifyMessage {
@@ -96,6 +541,205 @@ impl HeaderMut for NotifyMessage {
     {
         self.message.set_raw(name, value)
     }
+
+    fn remove<H>(&mut self) -> bool
+        where H: Header + HeaderFormat
+    {
+        self.message.remove::<H>()
+    }
+
+    fn remove_raw(&mut self, name: &str) -> bool {
+        self.message.remove_raw(name)
+    }
+}
+
+/// Builder for an `ssdp:alive` `NotifyMessage` that sets and validates the
+/// headers required by the standard.
+///
+/// The NTS header is always set, and the NT, USN, LOCATION, CACHE-CONTROL and
+/// SERVER headers are required.
+#[derive(Debug, Clone, Default)]
+pub struct AliveBuilder {
+    notification_type: Option<FieldMap>,
+    usn: Option<USN>,
+    location: Option<String>,
+    max_age: Option<u32>,
+    server: Option<String>,
+    secure_location: Option<String>,
+    tcp_port: Option<u16>,
+}
+
+impl AliveBuilder {
+    /// Construct a new AliveBuilder.
+    pub fn new() -> AliveBuilder {
+        AliveBuilder::default()
+    }
+
+    /// Set the notification type being announced, such as "upnp:rootdevice".
+    pub fn notification_type(mut self, field: FieldMap) -> AliveBuilder {
+        self.notification_type = Some(field);
+        self
+    }
+
+    /// Set the unique service name of the device or service being announced.
+    pub fn usn(mut self, usn: USN) -> AliveBuilder {
+        self.usn = Some(usn);
+        self
+    }
+
+    /// Set the URL for the description of the device.
+    ///
+    /// The URL may contain the `LOCATION_BIND_ADDR` placeholder, which is replaced
+    /// with the address of each local interface the message is sent from.
+    pub fn location<S>(mut self, location: S) -> AliveBuilder
+        where S: Into<String>
+    {
+        self.location = Some(location.into());
+        self
+    }
+
+    /// Set the number of seconds that the announcement is valid for.
+    pub fn max_age(mut self, seconds: u32) -> AliveBuilder {
+        self.max_age = Some(seconds);
+        self
+    }
+
+    /// Set the operating system, `UPnP` version and product of the device.
+    pub fn server<S>(mut self, server: S) -> AliveBuilder
+        where S: Into<String>
+    {
+        self.server = Some(server.into());
+        self
+    }
+
+    /// Set the HTTPS URL for the description of the device, for `UPnP` Device
+    /// Protection aware control points.
+    pub fn secure_location<S>(mut self, secure_location: S) -> AliveBuilder
+        where S: Into<String>
+    {
+        self.secure_location = Some(secure_location.into());
+        self
+    }
+
+    /// Set the TCP port that the device accepts `UPnP` Device Protection connections on.
+    pub fn tcp_port(mut self, port: u16) -> AliveBuilder {
+        self.tcp_port = Some(port);
+        self
+    }
+
+    /// Validate the headers and construct the NotifyMessage.
+    pub fn build(self) -> SSDPResult<NotifyMessage> {
+        let notification_type = try!(required(self.notification_type, NT::header_name()));
+        let usn = try!(required(self.usn, USN::header_name()));
+        let location = try!(required(self.location, Location::header_name()));
+        let max_age = try!(required(self.max_age, CacheControl::header_name()));
+        let server = try!(required(self.server, Server::header_name()));
+
+        let mut message = NotifyMessage::new();
+
+        message.set(NT(notification_type));
+        message.set(NTS::Alive);
+        message.set(usn);
+        message.set(Location(location));
+        message.set(CacheControl(vec![CacheDirective::MaxAge(max_age)]));
+        message.set(Server(server));
+        if let Some(secure_location) = self.secure_location {
+            message.set(SecureLocation(secure_location));
+        }
+        if let Some(tcp_port) = self.tcp_port {
+            message.set(TcpPort(tcp_port));
+        }
+
+        Ok(message)
+    }
+}
+
+/// Builder for an `ssdp:update` `NotifyMessage`, sent when a device is about to
+/// change its boot instance, that sets and validates the required headers.
+///
+/// The NTS header is always set, and the NT, USN, LOCATION, BOOTID.UPNP.ORG and
+/// NEXTBOOTID.UPNP.ORG headers are required.
+#[derive(Debug, Clone, Default)]
+pub struct UpdateBuilder {
+    notification_type: Option<FieldMap>,
+    usn: Option<USN>,
+    location: Option<String>,
+    boot_id: Option<u32>,
+    next_boot_id: Option<u32>,
+    config_id: Option<u32>,
+}
+
+impl UpdateBuilder {
+    /// Construct a new UpdateBuilder.
+    pub fn new() -> UpdateBuilder {
+        UpdateBuilder::default()
+    }
+
+    /// Set the notification type being announced, such as "upnp:rootdevice".
+    pub fn notification_type(mut self, field: FieldMap) -> UpdateBuilder {
+        self.notification_type = Some(field);
+        self
+    }
+
+    /// Set the unique service name of the device or service being announced.
+    pub fn usn(mut self, usn: USN) -> UpdateBuilder {
+        self.usn = Some(usn);
+        self
+    }
+
+    /// Set the URL for the description of the device.
+    pub fn location<S>(mut self, location: S) -> UpdateBuilder
+        where S: Into<String>
+    {
+        self.location = Some(location.into());
+        self
+    }
+
+    /// Set the current boot instance of the device.
+    pub fn boot_id(mut self, boot_id: u32) -> UpdateBuilder {
+        self.boot_id = Some(boot_id);
+        self
+    }
+
+    /// Set the boot instance that the device will use after the update.
+    pub fn next_boot_id(mut self, next_boot_id: u32) -> UpdateBuilder {
+        self.next_boot_id = Some(next_boot_id);
+        self
+    }
+
+    /// Set the configuration number of the device description.
+    pub fn config_id(mut self, config_id: u32) -> UpdateBuilder {
+        self.config_id = Some(config_id);
+        self
+    }
+
+    /// Validate the headers and construct the NotifyMessage.
+    pub fn build(self) -> SSDPResult<NotifyMessage> {
+        let notification_type = try!(required(self.notification_type, NT::header_name()));
+        let usn = try!(required(self.usn, USN::header_name()));
+        let location = try!(required(self.location, Location::header_name()));
+        let boot_id = try!(required(self.boot_id, BootID::header_name()));
+        let next_boot_id = try!(required(self.next_boot_id, NextBootID::header_name()));
+
+        let mut message = NotifyMessage::new();
+
+        message.set(NT(notification_type));
+        message.set(NTS::Update);
+        message.set(usn);
+        message.set(Location(location));
+        message.set(BootID(boot_id));
+        message.set(NextBootID(next_boot_id));
+        if let Some(config_id) = self.config_id {
+            message.set(ConfigID(config_id));
+        }
+
+        Ok(message)
+    }
+}
+
+/// Unwrap a required header value, returning an error naming the header if it was not set.
+fn required<T>(opt_value: Option<T>, name: &'static str) -> SSDPResult<T> {
+    opt_value.ok_or(SSDPError::MissingHeader(name))
 }
 
 /// Notify listener that can listen to notify messages sent within the network.
@@ -107,8 +751,303 @@ impl Listen for NotifyListener {
 
 #[cfg(test)]
 mod tests {
-    use super::NotifyMessage;
+    use std::time::Duration;
+
+    use FieldMap;
+    use error::SSDPError;
+    use header::{HeaderRef, HeaderMut, BootID, CacheControl, CacheDirective, ConfigID, Location, NextBootID,
+                 SecureLocation, Server, TcpPort, NT, NTS, ST, USN};
+    use message;
+    use super::{NotifyMessage, AliveBuilder};
     use receiver::FromRawSSDP;
+    use target::SearchTarget;
+
+    fn alive_builder() -> AliveBuilder {
+        NotifyMessage::alive()
+            .notification_type(FieldMap::upnp("rootdevice"))
+            .usn(USN(FieldMap::uuid("device"), Some(FieldMap::upnp("rootdevice"))))
+            .location("http://192.168.0.2:80/desc.xml")
+            .max_age(1800)
+            .server("Linux/3.0 UPnP/1.0 Test/1.0")
+    }
+
+    #[test]
+    fn positive_alive_builder() {
+        let message = alive_builder().build().unwrap();
+
+        assert_eq!(message.get::<NT>(), Some(NT(FieldMap::upnp("rootdevice"))));
+        assert_eq!(message.get::<NTS>(), Some(NTS::Alive));
+        assert_eq!(message.get::<USN>(),
+                   Some(USN(FieldMap::uuid("device"), Some(FieldMap::upnp("rootdevice")))));
+        assert_eq!(message.get::<Location>(),
+                   Some(Location("http://192.168.0.2:80/desc.xml".to_owned())));
+        assert_eq!(message.get::<CacheControl>(),
+                   Some(CacheControl(vec![CacheDirective::MaxAge(1800)])));
+        assert_eq!(message.get::<Server>(), Some(Server("Linux/3.0 UPnP/1.0 Test/1.0".to_owned())));
+    }
+
+    #[test]
+    fn positive_set_max_age() {
+        let mut message = alive_builder().build().unwrap();
+        message.set_max_age(Duration::from_millis(900500));
+
+        assert_eq!(message.get::<CacheControl>(),
+                   Some(CacheControl(vec![CacheDirective::MaxAge(900)])));
+        assert_eq!(message.max_age(), Some(Duration::from_secs(900)));
+    }
+
+    #[test]
+    fn positive_parse_max_age() {
+        let notify = NotifyMessage::raw_ssdp(b"NOTIFY * HTTP/1.1\r\n\
+                                               HOST: 239.255.255.250:1900\r\n\
+                                               CACHE-CONTROL: max-age=1800\r\n\
+                                               NTS: ssdp:alive\r\n\r\n")
+            .unwrap();
+
+        assert_eq!(notify.max_age(), Some(Duration::from_secs(1800)));
+    }
+
+    #[test]
+    fn negative_missing_max_age() {
+        let mut message = alive_builder().build().unwrap();
+        message.set(CacheControl(vec![CacheDirective::NoCache]));
+
+        assert!(message.max_age().is_none());
+    }
+
+    #[test]
+    fn positive_to_byebye() {
+        let mut alive = alive_builder().build().unwrap();
+        alive.set(BootID(7));
+
+        let byebye = alive.to_byebye().unwrap();
+
+        assert_eq!(byebye.get::<NTS>(), Some(NTS::ByeBye));
+        assert_eq!(byebye.get::<NT>(), alive.get::<NT>());
+        assert_eq!(byebye.get::<USN>(), alive.get::<USN>());
+        assert_eq!(byebye.get::<BootID>(), Some(BootID(7)));
+        assert!(byebye.get::<Location>().is_none());
+        assert!(byebye.get::<CacheControl>().is_none());
+    }
+
+    #[test]
+    fn positive_multicast_ttl() {
+        let mut alive = alive_builder().build().unwrap();
+        assert_eq!(alive.multicast_ttl(), message::UPNP_MULTICAST_TTL);
+
+        alive.set_multicast_ttl(4);
+        assert_eq!(alive.to_byebye().unwrap().multicast_ttl(), 4);
+        assert_eq!(alive.to_update(1, 2).unwrap().multicast_ttl(), 4);
+
+        alive.set_multicast_hops(Some(3));
+        assert_eq!(alive.to_byebye().unwrap().multicast_hops(), Some(3));
+    }
+
+    #[test]
+    fn positive_multicast_loop() {
+        let mut alive = alive_builder().build().unwrap();
+        assert_eq!(alive.multicast_loop(), None);
+
+        alive.set_multicast_loop(Some(true));
+        assert_eq!(alive.to_byebye().unwrap().multicast_loop(), Some(true));
+    }
+
+    #[test]
+    fn positive_tos() {
+        let mut alive = alive_builder().build().unwrap();
+        assert_eq!(alive.tos(), None);
+
+        alive.set_tos(Some(0xb8));
+        assert_eq!(alive.to_byebye().unwrap().tos(), Some(0xb8));
+        assert_eq!(alive.to_update(1, 2).unwrap().tos(), Some(0xb8));
+    }
+
+    #[test]
+    fn negative_to_byebye_missing_usn() {
+        let mut message = NotifyMessage::new();
+        message.set(NT(FieldMap::upnp("rootdevice")));
+
+        assert!(message.to_byebye().is_err());
+    }
+
+    #[test]
+    fn positive_to_update() {
+        let mut alive = alive_builder().build().unwrap();
+        alive.set(ConfigID(3));
+
+        let update = alive.to_update(7, 8).unwrap();
+
+        assert!(update.is_update());
+        assert_eq!(update.get::<NT>(), alive.get::<NT>());
+        assert_eq!(update.get::<USN>(), alive.get::<USN>());
+        assert_eq!(update.get::<Location>(), alive.get::<Location>());
+        assert_eq!(update.get::<BootID>(), Some(BootID(7)));
+        assert_eq!(update.get::<NextBootID>(), Some(NextBootID(8)));
+        assert_eq!(update.get::<ConfigID>(), Some(ConfigID(3)));
+    }
+
+    #[test]
+    fn positive_to_response() {
+        let mut alive = alive_builder().build().unwrap();
+        alive.set(BootID(7));
+
+        let response = alive.to_response(ST::All).unwrap();
+
+        assert_eq!(response.get::<ST>(), Some(ST::All));
+        assert_eq!(response.get::<USN>(), alive.get::<USN>());
+        assert_eq!(response.get::<Location>(), alive.get::<Location>());
+        assert_eq!(response.get::<CacheControl>(), alive.get::<CacheControl>());
+        assert_eq!(response.get::<Server>(), alive.get::<Server>());
+        assert_eq!(response.get::<BootID>(), Some(BootID(7)));
+        assert!(response.get_raw("EXT").is_some());
+    }
+
+    #[test]
+    fn positive_secure_location_and_tcp_port() {
+        let alive = alive_builder()
+            .secure_location("https://192.168.0.2:443/desc.xml")
+            .tcp_port(443)
+            .build()
+            .unwrap();
+
+        assert_eq!(alive.secure_location(), Some("https://192.168.0.2:443/desc.xml"));
+        assert_eq!(alive.tcp_port(), Some(443));
+
+        let response = alive.to_response(ST::All).unwrap();
+        assert_eq!(response.get::<SecureLocation>(), alive.get::<SecureLocation>());
+        assert_eq!(response.get::<TcpPort>(), Some(TcpPort(443)));
+
+        let update = alive.to_update(1, 2).unwrap();
+        assert_eq!(update.get::<SecureLocation>(), alive.get::<SecureLocation>());
+        assert_eq!(update.get::<TcpPort>(), Some(TcpPort(443)));
+    }
+
+    #[test]
+    fn negative_to_response_missing_location() {
+        let usn = USN(FieldMap::uuid("device"), None);
+        let message = NotifyMessage::byebye(FieldMap::upnp("rootdevice"), usn);
+
+        match message.to_response(ST::All) {
+            Err(SSDPError::MissingHeader("LOCATION")) => (),
+            other => panic!("Expected Missing Location Header, Got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn positive_respond_to() {
+        let alive = alive_builder().build().unwrap();
+
+        let response = alive.respond_to(&ST::All).unwrap().unwrap();
+        assert_eq!(response.get::<ST>(), Some(ST::Target(FieldMap::upnp("rootdevice"))));
+
+        let response = alive.respond_to(&ST::Target(FieldMap::upnp("rootdevice"))).unwrap().unwrap();
+        assert_eq!(response.get::<ST>(), Some(ST::Target(FieldMap::upnp("rootdevice"))));
+
+        assert!(alive.respond_to(&ST::Target(FieldMap::uuid("other"))).unwrap().is_none());
+    }
+
+    #[test]
+    fn positive_update_builder() {
+        let message = NotifyMessage::update()
+            .notification_type(FieldMap::upnp("rootdevice"))
+            .usn(USN(FieldMap::uuid("device"), Some(FieldMap::upnp("rootdevice"))))
+            .location("http://192.168.0.2:80/desc.xml")
+            .boot_id(1)
+            .next_boot_id(2)
+            .build()
+            .unwrap();
+
+        assert!(message.is_update());
+        assert_eq!(message.get::<BootID>(), Some(BootID(1)));
+        assert_eq!(message.get::<NextBootID>(), Some(NextBootID(2)));
+    }
+
+    #[test]
+    fn negative_update_builder_missing_next_boot_id() {
+        let builder = NotifyMessage::update()
+            .notification_type(FieldMap::upnp("rootdevice"))
+            .usn(USN(FieldMap::uuid("device"), None))
+            .location("http://192.168.0.2:80/desc.xml")
+            .boot_id(1);
+
+        match builder.build() {
+            Err(SSDPError::MissingHeader("NEXTBOOTID.UPNP.ORG")) => (),
+            other => panic!("Expected Missing NextBootID Header, Got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn positive_parse_update() {
+        let notify = NotifyMessage::raw_ssdp(b"NOTIFY * HTTP/1.1\r\n\
+                                               HOST: 239.255.255.250:1900\r\n\
+                                               NT: upnp:rootdevice\r\n\
+                                               NTS: ssdp:update\r\n\
+                                               BOOTID.UPNP.ORG: 1\r\n\
+                                               NEXTBOOTID.UPNP.ORG: 2\r\n\
+                                               USN: uuid:device::upnp:rootdevice\r\n\r\n")
+            .unwrap();
+
+        assert!(notify.is_update());
+        assert_eq!(notify.get::<NextBootID>(), Some(NextBootID(2)));
+        assert_eq!(notify.boot_id(), Some(1));
+        assert_eq!(notify.next_boot_id(), Some(2));
+        assert_eq!(notify.config_id(), None);
+    }
+
+    #[test]
+    fn positive_nls() {
+        let notify = NotifyMessage::raw_ssdp(b"NOTIFY * HTTP/1.1\r\n\
+                                               HOST: 239.255.255.250:1900\r\n\
+                                               NTS: ssdp:alive\r\n\
+                                               OPT: \"http://schemas.upnp.org/upnp/1/0/\"; ns=01\r\n\
+                                               01-NLS: c3a16b8e9fd4a0f0d6b3e4b84b1e97ae\r\n\r\n")
+            .unwrap();
+        assert_eq!(notify.nls(), Some("c3a16b8e9fd4a0f0d6b3e4b84b1e97ae"));
+
+        let notify = NotifyMessage::raw_ssdp(b"NOTIFY * HTTP/1.1\r\n\
+                                               HOST: 239.255.255.250:1900\r\n\
+                                               NLS: 0f1e2d3c\r\n\r\n")
+            .unwrap();
+        assert_eq!(notify.nls(), Some("0f1e2d3c"));
+    }
+
+    #[test]
+    fn positive_typed_nt_and_nts() {
+        let notify = NotifyMessage::raw_ssdp(b"NOTIFY * HTTP/1.1\r\n\
+                                               HOST: 239.255.255.250:1900\r\n\
+                                               NT: urn:schemas-upnp-org:service:ContentDirectory:1\r\n\
+                                               NTS: ssdp:byebye\r\n\
+                                               USN: uuid:device\r\n\r\n")
+            .unwrap();
+
+        assert_eq!(notify.nt(), Some(SearchTarget::service("ContentDirectory", 1)));
+        assert_eq!(notify.nts(), Some(NTS::ByeBye));
+    }
+
+    #[test]
+    fn negative_alive_builder_missing_location() {
+        let builder = NotifyMessage::alive()
+            .notification_type(FieldMap::upnp("rootdevice"))
+            .usn(USN(FieldMap::uuid("device"), None))
+            .max_age(1800)
+            .server("Linux/3.0 UPnP/1.0 Test/1.0");
+
+        match builder.build() {
+            Err(SSDPError::MissingHeader("LOCATION")) => (),
+            other => panic!("Expected Missing Location Header, Got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn negative_alive_builder_missing_server() {
+        let builder = NotifyMessage::alive()
+            .notification_type(FieldMap::upnp("rootdevice"))
+            .usn(USN(FieldMap::uuid("device"), None))
+            .location("http://192.168.0.2:80/desc.xml")
+            .max_age(1800);
+
+        assert!(builder.build().is_err());
+    }
 
     #[test]
     fn positive_notify_message_type() {
@@ -0,0 +1,151 @@
+/// Enumerates the known ways that real devices deviate from the specification
+/// when formatting messages.
+#[derive(Copy, Clone, Hash, Eq, PartialEq, Debug)]
+pub enum Quirk {
+    /// Lines ending with a bare LF instead of CRLF.
+    BareLineFeed,
+    /// Message ending without the empty line after the headers, or without a line
+    /// ending after the last header.
+    MissingFinalCrlf,
+    /// Method names that are not upper case, such as `notify`.
+    LowercaseMethod,
+    /// Status line of a search response without the reason phrase, such as `HTTP/1.1 200`.
+    MissingReasonPhrase,
+    /// Search responses with a status code other than 200, such as `HTTP/1.1 404 Not Found`.
+    NonOkStatus,
+    /// Header lines folded onto continuation lines starting with whitespace.
+    FoldedLines,
+    /// Whitespace between the name of a header and its colon.
+    SpaceBeforeColon,
+    /// Search responses without the EXT header.
+    MissingExt,
+    /// Search requests with a MAN header of `ssdp:discover` without the quotes.
+    UnquotedMan,
+    /// CACHE-CONTROL directives with whitespace around the "=", such as `max-age = 1800`.
+    SpacedCacheControl,
+}
+
+/// All Quirks, In The Order Of Their Bits
+const ALL_QUIRKS: [Quirk; 10] = [Quirk::BareLineFeed,
+                                 Quirk::MissingFinalCrlf,
+                                 Quirk::LowercaseMethod,
+                                 Quirk::MissingReasonPhrase,
+                                 Quirk::FoldedLines,
+                                 Quirk::SpaceBeforeColon,
+                                 Quirk::MissingExt,
+                                 Quirk::UnquotedMan,
+                                 Quirk::SpacedCacheControl,
+                                 Quirk::NonOkStatus];
+
+/// Set of quirks that are tolerated when parsing messages.
+///
+/// Messages showing a quirk that is not in the set fail to parse. The default set,
+/// which receivers parse messages with, tolerates every quirk but `Quirk::NonOkStatus`
+/// so that error responses are not mistaken for devices.
+#[derive(Copy, Clone, Hash, Eq, PartialEq, Debug)]
+pub struct Quirks {
+    bits: u16,
+}
+
+impl Quirks {
+    /// Tolerate none of the quirks, as in `ParseMode::Strict`.
+    pub fn none() -> Quirks {
+        Quirks { bits: 0 }
+    }
+
+    /// Tolerate every quirk, as in `ParseMode::Lenient`.
+    pub fn all() -> Quirks {
+        ALL_QUIRKS.iter().fold(Quirks::none(), |quirks, &quirk| quirks.with(quirk))
+    }
+
+    /// Tolerate the quirk in addition to those already in the set.
+    pub fn with(mut self, quirk: Quirk) -> Quirks {
+        self.enable(quirk);
+        self
+    }
+
+    /// Stop tolerating the quirk.
+    pub fn without(mut self, quirk: Quirk) -> Quirks {
+        self.disable(quirk);
+        self
+    }
+
+    /// Tolerate the quirk in addition to those already in the set.
+    pub fn enable(&mut self, quirk: Quirk) {
+        self.bits |= bit(quirk);
+    }
+
+    /// Stop tolerating the quirk.
+    pub fn disable(&mut self, quirk: Quirk) {
+        self.bits &= !bit(quirk);
+    }
+
+    /// Whether or not the quirk is tolerated.
+    pub fn is_enabled(&self, quirk: Quirk) -> bool {
+        self.bits & bit(quirk) != 0
+    }
+
+    /// Iterate over the quirks that are tolerated.
+    pub fn iter(&self) -> QuirksIter {
+        QuirksIter {
+            quirks: *self,
+            index: 0,
+        }
+    }
+}
+
+impl Default for Quirks {
+    fn default() -> Quirks {
+        Quirks::all().without(Quirk::NonOkStatus)
+    }
+}
+
+/// Iterator over the quirks in a `Quirks` set.
+pub struct QuirksIter {
+    quirks: Quirks,
+    index: usize,
+}
+
+impl Iterator for QuirksIter {
+    type Item = Quirk;
+
+    fn next(&mut self) -> Option<Quirk> {
+        while let Some(&quirk) = ALL_QUIRKS.get(self.index) {
+            self.index += 1;
+
+            if self.quirks.is_enabled(quirk) {
+                return Some(quirk);
+            }
+        }
+
+        None
+    }
+}
+
+fn bit(quirk: Quirk) -> u16 {
+    1 << ALL_QUIRKS.iter().position(|&n| n == quirk).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Quirk, Quirks};
+
+    #[test]
+    fn positive_toggle() {
+        let quirks = Quirks::none()
+            .with(Quirk::MissingExt)
+            .with(Quirk::UnquotedMan)
+            .without(Quirk::MissingExt);
+
+        assert!(quirks.is_enabled(Quirk::UnquotedMan));
+        assert!(!quirks.is_enabled(Quirk::MissingExt));
+        assert_eq!(quirks.iter().collect::<Vec<_>>(), vec![Quirk::UnquotedMan]);
+    }
+
+    #[test]
+    fn positive_all() {
+        assert_eq!(Quirks::all().iter().count(), 10);
+        assert_eq!(Quirks::none().iter().count(), 0);
+        assert_eq!(Quirks::default(), Quirks::all().without(Quirk::NonOkStatus));
+    }
+}
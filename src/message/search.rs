@@ -1,57 +1,359 @@
 use std::borrow::Cow;
-use std::fmt::Debug;
-use std::net::{ToSocketAddrs, SocketAddr, SocketAddrV6};
+use std::cmp;
+use std::collections::HashSet;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::net::{ToSocketAddrs, SocketAddr, UdpSocket};
 use std::str::FromStr;
-use std::time::Duration;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use std::io;
 
-use hyper::header::{Header, HeaderFormat};
+use time;
+#[cfg(feature = "url")]
+use url::Url;
 
-use error::{SSDPResult, MsgError};
-use header::{HeaderRef, HeaderMut, MX};
-use message::{self, MessageType, Listen};
+use error::{SSDPResult, SSDPError, MsgError};
+use header::{self, Header, HeaderFormat, HeaderRef, HeaderMut, HeadersItems, BootID, ConfigID, CPFN, CPUUID,
+             Date, HttpDate, Location, Man, ManValidity, MX, ProductTokens, SearchPort, SecureLocation,
+             Server, TcpPort, UserAgent, ST, USN, MX_HEADER_MIN, MX_HEADER_UDA11_MAX};
+use message::{self, Ipv6Scope, MessageType, Listen, Quirks};
 use message::ssdp::SSDPMessage;
-use receiver::{SSDPReceiver, FromRawSSDP};
-use net;
+use receiver::{self, SSDPReceiver, FromRawSSDP, ListenHandle, Filter, Verdict, ReceiveInfo, ReceiveHandlers,
+               DedupKey};
+use net::{self, SocketHook};
+use net::connector::UdpConnector;
+use queue::{self, OverflowPolicy};
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+use net::runtime::Runtime;
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+use stream::SSDPStream;
+use device::DiscoveredDevice;
+use target::{self, SearchTarget};
 
 /// Overhead to add to device response times to account for transport time.
 const NETWORK_TIMEOUT_OVERHEAD: u8 = 1;
 
 /// Devices are required to respond within 1 second of receiving unicast message.
-const DEFAULT_UNICAST_TIMEOUT: u8 = 1 + NETWORK_TIMEOUT_OVERHEAD;
+const DEFAULT_UNICAST_TIMEOUT: u8 = 1;
+
+/// Name of the header confirming that the MAN header of a search was understood.
+pub const EXT_HEADER_NAME: &'static str = header::names::EXT;
 
 /// Search request that can be sent via unicast or multicast to devices on the network.
 #[derive(Debug, Clone)]
 pub struct SearchRequest {
     message: SSDPMessage,
+    retransmit_count: u32,
+    retransmit_spacing: Duration,
+    max_responses: Option<usize>,
+    local_addr: Option<SocketAddr>,
+    response_slack: Duration,
+    match_versions: bool,
+    require_ext: bool,
+    multicast_dst: Option<SocketAddr>,
+    ipv6_scopes: Vec<Ipv6Scope>,
+    multicast_ttl: u32,
+    multicast_hops: Option<u32>,
+    multicast_loop: Option<bool>,
+    device: Option<String>,
+    tos: Option<u8>,
+    socket_hook: Option<SocketHook>,
+    source_port: Option<u16>,
+    receive_handlers: ReceiveHandlers,
+    quirks: Quirks,
 }
 
 impl SearchRequest {
     /// Construct a new SearchRequest.
     pub fn new() -> SearchRequest {
-        SearchRequest { message: SSDPMessage::new(MessageType::Search) }
+        SearchRequest::from_message(SSDPMessage::new(MessageType::Search))
+    }
+
+    fn from_message(message: SSDPMessage) -> SearchRequest {
+        SearchRequest {
+            message: message,
+            retransmit_count: 0,
+            retransmit_spacing: Duration::from_millis(0),
+            max_responses: None,
+            local_addr: None,
+            response_slack: Duration::new(NETWORK_TIMEOUT_OVERHEAD as u64, 0),
+            match_versions: false,
+            require_ext: false,
+            multicast_dst: None,
+            ipv6_scopes: vec![Ipv6Scope::LinkLocal],
+            multicast_ttl: message::UPNP_MULTICAST_TTL,
+            multicast_hops: None,
+            multicast_loop: None,
+            device: None,
+            tos: None,
+            socket_hook: None,
+            source_port: None,
+            receive_handlers: ReceiveHandlers::new(),
+            quirks: Quirks::default(),
+        }
+    }
+
+    /// Construct a new SearchRequestBuilder.
+    pub fn builder() -> SearchRequestBuilder {
+        SearchRequestBuilder::new()
+    }
+
+    /// Serialize this request as it is written to the network.
+    ///
+    /// A missing HOST header is left out, as it is only filled in from the
+    /// destination address when the request is sent.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.message.to_bytes()
+    }
+
+    /// Get the metadata about how this request was received, if it was received.
+    pub fn receive_info(&self) -> Option<&ReceiveInfo> {
+        self.message.receive_info()
+    }
+
+    /// Whether or not the HOST header names an SSDP multicast group and port, as it
+    /// must for a search request received by multicast.
+    pub fn has_multicast_host(&self) -> bool {
+        message::is_multicast_host(self)
+    }
+
+    /// Validate the MAN header of this request, which responders should check
+    /// before answering a received search.
+    pub fn man_validity(&self) -> ManValidity {
+        ManValidity::from_raw(self.get_raw(Man::header_name()))
     }
 
     /// Send this search request to a single host.
     ///
-    /// Currently this sends the unicast message on all available network
-    /// interfaces. This assumes that the network interfaces are operating
-    /// on either different subnets or different ip address ranges.
+    /// The unicast message is sent only from the local interface that the
+    /// operating system routes the destination address through, so other
+    /// devices on the network will not see the search.
+    ///
+    /// Devices that advertise a `SEARCHPORT.UPNP.ORG` header may not accept
+    /// unicast searches on the standard port, see `unicast_device`.
     pub fn unicast<A: ToSocketAddrs>(&mut self, dst_addr: A) -> SSDPResult<SSDPReceiver<SearchResponse>> {
-        let mode = try!(net::IpVersionMode::from_addr(&dst_addr));
-        let mut connectors = try!(message::all_local_connectors(None, mode));
+        let dst_addr = try!(net::addr_from_trait(dst_addr));
+        let local_addr = match self.local_addr {
+            Some(n) => n,
+            None => try!(net::route_local_addr(&dst_addr)),
+        };
 
-        // Send On All Connectors
-        for connector in &mut connectors {
-            try!(self.message.send(connector, &dst_addr));
-        }
+        let local_addr = self.with_source_port(local_addr);
 
-        let mut raw_connectors = Vec::with_capacity(connectors.len());
-        raw_connectors.extend(connectors.into_iter().map(|conn| conn.deconstruct()));
+        let mut connector = try!(UdpConnector::with_hook(local_addr, None, self.socket_hook()));
+        try!(self.message.send(&mut connector, &dst_addr));
+
+        let opt_timeout = opt_unicast_timeout(self.get::<MX>().as_ref(), self.response_slack);
+
+        Ok(try!(receiver::spawn_filtered(vec![connector.deconstruct()],
+                                         opt_timeout,
+                                         None,
+                                         OverflowPolicy::Block,
+                                         None,
+                                         self.receive_handlers.clone(),
+                                         self.quirks)))
+    }
+
+    /// Send this search request to a previously discovered device, on the port it
+    /// advertised in the `SEARCHPORT.UPNP.ORG` header or the standard port.
+    pub fn unicast_device(&mut self, device: &DiscoveredDevice) -> SSDPResult<SSDPReceiver<SearchResponse>> {
+        self.unicast(device.search_addr())
+    }
+
+    /// Send multicast searches an additional count times, waiting spacing in
+    /// between each transmission, since UDP packets may be lost.
+    ///
+    /// The receive window is extended to cover the retransmissions and, when
+    /// retransmitting, duplicate responses with the same USN from the same
+    /// address are dropped.
+    pub fn set_retransmit(&mut self, count: u32, spacing: Duration) {
+        self.retransmit_count = count;
+        self.retransmit_spacing = spacing;
+    }
+
+    /// Send and receive this search request from a socket bound to the given
+    /// local address only, instead of from every local network interface.
+    ///
+    /// Passing None will go back to using every local network interface.
+    pub fn set_local_addr(&mut self, opt_addr: Option<SocketAddr>) {
+        self.local_addr = opt_addr;
+    }
+
+    /// Send this search request from the given local port, and receive the responses
+    /// on it, instead of from an ephemeral port, such as a port that a firewall lets
+    /// responses through to.
+    ///
+    /// The port of an address set with `set_local_addr` takes precedence unless it is 0.
+    /// Sending fails while the sockets of a previous search from the port are still open.
+    /// Passing None will go back to using an ephemeral port.
+    pub fn set_source_port(&mut self, opt_port: Option<u16>) {
+        self.source_port = opt_port;
+    }
+
+    /// Local port that this search request is sent from, if it is not ephemeral.
+    pub fn source_port(&self) -> Option<u16> {
+        self.source_port
+    }
+
+    /// Send multicasts of this search request to the given address:port instead
+    /// of the standard multicast address:port, such as one for a test network or
+    /// for devices listening on a non-standard port.
+    ///
+    /// Only local interfaces of the same IP version as the address are sent from.
+    /// Passing None will go back to using the standard multicast address:port.
+    pub fn set_multicast_destination(&mut self, opt_addr: Option<SocketAddr>) {
+        self.multicast_dst = opt_addr;
+    }
+
+    /// Send multicasts of this search request from IPv6 interfaces to the SSDP
+    /// groups of each of the given scopes, such as `Ipv6Scope::SiteLocal` for
+    /// routed IPv6 deployments.
+    ///
+    /// Defaults to only the link-local group. Ignored if a multicast destination
+    /// override has been set.
+    pub fn set_ipv6_scopes(&mut self, scopes: Vec<Ipv6Scope>) {
+        self.ipv6_scopes = scopes;
+    }
+
+    /// Set the TTL of multicasts of this search request sent from IPv4 interfaces,
+    /// such as 4 or more for networks where SSDP has to cross several routers.
+    ///
+    /// Defaults to `UPNP_MULTICAST_TTL`, the value recommended by the `UPnP` standard.
+    pub fn set_multicast_ttl(&mut self, ttl: u32) {
+        self.multicast_ttl = ttl;
+    }
+
+    /// TTL of multicasts of this search request sent from IPv4 interfaces.
+    pub fn multicast_ttl(&self) -> u32 {
+        self.multicast_ttl
+    }
+
+    /// Set the hop limit of multicasts of this search request sent from IPv6 interfaces,
+    /// the IPv6 equivalent of the TTL.
+    ///
+    /// Passing None will go back to the default of the scope of each group,
+    /// `UPNP_MULTICAST_HOPS_LINK_LOCAL` or `UPNP_MULTICAST_HOPS_SITE_LOCAL`.
+    pub fn set_multicast_hops(&mut self, opt_hops: Option<u32>) {
+        self.multicast_hops = opt_hops;
+    }
+
+    /// Hop limit of multicasts of this search request sent from IPv6 interfaces, or None
+    /// if the default of the scope of each group is used.
+    pub fn multicast_hops(&self) -> Option<u32> {
+        self.multicast_hops
+    }
+
+    /// Enable or disable the looping back of multicasts of this search request to sockets
+    /// on the local host, such as to reach a stack running on the same host.
+    ///
+    /// Passing None will go back to using the default of the operating system.
+    pub fn set_multicast_loop(&mut self, opt_enabled: Option<bool>) {
+        self.multicast_loop = opt_enabled;
+    }
+
+    /// Whether or not multicasts of this search request are looped back to the local host,
+    /// or None if the default of the operating system is used.
+    pub fn multicast_loop(&self) -> Option<bool> {
+        self.multicast_loop
+    }
+
+    /// Send multicasts of this search request only through the local network device with
+    /// the given name, such as "eth0", binding the sockets to it with `SO_BINDTODEVICE`.
+    ///
+    /// Binding to a device is only supported on linux, sending fails on other systems.
+    /// Passing None will go back to sending through every local network device.
+    pub fn set_device(&mut self, opt_device: Option<String>) {
+        self.device = opt_device;
+    }
 
-        let opt_timeout = opt_unicast_timeout(self.get::<MX>());
+    /// Local network device that multicasts of this search request are sent through, if any.
+    pub fn device(&self) -> Option<&str> {
+        self.device.as_ref().map(|device| &device[..])
+    }
+
+    /// Set the TOS byte, or IPv6 traffic class, of packets of this search request so that
+    /// QoS policies can classify them, such as 0xB8 for the DSCP value 46.
+    ///
+    /// The DSCP value is the upper six bits of the byte. Passing None will go
+    /// back to using the default of the operating system.
+    pub fn set_tos(&mut self, opt_tos: Option<u8>) {
+        self.tos = opt_tos;
+    }
+
+    /// TOS byte of packets of this search request, or None if the default of the
+    /// operating system is used.
+    pub fn tos(&self) -> Option<u8> {
+        self.tos
+    }
+
+    /// Set the hook that is invoked on each socket that this search request is sent from,
+    /// before the socket is bound, to set options that are not otherwise exposed.
+    ///
+    /// Passing None will remove any previously set hook.
+    pub fn set_socket_hook(&mut self, opt_hook: Option<SocketHook>) {
+        self.socket_hook = opt_hook;
+    }
+
+    /// Hook that is invoked on each socket that this search request is sent from, if any.
+    pub fn socket_hook(&self) -> Option<&SocketHook> {
+        self.socket_hook.as_ref()
+    }
+
+    /// Set the handlers that receivers for this search request report errors and
+    /// malformed responses to.
+    pub fn set_receive_handlers(&mut self, handlers: ReceiveHandlers) {
+        self.receive_handlers = handlers;
+    }
+
+    /// Tolerate only the given quirks when parsing responses to this search request,
+    /// such as `ParseMode::Strict.quirks()` for conformance tooling.
+    ///
+    /// Responses showing other quirks are reported to the receive handlers as malformed.
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.quirks = quirks;
+    }
 
-        Ok(try!(SSDPReceiver::new(raw_connectors, opt_timeout)))
+    /// Quirks that are tolerated when parsing responses to this search request.
+    pub fn quirks(&self) -> Quirks {
+        self.quirks
+    }
+
+    /// Set the USER-AGENT header identifying the control point to the product tokens.
+    pub fn set_user_agent(&mut self, tokens: ProductTokens) {
+        self.message.set(UserAgent(tokens.to_string()));
+    }
+
+    /// End receivers for multicast searches once n responses have been received.
+    ///
+    /// Passing None will remove any previously set maximum.
+    pub fn set_max_responses(&mut self, opt_max: Option<usize>) {
+        self.max_responses = opt_max;
+    }
+
+    /// Drop responses to multicast searches that are missing the EXT header
+    /// required by the `UPnP` standard.
+    ///
+    /// Disabled by default, since many devices omit the header.
+    pub fn set_require_ext(&mut self, enabled: bool) {
+        self.require_ext = enabled;
+    }
+
+    /// Drop responses to multicast searches whose ST header does not match the
+    /// searched target, where newer versions of a device or service type match
+    /// searches for older versions.
+    ///
+    /// For `multicast_targets`, responses are tagged with the first searched
+    /// target that they match.
+    pub fn set_version_matching(&mut self, enabled: bool) {
+        self.match_versions = enabled;
+    }
+
+    /// Set the slack added on top of the MX wait bound when sizing the receive
+    /// window, accounting for the time responses spend in transit.
+    ///
+    /// Defaults to one second.
+    pub fn set_response_slack(&mut self, slack: Duration) {
+        self.response_slack = slack;
     }
 
     /// Send this search request to the standard multicast address:port.
@@ -60,34 +362,380 @@ impl SearchRequest {
     }
 
     /// Send this search request to the standard multicast address but a custom port
+    ///
+    /// The port is ignored if a multicast destination override has been set.
     pub fn multicast_with_port(&mut self, port: u16) -> SSDPResult<SSDPReceiver<SearchResponse>> {
-        let mcast_timeout = try!(multicast_timeout(self.get::<MX>()));
-        let mcast_ttl = Some(message::UPNP_MULTICAST_TTL);
+        let (raw_connectors, mcast_timeout) = try!(self.send_multicast(port));
+
+        let mut receiver = try!(receiver::spawn_filtered(raw_connectors,
+                                                         Some(mcast_timeout),
+                                                         None,
+                                                         OverflowPolicy::Block,
+                                                         self.response_filter(),
+                                                         self.receive_handlers.clone(),
+                                                         self.quirks));
+        receiver.set_max_messages(self.max_responses);
+
+        Ok(receiver)
+    }
+
+    /// Send this search request to the standard multicast address:port and
+    /// queue at most capacity responses, applying the policy when full.
+    pub fn multicast_with_capacity(&mut self,
+                                   capacity: usize,
+                                   policy: OverflowPolicy)
+                                   -> SSDPResult<SSDPReceiver<SearchResponse>> {
+        // Responses could not be received, do not send the request at all
+        try!(queue::check_capacity(Some(capacity)));
+        let (raw_connectors, mcast_timeout) = try!(self.send_multicast(message::UPNP_MULTICAST_PORT));
+
+        let mut receiver = try!(receiver::spawn_filtered(raw_connectors,
+                                                         Some(mcast_timeout),
+                                                         Some(capacity),
+                                                         policy,
+                                                         self.response_filter(),
+                                                         self.receive_handlers.clone(),
+                                                         self.quirks));
+        receiver.set_max_messages(self.max_responses);
+
+        Ok(receiver)
+    }
+
+    /// Send this search request to the standard multicast address:port and
+    /// stop receiving as soon as a response matching the predicate arrives.
+    ///
+    /// The matching response is the last one yielded by the receiver, after
+    /// which the sockets are closed and the receiver ends.
+    pub fn multicast_until<P>(&mut self, mut predicate: P) -> SSDPResult<SSDPReceiver<SearchResponse>>
+        where P: FnMut(&SearchResponse) -> bool + Send + 'static
+    {
+        let (raw_connectors, mcast_timeout) = try!(self.send_multicast(message::UPNP_MULTICAST_PORT));
+        let mut opt_dedup = self.response_filter();
+
+        let filter: Filter<SearchResponse> = Box::new(move |response, addr| {
+            match opt_dedup.as_mut().map_or(Verdict::Deliver, |dedup| dedup(response, addr)) {
+                Verdict::Deliver if predicate(response) => Verdict::Last,
+                verdict => verdict,
+            }
+        });
+
+        let mut receiver = try!(receiver::spawn_filtered(raw_connectors,
+                                                         Some(mcast_timeout),
+                                                         None,
+                                                         OverflowPolicy::Block,
+                                                         Some(filter),
+                                                         self.receive_handlers.clone(),
+                                                         self.quirks));
+        receiver.set_max_messages(self.max_responses);
+
+        Ok(receiver)
+    }
+
+    /// Send this search request to the standard multicast address:port and
+    /// invoke the callback for every response received.
+    ///
+    /// The callback is invoked on the receiver thread(s), so it should not block
+    /// for any significant amount of time.
+    pub fn multicast_with<F>(&mut self, callback: F) -> SSDPResult<ListenHandle>
+        where F: FnMut(SearchResponse, SocketAddr) + Send + 'static
+    {
+        let (raw_connectors, mcast_timeout) = try!(self.send_multicast(message::UPNP_MULTICAST_PORT));
+        let opt_filter = self.limit_filter(self.response_filter());
+
+        Ok(try!(receiver::spawn_callback_filtered(raw_connectors,
+                                                  Some(mcast_timeout),
+                                                  opt_filter,
+                                                  self.receive_handlers.clone(),
+                                                  self.quirks,
+                                                  callback)))
+    }
+
+    /// Send this search request to the standard multicast address:port and
+    /// receive the responses asynchronously on the `Runtime` R.
+    #[cfg(any(feature = "tokio", feature = "async-io"))]
+    pub fn multicast_async<R>(&mut self) -> SSDPResult<SSDPStream<SearchResponse, R>>
+        where R: Runtime
+    {
+        self.multicast_async_with_port(message::UPNP_MULTICAST_PORT)
+    }
+
+    /// Send this search request to the standard multicast address but a custom port
+    /// and receive the responses asynchronously on the `Runtime` R.
+    #[cfg(any(feature = "tokio", feature = "async-io"))]
+    pub fn multicast_async_with_port<R>(&mut self, port: u16) -> SSDPResult<SSDPStream<SearchResponse, R>>
+        where R: Runtime
+    {
+        let (raw_connectors, mcast_timeout) = try!(self.send_multicast(port));
+        let opt_filter = self.limit_filter(self.response_filter());
+
+        Ok(try!(SSDPStream::with_filter(raw_connectors, Some(mcast_timeout), opt_filter, self.quirks)))
+    }
+
+    /// Send this search request once for every target to the standard
+    /// multicast address:port, receiving all responses on the same sockets.
+    ///
+    /// Responses are tagged with the target they were sent in response to.
+    /// Responses to targets that were not searched for are dropped, unless
+    /// `ST::All` was one of the targets.
+    pub fn multicast_targets(&mut self,
+                             targets: Vec<ST>)
+                             -> SSDPResult<SSDPReceiver<TargetedResponse>> {
+        let mut messages = Vec::with_capacity(targets.len());
+        for target in &targets {
+            let mut message = self.message.clone();
+            message.set(target.clone());
+
+            messages.push(message);
+        }
+
+        let port = message::UPNP_MULTICAST_PORT;
+        let (raw_connectors, mcast_timeout) = try!(self.send_multicast_messages(messages, port));
+        let mut opt_dedup = self.dedup_filter();
+        let match_versions = self.match_versions;
+        let require_ext = self.require_ext;
 
-        let mut connectors = try!(message::all_local_connectors(mcast_ttl, net::IpVersionMode::Any));
+        let filter: Filter<TargetedResponse> = Box::new(move |response, addr| {
+            let opt_compatible = if match_versions {
+                targets.iter().find(|searched| target::target_matches(searched, &response.target)).cloned()
+            } else {
+                None
+            };
+
+            let matched = if targets.contains(&response.target) {
+                true
+            } else if let Some(searched) = opt_compatible {
+                response.target = searched;
+                true
+            } else if targets.contains(&ST::All) {
+                response.target = ST::All;
+                true
+            } else {
+                false
+            };
+
+            if matched && (!require_ext || has_ext(&response.response)) {
+                opt_dedup.as_mut().map_or(Verdict::Deliver, |dedup| dedup(response, addr))
+            } else {
+                Verdict::Drop
+            }
+        });
+
+        let mut receiver = try!(receiver::spawn_filtered(raw_connectors,
+                                                         Some(mcast_timeout),
+                                                         None,
+                                                         OverflowPolicy::Block,
+                                                         Some(filter),
+                                                         self.receive_handlers.clone(),
+                                                         self.quirks));
+        receiver.set_max_messages(self.max_responses);
+
+        Ok(receiver)
+    }
+
+    /// Replace the port of the local address with the source port, if one is set
+    /// and the address has none.
+    fn with_source_port(&self, mut local_addr: SocketAddr) -> SocketAddr {
+        if let (0, Some(port)) = (local_addr.port(), self.source_port) {
+            local_addr.set_port(port);
+        }
+
+        local_addr
+    }
+
+    /// Send this search request on all local connectors, returning the sockets
+    /// that responses should be read from along with the time to read for.
+    fn send_multicast(&mut self, port: u16) -> SSDPResult<(Vec<UdpSocket>, Duration)> {
+        let messages = vec![self.message.clone()];
+
+        self.send_multicast_messages(messages, port)
+    }
+
+    /// Send the messages on all local connectors, returning the sockets that
+    /// responses should be read from along with the time to read for.
+    ///
+    /// Retransmissions are sent from a separate thread.
+    fn send_multicast_messages(&self,
+                               messages: Vec<SSDPMessage>,
+                               port: u16)
+                               -> SSDPResult<(Vec<UdpSocket>, Duration)> {
+        let mcast_timeout = try!(multicast_timeout(self.get::<MX>().as_ref(), self.response_slack));
+        let mcast_ttl = Some(self.multicast_ttl);
+
+        let connectors = match self.local_addr {
+            Some(addr) => {
+                let local_addr = self.with_source_port(addr);
+
+                vec![try!(UdpConnector::with_hook(local_addr, mcast_ttl, self.socket_hook()))]
+            }
+            None => try!(message::all_local_connectors(mcast_ttl,
+                                                       net::IpVersionMode::Any,
+                                                       self.source_port.unwrap_or(0),
+                                                       self.socket_hook())),
+        };
+        let mut connectors = try!(message::configure_connectors(connectors,
+                                                                self.multicast_loop,
+                                                                self.device(),
+                                                                self.tos));
 
         // Send On All Connectors
         for conn in &mut connectors {
-            match try!(conn.local_addr()) {
-                SocketAddr::V4(_) => {
-                    try!(self.message.send(conn, &(message::UPNP_MULTICAST_IPV4_ADDR, port)))
-                }
-                SocketAddr::V6(n) => {
-                    try!(self.message.send(conn,
-                                           &SocketAddrV6::new(try!(
-                    FromStr::from_str(message::UPNP_MULTICAST_IPV6_LINK_LOCAL_ADDR)),
-                                                              port,
-                                                              n.flowinfo(),
-                                                              n.scope_id())))
-                }
+            for message in &messages {
+                try!(send_multicast_once(message,
+                                         conn,
+                                         port,
+                                         self.multicast_dst,
+                                         &self.ipv6_scopes,
+                                         self.multicast_hops));
             }
         }
 
         let mut raw_connectors = Vec::with_capacity(connectors.len());
         raw_connectors.extend(connectors.into_iter().map(|conn| conn.deconstruct()));
 
-        Ok(try!(SSDPReceiver::new(raw_connectors, Some(mcast_timeout))))
+        if self.retransmit_count == 0 {
+            return Ok((raw_connectors, mcast_timeout));
+        }
+
+        let mut resend_socks = Vec::with_capacity(raw_connectors.len());
+        for sock in &raw_connectors {
+            resend_socks.push(try!(sock.try_clone()));
+        }
+
+        let (count, spacing) = (self.retransmit_count, self.retransmit_spacing);
+        let multicast_dst = self.multicast_dst;
+        let ipv6_scopes = self.ipv6_scopes.clone();
+        let multicast_hops = self.multicast_hops;
+        thread::spawn(move || {
+            for _ in 0..count {
+                thread::sleep(spacing);
+
+                for sock in &resend_socks {
+                    let mut conn = match sock.try_clone() {
+                        Ok(n) => UdpConnector::from_socket(n),
+                        Err(_) => continue,
+                    };
+
+                    for message in &messages {
+                        let result = send_multicast_once(message,
+                                                         &mut conn,
+                                                         port,
+                                                         multicast_dst,
+                                                         &ipv6_scopes,
+                                                         multicast_hops);
+
+                        if let Err(err) = result {
+                            debug!("Failed to retransmit search request: {}", err);
+                        }
+                    }
+                }
+            }
+        });
+
+        Ok((raw_connectors, mcast_timeout + spacing * count))
+    }
+
+    /// Filter that drops duplicate responses, responses to other targets and
+    /// responses missing the EXT header, if enabled.
+    fn response_filter<T>(&self) -> Option<Filter<T>>
+        where T: HeaderRef + 'static
+    {
+        let mut opt_dedup = self.dedup_filter();
+        let opt_searched = match self.get::<ST>() {
+            Some(n) if self.match_versions => Some(n.clone()),
+            _ => None,
+        };
+        let require_ext = self.require_ext;
+
+        if opt_searched.is_none() && !require_ext {
+            return opt_dedup;
+        }
+
+        Some(Box::new(move |response: &mut T, addr: &SocketAddr| {
+            let matched = opt_searched.as_ref().map_or(true, |searched| {
+                response.get::<ST>().map_or(false, |advertised| target::target_matches(searched, &advertised))
+            });
+
+            if matched && (!require_ext || has_ext(response)) {
+                opt_dedup.as_mut().map_or(Verdict::Deliver, |dedup| dedup(response, addr))
+            } else {
+                Verdict::Drop
+            }
+        }))
+    }
+
+    /// Filter that ends receiving once the maximum number of responses, if set,
+    /// have been delivered by the given filter.
+    ///
+    /// Used where responses are not delivered through an `SSDPReceiver`, which
+    /// enforces the maximum itself.
+    fn limit_filter<T>(&self, mut opt_filter: Option<Filter<T>>) -> Option<Filter<T>>
+        where T: 'static
+    {
+        let mut remaining = match self.max_responses {
+            Some(n) => n,
+            None => return opt_filter,
+        };
+
+        Some(Box::new(move |response: &mut T, addr: &SocketAddr| {
+            if remaining == 0 {
+                return Verdict::Drop;
+            }
+
+            match opt_filter.as_mut().map_or(Verdict::Deliver, |filter| filter(response, addr)) {
+                Verdict::Drop => Verdict::Drop,
+                verdict => {
+                    remaining -= 1;
+
+                    if remaining == 0 { Verdict::Last } else { verdict }
+                }
+            }
+        }))
     }
+
+    /// Filter that drops duplicate responses caused by retransmissions, if enabled.
+    fn dedup_filter<T>(&self) -> Option<Filter<T>>
+        where T: HeaderRef
+    {
+        if self.retransmit_count == 0 {
+            return None;
+        }
+
+        let mut seen = HashSet::new();
+        Some(Box::new(move |response: &mut T, addr: &SocketAddr| {
+            match response.get_raw("USN") {
+                Some(usn) if !seen.insert((usn.to_vec(), *addr)) => Verdict::Drop,
+                _ => Verdict::Deliver,
+            }
+        }))
+    }
+}
+
+/// Whether or not the EXT header is present on the response.
+fn has_ext<T: HeaderRef>(response: &T) -> bool {
+    response.get_raw(EXT_HEADER_NAME).is_some()
+}
+
+/// Send the search message to the multicast addresses for the version of the connector.
+fn send_multicast_once(message: &SSDPMessage,
+                       conn: &mut UdpConnector,
+                       port: u16,
+                       multicast_dst: Option<SocketAddr>,
+                       ipv6_scopes: &[Ipv6Scope],
+                       multicast_hops: Option<u32>)
+                       -> SSDPResult<()> {
+    let local_addr = try!(conn.local_addr());
+    let mcast_addrs = try!(message::scoped_multicast_dst_addrs(local_addr, port, multicast_dst, ipv6_scopes));
+
+    if mcast_addrs.is_empty() {
+        trace!("Skipping multicast through {}, no destination for its IP version", local_addr);
+    }
+
+    for mcast_addr in mcast_addrs {
+        try!(message::set_multicast_hops(conn, &mcast_addr, multicast_hops));
+        try!(message.send(conn, &mcast_addr));
+    }
+
+    Ok(())
 }
 
 impl Default for SearchRequest {
@@ -96,19 +744,195 @@ impl Default for SearchRequest {
     }
 }
 
+/// Builder for a `SearchRequest` that sets and validates the standard search headers.
+///
+/// The MAN header is always set, and a search target is required.
+#[derive(Debug, Clone, Default)]
+pub struct SearchRequestBuilder {
+    search_target: Option<ST>,
+    mx: Option<u8>,
+    user_agent: Option<String>,
+    cp_friendly_name: Option<String>,
+    cp_uuid: Option<String>,
+    response_slack: Option<Duration>,
+}
+
+impl SearchRequestBuilder {
+    /// Construct a new SearchRequestBuilder.
+    pub fn new() -> SearchRequestBuilder {
+        SearchRequestBuilder::default()
+    }
+
+    /// Set the target that devices should match against.
+    ///
+    /// Accepts either a `SearchTarget` or a raw `ST` header.
+    pub fn search_target<T>(mut self, target: T) -> SearchRequestBuilder
+        where T: Into<ST>
+    {
+        self.search_target = Some(target.into());
+        self
+    }
+
+    /// Set the maximum number of seconds that devices should wait before responding.
+    ///
+    /// Required for multicast searches, and must be between 1 and 5 as specified
+    /// in the `UPnP` 1.1 standard. The receive window of the search is sized to
+    /// the wait bound plus the response slack.
+    pub fn mx(mut self, wait_bound: u8) -> SearchRequestBuilder {
+        self.mx = Some(wait_bound);
+        self
+    }
+
+    /// Set the user agent identifying the control point.
+    ///
+    /// Accepts either a raw string or `ProductTokens` in the standard format.
+    pub fn user_agent<S>(mut self, agent: S) -> SearchRequestBuilder
+        where S: Into<String>
+    {
+        self.user_agent = Some(agent.into());
+        self
+    }
+
+    /// Set the friendly name of the control point, sent in the `CPFN` header
+    /// for devices implementing the `UPnP` 2.0 standard.
+    pub fn cp_friendly_name<S>(mut self, name: S) -> SearchRequestBuilder
+        where S: Into<String>
+    {
+        self.cp_friendly_name = Some(name.into());
+        self
+    }
+
+    /// Set the UUID of the control point, sent in the `CPUUID` header for
+    /// devices implementing the `UPnP` 2.0 standard.
+    pub fn cp_uuid<S>(mut self, uuid: S) -> SearchRequestBuilder
+        where S: Into<String>
+    {
+        self.cp_uuid = Some(uuid.into());
+        self
+    }
+
+    /// Set the slack added on top of the MX wait bound when sizing the receive window.
+    pub fn response_slack(mut self, slack: Duration) -> SearchRequestBuilder {
+        self.response_slack = Some(slack);
+        self
+    }
+
+    /// Validate the headers and construct the SearchRequest.
+    pub fn build(self) -> SSDPResult<SearchRequest> {
+        let search_target = match self.search_target {
+            Some(n) => n,
+            None => return Err(SSDPError::MissingHeader(ST::header_name())),
+        };
+
+        let mut request = SearchRequest::new();
+
+        request.set(Man);
+        request.set(search_target);
+
+        if let Some(wait_bound) = self.mx {
+            request.set(try!(MX::strict(wait_bound)));
+        }
+
+        if let Some(slack) = self.response_slack {
+            request.set_response_slack(slack);
+        }
+
+        if let Some(agent) = self.user_agent {
+            request.set(UserAgent(agent));
+        }
+
+        if let Some(name) = self.cp_friendly_name {
+            if name.is_empty() {
+                return Err(SSDPError::InvalidHeader(CPFN::header_name(), "Friendly Name Is Empty"));
+            }
+            request.set(CPFN(name));
+        }
+
+        if let Some(uuid) = self.cp_uuid {
+            if uuid.is_empty() {
+                return Err(SSDPError::InvalidHeader(CPUUID::header_name(), "UUID Is Empty"));
+            }
+            request.set(CPUUID(uuid));
+        }
+
+        Ok(request)
+    }
+}
+
+/// Check that a previously discovered device is still present by sending a
+/// unicast search for the target directly to it.
+///
+/// Returns the first response received from the device within the timeout, or
+/// None if the device did not respond in time.
+pub fn check_alive<A, T>(dst_addr: A, target: T, timeout: Duration) -> SSDPResult<Option<SearchResponse>>
+    where A: ToSocketAddrs,
+          T: Into<ST>
+{
+    let dst_addr = try!(net::addr_from_trait(dst_addr));
+
+    let mut request = try!(SearchRequest::builder().search_target(target).build());
+    // Receive for at least the timeout, the deadline then cuts the window down to it
+    request.set_response_slack(timeout);
+    let mut receiver = try!(request.unicast(dst_addr));
+    receiver.set_deadline(Some(timeout));
+
+    for (response, src_addr) in receiver {
+        if src_addr.ip() == dst_addr.ip() {
+            return Ok(Some(response));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Search every local network interface for devices and services matching the
+/// target, collecting the responses received within the timeout.
+///
+/// The MX header is derived from the timeout, and responses with the same USN
+/// received on several interfaces are only returned once.
+pub fn search(target: SearchTarget, timeout: Duration) -> io::Result<Vec<SearchResponse>> {
+    let mut request = try!(SearchRequest::builder().search_target(target).mx(search_mx(timeout)).build());
+    // MX is capped well below long timeouts, the deadline cuts the window down to the timeout
+    request.set_response_slack(timeout);
+    let mut receiver = try!(request.multicast());
+    receiver.set_deadline(Some(timeout));
+    receiver.set_dedup(Some(DedupKey::Usn));
+
+    Ok(receiver.into_iter().map(|(response, _)| response).collect())
+}
+
+/// Get the MX wait bound to use for a search that should last for the timeout.
+fn search_mx(timeout: Duration) -> u8 {
+    cmp::max(MX_HEADER_MIN, cmp::min(timeout.as_secs(), MX_HEADER_UDA11_MAX as u64) as u8)
+}
+
 /// Get the require timeout to use for a multicast search request.
-fn multicast_timeout(mx: Option<&MX>) -> SSDPResult<Duration> {
+fn multicast_timeout(mx: Option<&MX>, slack: Duration) -> SSDPResult<Duration> {
     match mx {
-        Some(&MX(n)) => Ok(Duration::new((n + NETWORK_TIMEOUT_OVERHEAD) as u64, 0)),
+        Some(&MX(n)) => Ok(Duration::new(n as u64, 0) + slack),
         None => try!(Err(MsgError::new("Multicast Searches Require An MX Header"))),
     }
 }
 
 /// Get the default timeout to use for a unicast search request.
-fn opt_unicast_timeout(mx: Option<&MX>) -> Option<Duration> {
+fn opt_unicast_timeout(mx: Option<&MX>, slack: Duration) -> Option<Duration> {
     match mx {
-        Some(&MX(n)) => Some(Duration::new((n + NETWORK_TIMEOUT_OVERHEAD) as u64, 0)),
-        None => Some(Duration::new(DEFAULT_UNICAST_TIMEOUT as u64, 0)),
+        Some(&MX(n)) => Some(Duration::new(n as u64, 0) + slack),
+        None => Some(Duration::new(DEFAULT_UNICAST_TIMEOUT as u64, 0) + slack),
+    }
+}
+
+impl Display for SearchRequest {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        Display::fmt(&self.message, f)
+    }
+}
+
+impl FromStr for SearchRequest {
+    type Err = SSDPError;
+
+    fn from_str(s: &str) -> SSDPResult<SearchRequest> {
+        SearchRequest::raw_ssdp(s.as_bytes())
     }
 }
 
@@ -119,13 +943,17 @@ impl FromRawSSDP for SearchRequest {
         if message.message_type() != MessageType::Search {
             try!(Err(MsgError::new("SSDP Message Received Is Not A SearchRequest")))
         } else {
-            Ok(SearchRequest { message: message })
+            Ok(SearchRequest::from_message(message))
         }
     }
+
+    fn set_receive_info(&mut self, info: ReceiveInfo) {
+        self.message.set_receive_info(info)
+    }
 }
 
 impl HeaderRef for SearchRequest {
-    fn get<H>(&self) -> Option<&H>
+    fn get<H>(&self) -> Option<H>
         where H: Header + HeaderFormat
     {
         self.message.get::<H>()
@@ -134,6 +962,10 @@ impl HeaderRef for SearchRequest {
     fn get_raw(&self, name: &str) -> Option<&[Vec<u8>]> {
         self.message.get_raw(name)
     }
+
+    fn iter_headers(&self) -> HeadersItems {
+        self.message.iter_headers()
+    }
 }
 
 impl HeaderMut for SearchRequest {
@@ -148,7 +980,17 @@ impl HeaderMut for SearchRequest {
     {
         self.message.set_raw(name, value)
     }
-}
+
+    fn remove<H>(&mut self) -> bool
+        where H: Header + HeaderFormat
+    {
+        self.message.remove::<H>()
+    }
+
+    fn remove_raw(&mut self, name: &str) -> bool {
+        self.message.remove_raw(name)
+    }
+}
 
 /// Search response that can be received or sent via unicast to devices on the network.
 #[derive(Debug, Clone)]
@@ -162,21 +1004,163 @@ impl SearchResponse {
         SearchResponse { message: SSDPMessage::new(MessageType::Response) }
     }
 
+    /// Serialize this response as it is written to the network.
+    ///
+    /// A missing HOST header is left out, as it is only filled in from the
+    /// destination address when the response is sent.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.message.to_bytes()
+    }
+
+    /// Get the metadata about how this response was received, if it was received.
+    pub fn receive_info(&self) -> Option<&ReceiveInfo> {
+        self.message.receive_info()
+    }
+
+    /// Status code of the response, which is 200 unless a response with another
+    /// status code was received with the `NonOkStatus` quirk tolerated.
+    pub fn status_code(&self) -> u16 {
+        self.message.status_code()
+    }
+
+    /// Reason phrase of the response, exactly as it was received, or "OK".
+    pub fn reason_phrase(&self) -> &str {
+        self.message.reason_phrase()
+    }
+
+    /// URL for the description of the responding device or service.
+    pub fn location(&self) -> Option<&str> {
+        self.get_str(Location::header_name())
+    }
+
+    /// URL for the description of the device or service, which must be absolute.
+    #[cfg(feature = "url")]
+    pub fn location_url(&self) -> SSDPResult<Url> {
+        header::location_url(self)
+    }
+
+    /// URL for the description of the device or service, which must be an
+    /// absolute http or https URL.
+    #[cfg(feature = "url")]
+    pub fn strict_location_url(&self) -> SSDPResult<Url> {
+        header::strict_location_url(self)
+    }
+
+    /// Unique service name of the responding device or service.
+    pub fn usn(&self) -> Option<USN> {
+        self.get::<USN>()
+    }
+
+    /// Target that the responding device or service matched.
+    ///
+    /// Returns None if the ST header is missing or is not a standard target.
+    pub fn st(&self) -> Option<SearchTarget> {
+        self.get::<ST>().and_then(|st| SearchTarget::from_st(&st))
+    }
+
+    /// Operating system, `UPnP` version and product of the responding device.
+    pub fn server(&self) -> Option<&str> {
+        self.get_str(Server::header_name())
+    }
+
+    /// Operating system, `UPnP` version and product of the responding device,
+    /// split into their tokens.
+    ///
+    /// Returns None if there is no SERVER header or it has no `UPnP` version token.
+    pub fn server_tokens(&self) -> Option<ProductTokens> {
+        self.server().and_then(|server| server.parse().ok())
+    }
+
+    /// Boot instance of the responding device, from the BOOTID.UPNP.ORG header.
+    pub fn boot_id(&self) -> Option<u32> {
+        self.get::<BootID>().map(|boot_id| boot_id.0)
+    }
+
+    /// Configuration number of the responding device's description, from the
+    /// CONFIGID.UPNP.ORG header.
+    pub fn config_id(&self) -> Option<u32> {
+        self.get::<ConfigID>().map(|config_id| config_id.0)
+    }
+
+    /// Port that the responding device accepts unicast search requests on, if
+    /// it is not the standard port.
+    pub fn search_port(&self) -> Option<u16> {
+        self.get::<SearchPort>().map(|search_port| search_port.0)
+    }
+
+    /// Network Location Signature sent by Windows responding devices, which changes
+    /// whenever the device reconnects to the network.
+    pub fn nls(&self) -> Option<&str> {
+        header::nls(self)
+    }
+
+    /// HTTPS URL for the description of the responding device, for `UPnP`
+    /// Device Protection aware control points.
+    pub fn secure_location(&self) -> Option<&str> {
+        self.get_str(SecureLocation::header_name())
+    }
+
+    /// TCP port that the responding device accepts `UPnP` Device Protection connections on.
+    pub fn tcp_port(&self) -> Option<u16> {
+        self.get::<TcpPort>().map(|tcp_port| tcp_port.0)
+    }
+
+    /// Duration that this response is valid for.
+    pub fn max_age(&self) -> Option<Duration> {
+        header::max_age(self)
+    }
+
+    /// Time at which the responding device generated this response, taken from
+    /// the DATE header.
+    pub fn date(&self) -> Option<SystemTime> {
+        self.get::<Date>().and_then(|date| {
+            let seconds = (date.0).0.to_timespec().sec;
+
+            if seconds >= 0 {
+                Some(UNIX_EPOCH + Duration::from_secs(seconds as u64))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Whether or not the EXT header required by the `UPnP` standard is present.
+    pub fn has_ext(&self) -> bool {
+        has_ext(self)
+    }
+
+    /// Set the DATE header to the current time and the empty EXT header, unless
+    /// they were already set.
+    fn set_default_headers(&mut self) {
+        if self.get::<Date>().is_none() {
+            self.set(Date(HttpDate(time::now_utc())));
+        }
+
+        if !self.has_ext() {
+            self.set_raw(EXT_HEADER_NAME, vec![Vec::new()]);
+        }
+    }
+
     /// Send this search response to a single host.
     ///
     /// Currently this sends the unicast message on all available network
     /// interfaces. This assumes that the network interfaces are operating
     /// on either different subnets or different ip address ranges.
+    ///
+    /// A DATE header with the current time and the empty EXT header are added
+    /// if they were not set.
     pub fn unicast<A: ToSocketAddrs>(&mut self, dst_addr: A) -> SSDPResult<()> {
+        self.set_default_headers();
+
         let mode = try!(net::IpVersionMode::from_addr(&dst_addr));
-        let mut connectors = try!(message::all_local_connectors(None, mode));
+        let mut connectors = try!(message::all_local_connectors(None, mode, 0, None));
 
         let mut success_count = 0;
         let mut error_count = 0;
         // Send On All Connectors
         for conn in &mut connectors {
             // Some routing errors are expected, not all interfaces can find the target addresses
-            match self.message.send(conn, &dst_addr) {
+            match message::send_local(&self.message, conn, &dst_addr) {
                 Ok(_) => success_count += 1,
                 Err(_) => error_count += 1,
             }
@@ -188,6 +1172,23 @@ impl SearchResponse {
 
         Ok(())
     }
+
+    /// Send this search response to a single host from the given local address
+    /// only, such as the address of the interface that received the search.
+    ///
+    /// The port of the local address is ignored, an ephemeral port is used. A
+    /// DATE header with the current time and the empty EXT header are added if
+    /// they were not set.
+    pub fn unicast_from<A>(&mut self, mut local_addr: SocketAddr, dst_addr: A) -> SSDPResult<()>
+        where A: ToSocketAddrs
+    {
+        local_addr.set_port(0);
+        self.set_default_headers();
+
+        let mut connector = try!(UdpConnector::new(local_addr, None));
+
+        message::send_local(&self.message, &mut connector, dst_addr)
+    }
 }
 
 impl Default for SearchResponse {
@@ -196,6 +1197,66 @@ impl Default for SearchResponse {
     }
 }
 
+/// Search response tagged with the target of the search it was sent in response to.
+#[derive(Debug, Clone)]
+pub struct TargetedResponse {
+    target: ST,
+    response: SearchResponse,
+}
+
+impl TargetedResponse {
+    /// Target of the search that this response was sent in response to.
+    pub fn target(&self) -> &ST {
+        &self.target
+    }
+
+    /// View the search response.
+    pub fn response(&self) -> &SearchResponse {
+        &self.response
+    }
+
+    /// Destroy the TargetedResponse and return the search response.
+    pub fn into_response(self) -> SearchResponse {
+        self.response
+    }
+}
+
+impl FromRawSSDP for TargetedResponse {
+    fn raw_ssdp(bytes: &[u8]) -> SSDPResult<TargetedResponse> {
+        let response = try!(SearchResponse::raw_ssdp(bytes));
+
+        let target = match response.get::<ST>() {
+            Some(n) => n.clone(),
+            None => return Err(SSDPError::MissingHeader(ST::header_name())),
+        };
+
+        Ok(TargetedResponse {
+            target: target,
+            response: response,
+        })
+    }
+
+    fn set_receive_info(&mut self, info: ReceiveInfo) {
+        self.response.set_receive_info(info)
+    }
+}
+
+impl HeaderRef for TargetedResponse {
+    fn get<H>(&self) -> Option<H>
+        where H: Header + HeaderFormat
+    {
+        self.response.get::<H>()
+    }
+
+    fn get_raw(&self, name: &str) -> Option<&[Vec<u8>]> {
+        self.response.get_raw(name)
+    }
+
+    fn iter_headers(&self) -> HeadersItems {
+        self.response.iter_headers()
+    }
+}
+
 /// Search listener that can listen for search messages sent within the network.
 pub struct SearchListener;
 
@@ -203,6 +1264,28 @@ impl Listen for SearchListener {
     type Message = SearchResponse;
 }
 
+/// Search request listener that can listen for search requests multicast by
+/// control points within the network.
+pub struct SearchRequestListener;
+
+impl Listen for SearchRequestListener {
+    type Message = SearchRequest;
+}
+
+impl Display for SearchResponse {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        Display::fmt(&self.message, f)
+    }
+}
+
+impl FromStr for SearchResponse {
+    type Err = SSDPError;
+
+    fn from_str(s: &str) -> SSDPResult<SearchResponse> {
+        SearchResponse::raw_ssdp(s.as_bytes())
+    }
+}
+
 impl FromRawSSDP for SearchResponse {
     fn raw_ssdp(bytes: &[u8]) -> SSDPResult<SearchResponse> {
         let message = try!(SSDPMessage::raw_ssdp(bytes));
@@ -213,10 +1296,14 @@ impl FromRawSSDP for SearchResponse {
             Ok(SearchResponse { message: message })
         }
     }
+
+    fn set_receive_info(&mut self, info: ReceiveInfo) {
+        self.message.set_receive_info(info)
+    }
 }
 
 impl HeaderRef for SearchResponse {
-    fn get<H>(&self) -> Option<&H>
+    fn get<H>(&self) -> Option<H>
         where H: Header + HeaderFormat
     {
         self.message.get::<H>()
@@ -225,6 +1312,10 @@ impl HeaderRef for SearchResponse {
     fn get_raw(&self, name: &str) -> Option<&[Vec<u8>]> {
         self.message.get_raw(name)
     }
+
+    fn iter_headers(&self) -> HeadersItems {
+        self.message.iter_headers()
+    }
 }
 
 impl HeaderMut for SearchResponse {
@@ -239,30 +1330,560 @@ impl HeaderMut for SearchResponse {
     {
         self.message.set_raw(name, value)
     }
+
+    fn remove<H>(&mut self) -> bool
+        where H: Header + HeaderFormat
+    {
+        self.message.remove::<H>()
+    }
+
+    fn remove_raw(&mut self, name: &str) -> bool {
+        self.message.remove_raw(name)
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use header::MX;
+    use std::net::{SocketAddr, UdpSocket};
+    use std::sync::{mpsc, Arc};
+    use std::sync::mpsc::RecvTimeoutError;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::thread;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use FieldMap;
+    use header::{HeaderRef, HeaderMut, CPFN, CPUUID, Man, MX, ProductTokens, UserAgent, ST, USN};
+    use message::{self, ParseMode};
+    use net::SocketHook;
+    use receiver::{FromRawSSDP, Verdict};
+    use target::SearchTarget;
+    use super::{SearchRequest, SearchResponse, TargetedResponse};
+
+    #[test]
+    fn positive_builder() {
+        let request = SearchRequest::builder()
+            .search_target(ST::All)
+            .mx(3)
+            .user_agent("test/1.0")
+            .build()
+            .unwrap();
+
+        assert_eq!(request.get::<Man>(), Some(Man));
+        assert_eq!(request.get::<ST>(), Some(ST::All));
+        assert_eq!(request.get::<MX>(), Some(MX(3)));
+        assert!(request.get_raw("User-Agent").is_some());
+    }
+
+    #[test]
+    fn positive_display_builder() {
+        let request = SearchRequest::builder()
+            .search_target(ST::All)
+            .mx(3)
+            .build()
+            .unwrap();
+
+        assert_eq!(request.to_string(),
+                   "M-SEARCH * HTTP/1.1\r\nMAN: \"ssdp:discover\"\r\nST: ssdp:all\r\nMX: 3\r\n\
+                    Content-Length: 0\r\n\r\n");
+    }
+
+    #[test]
+    fn positive_from_str() {
+        let request = "M-SEARCH * HTTP/1.1\r\nHOST: 239.255.255.250:1900\r\nST: ssdp:all\r\n\r\n"
+            .parse::<SearchRequest>()
+            .unwrap();
+
+        assert_eq!(request.get::<ST>(), Some(ST::All));
+        assert!("HTTP/1.1 200 OK\r\n\r\n".parse::<SearchRequest>().is_err());
+    }
+
+    #[test]
+    fn positive_date() {
+        let response = SearchResponse::raw_ssdp(b"HTTP/1.1 200 OK\r\n\
+                                                  DATE: Sun, 06 Nov 1994 08:49:37 GMT\r\n\r\n")
+            .unwrap();
+
+        assert_eq!(response.date(), Some(UNIX_EPOCH + Duration::from_secs(784111777)));
+    }
+
+    #[test]
+    fn positive_non_ok_status() {
+        let raw_response = b"HTTP/1.1 503 Service Unavailable\r\nST: ssdp:all\r\n\r\n";
+        let response = SearchResponse::raw_ssdp(raw_response).unwrap();
+
+        assert_eq!(response.status_code(), 503);
+        assert_eq!(response.reason_phrase(), "Service Unavailable");
+        assert!(response.to_bytes().starts_with(b"HTTP/1.1 503 Service Unavailable\r\n"));
+        assert!(SearchResponse::raw_ssdp_with_mode(raw_response, ParseMode::Strict).is_err());
+    }
+
+    #[test]
+    fn positive_built_response_status() {
+        let response = SearchResponse::new();
+
+        assert_eq!(response.status_code(), 200);
+        assert_eq!(response.reason_phrase(), "OK");
+    }
+
+    #[test]
+    fn positive_boot_id_and_config_id() {
+        let response = SearchResponse::raw_ssdp(b"HTTP/1.1 200 OK\r\n\
+                                                  BOOTID.UPNP.ORG: 3\r\n\
+                                                  CONFIGID.UPNP.ORG: 2147483648\r\n\r\n")
+            .unwrap();
+
+        assert_eq!(response.boot_id(), Some(3));
+        assert_eq!(response.config_id(), None);
+    }
+
+    #[test]
+    fn positive_unicast_sets_date() {
+        let recv_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let recv_addr = recv_sock.local_addr().unwrap();
+        let before = SystemTime::now() - Duration::from_secs(1);
+
+        let mut response = SearchResponse::new();
+        response.unicast_from("127.0.0.1:0".parse().unwrap(), recv_addr).unwrap();
+
+        let date = response.date().unwrap();
+        assert!(date >= before && date <= SystemTime::now());
+        assert!(response.has_ext());
+    }
+
+    #[test]
+    fn positive_received_ext() {
+        let with_ext = SearchResponse::raw_ssdp(b"HTTP/1.1 200 OK\r\nEXT:\r\n\r\n").unwrap();
+        let without_ext = SearchResponse::raw_ssdp(b"HTTP/1.1 200 OK\r\n\r\n").unwrap();
+
+        assert!(with_ext.has_ext());
+        assert!(!without_ext.has_ext());
+    }
+
+    #[test]
+    fn positive_require_ext_filter() {
+        let mut request = SearchRequest::new();
+        request.set_require_ext(true);
+
+        let mut filter = request.response_filter::<SearchResponse>().unwrap();
+        let addr = "192.168.0.2:1900".parse().unwrap();
+        let mut with_ext = SearchResponse::raw_ssdp(b"HTTP/1.1 200 OK\r\nEXT:\r\n\r\n").unwrap();
+        let mut without_ext = SearchResponse::raw_ssdp(b"HTTP/1.1 200 OK\r\n\r\n").unwrap();
+
+        assert_eq!(filter(&mut with_ext, &addr), Verdict::Deliver);
+        assert_eq!(filter(&mut without_ext, &addr), Verdict::Drop);
+    }
+
+    #[test]
+    fn positive_server_tokens() {
+        let response = SearchResponse::raw_ssdp(b"HTTP/1.1 200 OK\r\n\
+                                                  SERVER: Linux/3.0 UPnP/1.1 Firmware/2.4.1\r\n\r\n")
+            .unwrap();
+        let tokens = response.server_tokens().unwrap();
+
+        assert_eq!(tokens.product_name(), "Firmware");
+        assert_eq!(tokens.product_version(), "2.4.1");
+    }
+
+    #[test]
+    fn positive_set_user_agent() {
+        let mut request = SearchRequest::new();
+        request.set_user_agent(ProductTokens::new("Remote", "1.0").os("Linux", "5.10"));
+
+        assert_eq!(request.get::<UserAgent>(),
+                   Some(UserAgent("Linux/5.10 UPnP/1.1 Remote/1.0".to_owned())));
+    }
+
+    #[test]
+    fn positive_builder_control_point() {
+        let request = SearchRequest::builder()
+            .search_target(ST::All)
+            .cp_friendly_name("Living Room Remote")
+            .cp_uuid("2fac1234-31f8-11b4-a222-08002b34c003")
+            .build()
+            .unwrap();
+
+        assert_eq!(request.get::<CPFN>(), Some(CPFN("Living Room Remote".to_owned())));
+        assert_eq!(request.get::<CPUUID>(),
+                   Some(CPUUID("2fac1234-31f8-11b4-a222-08002b34c003".to_owned())));
+    }
+
+    #[test]
+    fn negative_builder_empty_control_point() {
+        assert!(SearchRequest::builder().search_target(ST::All).cp_friendly_name("").build().is_err());
+        assert!(SearchRequest::builder().search_target(ST::All).cp_uuid("").build().is_err());
+    }
+
+    #[test]
+    fn positive_builder_search_target() {
+        let request = SearchRequest::builder().search_target(SearchTarget::RootDevice).build().unwrap();
+
+        assert_eq!(request.get::<ST>(), Some(ST::Target(FieldMap::upnp("rootdevice"))));
+    }
+
+    #[test]
+    fn positive_response_st() {
+        let mut response = SearchResponse::new();
+        response.set(ST::Target(FieldMap::urn("schemas-upnp-org:device:MediaServer:1")));
+
+        assert_eq!(response.st(), Some(SearchTarget::device("MediaServer", 1)));
+    }
+
+    #[test]
+    fn positive_unicast_search() {
+        let device_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let device_addr = device_sock.local_addr().unwrap();
+
+        let mut request = SearchRequest::builder().search_target(ST::All).build().unwrap();
+        let receiver = request.unicast(device_addr).unwrap();
+
+        let mut buf = [0u8; 1024];
+        let (len, src_addr) = device_sock.recv_from(&mut buf).unwrap();
+        assert!(SearchRequest::raw_ssdp(&buf[..len]).is_ok());
+
+        device_sock.send_to(b"HTTP/1.1 200 OK\r\n\r\n", src_addr).unwrap();
+        assert_eq!(receiver.recv().unwrap().1, device_addr);
+    }
+
+    #[test]
+    fn positive_unicast_socket_hook() {
+        let device_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let hooked = Arc::new(AtomicBool::new(false));
+
+        let mut request = SearchRequest::builder().search_target(ST::All).build().unwrap();
+        let flag = hooked.clone();
+        request.set_socket_hook(Some(SocketHook::new(move |_| Ok(flag.store(true, Ordering::SeqCst)))));
+        request.unicast(device_sock.local_addr().unwrap()).unwrap();
+
+        assert!(hooked.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn positive_unicast_source_port() {
+        let device_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let source_port = UdpSocket::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+
+        let mut request = SearchRequest::builder().search_target(ST::All).build().unwrap();
+        request.set_source_port(Some(source_port));
+        let _receiver = request.unicast(device_sock.local_addr().unwrap()).unwrap();
+
+        let mut buf = [0u8; 1024];
+        let (_, src_addr) = device_sock.recv_from(&mut buf).unwrap();
+        assert_eq!(src_addr.port(), source_port);
+    }
+
+    #[test]
+    fn positive_retransmit_dedup() {
+        let mut request = SearchRequest::new();
+        request.set_retransmit(2, Duration::from_millis(100));
+
+        let mut response = SearchResponse::new();
+        response.set_raw("USN", vec![b"uuid:device".to_vec()]);
+
+        let addr = "192.168.0.2:1900".parse().unwrap();
+        let mut filter = request.response_filter().unwrap();
+        assert_eq!(filter(&mut response, &addr), Verdict::Deliver);
+        assert_eq!(filter(&mut response, &addr), Verdict::Drop);
+        assert_eq!(filter(&mut response, &"192.168.0.3:1900".parse().unwrap()), Verdict::Deliver);
+    }
+
+    #[test]
+    fn positive_version_matching_filter() {
+        let mut request = SearchRequest::builder()
+            .search_target(SearchTarget::device("MediaServer", 1))
+            .build()
+            .unwrap();
+        request.set_version_matching(true);
+
+        let addr = "192.168.0.2:1900".parse().unwrap();
+        let mut filter = request.response_filter::<SearchResponse>().unwrap();
+
+        let mut newer = SearchResponse::new();
+        newer.set(ST::from(SearchTarget::device("MediaServer", 2)));
+        assert_eq!(filter(&mut newer, &addr), Verdict::Deliver);
+
+        let mut other = SearchResponse::new();
+        other.set(ST::from(SearchTarget::device("MediaRenderer", 2)));
+        assert_eq!(filter(&mut other, &addr), Verdict::Drop);
+
+        assert_eq!(filter(&mut SearchResponse::new(), &addr), Verdict::Drop);
+    }
+
+    #[test]
+    fn positive_no_retransmit_no_dedup() {
+        assert!(SearchRequest::new().response_filter::<SearchResponse>().is_none());
+    }
+
+    #[test]
+    fn positive_targeted_response() {
+        let response = TargetedResponse::raw_ssdp(b"HTTP/1.1 200 OK\r\nST: upnp:rootdevice\r\n\r\n").unwrap();
+
+        assert_eq!(response.target(), &ST::Target(FieldMap::upnp("rootdevice")));
+    }
+
+    #[test]
+    fn negative_targeted_response_missing_st() {
+        assert!(TargetedResponse::raw_ssdp(b"HTTP/1.1 200 OK\r\n\r\n").is_err());
+    }
+
+    #[test]
+    fn positive_unicast_search_local_addr() {
+        let device_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let local_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let local_addr = local_sock.local_addr().unwrap();
+        drop(local_sock);
+
+        let mut request = SearchRequest::builder().search_target(ST::All).build().unwrap();
+        request.set_local_addr(Some(local_addr));
+        request.unicast(device_sock.local_addr().unwrap()).unwrap();
+
+        let mut buf = [0u8; 1024];
+        assert_eq!(device_sock.recv_from(&mut buf).unwrap().1, local_addr);
+    }
+
+    #[test]
+    fn positive_multicast_destination_override() {
+        let device_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        device_sock.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+
+        let mut request = SearchRequest::builder().search_target(ST::All).mx(1).build().unwrap();
+        request.set_local_addr(Some("127.0.0.1:0".parse().unwrap()));
+        request.set_multicast_destination(Some(device_sock.local_addr().unwrap()));
+        let _receiver = request.multicast().unwrap();
+
+        let mut buf = [0u8; 1024];
+        let (len, _) = device_sock.recv_from(&mut buf).unwrap();
+        assert!(SearchRequest::raw_ssdp(&buf[..len]).is_ok());
+    }
+
+    /// Bind a device socket that answers the first search request it receives
+    /// with the given responses.
+    fn responding_device(responses: Vec<&'static [u8]>) -> SocketAddr {
+        let device_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let device_addr = device_sock.local_addr().unwrap();
+
+        thread::spawn(move || {
+            let mut buf = [0u8; 1024];
+            let (_, src_addr) = device_sock.recv_from(&mut buf).unwrap();
+
+            for response in responses {
+                device_sock.send_to(response, src_addr).unwrap();
+            }
+        });
+
+        device_addr
+    }
+
+    /// Search request sent to the device address in place of the multicast address.
+    fn device_search(device_addr: SocketAddr) -> SearchRequest {
+        let mut request = SearchRequest::builder().search_target(ST::All).mx(1).build().unwrap();
+        request.set_local_addr(Some("127.0.0.1:0".parse().unwrap()));
+        request.set_multicast_destination(Some(device_addr));
+        request.set_require_ext(true);
+        request.set_max_responses(Some(1));
+
+        request
+    }
+
+    #[test]
+    fn positive_limit_filter() {
+        let mut request = SearchRequest::new();
+        request.set_max_responses(Some(2));
+
+        let addr = "192.168.0.2:1900".parse().unwrap();
+        let mut filter = request.limit_filter::<SearchResponse>(None).unwrap();
+        assert_eq!(filter(&mut SearchResponse::new(), &addr), Verdict::Deliver);
+        assert_eq!(filter(&mut SearchResponse::new(), &addr), Verdict::Last);
+        assert_eq!(filter(&mut SearchResponse::new(), &addr), Verdict::Drop);
+    }
+
+    #[test]
+    fn positive_multicast_with_filters_responses() {
+        let device_addr = responding_device(vec![b"HTTP/1.1 200 OK\r\nUSN: no-ext\r\n\r\n",
+                                                 b"HTTP/1.1 200 OK\r\nEXT:\r\nUSN: first\r\n\r\n",
+                                                 b"HTTP/1.1 200 OK\r\nEXT:\r\nUSN: second\r\n\r\n"]);
+        let (send, recv) = mpsc::channel();
+
+        device_search(device_addr)
+            .multicast_with(move |response, _| {
+                send.send(response.get_raw("USN").unwrap()[0].clone()).unwrap();
+            })
+            .unwrap();
+
+        assert_eq!(recv.recv_timeout(Duration::from_secs(5)), Ok(b"first".to_vec()));
+        // Receiver threads exit after the maximum, hanging up on the channel
+        assert_eq!(recv.recv_timeout(Duration::from_secs(5)), Err(RecvTimeoutError::Disconnected));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn positive_multicast_async_filters_responses() {
+        use std::future;
+        use std::pin::Pin;
+
+        use futures_core::Stream;
+        use tokio::runtime::Builder;
+
+        use net::runtime::Tokio;
+
+        let device_addr = responding_device(vec![b"HTTP/1.1 200 OK\r\nUSN: no-ext\r\n\r\n",
+                                                 b"HTTP/1.1 200 OK\r\nEXT:\r\nUSN: first\r\n\r\n",
+                                                 b"HTTP/1.1 200 OK\r\nEXT:\r\nUSN: second\r\n\r\n"]);
+        let runtime = Builder::new_current_thread().enable_all().build().unwrap();
+
+        let mut stream = {
+            let _guard = runtime.enter();
+            device_search(device_addr).multicast_async::<Tokio>().unwrap()
+        };
+        let mut next = || runtime.block_on(future::poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)));
+        let mut usns = Vec::new();
+        while let Some((response, _)) = next() {
+            usns.push(response.get_raw("USN").unwrap()[0].clone());
+        }
+
+        assert_eq!(usns, vec![b"first".to_vec()]);
+    }
+
+    #[test]
+    fn positive_response_accessors() {
+        let response = SearchResponse::raw_ssdp(b"HTTP/1.1 200 OK\r\n\
+                                                  CACHE-CONTROL: max-age=1800\r\n\
+                                                  LOCATION: http://192.168.0.2:80/desc.xml\r\n\
+                                                  SEARCHPORT.UPNP.ORG: 50000\r\n\
+                                                  SERVER: Linux/3.0 UPnP/1.0 Test/1.0\r\n\
+                                                  USN: uuid:device::upnp:rootdevice\r\n\r\n")
+            .unwrap();
+
+        assert_eq!(response.location(), Some("http://192.168.0.2:80/desc.xml"));
+        assert_eq!(response.search_port(), Some(50000));
+        assert_eq!(response.usn(),
+                   Some(USN(FieldMap::uuid("device"), Some(FieldMap::upnp("rootdevice")))));
+        assert_eq!(response.server(), Some("Linux/3.0 UPnP/1.0 Test/1.0"));
+        assert_eq!(response.max_age(), Some(Duration::from_secs(1800)));
+    }
+
+    #[test]
+    fn negative_response_accessors_missing() {
+        let response = SearchResponse::new();
+
+        assert!(response.location().is_none());
+        assert!(response.usn().is_none());
+        assert!(response.st().is_none());
+        assert!(response.server().is_none());
+        assert!(response.max_age().is_none());
+        assert!(response.search_port().is_none());
+    }
+
+    #[test]
+    fn positive_check_alive() {
+        let device_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let device_addr = device_sock.local_addr().unwrap();
+
+        thread::spawn(move || {
+            let mut buf = [0u8; 1024];
+            let (_, src_addr) = device_sock.recv_from(&mut buf).unwrap();
+
+            device_sock.send_to(b"HTTP/1.1 200 OK\r\n\r\n", src_addr).unwrap();
+        });
+
+        assert!(super::check_alive(device_addr, ST::All, Duration::from_secs(5)).unwrap().is_some());
+    }
+
+    #[test]
+    fn negative_check_alive_non_ok_status() {
+        let device_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let device_addr = device_sock.local_addr().unwrap();
+
+        thread::spawn(move || {
+            let mut buf = [0u8; 1024];
+            let (_, src_addr) = device_sock.recv_from(&mut buf).unwrap();
+
+            device_sock.send_to(b"HTTP/1.1 503 Service Unavailable\r\n\r\n", src_addr).unwrap();
+        });
+
+        assert!(super::check_alive(device_addr, ST::All, Duration::from_millis(500)).unwrap().is_none());
+    }
+
+    #[test]
+    fn positive_check_alive_long_timeout() {
+        let device_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let device_addr = device_sock.local_addr().unwrap();
+
+        thread::spawn(move || {
+            let mut buf = [0u8; 1024];
+            let (_, src_addr) = device_sock.recv_from(&mut buf).unwrap();
+
+            // Respond after the default unicast receive window has passed
+            thread::sleep(Duration::from_millis(3500));
+            device_sock.send_to(b"HTTP/1.1 200 OK\r\n\r\n", src_addr).unwrap();
+        });
+
+        assert!(super::check_alive(device_addr, ST::All, Duration::from_secs(5)).unwrap().is_some());
+    }
+
+    #[test]
+    fn negative_check_alive_timeout() {
+        let device_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let device_addr = device_sock.local_addr().unwrap();
+
+        assert!(super::check_alive(device_addr, ST::All, Duration::from_millis(100)).unwrap().is_none());
+    }
+
+    #[test]
+    fn negative_builder_missing_search_target() {
+        assert!(SearchRequest::builder().mx(3).build().is_err());
+    }
+
+    #[test]
+    fn positive_search_mx() {
+        assert_eq!(super::search_mx(Duration::from_millis(100)), 1);
+        assert_eq!(super::search_mx(Duration::from_secs(3)), 3);
+        assert_eq!(super::search_mx(Duration::from_secs(30)), 5);
+    }
+
+    #[test]
+    fn positive_multicast_ttl() {
+        let mut request = SearchRequest::builder().search_target(ST::All).build().unwrap();
+        assert_eq!(request.multicast_ttl(), message::UPNP_MULTICAST_TTL);
+
+        request.set_multicast_ttl(4);
+        assert_eq!(request.multicast_ttl(), 4);
+
+        assert_eq!(request.multicast_hops(), None);
+        request.set_multicast_hops(Some(3));
+        assert_eq!(request.multicast_hops(), Some(3));
+    }
+
+    #[test]
+    fn positive_multicast_timeout_slack() {
+        let timeout = super::multicast_timeout(Some(&MX(3)), Duration::from_millis(500)).unwrap();
+
+        assert_eq!(timeout, Duration::from_millis(3500));
+    }
+
+    #[test]
+    fn negative_builder_invalid_mx() {
+        assert!(SearchRequest::builder().search_target(ST::All).mx(0).build().is_err());
+        assert!(SearchRequest::builder().search_target(ST::All).mx(6).build().is_err());
+    }
 
     #[test]
     fn positive_multicast_timeout() {
-        super::multicast_timeout(Some(&MX(5))).unwrap();
+        super::multicast_timeout(Some(&MX(5)), Duration::from_secs(1)).unwrap();
     }
 
     #[test]
     fn positive_some_opt_multicast_timeout() {
-        super::opt_unicast_timeout(Some(&MX(5))).unwrap();
+        super::opt_unicast_timeout(Some(&MX(5)), Duration::from_secs(1)).unwrap();
     }
 
     #[test]
     fn positive_none_opt_multicast_timeout() {
-        super::opt_unicast_timeout(None).unwrap();
+        super::opt_unicast_timeout(None, Duration::from_secs(1)).unwrap();
     }
 
     #[test]
     #[should_panic]
     fn negative_multicast_timeout() {
-        super::multicast_timeout(None).unwrap();
+        super::multicast_timeout(None, Duration::from_secs(1)).unwrap();
     }
 }
@@ -0,0 +1,215 @@
+//! Serde support for SSDP messages.
+//!
+//! Messages serialize as a struct with the raw header fields under `headers`,
+//! alongside typed convenience fields that are null if the corresponding
+//! header is missing or malformed. Only `headers` is read back when deserializing.
+
+use std::fmt::{self, Formatter};
+
+use serde::de::{Deserialize, Deserializer, IgnoredAny, MapAccess, Visitor};
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+use header::{Header, HeaderFormat, HeaderFormatter, HeaderMap, HeaderMut, HeaderRef, Location, MX, NT, NTS,
+             ST, USN};
+use message::{NotifyMessage, SearchRequest, SearchResponse};
+
+const HEADERS_FIELD: &'static str = "headers";
+
+impl Serialize for SearchRequest {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = try!(serializer.serialize_struct("SearchRequest", 3));
+
+        try!(state.serialize_field(HEADERS_FIELD, &header_map(self)));
+        try!(state.serialize_field("st", &formatted::<ST, _>(self)));
+        try!(state.serialize_field("mx", &self.get::<MX>().map(|mx| mx.0)));
+
+        state.end()
+    }
+}
+
+impl Serialize for SearchResponse {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = try!(serializer.serialize_struct("SearchResponse", 6));
+
+        try!(state.serialize_field(HEADERS_FIELD, &header_map(self)));
+        try!(state.serialize_field("st", &formatted::<ST, _>(self)));
+        try!(state.serialize_field("usn", &formatted::<USN, _>(self)));
+        try!(state.serialize_field("location", &self.location()));
+        try!(state.serialize_field("server", &self.server()));
+        try!(state.serialize_field("max_age", &self.max_age().map(|max_age| max_age.as_secs())));
+
+        state.end()
+    }
+}
+
+impl Serialize for NotifyMessage {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = try!(serializer.serialize_struct("NotifyMessage", 7));
+
+        try!(state.serialize_field(HEADERS_FIELD, &header_map(self)));
+        try!(state.serialize_field("nt", &formatted::<NT, _>(self)));
+        try!(state.serialize_field("nts", &formatted::<NTS, _>(self)));
+        try!(state.serialize_field("usn", &formatted::<USN, _>(self)));
+        try!(state.serialize_field("location", &self.get_str(Location::header_name())));
+        try!(state.serialize_field("server", &self.server()));
+        try!(state.serialize_field("max_age", &self.max_age().map(|max_age| max_age.as_secs())));
+
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for SearchRequest {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<SearchRequest, D::Error> {
+        deserializer.deserialize_struct("SearchRequest",
+                                        &[HEADERS_FIELD],
+                                        MessageVisitor::new("a search request", SearchRequest::new))
+    }
+}
+
+impl<'de> Deserialize<'de> for SearchResponse {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<SearchResponse, D::Error> {
+        deserializer.deserialize_struct("SearchResponse",
+                                        &[HEADERS_FIELD],
+                                        MessageVisitor::new("a search response", SearchResponse::new))
+    }
+}
+
+impl<'de> Deserialize<'de> for NotifyMessage {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<NotifyMessage, D::Error> {
+        deserializer.deserialize_struct("NotifyMessage",
+                                        &[HEADERS_FIELD],
+                                        MessageVisitor::new("a notify message", NotifyMessage::new))
+    }
+}
+
+/// Copy the header fields of a message into a `HeaderMap` for serializing.
+fn header_map<T: HeaderRef>(message: &T) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+
+    for view in message.iter_headers() {
+        headers.set_raw(view.name().to_owned(), view.raw().to_vec());
+    }
+
+    headers
+}
+
+/// Header value formatted as it would be sent, if it is present and valid.
+fn formatted<H, T>(message: &T) -> Option<String>
+    where H: Header + HeaderFormat,
+          T: HeaderRef
+{
+    message.get::<H>().map(|value| HeaderFormatter(&value).to_string())
+}
+
+/// Visitor that builds a message from its serialized header fields.
+struct MessageVisitor<T> {
+    expecting: &'static str,
+    new: fn() -> T,
+}
+
+impl<T> MessageVisitor<T> {
+    fn new(expecting: &'static str, new: fn() -> T) -> MessageVisitor<T> {
+        MessageVisitor {
+            expecting: expecting,
+            new: new,
+        }
+    }
+}
+
+impl<'de, T> Visitor<'de> for MessageVisitor<T>
+    where T: HeaderMut
+{
+    type Value = T;
+
+    fn expecting(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(self.expecting)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<T, A::Error> {
+        let mut message = (self.new)();
+
+        while let Some(key) = try!(access.next_key::<String>()) {
+            if key == HEADERS_FIELD {
+                let headers = try!(access.next_value::<HeaderMap>());
+
+                for view in headers.iter() {
+                    message.set_raw(view.name().to_owned(), view.raw().to_vec());
+                }
+            } else {
+                try!(access.next_value::<IgnoredAny>());
+            }
+        }
+
+        Ok(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serde_json::{self, Value};
+
+    use header::{HeaderRef, NTS, USN};
+    use message::{NotifyMessage, SearchRequest, SearchResponse};
+    use target::SearchTarget;
+
+    #[test]
+    fn positive_search_request_round_trip() {
+        let request = SearchRequest::builder()
+            .search_target(SearchTarget::All)
+            .mx(3)
+            .build()
+            .unwrap();
+
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["st"], "ssdp:all");
+        assert_eq!(json["mx"], 3);
+
+        let parsed = serde_json::from_value::<SearchRequest>(json).unwrap();
+        assert_eq!(parsed.get_raw("ST"), request.get_raw("ST"));
+        assert_eq!(parsed.get_raw("MX"), request.get_raw("MX"));
+    }
+
+    #[test]
+    fn positive_search_response_typed_fields() {
+        let json = r#"{"headers": {"ST": "upnp:rootdevice",
+                                   "USN": "uuid:device-UUID::upnp:rootdevice",
+                                   "LOCATION": "http://192.168.0.2/desc.xml",
+                                   "CACHE-CONTROL": "max-age=1800",
+                                   "X-MULTI": ["1", "2"]},
+                       "st": "ignored"}"#;
+
+        let response = serde_json::from_str::<SearchResponse>(json).unwrap();
+        assert_eq!(response.location(), Some("http://192.168.0.2/desc.xml"));
+        assert_eq!(response.max_age(), Some(Duration::from_secs(1800)));
+        assert_eq!(response.get_raw("x-multi").unwrap().len(), 2);
+
+        let value = serde_json::to_value(&response).unwrap();
+        assert_eq!(value["st"], "upnp:rootdevice");
+        assert_eq!(value["usn"], "uuid:device-UUID::upnp:rootdevice");
+        assert_eq!(value["max_age"], 1800);
+        assert_eq!(value["server"], Value::Null);
+    }
+
+    #[test]
+    fn positive_notify_message_round_trip() {
+        let json = r#"{"headers": {"NT": "upnp:rootdevice", "NTS": "ssdp:byebye",
+                                   "USN": "uuid:device-UUID::upnp:rootdevice"}}"#;
+
+        let notify = serde_json::from_str::<NotifyMessage>(json).unwrap();
+        assert_eq!(notify.nts(), Some(NTS::ByeBye));
+        assert!(notify.get::<USN>().is_some());
+
+        let value = serde_json::to_value(&notify).unwrap();
+        assert_eq!(value["nts"], "ssdp:byebye");
+
+        let parsed = serde_json::from_value::<NotifyMessage>(value).unwrap();
+        assert_eq!(parsed.iter_headers().count(), 3);
+    }
+
+    #[test]
+    fn negative_headers_not_a_map() {
+        assert!(serde_json::from_str::<SearchResponse>(r#"{"headers": 5}"#).is_err());
+    }
+}
@@ -1,42 +1,34 @@
 use std::borrow::{Cow, ToOwned};
-use std::fmt::Debug;
-use std::io::Write;
+use std::fmt::{self, Debug, Display, Formatter};
 use std::net::{ToSocketAddrs, SocketAddr};
 
-use hyper::Url;
-use hyper::buffer::BufReader;
-use hyper::client::request::Request;
-use hyper::header::{Headers, Header, HeaderFormat, ContentLength, Host};
-use hyper::http::RawStatus;
-use hyper::http::h1::{self, Incoming};
-use hyper::method::Method;
-use hyper::net::{NetworkConnector, NetworkStream};
-use hyper::server::response::Response;
-use hyper::status::StatusCode;
-use hyper::uri::RequestUri;
-use hyper::version::HttpVersion;
-
 use {SSDPResult, SSDPError};
-use header::{HeaderRef, HeaderMut};
-use message::MessageType;
+use header::{HeaderRef, HeaderMut, HeaderMap, HeadersItems, Header, HeaderFormat, Host};
+use message::{MessageRef, MessageType};
 use net;
-use receiver::FromRawSSDP;
+use net::connector::UdpConnector;
+use receiver::{FromRawSSDP, ReceiveInfo};
 
 /// Only Valid `SearchResponse` Code
-const VALID_RESPONSE_CODE: u16 = 200;
-
-/// Appended To Destination Socket Addresses For URLs
-const BASE_HOST_URL: &'static str = "http://";
+pub const VALID_RESPONSE_CODE: u16 = 200;
 
 /// Case-Sensitive Method Names
-const NOTIFY_METHOD: &'static str = "NOTIFY";
-const SEARCH_METHOD: &'static str = "M-SEARCH";
+pub const NOTIFY_METHOD: &'static str = "NOTIFY";
+pub const SEARCH_METHOD: &'static str = "M-SEARCH";
+
+/// Reason Phrase Of A Valid `SearchResponse` Code
+const VALID_REASON_PHRASE: &'static str = "OK";
+
+/// Header Added To Every Sent Message
+const CONTENT_LENGTH_HEADER_NAME: &'static str = "Content-Length";
 
 /// Represents an SSDP method combined with both SSDP and HTTP headers.
 #[derive(Debug, Clone)]
 pub struct SSDPMessage {
     method: MessageType,
-    headers: Headers,
+    status: (u16, String),
+    headers: HeaderMap,
+    receive_info: Option<ReceiveInfo>,
 }
 
 impl SSDPMessage {
@@ -44,118 +36,150 @@ impl SSDPMessage {
     pub fn new(message_type: MessageType) -> SSDPMessage {
         SSDPMessage {
             method: message_type,
-            headers: Headers::new(),
+            status: (VALID_RESPONSE_CODE, VALID_REASON_PHRASE.to_owned()),
+            headers: HeaderMap::new(),
+            receive_info: None,
         }
     }
 
+    /// Get the metadata about how this message was received, if it was received.
+    pub fn receive_info(&self) -> Option<&ReceiveInfo> {
+        self.receive_info.as_ref()
+    }
+
     /// Get the type of this message.
     pub fn message_type(&self) -> MessageType {
         self.method
     }
 
-    /// Send this request to the given destination address using the given connector.
+    /// Status code of the status line, which is only meaningful for responses.
+    pub fn status_code(&self) -> u16 {
+        self.status.0
+    }
+
+    /// Reason phrase of the status line, which is only meaningful for responses.
+    pub fn reason_phrase(&self) -> &str {
+        &self.status.1
+    }
+
+    /// Serialize this message as it is written to the network, with a Content-Length
+    /// of zero added if the message does not have one.
     ///
-    /// The host header field will be taken care of by the underlying library.
-    pub fn send<A: ToSocketAddrs, C, S>(&self, connector: &mut C, dst_addr: A) -> SSDPResult<()>
-        where C: NetworkConnector<Stream = S>,
-              S: Into<Box<NetworkStream + Send>>
-    {
+    /// Header fields are written in the order they were set or received, with the
+    /// case of their names and their values kept exactly, so that unknown vendor
+    /// headers survive being parsed and serialized again.
+    ///
+    /// Requests without a HOST header are serialized without one, as it is only
+    /// filled in from the destination address when the message is sent.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes_with_host(None)
+    }
+
+    /// Serialize this message like `to_bytes`, writing the host as the HOST header
+    /// of requests that do not have one.
+    fn to_bytes_with_host(&self, opt_host: Option<&str>) -> Vec<u8> {
+        let mut bytes = Vec::new();
+
+        let start_line = match self.method {
+            MessageType::Notify => format!("{} * HTTP/1.1", NOTIFY_METHOD),
+            MessageType::Search => format!("{} * HTTP/1.1", SEARCH_METHOD),
+            MessageType::Response if self.status.1.is_empty() => format!("HTTP/1.1 {}", self.status.0),
+            MessageType::Response => format!("HTTP/1.1 {} {}", self.status.0, self.status.1),
+        };
+        bytes.extend_from_slice(start_line.as_bytes());
+        bytes.extend_from_slice(b"\r\n");
+
+        let missing_host = self.headers.get_raw(Host::header_name()).is_none();
+        match opt_host {
+            Some(host) if self.method != MessageType::Response && missing_host => {
+                bytes.extend_from_slice(Host::header_name().as_bytes());
+                bytes.extend_from_slice(b": ");
+                bytes.extend_from_slice(host.as_bytes());
+                bytes.extend_from_slice(b"\r\n");
+            }
+            _ => (),
+        }
+
+        for view in self.headers.iter() {
+            for line in view.raw() {
+                bytes.extend_from_slice(view.name().as_bytes());
+                bytes.push(b':');
+                if !line.is_empty() {
+                    bytes.push(b' ');
+                    bytes.extend_from_slice(line);
+                }
+                bytes.extend_from_slice(b"\r\n");
+            }
+        }
+
+        if self.headers.get_raw(CONTENT_LENGTH_HEADER_NAME).is_none() {
+            bytes.extend_from_slice(CONTENT_LENGTH_HEADER_NAME.as_bytes());
+            bytes.extend_from_slice(b": 0\r\n");
+        }
+
+        bytes.extend_from_slice(b"\r\n");
+        bytes
+    }
+
+    /// Send this message to the given destination address using the given connector.
+    ///
+    /// The HOST header of requests is filled in with the destination address, such
+    /// as the multicast group and port, unless the message already has one.
+    pub fn send<A: ToSocketAddrs>(&self, connector: &UdpConnector, dst_addr: A) -> SSDPResult<()> {
         let dst_sock_addr = try!(net::addr_from_trait(dst_addr));
         match self.method {
             MessageType::Notify => {
                 trace!("Notify to: {:?}", dst_sock_addr);
-                send_request(NOTIFY_METHOD, &self.headers, connector, dst_sock_addr)
+                send_request(self, connector, dst_sock_addr)
             }
             MessageType::Search => {
                 trace!("Sending search request...");
-                send_request(SEARCH_METHOD, &self.headers, connector, dst_sock_addr)
+                send_request(self, connector, dst_sock_addr)
             }
             MessageType::Response => {
                 trace!("Sending response to: {:?}", dst_sock_addr);
-                // This might need fixing for IPV6, passing down the IP loses the scope information
-                let dst_ip_string = dst_sock_addr.ip().to_string();
-                let dst_port = dst_sock_addr.port();
-
-                let net_stream = try!(connector.connect(&dst_ip_string[..], dst_port, "")).into();
-
-                send_response(&self.headers, net_stream)
+                send_response(self, connector, dst_sock_addr)
             }
         }
     }
 }
 
-#[allow(unused)]
-/// Send a request using the connector with the supplied method and headers.
-fn send_request<C, S>(method: &str,
-                      headers: &Headers,
-                      connector: &mut C,
-                      dst_addr: SocketAddr)
-                      -> SSDPResult<()>
-    where C: NetworkConnector<Stream = S>,
-          S: Into<Box<NetworkStream + Send>>
-{
-    trace!("Trying to parse url...");
-    let url = try!(url_from_addr(dst_addr));
-    trace!("Url: {}", url);
-
-    let mut request = try!(Request::with_connector(Method::Extension(method.to_owned()), url, connector));
-
-    trace!("Copying headers...");
-    copy_headers(headers, request.headers_mut());
-    trace!("Setting length");
-    request.headers_mut().set(ContentLength(0));
-
-    // Send Will Always Fail Within The UdpConnector Which Is Intended So That
-    // Hyper Does Not Block For A Response Since We Are Handling That Ourselves.
-
-    trace!("actual .send ...");
-    try!(request.start()).send();
-
-    Ok(())
+/// Renders the message as it is written to the network, see `SSDPMessage::to_bytes`.
+impl Display for SSDPMessage {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(&String::from_utf8_lossy(&self.to_bytes()))
+    }
 }
 
-/// Send an Ok response on the Writer with the supplied headers.
-fn send_response<W>(headers: &Headers, mut dst_writer: W) -> SSDPResult<()>
-    where W: Write
-{
-    let mut temp_headers = Headers::new();
-
-    copy_headers(headers, &mut temp_headers);
-    temp_headers.set(ContentLength(0));
+/// Send a request using the connector, with the destination address as its HOST.
+fn send_request(message: &SSDPMessage, connector: &UdpConnector, dst_addr: SocketAddr) -> SSDPResult<()> {
+    let host = host_from_addr(dst_addr);
 
-    let mut response = Response::new(&mut dst_writer as &mut Write, &mut temp_headers);
-    *response.status_mut() = StatusCode::Ok;
-
-    // Have to make sure response is destroyed here for lifetime issues with temp_headers
-    try!(try!(response.start()).end());
+    try!(connector.send_to(&message.to_bytes_with_host(Some(&host)), dst_addr));
 
     Ok(())
 }
 
-/// Convert the given address to a Url with a base of "udp://".
-fn url_from_addr(addr: SocketAddr) -> SSDPResult<Url> {
-    let str_url = BASE_HOST_URL.chars()
-        .chain(addr.to_string()[..].chars())
-        .collect::<String>();
+/// Send a response using the connector with its own status line and headers.
+fn send_response(message: &SSDPMessage, connector: &UdpConnector, dst_addr: SocketAddr) -> SSDPResult<()> {
+    try!(connector.send_to(&message.to_bytes(), dst_addr));
 
-    Ok(try!(Url::parse(&str_url[..])))
+    Ok(())
 }
 
-/// Copy the headers from the source header to the destination header.
-fn copy_headers(src_headers: &Headers, dst_headers: &mut Headers) {
-    // Not the best solution since we are doing a lot of string
-    // allocations for no benefit other than to transfer the headers.
-
-    // TODO: See if there is a way around calling to_owned() since set_raw
-    // requires a Cow<'static, _> and we only have access to Cow<'a, _>.
-    let iter = src_headers.iter();
-    for view in iter {
-        dst_headers.set_raw(Cow::Owned(view.name().to_owned()), vec![view.value_string().into_bytes()]);
+/// Format the given address as the value of a HOST header.
+///
+/// The scope id of IPv6 addresses is left out, giving "[ff02::c]:1900" for the
+/// multicast group.
+fn host_from_addr(addr: SocketAddr) -> String {
+    match addr {
+        SocketAddr::V4(n) => n.to_string(),
+        SocketAddr::V6(n) => format!("[{}]:{}", n.ip(), n.port()),
     }
 }
 
 impl HeaderRef for SSDPMessage {
-    fn get<H>(&self) -> Option<&H>
+    fn get<H>(&self) -> Option<H>
         where H: Header + HeaderFormat
     {
         HeaderRef::get::<H>(&self.headers)
@@ -164,6 +188,10 @@ impl HeaderRef for SSDPMessage {
     fn get_raw(&self, name: &str) -> Option<&[Vec<u8>]> {
         HeaderRef::get_raw(&self.headers, name)
     }
+
+    fn iter_headers(&self) -> HeadersItems {
+        HeaderRef::iter_headers(&self.headers)
+    }
 }
 
 impl HeaderMut for SSDPMessage {
@@ -178,27 +206,28 @@ impl HeaderMut for SSDPMessage {
     {
         HeaderMut::set_raw(&mut self.headers, name, value)
     }
+
+    fn remove<H>(&mut self) -> bool
+        where H: Header + HeaderFormat
+    {
+        HeaderMut::remove::<H>(&mut self.headers)
+    }
+
+    fn remove_raw(&mut self, name: &str) -> bool {
+        HeaderMut::remove_raw(&mut self.headers, name)
+    }
 }
 
 impl FromRawSSDP for SSDPMessage {
     fn raw_ssdp(bytes: &[u8]) -> SSDPResult<SSDPMessage> {
-        let mut buf_reader = BufReader::new(bytes);
-
-        if let Ok(parts) = h1::parse_request(&mut buf_reader) {
-            let message_result = message_from_request(parts);
+        let message_result = MessageRef::parse(bytes).and_then(|message| message_from_ref(&message));
 
-            log_message_result(&message_result, bytes);
-            message_result
-        } else if let Ok(parts) = h1::parse_response(&mut buf_reader) {
-            let message_result = message_from_response(parts);
-
-            log_message_result(&message_result, bytes);
-            message_result
-        } else {
-            debug!("Received Invalid HTTP: {}", String::from_utf8_lossy(bytes));
+        log_message_result(&message_result, bytes);
+        message_result
+    }
 
-            Err(SSDPError::InvalidHttp(bytes.to_owned()))
-        }
+    fn set_receive_info(&mut self, info: ReceiveInfo) {
+        self.receive_info = Some(info);
     }
 }
 
@@ -210,67 +239,34 @@ fn log_message_result(result: &SSDPResult<SSDPMessage>, message: &[u8]) {
     }
 }
 
-/// Attempts to construct an `SSDPMessage` from the given request pieces.
-fn message_from_request(parts: Incoming<(Method, RequestUri)>) -> SSDPResult<SSDPMessage> {
-    let headers = parts.headers;
-
-    try!(validate_http_version(parts.version));
-    try!(validate_http_host(&headers));
-
-    match parts.subject {
-        (Method::Extension(n), RequestUri::Star) => {
-            match &n[..] {
-                NOTIFY_METHOD => {
-                    Ok(SSDPMessage {
-                        method: MessageType::Notify,
-                        headers: headers,
-                    })
-                }
-                SEARCH_METHOD => {
-                    Ok(SSDPMessage {
-                        method: MessageType::Search,
-                        headers: headers,
-                    })
-                }
-                _ => Err(SSDPError::InvalidMethod(n)),
-            }
-        }
-        (n, RequestUri::Star) => Err(SSDPError::InvalidMethod(n.to_string())),
-        (_, RequestUri::AbsolutePath(n)) |
-        (_, RequestUri::Authority(n)) => Err(SSDPError::InvalidUri(n)),
-        (_, RequestUri::AbsoluteUri(n)) => Err(SSDPError::InvalidUri(n.into_string())),
+/// Attempts to construct an `SSDPMessage` by copying the header fields of the view.
+fn message_from_ref(message: &MessageRef) -> SSDPResult<SSDPMessage> {
+    let mut headers = HeaderMap::new();
+    for (name, value) in message.iter() {
+        headers.append_raw(Cow::Owned(name.to_owned()), value.to_vec());
     }
-}
 
-/// Attempts to construct an `SSDPMessage` from the given response pieces.
-fn message_from_response(parts: Incoming<RawStatus>) -> SSDPResult<SSDPMessage> {
-    let RawStatus(status_code, _) = parts.subject;
-    let headers = parts.headers;
+    if message.message_type() != MessageType::Response {
+        try!(validate_http_host(&headers));
+    }
 
-    try!(validate_http_version(parts.version));
-    try!(validate_response_code(status_code));
+    let status = match (message.status_code(), message.reason_phrase()) {
+        (Some(code), Some(reason)) => (code, reason.to_owned()),
+        _ => (VALID_RESPONSE_CODE, VALID_REASON_PHRASE.to_owned()),
+    };
 
     Ok(SSDPMessage {
-        method: MessageType::Response,
+        method: message.message_type(),
+        status: status,
         headers: headers,
+        receive_info: None,
     })
 }
 
-/// Validate the HTTP version for an SSDP message.
-fn validate_http_version(version: HttpVersion) -> SSDPResult<()> {
-    if version != HttpVersion::Http11 {
-        Err(SSDPError::InvalidHttpVersion)
-    } else {
-        Ok(())
-    }
-}
-
-/// Validate that the Host header is present.
+/// Validate that the Host header is present and valid.
 fn validate_http_host<T>(headers: T) -> SSDPResult<()>
     where T: HeaderRef
 {
-    // Shouldn't have to do this but hyper doesn't make sure that HTTP/1.1
-    // messages contain Host headers so we will assure conformance ourselves.
     if headers.get::<Host>().is_none() {
         Err(SSDPError::MissingHeader(Host::header_name()))
     } else {
@@ -278,125 +274,34 @@ fn validate_http_host<T>(headers: T) -> SSDPResult<()>
     }
 }
 
-/// Validate the response code for an SSDP message.
-fn validate_response_code(code: u16) -> SSDPResult<()> {
-    if code != VALID_RESPONSE_CODE {
-        Err(SSDPError::ResponseCode(code))
-    } else {
-        Ok(())
-    }
-}
-
-#[cfg(test)]
-mod mocks {
-    use std::cell::RefCell;
-    use std::io::{self, Read, Write, ErrorKind};
-    use std::net::SocketAddr;
-    use std::time::Duration;
-    use std::sync::mpsc::{self, Sender, Receiver};
-
-    use hyper::error;
-    use hyper::net::{NetworkConnector, NetworkStream};
-
-    pub struct MockConnector {
-        pub receivers: RefCell<Vec<Receiver<Vec<u8>>>>,
-    }
-
-    impl MockConnector {
-        pub fn new() -> MockConnector {
-            MockConnector { receivers: RefCell::new(Vec::new()) }
-        }
-    }
-
-    impl NetworkConnector for MockConnector {
-        type Stream = MockStream;
-
-        fn connect(&self, _: &str, _: u16, _: &str) -> error::Result<Self::Stream> {
-            let (send, recv) = mpsc::channel();
-
-            self.receivers.borrow_mut().push(recv);
-
-            Ok(MockStream { sender: send })
-        }
-    }
-
-    pub struct MockStream {
-        sender: Sender<Vec<u8>>,
-    }
-
-    impl NetworkStream for MockStream {
-        fn peer_addr(&mut self) -> io::Result<SocketAddr> {
-            Err(io::Error::new(ErrorKind::AddrNotAvailable, ""))
-        }
-        fn set_read_timeout(&self, _dur: Option<Duration>) -> io::Result<()> {
-            Ok(())
-        }
-        fn set_write_timeout(&self, _dur: Option<Duration>) -> io::Result<()> {
-            Ok(())
-        }
-    }
-
-    impl Read for MockStream {
-        fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
-            Err(io::Error::new(ErrorKind::ConnectionAborted, ""))
-        }
-    }
-
-    impl Write for MockStream {
-        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-            // Hyper will generate a request with a /, we need to intercept that.
-            let mut buffer = vec![0u8; buf.len()];
-
-            let mut found = false;
-            for (src, dst) in buf.iter().zip(buffer.iter_mut()) {
-                if *src == b'/' && !found && buf[0] != b'H' {
-                    *dst = b'*';
-                    found = true;
-                } else {
-                    *dst = *src;
-                }
-            }
-
-            self.sender.send(buffer).unwrap();
-
-            Ok(buf.len())
-        }
-
-        fn flush(&mut self) -> io::Result<()> {
-            Ok(())
-        }
-    }
-}
-
 #[cfg(test)]
 mod tests {
     mod send {
-        use std::sync::mpsc::Receiver;
+        use std::net::{SocketAddrV6, UdpSocket};
 
-        use super::super::mocks::MockConnector;
-        use super::super::SSDPMessage;
+        use super::super::{SSDPMessage, host_from_addr};
         use message::MessageType;
+        use net::connector::UdpConnector;
+        use receiver::FromRawSSDP;
 
-        fn join_buffers(recv_list: &[Receiver<Vec<u8>>]) -> Vec<u8> {
-            let mut buffer = Vec::new();
+        /// Send the message to a local socket, returning the packet it received.
+        fn send_and_receive(message: &SSDPMessage) -> Vec<u8> {
+            let recv_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+            let connector = UdpConnector::new("127.0.0.1:0", None).unwrap();
 
-            for recv in recv_list {
-                for recv_buf in recv {
-                    buffer.extend(&recv_buf[..])
-                }
-            }
+            message.send(&connector, recv_sock.local_addr().unwrap()).unwrap();
 
-            buffer
+            let mut buf = [0u8; 1500];
+            let (size, _) = recv_sock.recv_from(&mut buf).unwrap();
+
+            buf[..size].to_vec()
         }
 
         #[test]
         fn positive_search_method_line() {
             let message = SSDPMessage::new(MessageType::Search);
-            let mut connector = MockConnector::new();
-
-            message.send(&mut connector, ("127.0.0.1", 0)).unwrap();
 
-            let sent_message = String::from_utf8(join_buffers(&*connector.receivers.borrow())).unwrap();
+            let sent_message = String::from_utf8(send_and_receive(&message)).unwrap();
 
             assert_eq!(&sent_message[..19], "M-SEARCH * HTTP/1.1");
         }
@@ -404,11 +309,8 @@ mod tests {
         #[test]
         fn positive_notify_method_line() {
             let message = SSDPMessage::new(MessageType::Notify);
-            let mut connector = MockConnector::new();
-
-            message.send(&mut connector, ("127.0.0.1", 0)).unwrap();
 
-            let sent_message = String::from_utf8(join_buffers(&*connector.receivers.borrow())).unwrap();
+            let sent_message = String::from_utf8(send_and_receive(&message)).unwrap();
 
             assert_eq!(&sent_message[..17], "NOTIFY * HTTP/1.1");
         }
@@ -416,11 +318,8 @@ mod tests {
         #[test]
         fn positive_response_method_line() {
             let message = SSDPMessage::new(MessageType::Response);
-            let mut connector = MockConnector::new();
 
-            message.send(&mut connector, ("127.0.0.1", 0)).unwrap();
-
-            let sent_message = String::from_utf8(join_buffers(&*connector.receivers.borrow())).unwrap();
+            let sent_message = String::from_utf8(send_and_receive(&message)).unwrap();
 
             assert_eq!(&sent_message[..15], "HTTP/1.1 200 OK");
         }
@@ -428,13 +327,57 @@ mod tests {
         #[test]
         fn positive_host_header() {
             let message = SSDPMessage::new(MessageType::Search);
-            let mut connector = MockConnector::new();
+            let recv_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+            let connector = UdpConnector::new("127.0.0.1:0", None).unwrap();
+            let recv_addr = recv_sock.local_addr().unwrap();
+
+            message.send(&connector, recv_addr).unwrap();
 
-            message.send(&mut connector, ("127.0.0.1", 0)).unwrap();
+            let mut buf = [0u8; 1500];
+            let (size, _) = recv_sock.recv_from(&mut buf).unwrap();
+            let sent_message = String::from_utf8(buf[..size].to_vec()).unwrap();
 
-            let sent_message = String::from_utf8(join_buffers(&*connector.receivers.borrow())).unwrap();
+            assert!(sent_message.contains(&format!("\r\nHOST: {}\r\n", recv_addr)));
+        }
+
+        #[test]
+        fn positive_host_header_kept() {
+            let raw_message = b"NOTIFY * HTTP/1.1\r\nHOST: 239.255.255.250:1900\r\n\r\n";
+            let message = SSDPMessage::raw_ssdp(&raw_message[..]).unwrap();
+
+            assert_eq!(send_and_receive(&message), message.to_bytes());
+        }
 
-            assert!(sent_message.contains("Host: 127.0.0.1:0"));
+        #[test]
+        fn positive_response_sent_verbatim() {
+            let raw_message = b"HTTP/1.1 200 Ok\r\nCACHE-CONTROL: max-age=1800\r\nEXT:\r\n\
+                                X-User-Agent: redsonic\r\nX-RINCON-HOUSEHOLD: Sonos_abc123\r\n\
+                                Content-Length: 0\r\n\r\n";
+            let message = SSDPMessage::raw_ssdp(&raw_message[..]).unwrap().clone();
+
+            let sent_message = send_and_receive(&message);
+
+            assert_eq!(sent_message, message.to_bytes());
+            assert_eq!(&sent_message[..], &raw_message[..]);
+        }
+
+        #[test]
+        fn positive_host_header_ipv6_without_scope() {
+            let dst_addr = SocketAddrV6::new("ff02::c".parse().unwrap(), 1900, 0, 3);
+
+            assert_eq!(host_from_addr(dst_addr.into()), "[ff02::c]:1900");
+        }
+
+        #[test]
+        fn positive_vendor_headers_sent() {
+            let raw_message = b"NOTIFY * HTTP/1.1\r\nHOST: 239.255.255.250:1900\r\n\
+                                X-AV-Server-Info: av=5.0; cn=\"Sony Corporation\"; mv=\"2.0\"\r\n\r\n";
+            let message = SSDPMessage::raw_ssdp(&raw_message[..]).unwrap().clone();
+
+            let sent_message = String::from_utf8(send_and_receive(&message)).unwrap();
+
+            assert!(sent_message.contains("X-AV-Server-Info: av=5.0; cn=\"Sony Corporation\"; \
+                                           mv=\"2.0\"\r\n"));
         }
     }
 
@@ -458,6 +401,28 @@ mod tests {
             assert_eq!(&message.get_raw("Host").unwrap()[0][..], &b"192.168.1.1"[..]);
         }
 
+        #[test]
+        fn positive_header_case_insensitive() {
+            let raw_message = "NOTIFY * HTTP/1.1\r\nHOST: 192.168.1.1\r\n\
+                               location: http://192.168.1.1/\r\n\r\n";
+            let message = SSDPMessage::raw_ssdp(raw_message.as_bytes()).unwrap();
+
+            for name in &["location", "Location", "LOCATION"] {
+                assert_eq!(&message.get_raw(name).unwrap()[0][..], &b"http://192.168.1.1/"[..]);
+            }
+        }
+
+        #[test]
+        fn positive_folded_header() {
+            let raw_message = "NOTIFY * HTTP/1.1\r\nHOST : 192.168.1.1\r\n\
+                               SERVER: Linux/2.6\r\n UPnP/1.0 product/1.0\r\n\r\n";
+            let message = SSDPMessage::raw_ssdp(raw_message.as_bytes()).unwrap();
+
+            assert_eq!(&message.get_raw("SERVER").unwrap()[0][..],
+                       &b"Linux/2.6 UPnP/1.0 product/1.0"[..]);
+            assert!(message.get_raw("HOST").is_some());
+        }
+
         #[test]
         #[should_panic]
         fn negative_http_version() {
@@ -482,4 +447,56 @@ mod tests {
             SSDPMessage::raw_ssdp(raw_message.as_bytes()).unwrap();
         }
     }
+    mod display {
+        use super::super::SSDPMessage;
+        use header::HeaderMut;
+        use message::MessageType;
+        use receiver::FromRawSSDP;
+
+        #[test]
+        fn positive_request_wire_text() {
+            let mut message = SSDPMessage::new(MessageType::Search);
+            message.set_raw("HOST", vec![b"239.255.255.250:1900".to_vec()]);
+            message.set_raw("MAN", vec![b"\"ssdp:discover\"".to_vec()]);
+
+            assert_eq!(message.to_string(),
+                       "M-SEARCH * HTTP/1.1\r\nHOST: 239.255.255.250:1900\r\nMAN: \"ssdp:discover\"\r\n\
+                        Content-Length: 0\r\n\r\n");
+        }
+
+        #[test]
+        fn positive_response_multiple_lines() {
+            let mut message = SSDPMessage::new(MessageType::Response);
+            message.set_raw("X-MULTI", vec![b"1".to_vec(), b"2".to_vec()]);
+
+            assert_eq!(message.to_string(),
+                       "HTTP/1.1 200 OK\r\nX-MULTI: 1\r\nX-MULTI: 2\r\nContent-Length: 0\r\n\r\n");
+        }
+
+        #[test]
+        fn positive_round_trip() {
+            let raw_message = "NOTIFY * HTTP/1.1\r\nHOST: 239.255.255.250:1900\r\nNTS: ssdp:alive\r\n\
+                               Content-Length: 0\r\n\r\n";
+            let message = SSDPMessage::raw_ssdp(raw_message.as_bytes()).unwrap();
+
+            assert_eq!(message.to_string(), raw_message);
+        }
+
+        #[test]
+        fn positive_vendor_headers_round_trip() {
+            let raw_message = "HTTP/1.1 200 OK\r\nCACHE-CONTROL: max-age=1800\r\nEXT:\r\n\
+                               LOCATION: http://192.168.1.20:1400/xml/device_description.xml\r\n\
+                               SERVER: Linux UPnP/1.0 Sonos/70.3-35220 (ZPS9)\r\n\
+                               X-RINCON-HOUSEHOLD: Sonos_a1b2C3d4E5f6G7h8I9j0\r\n\
+                               X-RINCON-BOOTSEQ: 95\r\n\
+                               X-User-Agent: redsonic\r\n\
+                               x-av-client-info: av=5.0; cn=\"Sony Corporation\"; mn=\"Bravia\"\r\n\
+                               X-Empty:\r\n\
+                               X-Repeated: 1\r\nX-Repeated: 2\r\n\
+                               Content-Length: 0\r\n\r\n";
+            let message = SSDPMessage::raw_ssdp(raw_message.as_bytes()).unwrap();
+
+            assert_eq!(message.clone().to_bytes(), raw_message.as_bytes());
+        }
+    }
 }
@@ -1,71 +1,209 @@
 use std::io;
-use std::net::{UdpSocket, ToSocketAddrs, SocketAddr, SocketAddrV4, SocketAddrV6};
-use std::str::FromStr;
+use std::net::{IpAddr, UdpSocket, ToSocketAddrs, SocketAddr};
 
-use hyper::error;
-use hyper::net::NetworkConnector;
+use net2::UdpSocketExt;
 
-use net::sender::UdpSender;
-use net;
+use net::{self, SocketHook, SocketReuse};
 
-/// A `UdpConnector` allows Hyper to obtain `NetworkStream` objects over `UdpSockets`
-/// so that Http messages created by Hyper can be sent over UDP instead of TCP.
-pub struct UdpConnector(UdpSocket);
+/// A `UdpConnector` wraps the `UdpSocket` that SSDP messages are sent from, along
+/// with the interface that IPv6 multicasts are sent through.
+pub struct UdpConnector {
+    udp: UdpSocket,
+    scope_id: u32,
+}
 
 impl UdpConnector {
     /// Create a new UdpConnector that will be bound to the given local address.
-    pub fn new<A: ToSocketAddrs>(local_addr: A, _: Option<u32>) -> io::Result<UdpConnector> {
-        let addr = try!(net::addr_from_trait(local_addr));
+    ///
+    /// The multicast TTL, if given, is only applied to IPv4 sockets. IPv6 sockets
+    /// send multicasts through the interface of the scope id of the address, which
+    /// is looked up from the local network interfaces if the address has none.
+    pub fn new<A: ToSocketAddrs>(local_addr: A, multicast_ttl: Option<u32>) -> io::Result<UdpConnector> {
+        UdpConnector::with_hook(local_addr, multicast_ttl, None)
+    }
+
+    /// Create a new UdpConnector like `new`, invoking the hook on the socket before
+    /// it is bound.
+    pub fn with_hook<A>(local_addr: A,
+                        multicast_ttl: Option<u32>,
+                        hook: Option<&SocketHook>)
+                        -> io::Result<UdpConnector>
+        where A: ToSocketAddrs
+    {
+        let mut addr = try!(net::addr_from_trait(local_addr));
         debug!("Attempting to connect to {}", addr);
 
-        let udp = try!(UdpSocket::bind(addr));
+        let scope_id = match addr {
+            SocketAddr::V6(ref mut n) => {
+                let scope_id = net::scope_id_v6(n);
+
+                // Only link-local addresses have to be bound with their scope
+                if net::is_link_local_v6(&IpAddr::V6(*n.ip())) {
+                    n.set_scope_id(scope_id);
+                }
+                scope_id
+            }
+            SocketAddr::V4(_) => 0,
+        };
+
+        let udp = try!(net::bind_with_hook(addr, SocketReuse::Exclusive, hook));
+
+        match (multicast_ttl, addr) {
+            (Some(n), SocketAddr::V4(_)) => {
+                trace!("Setting ttl to {}", n);
+                try!(udp.set_multicast_ttl_v4(n));
+            }
+            _ => (),
+        }
+
+        if scope_id != 0 {
+            trace!("Setting ipv6 multicast interface to {}", scope_id);
+            try!(udp.set_multicast_if_v6(scope_id));
+        }
+
+        Ok(UdpConnector {
+            udp: udp,
+            scope_id: scope_id,
+        })
+    }
+
+    /// Set the hop limit of IPv6 multicasts sent through this connector.
+    pub fn set_multicast_hops(&self, hops: u32) -> io::Result<()> {
+        trace!("Setting ipv6 multicast hops to {}", hops);
+
+        self.udp.set_multicast_hops_v6(hops)
+    }
+
+    /// Set the TOS byte, or IPv6 traffic class, of packets sent through this
+    /// connector, such as 0xB8 for the Expedited Forwarding DSCP value of 46.
+    pub fn set_tos(&self, tos: u8) -> io::Result<()> {
+        trace!("Setting tos to {:#x}", tos);
+
+        net::set_tos(&self.udp, tos)
+    }
+
+    /// Enable or disable the looping back of multicasts sent through this connector
+    /// to sockets on the local host, for the IP version of the bound address.
+    pub fn set_multicast_loop(&self, enabled: bool) -> io::Result<()> {
+        trace!("Setting multicast loop to {}", enabled);
+
+        match try!(self.local_addr()) {
+            SocketAddr::V4(_) => self.udp.set_multicast_loop_v4(enabled),
+            SocketAddr::V6(_) => self.udp.set_multicast_loop_v6(enabled),
+        }
+    }
+
+    /// Bind this connector to the local network device with the given name, such
+    /// as "eth0", so that multicasts leave through that device regardless of routing.
+    ///
+    /// Binding to a device is only supported on linux.
+    pub fn bind_to_device(&self, device: &str) -> io::Result<()> {
+        trace!("Binding to device {}", device);
+
+        net::bind_to_device(&self.udp, device)
+    }
+
+    /// Create a new UdpConnector from an already bound UdpSocket.
+    ///
+    /// The scope id is taken from the IPv6 multicast interface of the socket.
+    pub fn from_socket(udp: UdpSocket) -> UdpConnector {
+        let scope_id = match udp.local_addr() {
+            Ok(SocketAddr::V6(_)) => udp.multicast_if_v6().unwrap_or(0),
+            _ => 0,
+        };
 
-        // TODO: This throws an invalid argument error
-        // if let Some(n) = multicast_ttl {
-        //     trace!("Setting ttl to {}", n);
-        //     try!(udp.set_multicast_ttl_v4(n));
-        // }
+        UdpConnector {
+            udp: udp,
+            scope_id: scope_id,
+        }
+    }
 
-        Ok(UdpConnector(udp))
+    /// Scope id of the interface that IPv6 multicasts are sent through, or 0 if
+    /// it is left to the routing table or the connector is not IPv6.
+    pub fn scope_id(&self) -> u32 {
+        self.scope_id
     }
 
     pub fn local_addr(&self) -> io::Result<SocketAddr> {
-        self.0.local_addr()
+        self.udp.local_addr()
+    }
+
+    /// Send the bytes of a message to the destination address as a single packet.
+    ///
+    /// IPv6 destinations without a scope id are sent through the interface of
+    /// the scope id of this connector.
+    pub fn send_to(&self, buf: &[u8], dst_addr: SocketAddr) -> io::Result<()> {
+        let dst_addr = match dst_addr {
+            SocketAddr::V6(mut n) if n.scope_id() == 0 => {
+                n.set_scope_id(self.scope_id());
+                SocketAddr::V6(n)
+            }
+            addr => addr,
+        };
+        debug!("Sending SSDP Message To {}:\n{}", dst_addr, String::from_utf8_lossy(buf));
+
+        try!(self.udp.send_to(buf, dst_addr));
+
+        Ok(())
     }
 
     /// Destroy the UdpConnector and return the underlying UdpSocket.
     pub fn deconstruct(self) -> UdpSocket {
-        self.0
+        self.udp
     }
 }
 
-impl NetworkConnector for UdpConnector {
-    type Stream = UdpSender;
-
-    fn connect(&self, host: &str, port: u16, _: &str) -> error::Result<<Self as NetworkConnector>::Stream> {
-        let udp_sock = try!(self.0.try_clone());
-        let sock_addr = match try!(self.local_addr()) {
-            SocketAddr::V4(_) => {
-                SocketAddr::V4(SocketAddrV4::new(try!(FromStr::from_str(host).map_err(|err| {
-                                                     io::Error::new(io::ErrorKind::InvalidInput, err)
-                                                 })),
-                                                 port))
-            }
-            SocketAddr::V6(n) => {
-                let mut addr: SocketAddrV6 = if host.find('[') == Some(0) &&
-                                                host.rfind(']') == Some(host.len() - 1) {
-                    try!(FromStr::from_str(format!("{}:{}", host, port).as_str())
-                        .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err)))
-                } else {
-                    try!(FromStr::from_str(format!("[{}]:{}", host, port).as_str())
-                        .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err)))
-                };
-                addr.set_flowinfo(n.flowinfo());
-                addr.set_scope_id(n.scope_id());
-                SocketAddr::V6(addr)
-            }
-        };
+#[cfg(test)]
+mod tests {
+    use std::net::{SocketAddrV6, UdpSocket};
+
+    use net2::UdpSocketExt;
+
+    use super::UdpConnector;
+
+    #[test]
+    fn positive_multicast_ttl_v4() {
+        let connector = UdpConnector::new("127.0.0.1:0", Some(4)).unwrap();
+
+        assert_eq!(connector.deconstruct().multicast_ttl_v4().unwrap(), 4);
+    }
+
+    #[test]
+    fn positive_multicast_hops() {
+        let connector = UdpConnector::new("[::1]:0", None).unwrap();
+
+        connector.set_multicast_hops(5).unwrap();
+        assert_eq!(connector.deconstruct().multicast_hops_v6().unwrap(), 5);
+    }
+
+    #[test]
+    fn positive_multicast_loop() {
+        let connector = UdpConnector::new("127.0.0.1:0", None).unwrap();
+
+        connector.set_multicast_loop(false).unwrap();
+        assert!(!connector.deconstruct().multicast_loop_v4().unwrap());
+    }
+
+    #[test]
+    fn positive_ipv6_scope_id() {
+        let connector = UdpConnector::new(SocketAddrV6::new("::1".parse().unwrap(), 0, 0, 1), None).unwrap();
+        assert_eq!(connector.scope_id(), 1);
+
+        let udp = connector.deconstruct();
+        assert_eq!(udp.multicast_if_v6().unwrap(), 1);
+        assert_eq!(UdpConnector::from_socket(udp).scope_id(), 1);
+    }
+
+    #[test]
+    fn positive_send_to() {
+        let recv_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let connector = UdpConnector::new("127.0.0.1:0", None).unwrap();
+
+        connector.send_to(b"NOTIFY * HTTP/1.1\r\n\r\n", recv_sock.local_addr().unwrap()).unwrap();
 
-        Ok(UdpSender::new(udp_sock, sock_addr))
+        let mut buf = [0u8; 64];
+        let (size, src_addr) = recv_sock.recv_from(&mut buf).unwrap();
+        assert_eq!(&buf[..size], &b"NOTIFY * HTTP/1.1\r\n\r\n"[..]);
+        assert_eq!(src_addr, connector.local_addr().unwrap());
     }
 }
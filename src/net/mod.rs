@@ -3,17 +3,127 @@
 //! This module deals with primitives for working with external libraries to write
 //! data to UDP sockets as a stream, and read data from UDP sockets as packets.
 
+use std::fmt::{self, Debug, Formatter};
 use std::io::{self, ErrorKind};
+#[cfg(not(windows))]
+use std::ffi::{CStr, CString};
+#[cfg(not(windows))]
+use std::mem;
 use std::net::{ToSocketAddrs, UdpSocket};
-use std::net::{SocketAddr, IpAddr};
+#[cfg(not(windows))]
+use std::os::unix::io::{AsRawFd, RawFd};
+#[cfg(windows)]
+use std::os::windows::io::{AsRawSocket, RawSocket};
+use std::net::{SocketAddr, SocketAddrV6, IpAddr, Ipv4Addr, Ipv6Addr};
+#[cfg(windows)]
+use std::net::lookup_host;
+use std::sync::Arc;
 
+#[cfg(not(windows))]
+use ifaces;
+#[cfg(not(windows))]
+use libc;
 #[cfg(not(windows))]
 use net2::unix::UnixUdpBuilderExt;
 use net2::{UdpBuilder};
 
+use self::packet::PacketDst;
+
 pub mod connector;
 pub mod packet;
-pub mod sender;
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+pub mod runtime;
+
+/// Selects the local network interfaces that multicasts are sent from.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum InterfaceFilter {
+    /// Every local network interface.
+    All,
+    /// Only the interfaces with one of the given names, such as "br0".
+    Names(Vec<String>),
+    /// Every interface except those with one of the given names, such as "tun0".
+    ExcludeNames(Vec<String>),
+    /// Only the interfaces with one of the given addresses.
+    Addrs(Vec<IpAddr>),
+}
+
+impl InterfaceFilter {
+    /// Whether or not the interface with the given local address is selected.
+    ///
+    /// Interfaces whose name can not be found are never selected by name.
+    pub fn matches(&self, ip: &IpAddr) -> bool {
+        match *self {
+            InterfaceFilter::All => true,
+            InterfaceFilter::Names(ref names) => {
+                interface_name(ip).map_or(false, |name| names.contains(&name))
+            }
+            InterfaceFilter::ExcludeNames(ref names) => {
+                interface_name(ip).map_or(true, |name| !names.contains(&name))
+            }
+            InterfaceFilter::Addrs(ref addrs) => addrs.contains(ip),
+        }
+    }
+}
+
+impl Default for InterfaceFilter {
+    fn default() -> Self {
+        InterfaceFilter::All
+    }
+}
+
+/// Subnet that a local network interface is directly attached to.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct LocalSubnet {
+    addr: IpAddr,
+    mask: IpAddr,
+}
+
+impl LocalSubnet {
+    /// Construct a new LocalSubnet from the address of the interface and its netmask.
+    pub fn new(addr: IpAddr, mask: IpAddr) -> LocalSubnet {
+        LocalSubnet {
+            addr: addr,
+            mask: mask,
+        }
+    }
+
+    /// Address of the local network interface.
+    pub fn addr(&self) -> IpAddr {
+        self.addr
+    }
+
+    /// Netmask of the subnet.
+    pub fn mask(&self) -> IpAddr {
+        self.mask
+    }
+
+    /// Whether or not the address is within this subnet.
+    ///
+    /// Addresses of a different IP version than the subnet are never contained.
+    pub fn contains(&self, ip: &IpAddr) -> bool {
+        match (self.addr, self.mask, *ip) {
+            (IpAddr::V4(addr), IpAddr::V4(mask), IpAddr::V4(ip)) => {
+                let mask = u32::from(mask);
+
+                u32::from(addr) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(addr), IpAddr::V6(mask), IpAddr::V6(ip)) => {
+                let (addr, mask, ip) = (addr.segments(), mask.segments(), ip.segments());
+
+                (0..8).all(|i| addr[i] & mask[i] == ip[i] & mask[i])
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Whether or not the address is an IPv6 link-local unicast address, fe80::/10.
+pub fn is_link_local_v6(ip: &IpAddr) -> bool {
+    match *ip {
+        IpAddr::V6(n) => n.segments()[0] & 0xffc0 == 0xfe80,
+        IpAddr::V4(_) => false,
+    }
+}
 
 pub enum IpVersionMode {
     V4Only,
@@ -40,8 +150,325 @@ pub fn addr_from_trait<A: ToSocketAddrs>(addr: A) -> io::Result<SocketAddr> {
     }
 }
 
-/// Bind to a `UdpSocket`, setting `SO_REUSEADDR` on the underlying socket before binding.
-pub fn bind_reuse<A: ToSocketAddrs>(local_addr: A) -> io::Result<UdpSocket> {
+/// Find the local address that the operating system would send packets from
+/// in order to reach the given destination address.
+///
+/// No packets are sent, the returned address has an unspecified port.
+pub fn route_local_addr(dst_addr: &SocketAddr) -> io::Result<SocketAddr> {
+    let unspecified_addr = match *dst_addr {
+        SocketAddr::V4(_) => SocketAddr::new(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)), 0),
+        SocketAddr::V6(_) => SocketAddr::new(IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 0)), 0),
+    };
+
+    // Connecting a udp socket only performs the route lookup
+    let udp = try!(UdpSocket::bind(unspecified_addr));
+    try!(udp.connect(dst_addr));
+
+    let mut local_addr = try!(udp.local_addr());
+    local_addr.set_port(0);
+
+    Ok(local_addr)
+}
+
+/// Find the addresses of every local network interface.
+///
+/// If any of the `SocketAddr`'s fail to resolve, this function will not return an error.
+#[cfg(windows)]
+pub fn local_addrs() -> io::Result<Vec<SocketAddr>> {
+    let host_iter = try!(lookup_host(""));
+    Ok(host_iter.collect())
+}
+
+/// Find the addresses of every local network interface.
+///
+/// Addresses of packet interfaces are skipped.
+#[cfg(not(windows))]
+pub fn local_addrs() -> io::Result<Vec<SocketAddr>> {
+    let iface_iter = try!(ifaces::Interface::get_all()).into_iter();
+    Ok(iface_iter.filter(|iface| iface.kind != ifaces::Kind::Packet)
+        .filter_map(|iface| iface.addr)
+        .collect())
+}
+
+/// Find the subnets that the local network interfaces are directly attached to.
+///
+/// Subnets are not currently looked up on windows.
+#[cfg(windows)]
+pub fn local_subnets() -> io::Result<Vec<LocalSubnet>> {
+    Err(io::Error::new(ErrorKind::Other, "Local Subnets Can Not Be Found On Windows"))
+}
+
+/// Find the subnets that the local network interfaces are directly attached to.
+///
+/// Interfaces without an address or netmask are skipped.
+#[cfg(not(windows))]
+pub fn local_subnets() -> io::Result<Vec<LocalSubnet>> {
+    let iface_iter = try!(ifaces::Interface::get_all()).into_iter();
+    Ok(iface_iter.filter(|iface| iface.kind != ifaces::Kind::Packet)
+        .filter_map(|iface| match (iface.addr, iface.mask) {
+            (Some(addr), Some(mask)) => Some(LocalSubnet::new(addr.ip(), mask.ip())),
+            _ => None,
+        })
+        .collect())
+}
+
+/// Find the name of the local network interface that has the given address.
+///
+/// Returns None for unspecified addresses or if no interface has the address.
+#[cfg(not(windows))]
+pub fn interface_name(ip: &IpAddr) -> Option<String> {
+    let ifaces = match ifaces::Interface::get_all() {
+        Ok(n) => n,
+        Err(_) => return None,
+    };
+
+    ifaces.into_iter()
+        .find(|iface| iface.addr.map_or(false, |addr| addr.ip() == *ip))
+        .map(|iface| iface.name)
+}
+
+/// Find the name of the local network interface that has the given address.
+///
+/// Interface names are not currently looked up on windows.
+#[cfg(windows)]
+pub fn interface_name(_: &IpAddr) -> Option<String> {
+    None
+}
+
+/// Find the index of the local network interface that has the given address,
+/// which is the scope id of link-local IPv6 addresses on that interface.
+///
+/// Returns None if no interface has the address or the index can not be found,
+/// which is the case for interfaces without an IPv6 link-local address.
+#[cfg(not(windows))]
+pub fn interface_index(ip: &IpAddr) -> Option<u32> {
+    let ifaces = match ifaces::Interface::get_all() {
+        Ok(n) => n,
+        Err(_) => return None,
+    };
+    let name = match ifaces.iter().find(|iface| iface.addr.map_or(false, |addr| addr.ip() == *ip)) {
+        Some(iface) => iface.name.clone(),
+        None => return None,
+    };
+
+    ifaces.into_iter()
+        .filter(|iface| iface.name == name)
+        .filter_map(|iface| match iface.addr {
+            Some(SocketAddr::V6(n)) if n.scope_id() != 0 => Some(n.scope_id()),
+            _ => None,
+        })
+        .next()
+}
+
+/// Find the index of the local network interface that has the given address.
+///
+/// Interface indexes are not currently looked up on windows.
+#[cfg(windows)]
+pub fn interface_index(_: &IpAddr) -> Option<u32> {
+    None
+}
+
+/// Find the index of the local network interface with the given name, such as "eth0".
+///
+/// Returns None if there is no interface with the name.
+#[cfg(not(windows))]
+pub fn interface_index_by_name(name: &str) -> Option<u32> {
+    let c_name = match CString::new(name) {
+        Ok(n) => n,
+        Err(_) => return None,
+    };
+
+    match unsafe { libc::if_nametoindex(c_name.as_ptr()) } {
+        0 => None,
+        index => Some(index),
+    }
+}
+
+/// Find the index of the local network interface with the given name.
+///
+/// Interface indexes are not currently looked up on windows.
+#[cfg(windows)]
+pub fn interface_index_by_name(_: &str) -> Option<u32> {
+    None
+}
+
+/// Find the name of the local network interface with the given index.
+///
+/// Returns None if there is no interface with the index.
+#[cfg(not(windows))]
+pub fn interface_name_by_index(index: u32) -> Option<String> {
+    let mut buf = [0 as libc::c_char; libc::IF_NAMESIZE];
+    if unsafe { libc::if_indextoname(index, buf.as_mut_ptr()).is_null() } {
+        return None;
+    }
+
+    Some(unsafe { CStr::from_ptr(buf.as_ptr()) }.to_string_lossy().into_owned())
+}
+
+/// Find the name of the local network interface with the given index.
+///
+/// Interface names are not currently looked up on windows.
+#[cfg(windows)]
+pub fn interface_name_by_index(_: u32) -> Option<String> {
+    None
+}
+
+/// Find the addresses of the local network interface with the given index.
+#[cfg(not(windows))]
+fn interface_addrs(index: u32) -> io::Result<Vec<SocketAddr>> {
+    let name = match interface_name_by_index(index) {
+        Some(n) => n,
+        None => return Err(io::Error::last_os_error()),
+    };
+
+    let iface_iter = try!(ifaces::Interface::get_all()).into_iter();
+    Ok(iface_iter.filter(|iface| iface.name == name)
+        .filter_map(|iface| iface.addr)
+        .collect())
+}
+
+/// Find the IPv4 address of the local network interface with the given index.
+#[cfg(not(windows))]
+fn interface_addr_v4(index: u32) -> io::Result<Ipv4Addr> {
+    try!(interface_addrs(index))
+        .into_iter()
+        .filter_map(|addr| match addr {
+            SocketAddr::V4(n) => Some(*n.ip()),
+            _ => None,
+        })
+        .next()
+        .ok_or_else(|| io::Error::new(ErrorKind::NotFound, "Interface Has No Ipv4 Address"))
+}
+
+/// Find the IPv4 address of the local network interface with the given index.
+///
+/// Interface indexes are not currently looked up on windows.
+#[cfg(windows)]
+fn interface_addr_v4(_: u32) -> io::Result<Ipv4Addr> {
+    Err(io::Error::new(ErrorKind::Other, "Interface Indexes Can Not Be Found On Windows"))
+}
+
+/// Find an IPv6 address of the local network interface with the given index,
+/// preferring a link-local address if link_local is set and any other otherwise.
+#[cfg(not(windows))]
+fn interface_addr_v6(index: u32, link_local: bool) -> Option<SocketAddrV6> {
+    let addrs: Vec<SocketAddrV6> = match interface_addrs(index) {
+        Ok(addrs) => {
+            addrs.into_iter()
+                .filter_map(|addr| match addr {
+                    SocketAddr::V6(n) => Some(n),
+                    _ => None,
+                })
+                .collect()
+        }
+        Err(_) => return None,
+    };
+
+    addrs.iter()
+        .find(|addr| is_link_local_v6(&IpAddr::V6(*addr.ip())) == link_local)
+        .or_else(|| addrs.first())
+        .cloned()
+}
+
+/// Find an IPv6 address of the local network interface with the given index.
+///
+/// Interface indexes are not currently looked up on windows.
+#[cfg(windows)]
+fn interface_addr_v6(_: u32, _: bool) -> Option<SocketAddrV6> {
+    None
+}
+
+/// Local address of the interface that received a packet with the destination,
+/// with the given port, which replies to the packet should be sent from.
+///
+/// Returns None if the address could not be found.
+pub fn receiving_addr(dst: &PacketDst, port: u16) -> Option<SocketAddr> {
+    match (dst.addr(), dst.local()) {
+        (IpAddr::V4(_), Some(local)) if !local.is_unspecified() => Some(SocketAddr::new(local, port)),
+        (IpAddr::V4(_), _) => {
+            interface_addr_v4(dst.index()).ok().map(|ip| SocketAddr::new(IpAddr::V4(ip), port))
+        }
+        (IpAddr::V6(ip), _) if !ip.is_multicast() => {
+            let scope_id = if is_link_local_v6(&IpAddr::V6(ip)) { dst.index() } else { 0 };
+
+            Some(SocketAddr::V6(SocketAddrV6::new(ip, port, 0, scope_id)))
+        }
+        (IpAddr::V6(group), _) => {
+            // Link-local groups are only reachable through link-local addresses
+            let link_local = group.segments()[0] & 0x000f == 0x2;
+
+            interface_addr_v6(dst.index(), link_local).map(|mut addr| {
+                addr.set_port(port);
+                addr.set_scope_id(if is_link_local_v6(&IpAddr::V6(*addr.ip())) { dst.index() } else { 0 });
+
+                SocketAddr::V6(addr)
+            })
+        }
+    }
+}
+
+/// Selects the options that let a socket share its local address:port with the
+/// sockets of other applications, such as other SSDP stacks on the same host.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum SocketReuse {
+    /// Do not share the address:port, binding fails if it is already in use.
+    Exclusive,
+    /// Set `SO_REUSEADDR` on the socket.
+    Address,
+    /// Set `SO_REUSEADDR` and, where supported, `SO_REUSEPORT` on the socket.
+    AddressAndPort,
+}
+
+impl Default for SocketReuse {
+    fn default() -> Self {
+        SocketReuse::AddressAndPort
+    }
+}
+
+/// Raw handle of a socket, a `RawFd` on unix and a `RawSocket` on windows.
+#[cfg(not(windows))]
+pub type RawSocketHandle = RawFd;
+/// Raw handle of a socket, a `RawFd` on unix and a `RawSocket` on windows.
+#[cfg(windows)]
+pub type RawSocketHandle = RawSocket;
+
+/// Callback that is handed each socket after it is created but before it is bound
+/// or joins any multicast groups, so that options the crate does not wrap can be set.
+///
+/// The socket remains owned by the crate, the callback must not close the handle.
+#[derive(Clone)]
+pub struct SocketHook(Arc<Fn(RawSocketHandle) -> io::Result<()> + Send + Sync>);
+
+impl SocketHook {
+    /// Construct a new SocketHook from the callback.
+    ///
+    /// An error returned by the callback fails the creation of the socket.
+    pub fn new<F>(callback: F) -> SocketHook
+        where F: Fn(RawSocketHandle) -> io::Result<()> + Send + Sync + 'static
+    {
+        SocketHook(Arc::new(callback))
+    }
+
+    /// Invoke the callback on the socket.
+    pub fn call(&self, handle: RawSocketHandle) -> io::Result<()> {
+        (self.0)(handle)
+    }
+}
+
+impl Debug for SocketHook {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str("SocketHook")
+    }
+}
+
+/// Bind to a `UdpSocket`, setting the reuse options and invoking the hook on the
+/// underlying socket before binding.
+pub fn bind_with_hook<A>(local_addr: A,
+                         reuse: SocketReuse,
+                         hook: Option<&SocketHook>)
+                         -> io::Result<UdpSocket>
+    where A: ToSocketAddrs
+{
     let local_addr = try!(addr_from_trait(local_addr));
 
     let builder = match local_addr {
@@ -49,10 +476,32 @@ pub fn bind_reuse<A: ToSocketAddrs>(local_addr: A) -> io::Result<UdpSocket> {
         SocketAddr::V6(_) => try!(UdpBuilder::new_v6()),
     };
 
-    try!(reuse_port(&builder));
+    match reuse {
+        SocketReuse::Exclusive => (),
+        SocketReuse::Address => {
+            // Allow wildcards + specific to not overlap
+            try!(builder.reuse_address(true));
+        }
+        SocketReuse::AddressAndPort => try!(reuse_port(&builder)),
+    }
+
+    if let Some(hook) = hook {
+        try!(hook.call(raw_handle(&builder)));
+    }
+
     builder.bind(local_addr)
 }
 
+#[cfg(not(windows))]
+fn raw_handle(builder: &UdpBuilder) -> RawSocketHandle {
+    builder.as_raw_fd()
+}
+
+#[cfg(windows)]
+fn raw_handle(builder: &UdpBuilder) -> RawSocketHandle {
+    builder.as_raw_socket()
+}
+
 #[cfg(windows)]
 fn reuse_port(builder: &UdpBuilder) -> io::Result<()> {
     // Allow wildcards + specific to not overlap
@@ -69,11 +518,71 @@ fn reuse_port(builder: &UdpBuilder) -> io::Result<()> {
     Ok(())
 }
 
+/// Bind the `UdpSocket` to the local network device with the given name, such as "eth0",
+/// by setting `SO_BINDTODEVICE` so that it only sends and receives through that device.
+#[cfg(target_os = "linux")]
+pub fn bind_to_device(sock: &UdpSocket, device: &str) -> io::Result<()> {
+    let result = unsafe {
+        libc::setsockopt(sock.as_raw_fd(),
+                         libc::SOL_SOCKET,
+                         libc::SO_BINDTODEVICE,
+                         device.as_ptr() as *const libc::c_void,
+                         device.len() as libc::socklen_t)
+    };
+
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+/// Bind the `UdpSocket` to the local network device with the given name.
+///
+/// Binding to a device is only supported on linux.
+#[cfg(not(target_os = "linux"))]
+pub fn bind_to_device(_: &UdpSocket, _: &str) -> io::Result<()> {
+    Err(io::Error::new(ErrorKind::Other, "Binding To A Device Is Only Supported On Linux"))
+}
+
+/// Set the TOS byte of IPv4 packets, or the traffic class of IPv6 packets, sent
+/// from the `UdpSocket`, which carries the DSCP value in its upper six bits.
+#[cfg(not(windows))]
+pub fn set_tos(sock: &UdpSocket, tos: u8) -> io::Result<()> {
+    let (level, name) = match try!(sock.local_addr()) {
+        SocketAddr::V4(_) => (libc::IPPROTO_IP, libc::IP_TOS),
+        SocketAddr::V6(_) => (libc::IPPROTO_IPV6, libc::IPV6_TCLASS),
+    };
+    let value = tos as libc::c_int;
+
+    let result = unsafe {
+        libc::setsockopt(sock.as_raw_fd(),
+                         level,
+                         name,
+                         &value as *const libc::c_int as *const libc::c_void,
+                         mem::size_of::<libc::c_int>() as libc::socklen_t)
+    };
+
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+/// Set the TOS byte of packets sent from the `UdpSocket`.
+///
+/// Setting the TOS byte is not currently supported on windows.
+#[cfg(windows)]
+pub fn set_tos(_: &UdpSocket, _: u8) -> io::Result<()> {
+    Err(io::Error::new(ErrorKind::Other, "Setting The TOS Is Not Supported On Windows"))
+}
+
 /// Join a multicast address on the current `UdpSocket`.
 pub fn join_multicast(sock: &UdpSocket, iface: &SocketAddr, mcast_addr: &IpAddr) -> io::Result<()> {
     match (iface, mcast_addr) {
         (&SocketAddr::V4(ref i), &IpAddr::V4(ref m)) => sock.join_multicast_v4(m, i.ip()),
-        (&SocketAddr::V6(ref i), &IpAddr::V6(ref m)) => sock.join_multicast_v6(m, i.scope_id()),
+        (&SocketAddr::V6(ref i), &IpAddr::V6(ref m)) => sock.join_multicast_v6(m, scope_id_v6(i)),
         _ => {
             Err(io::Error::new(ErrorKind::InvalidInput,
                                "Multicast And Interface Addresses Are Not The Same Version"))
@@ -81,12 +590,38 @@ pub fn join_multicast(sock: &UdpSocket, iface: &SocketAddr, mcast_addr: &IpAddr)
     }
 }
 
+/// Join a multicast address on the current `UdpSocket` at the local network interface
+/// with the given index, or at the interface chosen by the routing table if it is 0.
+pub fn join_multicast_index(sock: &UdpSocket, mcast_addr: &IpAddr, index: u32) -> io::Result<()> {
+    match *mcast_addr {
+        IpAddr::V4(ref m) => sock.join_multicast_v4(m, &try!(index_addr_v4(index))),
+        IpAddr::V6(ref m) => sock.join_multicast_v6(m, index),
+    }
+}
+
+/// Leave a multicast address on the current `UdpSocket` at the local network interface
+/// with the given index.
+pub fn leave_multicast_index(sock: &UdpSocket, mcast_addr: &IpAddr, index: u32) -> io::Result<()> {
+    match *mcast_addr {
+        IpAddr::V4(ref m) => sock.leave_multicast_v4(m, &try!(index_addr_v4(index))),
+        IpAddr::V6(ref m) => sock.leave_multicast_v6(m, index),
+    }
+}
+
+/// IPv4 address identifying the interface with the index in a membership, where
+/// the unspecified address leaves the choice to the routing table.
+fn index_addr_v4(index: u32) -> io::Result<Ipv4Addr> {
+    match index {
+        0 => Ok(Ipv4Addr::new(0, 0, 0, 0)),
+        n => interface_addr_v4(n),
+    }
+}
+
 /// Leave a multicast address on the current `UdpSocket`.
-#[allow(dead_code)] // TODO: call this from somewhere?
-pub fn leave_multicast(sock: &UdpSocket, iface_addr: &SocketAddr, mcast_addr: &SocketAddr) -> io::Result<()> {
+pub fn leave_multicast(sock: &UdpSocket, iface_addr: &SocketAddr, mcast_addr: &IpAddr) -> io::Result<()> {
     match (iface_addr, mcast_addr) {
-        (&SocketAddr::V4(ref i), &SocketAddr::V4(ref m)) => sock.leave_multicast_v4(m.ip(), i.ip()),
-        (&SocketAddr::V6(ref i), &SocketAddr::V6(ref m)) => sock.leave_multicast_v6(m.ip(), i.scope_id()),
+        (&SocketAddr::V4(ref i), &IpAddr::V4(ref m)) => sock.leave_multicast_v4(m, i.ip()),
+        (&SocketAddr::V6(ref i), &IpAddr::V6(ref m)) => sock.leave_multicast_v6(m, scope_id_v6(i)),
         _ => {
             Err(io::Error::new(ErrorKind::InvalidInput,
                                "Multicast And Interface Addresses Are Not The Same Version"))
@@ -94,14 +629,191 @@ pub fn leave_multicast(sock: &UdpSocket, iface_addr: &SocketAddr, mcast_addr: &S
     }
 }
 
+/// Scope id of the address, or the index of its local network interface if it
+/// has none, such as for site-local and global addresses.
+pub fn scope_id_v6(addr: &SocketAddrV6) -> u32 {
+    match addr.scope_id() {
+        0 => interface_index(&IpAddr::V6(*addr.ip())).unwrap_or(0),
+        n => n,
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use std::io;
+    use std::net::{Ipv4Addr, SocketAddr, SocketAddrV6};
+    #[cfg(not(windows))]
+    use std::net::UdpSocket;
+    #[cfg(not(windows))]
+    use std::mem::ManuallyDrop;
+    #[cfg(not(windows))]
+    use std::os::unix::io::FromRawFd;
+
+    use super::{InterfaceFilter, LocalSubnet, PacketDst, SocketHook, SocketReuse};
+
+    #[test]
+    fn positive_interface_filter_addrs() {
+        let filter = InterfaceFilter::Addrs(vec!["192.168.0.2".parse().unwrap()]);
+
+        assert!(filter.matches(&"192.168.0.2".parse().unwrap()));
+        assert!(!filter.matches(&"192.168.0.3".parse().unwrap()));
+        assert!(InterfaceFilter::All.matches(&"192.168.0.3".parse().unwrap()));
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn positive_interface_filter_names() {
+        let loopback = "127.0.0.1".parse().unwrap();
+        let name = super::interface_name(&loopback).unwrap();
+
+        assert!(InterfaceFilter::Names(vec![name.clone()]).matches(&loopback));
+        assert!(!InterfaceFilter::ExcludeNames(vec![name]).matches(&loopback));
+        assert!(!InterfaceFilter::Names(vec!["not-an-interface".to_owned()]).matches(&loopback));
+    }
+
+    #[test]
+    fn positive_interface_index() {
+        for addr in super::local_addrs().unwrap() {
+            if let SocketAddr::V6(n) = addr {
+                if n.scope_id() != 0 {
+                    assert_eq!(super::interface_index(&addr.ip()), Some(n.scope_id()));
+                }
+            }
+        }
+
+        assert_eq!(super::interface_index(&"192.0.2.1".parse().unwrap()), None);
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn positive_interface_index_by_name() {
+        let name = super::interface_name(&"127.0.0.1".parse().unwrap()).unwrap();
+        let index = super::interface_index_by_name(&name).unwrap();
+
+        assert_eq!(super::interface_addr_v4(index).unwrap(), "127.0.0.1".parse::<Ipv4Addr>().unwrap());
+        assert_eq!(super::interface_index_by_name("not-an-interface"), None);
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn positive_receiving_addr() {
+        let loopback = "127.0.0.1".parse().unwrap();
+        let index = super::interface_index_by_name(&super::interface_name(&loopback).unwrap()).unwrap();
+
+        let unicast = PacketDst::new(loopback, index).with_local(loopback);
+        assert_eq!(super::receiving_addr(&unicast, 1900), Some("127.0.0.1:1900".parse().unwrap()));
+
+        let multicast = PacketDst::new("239.255.255.250".parse().unwrap(), index);
+        assert_eq!(super::receiving_addr(&multicast, 1900), Some("127.0.0.1:1900".parse().unwrap()));
+
+        let link_local = PacketDst::new("fe80::1".parse().unwrap(), 7);
+        assert_eq!(super::receiving_addr(&link_local, 1900),
+                   Some(SocketAddr::V6(SocketAddrV6::new("fe80::1".parse().unwrap(), 1900, 0, 7))));
+    }
+
+    #[test]
+    fn positive_local_subnet_contains() {
+        let subnet = LocalSubnet::new("192.168.0.2".parse().unwrap(), "255.255.255.0".parse().unwrap());
+
+        assert!(subnet.contains(&"192.168.0.200".parse().unwrap()));
+        assert!(!subnet.contains(&"192.168.1.2".parse().unwrap()));
+        assert!(!subnet.contains(&"fe80::1".parse().unwrap()));
+
+        let subnet = LocalSubnet::new("2001:db8::2".parse().unwrap(),
+                                      "ffff:ffff:ffff:ffff::".parse().unwrap());
+
+        assert!(subnet.contains(&"2001:db8::1234".parse().unwrap()));
+        assert!(!subnet.contains(&"2001:db9::2".parse().unwrap()));
+    }
+
+    #[test]
+    fn positive_is_link_local_v6() {
+        assert!(super::is_link_local_v6(&"fe80::1".parse().unwrap()));
+        assert!(super::is_link_local_v6(&"febf::1".parse().unwrap()));
+        assert!(!super::is_link_local_v6(&"fec0::1".parse().unwrap()));
+        assert!(!super::is_link_local_v6(&"169.254.0.1".parse().unwrap()));
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn positive_bind_with_hook_reuse_shared() {
+        let first = super::bind_with_hook("127.0.0.1:0", SocketReuse::AddressAndPort, None).unwrap();
+        let local_addr = first.local_addr().unwrap();
+
+        assert!(super::bind_with_hook(local_addr, SocketReuse::AddressAndPort, None).is_ok());
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn positive_bind_with_hook() {
+        let hook = SocketHook::new(|fd| {
+            // The socket is closed by the crate, not the hook
+            let sock = ManuallyDrop::new(unsafe { UdpSocket::from_raw_fd(fd) });
+            sock.set_ttl(7)
+        });
+        let sock = super::bind_with_hook("127.0.0.1:0", SocketReuse::Exclusive, Some(&hook)).unwrap();
+
+        assert_eq!(sock.ttl().unwrap(), 7);
+
+        let hook = SocketHook::new(|_| Err(io::Error::new(io::ErrorKind::Other, "Rejected")));
+        assert!(super::bind_with_hook("127.0.0.1:0", SocketReuse::Exclusive, Some(&hook)).is_err());
+    }
+
+    #[test]
+    fn negative_bind_with_hook_reuse_exclusive() {
+        let first = super::bind_with_hook("127.0.0.1:0", SocketReuse::Exclusive, None).unwrap();
+        let local_addr = first.local_addr().unwrap();
+
+        assert!(super::bind_with_hook(local_addr, SocketReuse::Exclusive, None).is_err());
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn positive_bind_to_device() {
+        let sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let name = super::interface_name(&"127.0.0.1".parse().unwrap()).unwrap();
+
+        super::bind_to_device(&sock, &name).unwrap();
+        assert!(super::bind_to_device(&sock, "not-an-interface").is_err());
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn positive_set_tos() {
+        use std::mem;
+        use std::os::unix::io::AsRawFd;
+
+        use libc;
+
+        let sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+        super::set_tos(&sock, 0xb8).unwrap();
+
+        let mut value: libc::c_int = 0;
+        let mut len = mem::size_of::<libc::c_int>() as libc::socklen_t;
+        let result = unsafe {
+            libc::getsockopt(sock.as_raw_fd(),
+                             libc::IPPROTO_IP,
+                             libc::IP_TOS,
+                             &mut value as *mut libc::c_int as *mut libc::c_void,
+                             &mut len)
+        };
+        assert_eq!(result, 0);
+        assert_eq!(value, 0xb8);
+    }
 
     #[test]
     fn positive_addr_from_trait() {
         super::addr_from_trait("192.168.0.1:0").unwrap();
     }
 
+    #[test]
+    fn positive_route_local_addr() {
+        let local_addr = super::route_local_addr(&"127.0.0.1:1900".parse().unwrap()).unwrap();
+
+        assert_eq!(local_addr, "127.0.0.1:0".parse().unwrap());
+    }
+
     #[test]
     #[should_panic]
     fn negative_addr_from_trait() {
@@ -1,37 +1,227 @@
 use std::io::{self, Error, ErrorKind};
-use std::net::{UdpSocket, SocketAddr};
+#[cfg(target_os = "linux")]
+use std::mem;
+use std::net::{UdpSocket, SocketAddr, IpAddr};
+#[cfg(target_os = "linux")]
+use std::net::{SocketAddrV4, SocketAddrV6, Ipv4Addr, Ipv6Addr};
+#[cfg(target_os = "linux")]
+use std::os::unix::io::{AsRawFd, RawFd};
+#[cfg(target_os = "linux")]
+use std::ptr;
 use std::fmt;
+use std::time::Duration;
+
+#[cfg(target_os = "linux")]
+use libc;
 
 /// Maximum length for packets received on a `PacketReceiver`.
 pub const MAX_PCKT_LEN: usize = 600;
 
+/// Destination of a received packet, as reported by the operating system.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct PacketDst {
+    addr: IpAddr,
+    local: Option<IpAddr>,
+    index: u32,
+}
+
+impl PacketDst {
+    /// Construct a PacketDst for a packet sent to the address, which was received
+    /// by the local network interface with the given index.
+    pub fn new(addr: IpAddr, index: u32) -> PacketDst {
+        PacketDst {
+            addr: addr,
+            local: None,
+            index: index,
+        }
+    }
+
+    /// Attach the local address of the interface that received the packet.
+    pub fn with_local(mut self, local: IpAddr) -> PacketDst {
+        self.local = Some(local);
+        self
+    }
+
+    /// Destination address of the packet, which is the group for multicast packets.
+    pub fn addr(&self) -> IpAddr {
+        self.addr
+    }
+
+    /// Local address of the interface that received the packet, if it was reported.
+    pub fn local(&self) -> Option<IpAddr> {
+        self.local
+    }
+
+    /// Index of the local network interface that received the packet.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+}
+
+/// Ask the operating system to report the destination of packets received on
+/// the `UdpSocket`, with `IP_PKTINFO` or `IPV6_RECVPKTINFO`.
+#[cfg(target_os = "linux")]
+pub fn set_recv_dst(sock: &UdpSocket) -> io::Result<()> {
+    let (level, name) = match try!(sock.local_addr()) {
+        SocketAddr::V4(_) => (libc::IPPROTO_IP, libc::IP_PKTINFO),
+        SocketAddr::V6(_) => (libc::IPPROTO_IPV6, libc::IPV6_RECVPKTINFO),
+    };
+    let value: libc::c_int = 1;
+
+    let result = unsafe {
+        libc::setsockopt(sock.as_raw_fd(),
+                         level,
+                         name,
+                         &value as *const libc::c_int as *const libc::c_void,
+                         mem::size_of::<libc::c_int>() as libc::socklen_t)
+    };
+
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+/// Ask the operating system to report the destination of packets received on the `UdpSocket`.
+///
+/// Packet destinations are only reported on linux.
+#[cfg(not(target_os = "linux"))]
+pub fn set_recv_dst(_: &UdpSocket) -> io::Result<()> {
+    Err(Error::new(ErrorKind::Other, "Packet Destinations Are Only Reported On Linux"))
+}
+
+/// Receive a packet from the `UdpSocket`, along with its destination if the
+/// socket was set up with `set_recv_dst`.
+#[cfg(target_os = "linux")]
+pub fn recv_with_dst(sock: &UdpSocket, buf: &mut [u8]) -> io::Result<(usize, SocketAddr, Option<PacketDst>)> {
+    recv_fd_with_dst(sock.as_raw_fd(), buf)
+}
+
+/// Receive a packet from the `UdpSocket`.
+///
+/// Packet destinations are only reported on linux.
+#[cfg(not(target_os = "linux"))]
+pub fn recv_with_dst(sock: &UdpSocket, buf: &mut [u8]) -> io::Result<(usize, SocketAddr, Option<PacketDst>)> {
+    sock.recv_from(buf).map(|(size, addr)| (size, addr, None))
+}
+
+/// Receive a packet from the socket with the given file descriptor using `recvmsg`,
+/// along with its destination if the socket was set up with `set_recv_dst`.
+#[cfg(target_os = "linux")]
+pub fn recv_fd_with_dst(fd: RawFd, buf: &mut [u8]) -> io::Result<(usize, SocketAddr, Option<PacketDst>)> {
+    let mut src: libc::sockaddr_storage = unsafe { mem::zeroed() };
+    let mut iov = libc::iovec {
+        iov_base: buf.as_mut_ptr() as *mut libc::c_void,
+        iov_len: buf.len(),
+    };
+    // Aligned and large enough for either an in_pktinfo or an in6_pktinfo message
+    let mut control = [0u64; 8];
+
+    let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+    msg.msg_name = &mut src as *mut libc::sockaddr_storage as *mut libc::c_void;
+    msg.msg_namelen = mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+    msg.msg_controllen = mem::size_of_val(&control) as _;
+
+    let size = unsafe { libc::recvmsg(fd, &mut msg, 0) };
+    if size < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let src_addr = try!(sockaddr_to_addr(&src));
+
+    let mut opt_dst = None;
+    let mut cmsg = unsafe { libc::CMSG_FIRSTHDR(&msg) };
+    while !cmsg.is_null() {
+        let header = unsafe { ptr::read_unaligned(cmsg) };
+
+        match (header.cmsg_level, header.cmsg_type) {
+            (libc::IPPROTO_IP, libc::IP_PKTINFO) => {
+                let info = unsafe { ptr::read_unaligned(libc::CMSG_DATA(cmsg) as *const libc::in_pktinfo) };
+                let addr = Ipv4Addr::from(u32::from_be(info.ipi_addr.s_addr));
+                let local = Ipv4Addr::from(u32::from_be(info.ipi_spec_dst.s_addr));
+
+                let dst = PacketDst::new(IpAddr::V4(addr), info.ipi_ifindex as u32);
+                opt_dst = Some(dst.with_local(IpAddr::V4(local)));
+            }
+            (libc::IPPROTO_IPV6, libc::IPV6_PKTINFO) => {
+                let info = unsafe { ptr::read_unaligned(libc::CMSG_DATA(cmsg) as *const libc::in6_pktinfo) };
+                let addr = Ipv6Addr::from(info.ipi6_addr.s6_addr);
+
+                opt_dst = Some(PacketDst::new(IpAddr::V6(addr), info.ipi6_ifindex as u32));
+            }
+            _ => (),
+        }
+
+        cmsg = unsafe { libc::CMSG_NXTHDR(&msg, cmsg) };
+    }
+
+    Ok((size as usize, src_addr, opt_dst))
+}
+
+/// Convert a socket address filled in by the operating system.
+#[cfg(target_os = "linux")]
+fn sockaddr_to_addr(storage: &libc::sockaddr_storage) -> io::Result<SocketAddr> {
+    match storage.ss_family as libc::c_int {
+        libc::AF_INET => {
+            let addr = unsafe { &*(storage as *const libc::sockaddr_storage as *const libc::sockaddr_in) };
+            let ip = Ipv4Addr::from(u32::from_be(addr.sin_addr.s_addr));
+
+            Ok(SocketAddr::V4(SocketAddrV4::new(ip, u16::from_be(addr.sin_port))))
+        }
+        libc::AF_INET6 => {
+            let addr = unsafe { &*(storage as *const libc::sockaddr_storage as *const libc::sockaddr_in6) };
+            let ip = Ipv6Addr::from(addr.sin6_addr.s6_addr);
+
+            Ok(SocketAddr::V6(SocketAddrV6::new(ip,
+                                                u16::from_be(addr.sin6_port),
+                                                addr.sin6_flowinfo,
+                                                addr.sin6_scope_id)))
+        }
+        _ => Err(Error::new(ErrorKind::InvalidData, "Received Packet From An Unknown Address Family")),
+    }
+}
+
 /// A `PacketReceiver` that abstracts over a network socket and reads full packets
 /// from the connection. Packets received from this connection are assumed to
 /// be no larger than what the typical MTU would be on a standard router.
 ///
 /// See `net::packet::MAX_PCKT_LEN`.
+#[cfg_attr(feature = "mio", allow(dead_code))] // Sockets are read directly when polling
 pub struct PacketReceiver(UdpSocket);
 
+#[cfg_attr(feature = "mio", allow(dead_code))]
 impl PacketReceiver {
     /// Create a new PacketReceiver from the given UdpSocket.
     pub fn new(udp: UdpSocket) -> PacketReceiver {
         PacketReceiver(udp)
     }
 
-    /// Receive a packet from the underlying connection.
-    pub fn recv_pckt(&self) -> io::Result<(Vec<u8>, SocketAddr)> {
-        let mut pckt_buf = vec![0u8; MAX_PCKT_LEN];
+    /// Set the timeout for receiving a packet from the underlying connection.
+    pub fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.0.set_read_timeout(dur)
+    }
+
+    /// Ask the operating system to report the destination of received packets.
+    pub fn set_recv_dst(&self) -> io::Result<()> {
+        set_recv_dst(&self.0)
+    }
 
-        let (size, addr) = try!(self.0.recv_from(&mut pckt_buf));
+    /// Receive a packet from the underlying connection into the buffer, along with
+    /// its destination if it was reported.
+    ///
+    /// Returns the number of bytes of the packet written to the buffer.
+    pub fn recv_pckt(&self, pckt_buf: &mut [u8]) -> io::Result<(usize, SocketAddr, Option<PacketDst>)> {
+        let (size, addr, opt_dst) = try!(recv_with_dst(&self.0, pckt_buf));
 
         // Check For Something That SHOULD NEVER Occur.
         if size > pckt_buf.len() {
             Err(Error::new(ErrorKind::Other, "UdpSocket Reported Receive Length Greater Than Buffer"))
         } else {
-            // `truncate` does not reallocate the vec's backing storage
-            pckt_buf.truncate(size);
-
-            Ok((pckt_buf, addr))
+            Ok((size, addr, opt_dst))
         }
     }
 }
@@ -44,3 +234,51 @@ impl fmt::Display for PacketReceiver {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::net::UdpSocket;
+
+    use super::MAX_PCKT_LEN;
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn positive_recv_with_dst() {
+        use std::net::IpAddr;
+
+        use net;
+
+        let recv_sock = UdpSocket::bind("0.0.0.0:0").unwrap();
+        let send_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let loopback: IpAddr = "127.0.0.1".parse().unwrap();
+        let recv_port = recv_sock.local_addr().unwrap().port();
+
+        super::set_recv_dst(&recv_sock).unwrap();
+        send_sock.send_to(b"packet", (loopback, recv_port)).unwrap();
+
+        let mut buf = [0u8; MAX_PCKT_LEN];
+        let (size, src_addr, opt_dst) = super::recv_with_dst(&recv_sock, &mut buf).unwrap();
+        let dst = opt_dst.unwrap();
+
+        assert_eq!(&buf[..size], b"packet");
+        assert_eq!(src_addr, send_sock.local_addr().unwrap());
+        assert_eq!(dst.addr(), loopback);
+        assert_eq!(dst.local(), Some(loopback));
+        assert_eq!(net::interface_name_by_index(dst.index()), net::interface_name(&loopback));
+    }
+
+    #[test]
+    fn positive_recv_without_dst() {
+        let recv_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let send_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+        send_sock.send_to(b"packet", recv_sock.local_addr().unwrap()).unwrap();
+
+        let mut buf = [0u8; MAX_PCKT_LEN];
+        let (size, src_addr, opt_dst) = super::recv_with_dst(&recv_sock, &mut buf).unwrap();
+
+        assert_eq!(&buf[..size], b"packet");
+        assert_eq!(src_addr, send_sock.local_addr().unwrap());
+        assert_eq!(opt_dst, None);
+    }
+}
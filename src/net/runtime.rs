@@ -0,0 +1,167 @@
+//! Abstractions over the asynchronous runtimes that can drive an `SSDPStream`.
+
+use std::future::Future;
+use std::io;
+use std::net::{UdpSocket, SocketAddr};
+#[cfg(all(feature = "tokio", target_os = "linux"))]
+use std::os::unix::io::AsRawFd;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+#[cfg(feature = "async-io")]
+use async_io::{Async, Timer};
+#[cfg(all(feature = "tokio", target_os = "linux"))]
+use tokio::io::Interest;
+#[cfg(feature = "tokio")]
+use tokio::io::ReadBuf;
+#[cfg(feature = "tokio")]
+use tokio::time::{self, Sleep};
+
+#[cfg(any(feature = "async-io", all(feature = "tokio", target_os = "linux")))]
+use net::packet;
+use net::packet::PacketDst;
+
+/// Trait for a reactor that is able to drive non-blocking `UdpSocket`s and timers.
+///
+/// Implementations should not spawn any threads of their own, all work is done
+/// from within the task polling the `SSDPStream`.
+pub trait Runtime {
+    /// Socket registered with the reactor.
+    type Socket;
+    /// Timer registered with the reactor.
+    type Timer;
+
+    /// Register the given `UdpSocket` with the reactor.
+    fn socket(sock: UdpSocket) -> io::Result<Self::Socket>;
+
+    /// Create a timer that will fire once after the given duration.
+    fn timer(dur: Duration) -> Self::Timer;
+
+    /// Poll the timer, returning ready once it has fired.
+    fn poll_timer(timer: &mut Self::Timer, cx: &mut Context) -> Poll<()>;
+
+    /// Poll the socket for a single packet, writing the packet into the buffer.
+    fn poll_recv_from(sock: &Self::Socket,
+                      cx: &mut Context,
+                      buf: &mut [u8])
+                      -> Poll<io::Result<(usize, SocketAddr)>>;
+
+    /// Poll the socket for a single packet like `poll_recv_from`, also returning
+    /// the destination of the packet if it was reported.
+    ///
+    /// By default, the destination is never reported.
+    fn poll_recv_dst(sock: &Self::Socket,
+                     cx: &mut Context,
+                     buf: &mut [u8])
+                     -> Poll<io::Result<(usize, SocketAddr, Option<PacketDst>)>> {
+        Self::poll_recv_from(sock, cx, buf).map(|result| result.map(|(size, addr)| (size, addr, None)))
+    }
+}
+
+/// Runtime backed by the tokio reactor.
+///
+/// Sockets and timers must be created from within the context of a tokio runtime.
+#[cfg(feature = "tokio")]
+pub enum Tokio {}
+
+#[cfg(feature = "tokio")]
+impl Runtime for Tokio {
+    type Socket = ::tokio::net::UdpSocket;
+    type Timer = Pin<Box<Sleep>>;
+
+    fn socket(sock: UdpSocket) -> io::Result<Self::Socket> {
+        try!(sock.set_nonblocking(true));
+
+        ::tokio::net::UdpSocket::from_std(sock)
+    }
+
+    fn timer(dur: Duration) -> Self::Timer {
+        Box::pin(time::sleep(dur))
+    }
+
+    fn poll_timer(timer: &mut Self::Timer, cx: &mut Context) -> Poll<()> {
+        timer.as_mut().poll(cx)
+    }
+
+    fn poll_recv_from(sock: &Self::Socket,
+                      cx: &mut Context,
+                      buf: &mut [u8])
+                      -> Poll<io::Result<(usize, SocketAddr)>> {
+        let mut read_buf = ReadBuf::new(buf);
+
+        match sock.poll_recv_from(cx, &mut read_buf) {
+            Poll::Ready(Ok(addr)) => Poll::Ready(Ok((read_buf.filled().len(), addr))),
+            Poll::Ready(Err(err)) => Poll::Ready(Err(err)),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn poll_recv_dst(sock: &Self::Socket,
+                     cx: &mut Context,
+                     buf: &mut [u8])
+                     -> Poll<io::Result<(usize, SocketAddr, Option<PacketDst>)>> {
+        loop {
+            match sock.poll_recv_ready(cx) {
+                Poll::Ready(Ok(())) => (),
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                Poll::Pending => return Poll::Pending,
+            }
+
+            // Readiness is cleared when the socket turns out to be drained
+            match sock.try_io(Interest::READABLE, || packet::recv_fd_with_dst(sock.as_raw_fd(), buf)) {
+                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => (),
+                result => return Poll::Ready(result),
+            }
+        }
+    }
+}
+
+/// Runtime backed by the async-io reactor, which is shared by async-std and smol.
+#[cfg(feature = "async-io")]
+pub enum AsyncIo {}
+
+#[cfg(feature = "async-io")]
+impl Runtime for AsyncIo {
+    type Socket = Async<UdpSocket>;
+    type Timer = Timer;
+
+    fn socket(sock: UdpSocket) -> io::Result<Self::Socket> {
+        Async::new(sock)
+    }
+
+    fn timer(dur: Duration) -> Self::Timer {
+        Timer::after(dur)
+    }
+
+    fn poll_timer(timer: &mut Self::Timer, cx: &mut Context) -> Poll<()> {
+        Pin::new(timer).poll(cx).map(|_| ())
+    }
+
+    fn poll_recv_from(sock: &Self::Socket,
+                      cx: &mut Context,
+                      buf: &mut [u8])
+                      -> Poll<io::Result<(usize, SocketAddr)>> {
+        Self::poll_recv_dst(sock, cx, buf).map(|result| result.map(|(size, addr, _)| (size, addr)))
+    }
+
+    fn poll_recv_dst(sock: &Self::Socket,
+                     cx: &mut Context,
+                     buf: &mut [u8])
+                     -> Poll<io::Result<(usize, SocketAddr, Option<PacketDst>)>> {
+        loop {
+            match packet::recv_with_dst(sock.get_ref(), buf) {
+                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => (),
+                result => return Poll::Ready(result),
+            }
+
+            // Socket was drained, wait for the reactor to tell us there is more
+            match sock.poll_readable(cx) {
+                Poll::Ready(Ok(())) => (),
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
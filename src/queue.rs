@@ -0,0 +1,308 @@
+//! Channel used to hand messages from the receiver threads to consumers.
+
+use std::collections::VecDeque;
+use std::io;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::sync::mpsc::{TryRecvError, RecvError, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+/// Policy applied when a message arrives at a bounded receiver that is full.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Discard the oldest queued message to make room for the new one.
+    DropOldest,
+    /// Discard the new message, keeping the queued messages intact.
+    DropNewest,
+    /// Block the receiver thread until the consumer makes room.
+    ///
+    /// While blocked, packets will back up in the socket buffers where the
+    /// operating system will eventually start dropping them.
+    Block,
+}
+
+struct State<T> {
+    items: VecDeque<T>,
+    capacity: Option<usize>,
+    policy: OverflowPolicy,
+    senders: usize,
+    receiver: bool,
+}
+
+struct Shared<T> {
+    state: Mutex<State<T>>,
+    not_empty: Condvar,
+    not_full: Condvar,
+}
+
+impl<T> Shared<T> {
+    fn lock<'a>(&'a self) -> MutexGuard<'a, State<T>> {
+        // No user code runs while the lock is held, recover from poisoning
+        match self.state.lock() {
+            Ok(n) => n,
+            Err(err) => err.into_inner(),
+        }
+    }
+}
+
+/// Check that a channel bounded at the given capacity could hold a message.
+pub fn check_capacity(capacity: Option<usize>) -> io::Result<()> {
+    match capacity {
+        Some(0) => Err(io::Error::new(io::ErrorKind::InvalidInput, "Queue Capacity Must Be Nonzero")),
+        _ => Ok(()),
+    }
+}
+
+/// Create a channel, bounded at the given capacity if one is provided.
+pub fn channel<T>(capacity: Option<usize>, policy: OverflowPolicy) -> (QueueSender<T>, QueueReceiver<T>) {
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            items: VecDeque::new(),
+            capacity: capacity,
+            policy: policy,
+            senders: 1,
+            receiver: true,
+        }),
+        not_empty: Condvar::new(),
+        not_full: Condvar::new(),
+    });
+
+    (QueueSender { shared: shared.clone() }, QueueReceiver { shared: shared })
+}
+
+/// Sending half of a channel.
+pub struct QueueSender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> QueueSender<T> {
+    /// Send a message on the channel, applying the overflow policy if it is full.
+    ///
+    /// Returns the message if the receiving half has hung up.
+    pub fn send(&self, msg: T) -> Result<(), T> {
+        let mut state = self.shared.lock();
+
+        loop {
+            if !state.receiver {
+                return Err(msg);
+            }
+
+            match state.capacity {
+                Some(capacity) if state.items.len() >= capacity => {
+                    match state.policy {
+                        OverflowPolicy::DropOldest => {
+                            trace!("Receiver queue is full, dropping oldest message");
+                            state.items.pop_front();
+                            break;
+                        }
+                        OverflowPolicy::DropNewest => {
+                            trace!("Receiver queue is full, dropping newest message");
+                            return Ok(());
+                        }
+                        OverflowPolicy::Block => {
+                            state = match self.shared.not_full.wait(state) {
+                                Ok(n) => n,
+                                Err(err) => err.into_inner(),
+                            };
+                        }
+                    }
+                }
+                _ => break,
+            }
+        }
+
+        // Zero capacity with drop oldest has nothing to pop, nothing to queue either
+        if state.capacity != Some(0) {
+            state.items.push_back(msg);
+            self.shared.not_empty.notify_one();
+        }
+
+        Ok(())
+    }
+}
+
+impl<T> Clone for QueueSender<T> {
+    fn clone(&self) -> QueueSender<T> {
+        self.shared.lock().senders += 1;
+
+        QueueSender { shared: self.shared.clone() }
+    }
+}
+
+impl<T> Drop for QueueSender<T> {
+    fn drop(&mut self) {
+        let mut state = self.shared.lock();
+
+        state.senders -= 1;
+        if state.senders == 0 {
+            self.shared.not_empty.notify_all();
+        }
+    }
+}
+
+/// Receiving half of a channel.
+pub struct QueueReceiver<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> QueueReceiver<T> {
+    /// Non-blocking method that attempts to read a message from the channel.
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        let mut state = self.shared.lock();
+
+        match self.pop(&mut state) {
+            Some(n) => Ok(n),
+            None if state.senders == 0 => Err(TryRecvError::Disconnected),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+
+    /// Blocking method that reads a message from the channel until one is available.
+    pub fn recv(&self) -> Result<T, RecvError> {
+        let mut state = self.shared.lock();
+
+        loop {
+            if let Some(n) = self.pop(&mut state) {
+                return Ok(n);
+            } else if state.senders == 0 {
+                return Err(RecvError);
+            }
+
+            state = match self.shared.not_empty.wait(state) {
+                Ok(n) => n,
+                Err(err) => err.into_inner(),
+            };
+        }
+    }
+
+    /// Blocking method that reads a message from the channel until one is
+    /// available or the timeout elapses.
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.shared.lock();
+
+        loop {
+            if let Some(n) = self.pop(&mut state) {
+                return Ok(n);
+            } else if state.senders == 0 {
+                return Err(RecvTimeoutError::Disconnected);
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(RecvTimeoutError::Timeout);
+            }
+
+            state = match self.shared.not_empty.wait_timeout(state, deadline - now) {
+                Ok((n, _)) => n,
+                Err(err) => err.into_inner().0,
+            };
+        }
+    }
+
+    /// Hang up the channel, discarding any queued messages and waking up any
+    /// senders blocked on a full channel.
+    pub fn close(&self) {
+        let mut state = self.shared.lock();
+
+        state.receiver = false;
+        state.items.clear();
+        self.shared.not_full.notify_all();
+    }
+
+    fn pop(&self, state: &mut State<T>) -> Option<T> {
+        let opt_item = state.items.pop_front();
+
+        if opt_item.is_some() {
+            self.shared.not_full.notify_one();
+        }
+
+        opt_item
+    }
+}
+
+impl<T> Drop for QueueReceiver<T> {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::{TryRecvError, RecvTimeoutError};
+    use std::thread;
+    use std::time::Duration;
+
+    use super::{channel, OverflowPolicy};
+
+    #[test]
+    fn positive_unbounded() {
+        let (send, recv) = channel(None, OverflowPolicy::Block);
+
+        for i in 0..100 {
+            send.send(i).unwrap();
+        }
+
+        assert_eq!((0..100).map(|_| recv.try_recv().unwrap()).collect::<Vec<_>>(),
+                   (0..100).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn positive_drop_oldest() {
+        let (send, recv) = channel(Some(2), OverflowPolicy::DropOldest);
+
+        send.send(1).unwrap();
+        send.send(2).unwrap();
+        send.send(3).unwrap();
+
+        assert_eq!(recv.try_recv(), Ok(2));
+        assert_eq!(recv.try_recv(), Ok(3));
+        assert_eq!(recv.try_recv(), Err(TryRecvError::Empty));
+    }
+
+    #[test]
+    fn positive_drop_newest() {
+        let (send, recv) = channel(Some(2), OverflowPolicy::DropNewest);
+
+        send.send(1).unwrap();
+        send.send(2).unwrap();
+        send.send(3).unwrap();
+
+        assert_eq!(recv.try_recv(), Ok(1));
+        assert_eq!(recv.try_recv(), Ok(2));
+        assert_eq!(recv.try_recv(), Err(TryRecvError::Empty));
+    }
+
+    #[test]
+    fn positive_block_until_room() {
+        let (send, recv) = channel(Some(1), OverflowPolicy::Block);
+
+        send.send(1).unwrap();
+        let handle = thread::spawn(move || send.send(2));
+
+        assert_eq!(recv.recv_timeout(Duration::from_secs(5)), Ok(1));
+        assert_eq!(recv.recv_timeout(Duration::from_secs(5)), Ok(2));
+        assert!(handle.join().unwrap().is_ok());
+    }
+
+    #[test]
+    fn positive_close_unblocks_sender() {
+        let (send, recv) = channel(Some(1), OverflowPolicy::Block);
+
+        send.send(1).unwrap();
+        let handle = thread::spawn(move || send.send(2));
+
+        thread::sleep(Duration::from_millis(50));
+        recv.close();
+
+        assert_eq!(handle.join().unwrap(), Err(2));
+    }
+
+    #[test]
+    fn positive_sender_hang_up() {
+        let (send, recv) = channel::<u8>(None, OverflowPolicy::Block);
+
+        drop(send);
+
+        assert_eq!(recv.recv_timeout(Duration::from_secs(5)), Err(RecvTimeoutError::Disconnected));
+    }
+}
@@ -1,112 +1,1251 @@
 //! Primitives for non-blocking SSDP message receiving.
 
 use std::io;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
 use std::result::Result;
 use std::thread;
-use std::sync::mpsc::{self, Receiver, Sender, TryRecvError, RecvError, Iter};
-use std::net::{UdpSocket, SocketAddr};
-use std::time::Duration;
+use std::cell::{Cell, RefCell};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex, Weak};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{TryRecvError, RecvError, RecvTimeoutError};
+use std::net::{UdpSocket, SocketAddr, IpAddr};
+use std::time::{Duration, Instant, SystemTime};
+#[cfg(all(feature = "mio", target_os = "linux"))]
+use std::os::unix::io::AsRawFd;
+#[cfg(feature = "futures")]
+use std::pin::Pin;
+#[cfg(feature = "futures")]
+use std::task::{Context, Poll};
 
-use SSDPResult;
+#[cfg(feature = "futures")]
+use futures_channel::mpsc::{self as async_mpsc, UnboundedReceiver};
+#[cfg(feature = "futures")]
+use futures_core::Stream;
+
+#[cfg(feature = "mio")]
+use mio::{Events, Interest, Token, Waker};
+
+use {SSDPError, SSDPResult};
+use header::HeaderRef;
+use message::{MessageRef, ParseMode, Quirks};
+use queue::{self, OverflowPolicy, QueueSender, QueueReceiver};
+use net;
+use net::packet::{PacketDst, MAX_PCKT_LEN};
+#[cfg(feature = "mio")]
+use net::packet;
+#[cfg(not(feature = "mio"))]
 use net::packet::PacketReceiver;
 
-/// Trait for constructing an object from some serialized SSDP message.
-pub trait FromRawSSDP: Sized {
-    fn raw_ssdp(bytes: &[u8]) -> SSDPResult<Self>;
-}
+/// Interval at which receiver threads check if they have been shutdown.
+#[cfg(not(feature = "mio"))]
+const SHUTDOWN_POLL_INTERVAL_MS: u64 = 100;
+
+/// Interval at which stream forwarding threads check if the stream has been dropped.
+#[cfg(feature = "futures")]
+const STREAM_POLL_INTERVAL_MS: u64 = 100;
+
+/// Trait for constructing an object from some serialized SSDP message.
+pub trait FromRawSSDP: Sized {
+    fn raw_ssdp(bytes: &[u8]) -> SSDPResult<Self>;
+
+    /// Construct the object from a serialized SSDP message using the given parse mode.
+    fn raw_ssdp_with_mode(bytes: &[u8], mode: ParseMode) -> SSDPResult<Self> {
+        Self::raw_ssdp_with_quirks(bytes, mode.quirks())
+    }
+
+    /// Construct the object from a serialized SSDP message, tolerating only the given quirks.
+    ///
+    /// `raw_ssdp` tolerates every quirk, so by default the message is first checked
+    /// for the quirks that are not tolerated.
+    fn raw_ssdp_with_quirks(bytes: &[u8], quirks: Quirks) -> SSDPResult<Self> {
+        if quirks != Quirks::all() {
+            try!(MessageRef::parse_with_quirks(bytes, quirks));
+        }
+
+        Self::raw_ssdp(bytes)
+    }
+
+    /// Attach metadata about how the message was received, ignored by default.
+    fn set_receive_info(&mut self, _info: ReceiveInfo) {}
+}
+
+/// Metadata about where and when a message was received.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ReceiveInfo {
+    local_addr: SocketAddr,
+    interface: Option<Arc<str>>,
+    dst_addr: Option<IpAddr>,
+    interface_index: Option<u32>,
+    received_at: SystemTime,
+    bytes: Arc<[u8]>,
+}
+
+impl ReceiveInfo {
+    /// Construct a ReceiveInfo for a message received right now.
+    pub fn new(local_addr: SocketAddr, interface: Option<String>) -> ReceiveInfo {
+        ReceiveInfo {
+            local_addr: local_addr,
+            interface: interface.map(Arc::from),
+            dst_addr: None,
+            interface_index: None,
+            received_at: SystemTime::now(),
+            bytes: Arc::from(&[][..]),
+        }
+    }
+
+    /// Construct a ReceiveInfo for a message received right now by the socket
+    /// bound to the local address, with the destination reported for the packet.
+    ///
+    /// For sockets bound to an unspecified address, the local address and interface
+    /// are those of the interface that received the packet.
+    fn received(local_addr: SocketAddr,
+                interface: &Option<Arc<str>>,
+                opt_dst: Option<PacketDst>)
+                -> ReceiveInfo {
+        let mut info = ReceiveInfo::new(local_addr, None);
+        info.interface = interface.clone();
+
+        if let Some(dst) = opt_dst {
+            info.dst_addr = Some(dst.addr());
+            info.interface_index = Some(dst.index());
+
+            if local_addr.ip().is_unspecified() {
+                info.local_addr = net::receiving_addr(&dst, local_addr.port()).unwrap_or(local_addr);
+                info.interface = net::interface_name_by_index(dst.index()).map(Arc::from);
+            }
+        }
+
+        info
+    }
+
+    /// Attach the original bytes of the datagram the message was parsed from.
+    ///
+    /// The bytes are shared, not copied, between clones of the ReceiveInfo.
+    pub fn with_bytes<B>(mut self, bytes: B) -> ReceiveInfo
+        where B: Into<Arc<[u8]>>
+    {
+        self.bytes = bytes.into();
+        self
+    }
+
+    /// Local address of the socket that received the message.
+    ///
+    /// For sockets bound to an unspecified address, this is the address of the
+    /// interface that received the message where the platform reports it, which
+    /// is currently only linux, and the unspecified address otherwise.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Name of the local network interface that received the message, such as "eth0".
+    ///
+    /// Returns None if the interface could not be found, which is the case for
+    /// sockets bound to an unspecified address where the platform does not
+    /// report the receiving interface.
+    pub fn interface(&self) -> Option<&str> {
+        self.interface.as_ref().map(|name| &name[..])
+    }
+
+    /// Index of the local network interface that received the message, if the
+    /// platform reported it.
+    pub fn interface_index(&self) -> Option<u32> {
+        self.interface_index
+    }
+
+    /// Destination address of the datagram the message was parsed from, which is
+    /// the group for multicast messages, if the platform reported it.
+    pub fn dst_addr(&self) -> Option<IpAddr> {
+        self.dst_addr
+    }
+
+    /// Time at which the message was received.
+    pub fn received_at(&self) -> SystemTime {
+        self.received_at
+    }
+
+    /// Original bytes of the datagram the message was parsed from, verbatim.
+    ///
+    /// Useful for inspecting non-standard data that devices place in their packets.
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+/// Parse a received packet tolerating only the given quirks, attaching the receive
+/// metadata to it.
+///
+/// The bytes are only copied out of the receive buffer once the packet parsed.
+pub fn parse_packet<T>(bytes: &[u8],
+                       local_addr: SocketAddr,
+                       interface: &Option<Arc<str>>,
+                       opt_dst: Option<PacketDst>,
+                       quirks: Quirks)
+                       -> SSDPResult<T>
+    where T: FromRawSSDP
+{
+    let mut msg = try!(T::raw_ssdp_with_quirks(bytes, quirks));
+    msg.set_receive_info(ReceiveInfo::received(local_addr, interface, opt_dst).with_bytes(bytes));
+
+    Ok(msg)
+}
+
+/// Datagram received by a receiver that could not be parsed as a message.
+#[derive(Debug)]
+pub struct MalformedPacket {
+    bytes: Arc<[u8]>,
+    source: SocketAddr,
+    error: SSDPError,
+}
+
+impl MalformedPacket {
+    /// Construct a MalformedPacket for the bytes received from the source address.
+    pub fn new<B>(bytes: B, source: SocketAddr, error: SSDPError) -> MalformedPacket
+        where B: Into<Arc<[u8]>>
+    {
+        MalformedPacket {
+            bytes: bytes.into(),
+            source: source,
+            error: error,
+        }
+    }
+
+    /// Original bytes of the datagram, verbatim.
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Address the datagram was received from.
+    pub fn source(&self) -> SocketAddr {
+        self.source
+    }
+
+    /// Error encountered while parsing the datagram.
+    pub fn error(&self) -> &SSDPError {
+        &self.error
+    }
+}
+
+/// Trait for a destination that receiver threads can deliver messages to.
+trait Sink<T>: Clone + Send + 'static {
+    /// Deliver the message, returning false if the sink has hung up.
+    fn deliver(&self, msg: (T, SocketAddr)) -> bool;
+
+    /// Whether or not the sink has hung up, without delivering a message.
+    fn is_hung_up(&self) -> bool {
+        false
+    }
+}
+
+/// List of channels that messages are broadcast to.
+struct Subscribers<T> {
+    next_id: usize,
+    capacity: Option<usize>,
+    policy: OverflowPolicy,
+    senders: Vec<(usize, QueueSender<(T, SocketAddr)>)>,
+}
+
+impl<T> Subscribers<T> {
+    /// Add a new channel to the list, returning its id and receiving end.
+    fn subscribe(&mut self) -> (usize, QueueReceiver<(T, SocketAddr)>) {
+        let (send, recv) = queue::channel(self.capacity, self.policy);
+        let id = self.next_id;
+
+        self.next_id += 1;
+        self.senders.push((id, send));
+
+        (id, recv)
+    }
+
+    /// Remove the channel with the given id, returning true if no channels remain.
+    fn unsubscribe(&mut self, id: usize) -> bool {
+        self.senders.retain(|&(sender_id, _)| sender_id != id);
+
+        self.senders.is_empty()
+    }
+}
+
+/// Enumerates what should happen to a message that was passed to a `Filter`.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Verdict {
+    /// Deliver the message.
+    Deliver,
+    /// Drop the message.
+    Drop,
+    /// Deliver the message, then stop receiving.
+    ///
+    /// Messages already queued up can still be received before the receiver ends.
+    Last,
+}
+
+/// Function deciding what should happen to a received message.
+///
+/// The message may be modified before it is delivered.
+pub type Filter<T> = Box<FnMut(&mut T, &SocketAddr) -> Verdict + Send>;
+
+/// Sink that broadcasts every message delivered to it to all subscribers.
+///
+/// Receiver threads hold the only strong references to the subscribers so that
+/// all channels hang up once the receiver threads exit.
+struct BroadcastSink<T> {
+    subscribers: Arc<Mutex<Subscribers<T>>>,
+    filter: Option<Arc<Mutex<Filter<T>>>>,
+    hung_up: Arc<AtomicBool>,
+}
+
+impl<T> Clone for BroadcastSink<T> {
+    fn clone(&self) -> BroadcastSink<T> {
+        BroadcastSink {
+            subscribers: self.subscribers.clone(),
+            filter: self.filter.clone(),
+            hung_up: self.hung_up.clone(),
+        }
+    }
+}
+
+impl<T> Sink<T> for BroadcastSink<T>
+    where T: Clone + Send + 'static
+{
+    fn deliver(&self, mut msg: (T, SocketAddr)) -> bool {
+        let verdict = match self.filter {
+            Some(ref filter) => {
+                match filter.lock() {
+                    Ok(mut filter) => (&mut *filter)(&mut msg.0, &msg.1),
+                    Err(_) => return false,
+                }
+            }
+            None => Verdict::Deliver,
+        };
+
+        if verdict == Verdict::Drop {
+            return true;
+        }
+
+        // Sending may block on a full queue, so do it without holding the lock that
+        // consumers need in order to subscribe or close
+        let senders = {
+            let mut subscribers = match self.subscribers.lock() {
+                Ok(n) => n,
+                Err(_) => return false,
+            };
+
+            // Another receiver thread may have delivered the last message already
+            if self.is_hung_up() {
+                return false;
+            }
+
+            let senders = subscribers.senders.clone();
+
+            // Hanging up on all queues lets consumers drain them before they disconnect
+            if verdict == Verdict::Last {
+                subscribers.senders.clear();
+                self.hung_up.store(true, Ordering::SeqCst);
+            }
+
+            senders
+        };
+
+        // Send a copy to all but the last subscriber, which gets the original
+        let mut opt_msg = Some(msg);
+        let mut remaining = senders.len();
+        let mut closed = Vec::new();
+        for &(id, ref sender) in &senders {
+            remaining -= 1;
+
+            let next_msg = if remaining == 0 {
+                opt_msg.take().unwrap()
+            } else {
+                opt_msg.clone().unwrap()
+            };
+
+            if sender.send(next_msg).is_err() {
+                closed.push(id);
+            }
+        }
+
+        if verdict == Verdict::Last {
+            return false;
+        }
+
+        let mut subscribers = match self.subscribers.lock() {
+            Ok(n) => n,
+            Err(_) => return false,
+        };
+        subscribers.senders.retain(|&(id, _)| !closed.contains(&id));
+
+        if subscribers.senders.is_empty() {
+            self.hung_up.store(true, Ordering::SeqCst);
+        }
+
+        !subscribers.senders.is_empty()
+    }
+
+    fn is_hung_up(&self) -> bool {
+        self.hung_up.load(Ordering::SeqCst)
+    }
+}
+
+/// Sink that invokes a callback for every message delivered to it.
+struct CallbackSink<T, F> {
+    callback: Arc<Mutex<F>>,
+    filter: Option<Arc<Mutex<Filter<T>>>>,
+    hung_up: Arc<AtomicBool>,
+}
+
+impl<T, F> Clone for CallbackSink<T, F> {
+    fn clone(&self) -> CallbackSink<T, F> {
+        CallbackSink {
+            callback: self.callback.clone(),
+            filter: self.filter.clone(),
+            hung_up: self.hung_up.clone(),
+        }
+    }
+}
+
+impl<T, F> Sink<T> for CallbackSink<T, F>
+    where T: Send + 'static,
+          F: FnMut(T, SocketAddr) + Send + 'static
+{
+    fn deliver(&self, mut msg: (T, SocketAddr)) -> bool {
+        // Lock is poisoned if the callback panicked, treat that as hanging up
+        let mut callback = match self.callback.lock() {
+            Ok(n) => n,
+            Err(_) => return false,
+        };
+
+        // Another receiver thread may have delivered the last message already
+        if self.is_hung_up() {
+            return false;
+        }
+
+        let verdict = match self.filter {
+            Some(ref filter) => {
+                match filter.lock() {
+                    Ok(mut filter) => (&mut *filter)(&mut msg.0, &msg.1),
+                    Err(_) => return false,
+                }
+            }
+            None => Verdict::Deliver,
+        };
+
+        match verdict {
+            Verdict::Deliver => (&mut *callback)(msg.0, msg.1),
+            Verdict::Drop => (),
+            Verdict::Last => {
+                (&mut *callback)(msg.0, msg.1);
+                self.hung_up.store(true, Ordering::SeqCst);
+            }
+        }
+
+        !self.is_hung_up()
+    }
+
+    fn is_hung_up(&self) -> bool {
+        self.hung_up.load(Ordering::SeqCst)
+    }
+}
+
+/// Receive bytes from a number of UdpSockets and try to construct an object T
+/// from them, invoking the callback with every object the filter, if provided,
+/// delivers. If a duration is provided, the sockets will be closed after the
+/// specified duration.
+///
+/// The callback is invoked on the receiver thread(s), so it should not block
+/// for any significant amount of time. Once the filter returns `Verdict::Last`,
+/// the callback is invoked one more time and the receiver threads exit. Errors
+/// and malformed packets are reported to the handlers, packets showing quirks
+/// that are not tolerated are malformed.
+pub fn spawn_callback_filtered<T, F>(socks: Vec<UdpSocket>,
+                                     time: Option<Duration>,
+                                     filter: Option<Filter<T>>,
+                                     handlers: ReceiveHandlers,
+                                     quirks: Quirks,
+                                     callback: F)
+                                     -> io::Result<ListenHandle>
+    where T: FromRawSSDP + Send + 'static,
+          F: FnMut(T, SocketAddr) + Send + 'static
+{
+    let sink = CallbackSink {
+        callback: Arc::new(Mutex::new(callback)),
+        filter: filter.map(|filter| Arc::new(Mutex::new(filter))),
+        hung_up: Arc::new(AtomicBool::new(false)),
+    };
+
+    spawn_receivers(socks, time, handlers, quirks, sink)
+}
+
+/// Construct a receiver like `SSDPReceiver::with_capacity` that only delivers
+/// messages the filter, if provided, returns true for.
+///
+/// The filter is invoked on the receiver thread(s) before any subscribed
+/// receiver sees the message. Packets showing quirks that are not tolerated
+/// are reported to the handlers as malformed.
+pub fn spawn_filtered<T>(socks: Vec<UdpSocket>,
+                         time: Option<Duration>,
+                         capacity: Option<usize>,
+                         policy: OverflowPolicy,
+                         filter: Option<Filter<T>>,
+                         handlers: ReceiveHandlers,
+                         quirks: Quirks)
+                         -> io::Result<SSDPReceiver<T>>
+    where T: FromRawSSDP + Clone + Send + 'static
+{
+    SSDPReceiver::build(socks, time, capacity, policy, filter, handlers, quirks)
+}
+
+/// Iterator for an `SSDPReceiver`.
+pub struct SSDPIter<T> {
+    recv: SSDPReceiver<T>,
+}
+
+impl<T> SSDPIter<T> {
+    fn new(recv: SSDPReceiver<T>) -> SSDPIter<T> {
+        SSDPIter { recv: recv }
+    }
+}
+
+impl<T> Iterator for SSDPIter<T> {
+    type Item = (T, SocketAddr);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.recv.recv().ok()
+    }
+}
+
+/// Borrowed iterator for an `SSDPReceiver`.
+pub struct SSDPRefIter<'a, T: 'a> {
+    recv: &'a SSDPReceiver<T>,
+}
+
+impl<'a, T> Iterator for SSDPRefIter<'a, T> {
+    type Item = (T, SocketAddr);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.recv.recv().ok()
+    }
+}
+
+/// Error encountered by a receiver thread while receiving messages.
+#[derive(Debug)]
+pub enum ReceiveError {
+    /// Reading from a socket failed.
+    ///
+    /// Local address of the socket with the error are supplied.
+    Socket(SocketAddr, io::Error),
+}
+
+impl Display for ReceiveError {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            ReceiveError::Socket(addr, ref err) => {
+                f.write_fmt(format_args!("Socket Error At {}: {}", addr, err))
+            }
+        }
+    }
+}
+
+impl Error for ReceiveError {
+    fn description(&self) -> &str {
+        match *self {
+            ReceiveError::Socket(..) => "Socket Error",
+        }
+    }
+
+    fn cause(&self) -> Option<&Error> {
+        match *self {
+            ReceiveError::Socket(_, ref err) => Some(err),
+        }
+    }
+}
+
+/// Function that receiver threads report errors to.
+type ErrorHandler = Box<FnMut(ReceiveError) + Send>;
+
+/// Function that receiver threads report malformed packets to.
+type MalformedHandler = Box<FnMut(MalformedPacket) + Send>;
+
+/// Handlers that receiver threads report errors and malformed packets to.
+///
+/// The handlers are passed in when constructing a receiver, before any packet
+/// is received. Clones share the same handlers.
+#[derive(Clone, Default)]
+pub struct ReceiveHandlers {
+    on_error: Arc<Mutex<Option<ErrorHandler>>>,
+    on_malformed: Arc<Mutex<Option<MalformedHandler>>>,
+}
+
+impl ReceiveHandlers {
+    /// Construct ReceiveHandlers that only log what they are reported.
+    pub fn new() -> ReceiveHandlers {
+        ReceiveHandlers::default()
+    }
+
+    /// Set the handler that receiver threads will invoke with any errors they
+    /// encounter.
+    ///
+    /// Receiver threads will keep reading from a socket that had an error, the
+    /// handler may call `ListenHandle::shutdown` to stop the receiver instead.
+    /// Without a handler, errors are only logged.
+    pub fn on_error<F>(self, handler: F) -> ReceiveHandlers
+        where F: FnMut(ReceiveError) + Send + 'static
+    {
+        if let Ok(mut on_error) = self.on_error.lock() {
+            *on_error = Some(Box::new(handler));
+        }
+
+        self
+    }
+
+    /// Set the handler that receiver threads will invoke with any packets that
+    /// could not be parsed.
+    ///
+    /// Without a handler, malformed packets are dropped after being logged.
+    pub fn on_malformed<F>(self, handler: F) -> ReceiveHandlers
+        where F: FnMut(MalformedPacket) + Send + 'static
+    {
+        if let Ok(mut on_malformed) = self.on_malformed.lock() {
+            *on_malformed = Some(Box::new(handler));
+        }
+
+        self
+    }
+
+    /// Report an error to the error handler, if one was set.
+    fn report(&self, err: ReceiveError) {
+        match self.on_error.lock() {
+            Ok(mut on_error) => {
+                match *on_error {
+                    Some(ref mut handler) => handler(err),
+                    None => warn!("Receiver encountered an error: {}", err),
+                }
+            }
+            // Handler panicked, just log from now on
+            Err(_) => warn!("Receiver encountered an error: {}", err),
+        }
+    }
+
+    /// Report a malformed packet to the malformed packet handler, if one was set.
+    fn report_malformed(&self, packet: MalformedPacket) {
+        match self.on_malformed.lock() {
+            Ok(mut on_malformed) => {
+                match *on_malformed {
+                    Some(ref mut handler) => handler(packet),
+                    None => debug!("Dropping malformed packet from {}: {}", packet.source(), packet.error()),
+                }
+            }
+            // Handler panicked, just log from now on
+            Err(_) => debug!("Dropping malformed packet from {}: {}", packet.source(), packet.error()),
+        }
+    }
+}
+
+impl fmt::Debug for ReceiveHandlers {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        f.write_str("ReceiveHandlers")
+    }
+}
+
+/// Handle for shutting down an `SSDPReceiver`, possibly from another thread.
+#[derive(Clone)]
+pub struct ListenHandle {
+    shutdown: Arc<AtomicBool>,
+    handlers: ReceiveHandlers,
+    #[cfg(feature = "mio")]
+    waker: Option<Arc<Waker>>,
+}
+
+impl ListenHandle {
+    fn new(handlers: ReceiveHandlers) -> ListenHandle {
+        ListenHandle {
+            shutdown: Arc::new(AtomicBool::new(false)),
+            handlers: handlers,
+            #[cfg(feature = "mio")]
+            waker: None,
+        }
+    }
+
+    fn report(&self, err: ReceiveError) {
+        self.handlers.report(err)
+    }
+
+    fn report_malformed(&self, packet: MalformedPacket) {
+        self.handlers.report_malformed(packet)
+    }
+
+    /// Signal the receiver threads to close their sockets and exit.
+    ///
+    /// Any messages still queued up in the receiver will be discarded and the
+    /// receiver will not yield any more messages.
+    pub fn shutdown(&self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+
+        #[cfg(feature = "mio")]
+        {
+            if let Some(ref waker) = self.waker {
+                // Thread will still see the flag on its next wake up if this fails
+                let _ = waker.wake();
+            }
+        }
+    }
+
+    /// Whether or not the receiver has been shutdown.
+    pub fn is_shutdown(&self) -> bool {
+        self.shutdown.load(Ordering::SeqCst)
+    }
+}
+
+/// Enumerates the headers that duplicate messages can be identified by.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum DedupKey {
+    /// Messages with the same USN are duplicates.
+    Usn,
+    /// Messages with the same USN and LOCATION are duplicates.
+    UsnLocation,
+}
+
+/// Extract the key that duplicate messages share from a message.
+type KeyFn<T> = fn(&T) -> Option<Vec<Vec<u8>>>;
+
+/// Keeps track of the messages that have already been received.
+struct Dedup<T> {
+    key: KeyFn<T>,
+    seen: RefCell<HashSet<Vec<Vec<u8>>>>,
+}
+
+fn usn_key<T: HeaderRef>(msg: &T) -> Option<Vec<Vec<u8>>> {
+    msg.get_raw("USN").map(|usn| usn.to_vec())
+}
+
+fn usn_location_key<T: HeaderRef>(msg: &T) -> Option<Vec<Vec<u8>>> {
+    msg.get_raw("USN").map(|usn| {
+        let mut key = usn.to_vec();
+        key.extend(msg.get_raw("LOCATION").unwrap_or(&[]).iter().cloned());
+
+        key
+    })
+}
+
+/// A non-blocking SSDP message receiver.
+pub struct SSDPReceiver<T> {
+    recvr: QueueReceiver<(T, SocketAddr)>,
+    id: usize,
+    subscribers: Weak<Mutex<Subscribers<T>>>,
+    handle: ListenHandle,
+    closed: Cell<bool>,
+    deadline: Option<Instant>,
+    idle_timeout: Option<Duration>,
+    dedup: Option<Dedup<T>>,
+    remaining: Cell<Option<usize>>,
+}
+
+impl<T> SSDPReceiver<T>
+    where T: FromRawSSDP + Clone + Send + 'static
+{
+    /// Construct a receiver that receives bytes from a number of UdpSockets and
+    /// tries to construct an object T from them. If a duration is provided, the
+    /// channel will be shutdown after the specified duration.
+    ///
+    /// Messages received by UdpSockets bound to an unspecified address, such as
+    /// 0.0.0.0, only carry the address of the receiving interface in their
+    /// `ReceiveInfo` where the platform reports it, which is currently only linux.
+    ///
+    /// When the `mio` feature is enabled, all of the UdpSockets will be driven
+    /// by a single thread as opposed to a thread per UdpSocket.
+    pub fn new(socks: Vec<UdpSocket>, time: Option<Duration>) -> io::Result<SSDPReceiver<T>> {
+        SSDPReceiver::with_handlers(socks, time, ReceiveHandlers::new())
+    }
+
+    /// Construct a receiver like `SSDPReceiver::new` that reports any errors and
+    /// malformed packets its threads encounter to the handlers.
+    pub fn with_handlers(socks: Vec<UdpSocket>,
+                         time: Option<Duration>,
+                         handlers: ReceiveHandlers)
+                         -> io::Result<SSDPReceiver<T>> {
+        SSDPReceiver::build(socks, time, None, OverflowPolicy::Block, None, handlers, Quirks::default())
+    }
+
+    /// Construct a receiver like `SSDPReceiver::new` but with the number of
+    /// queued messages bounded to the given capacity.
+    ///
+    /// When a message is received while the queue is full, the policy decides
+    /// which message is dropped, or whether to wait for the consumer instead.
+    /// A capacity of zero could never hold a message and is rejected.
+    pub fn with_capacity(socks: Vec<UdpSocket>,
+                         time: Option<Duration>,
+                         capacity: usize,
+                         policy: OverflowPolicy)
+                         -> io::Result<SSDPReceiver<T>> {
+        SSDPReceiver::build(socks,
+                            time,
+                            Some(capacity),
+                            policy,
+                            None,
+                            ReceiveHandlers::new(),
+                            Quirks::default())
+    }
+
+    fn build(socks: Vec<UdpSocket>,
+             time: Option<Duration>,
+             capacity: Option<usize>,
+             policy: OverflowPolicy,
+             filter: Option<Filter<T>>,
+             handlers: ReceiveHandlers,
+             quirks: Quirks)
+             -> io::Result<SSDPReceiver<T>> {
+        try!(queue::check_capacity(capacity));
+
+        let mut subscribers = Subscribers {
+            next_id: 0,
+            capacity: capacity,
+            policy: policy,
+            senders: Vec::new(),
+        };
+        let (id, recv) = subscribers.subscribe();
+
+        let sink = BroadcastSink {
+            subscribers: Arc::new(Mutex::new(subscribers)),
+            filter: filter.map(|filter| Arc::new(Mutex::new(filter))),
+            hung_up: Arc::new(AtomicBool::new(false)),
+        };
+        let weak_subscribers = Arc::downgrade(&sink.subscribers);
+
+        // Spawn Receiver Threads
+        let handle = try!(spawn_receivers(socks, time, handlers, quirks, sink));
+
+        Ok(SSDPReceiver {
+            recvr: recv,
+            id: id,
+            subscribers: weak_subscribers,
+            handle: handle,
+            closed: Cell::new(false),
+            deadline: None,
+            idle_timeout: None,
+            dedup: None,
+            remaining: Cell::new(None),
+        })
+    }
+
+    /// Subscribe to the messages received by this receiver.
+    ///
+    /// Returns an independent receiver that will yield a copy of every message
+    /// received from this point on. The sockets will stay open until all
+    /// subscribed receivers have been closed, or the `ListenHandle` is shutdown.
+    ///
+    /// The new receiver is bounded by the same capacity and policy as this one.
+    pub fn subscribe(&self) -> SSDPReceiver<T> {
+        let (id, recv) = match self.subscribers.upgrade() {
+            Some(subscribers) => {
+                match subscribers.lock() {
+                    Ok(mut n) => n.subscribe(),
+                    Err(_) => (0, queue::channel(None, OverflowPolicy::Block).1),
+                }
+            }
+            // Receiver threads have exited, channel will hang up immediately
+            None => (0, queue::channel(None, OverflowPolicy::Block).1),
+        };
+
+        SSDPReceiver {
+            recvr: recv,
+            id: id,
+            subscribers: self.subscribers.clone(),
+            handle: self.handle.clone(),
+            closed: Cell::new(false),
+            deadline: None,
+            idle_timeout: None,
+            dedup: None,
+            remaining: Cell::new(None),
+        }
+    }
+}
+
+/// Spawn a number of receiver threads that will receive packets, forward the
+/// bytes on to T, and deliver successfully constructed objects to the sink.
+#[cfg(not(feature = "mio"))]
+fn spawn_receivers<T, S>(socks: Vec<UdpSocket>,
+                         time: Option<Duration>,
+                         handlers: ReceiveHandlers,
+                         quirks: Quirks,
+                         sender: S)
+                         -> io::Result<ListenHandle>
+    where T: FromRawSSDP + Send + 'static,
+          S: Sink<T>
+{
+    let deadline = time.map(|dur| Instant::now() + dur);
+    let handle = ListenHandle::new(handlers);
+
+    for sock in socks {
+        let local_addr = try!(sock.local_addr());
+        let pckt_recv = PacketReceiver::new(sock);
+        if let Err(err) = pckt_recv.set_recv_dst() {
+            debug!("Packet destinations will not be reported at {}: {}", local_addr, err);
+        }
+        let handle = handle.clone();
+        let sender = sender.clone();
+
+        thread::spawn(move || {
+            receive_packets(pckt_recv, local_addr, deadline, handle, quirks, sender);
+        });
+    }
+
+    Ok(handle)
+}
+
+/// Spawn a single receiver thread that will poll all of the sockets for packets,
+/// forward the bytes on to T, and deliver successfully constructed objects to
+/// the sink.
+#[cfg(feature = "mio")]
+fn spawn_receivers<T, S>(socks: Vec<UdpSocket>,
+                         time: Option<Duration>,
+                         handlers: ReceiveHandlers,
+                         quirks: Quirks,
+                         sender: S)
+                         -> io::Result<ListenHandle>
+    where T: FromRawSSDP + Send + 'static,
+          S: Sink<T>
+{
+    let mut handle = ListenHandle::new(handlers);
+
+    // Nothing will ever be received, drop the sink so the receiver hangs up
+    if socks.is_empty() {
+        return Ok(handle);
+    }
+
+    let poll = try!(mio::Poll::new());
+    let waker = Arc::new(try!(Waker::new(poll.registry(), Token(socks.len()))));
+    let mut poll_socks = Vec::with_capacity(socks.len());
+
+    for (index, sock) in socks.into_iter().enumerate() {
+        try!(sock.set_nonblocking(true));
+        if let Err(err) = packet::set_recv_dst(&sock) {
+            debug!("Packet destinations will not be reported at {:?}: {}", sock.local_addr(), err);
+        }
+
+        let mut poll_sock = mio::net::UdpSocket::from_std(sock);
+        try!(poll.registry().register(&mut poll_sock, Token(index), Interest::READABLE));
+
+        poll_socks.push(poll_sock);
+    }
+
+    handle.waker = Some(waker);
+
+    let thread_handle = handle.clone();
+    thread::spawn(move || {
+        poll_packets(poll, poll_socks, time, thread_handle, quirks, sender);
+    });
+
+    Ok(handle)
+}
+
+impl<T> SSDPReceiver<T> {
+    /// Non-blocking method that attempts to read a value from the receiver.
+    pub fn try_recv(&self) -> Result<(T, SocketAddr), TryRecvError> {
+        loop {
+            if self.is_closed() || self.deadline_elapsed() {
+                return Err(TryRecvError::Disconnected);
+            }
+
+            let msg = try!(self.recvr.try_recv());
+            if self.is_new(&msg.0) {
+                self.count_message();
+
+                return Ok(msg);
+            }
+        }
+    }
+
+    /// Blocking method that reads a value from the receiver until one is available.
+    ///
+    /// If a deadline or idle timeout was set and expires while waiting, the
+    /// receiver will be closed.
+    pub fn recv(&self) -> Result<(T, SocketAddr), RecvError> {
+        loop {
+            let msg = try!(self.recv_any());
+            if self.is_new(&msg.0) {
+                self.count_message();
+
+                return Ok(msg);
+            }
+        }
+    }
+
+    /// Blocking method that reads a value from the receiver until one is available
+    /// or the timeout elapses.
+    ///
+    /// The receiver stays open when the timeout elapses, so this can be used to
+    /// poll the receiver from an event loop. If a deadline or idle timeout was
+    /// set and expires while waiting, the receiver will be closed.
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<(T, SocketAddr), RecvTimeoutError> {
+        let until = Instant::now() + timeout;
+
+        loop {
+            if self.is_closed() || self.deadline_elapsed() {
+                return Err(RecvTimeoutError::Disconnected);
+            }
+
+            // Whichever of our timeout and the receiver's own timeouts comes first
+            let remaining = until.saturating_duration_since(Instant::now());
+            let (wait, expires_receiver) = match self.next_timeout() {
+                Some(n) if n <= remaining => (n, true),
+                _ => (remaining, false),
+            };
+
+            let msg = match self.recvr.recv_timeout(wait) {
+                Ok(n) => n,
+                Err(RecvTimeoutError::Timeout) if expires_receiver => {
+                    trace!("Receiver deadline or idle timeout expired");
+                    self.close();
+
+                    return Err(RecvTimeoutError::Disconnected);
+                }
+                Err(err) => return Err(err),
+            };
+
+            // Receiver may have been shutdown while we were blocked
+            if self.is_closed() {
+                return Err(RecvTimeoutError::Disconnected);
+            }
+
+            if self.is_new(&msg.0) {
+                self.count_message();
+
+                return Ok(msg);
+            }
+        }
+    }
+
+    /// Blocking method that reads a value from the receiver, including duplicates.
+    fn recv_any(&self) -> Result<(T, SocketAddr), RecvError> {
+        if self.is_closed() || self.deadline_elapsed() {
+            return Err(RecvError);
+        }
+
+        let result = match self.next_timeout() {
+            Some(timeout) => {
+                match self.recvr.recv_timeout(timeout) {
+                    Ok(n) => Ok(n),
+                    Err(RecvTimeoutError::Timeout) => {
+                        trace!("Receiver deadline or idle timeout expired");
+                        self.close();
+
+                        Err(RecvError)
+                    }
+                    Err(RecvTimeoutError::Disconnected) => Err(RecvError),
+                }
+            }
+            None => self.recvr.recv(),
+        };
+
+        match result {
+            // Receiver may have been shutdown while we were blocked
+            Ok(_) if self.is_closed() => Err(RecvError),
+            result => result,
+        }
+    }
+
+    /// Set a hard deadline, relative to now, after which the receiver will be
+    /// closed regardless of the timeout the receiver was constructed with.
+    ///
+    /// Passing None will remove any previously set deadline.
+    pub fn set_deadline(&mut self, dur: Option<Duration>) {
+        self.deadline = dur.map(|n| Instant::now() + n);
+    }
+
+    /// Set a timeout after which the receiver will be closed if no message
+    /// was received within that time.
+    ///
+    /// Passing None will remove any previously set idle timeout.
+    pub fn set_idle_timeout(&mut self, dur: Option<Duration>) {
+        self.idle_timeout = dur;
+    }
+
+    /// Close the receiver after the given number of messages have been received.
+    ///
+    /// The count starts from the time this is called and excludes any dropped
+    /// duplicates. Passing None will remove any previously set maximum.
+    pub fn set_max_messages(&mut self, opt_max: Option<usize>) {
+        self.remaining.set(opt_max);
+
+        if opt_max == Some(0) {
+            self.close();
+        }
+    }
+
+    /// Count a message against the maximum, closing the receiver once reached.
+    fn count_message(&self) {
+        match self.remaining.get() {
+            Some(1) => {
+                trace!("Receiver reached the maximum number of messages");
+                self.remaining.set(Some(0));
+                self.close();
+            }
+            Some(n) => self.remaining.set(Some(n.saturating_sub(1))),
+            None => (),
+        }
+    }
+
+    /// Whether or not the message has not been seen before, if deduplicating.
+    fn is_new(&self, msg: &T) -> bool {
+        let dedup = match self.dedup {
+            Some(ref n) => n,
+            None => return true,
+        };
+
+        match (dedup.key)(msg) {
+            Some(key) => dedup.seen.borrow_mut().insert(key),
+            // Messages we cannot identify are never duplicates
+            None => true,
+        }
+    }
+
+    /// Whether or not this receiver was closed or the `ListenHandle` was shutdown.
+    fn is_closed(&self) -> bool {
+        self.closed.get() || self.handle.is_shutdown()
+    }
+
+    /// Stop receiving messages on this receiver, shutting down the sockets if
+    /// there are no other subscribed receivers.
+    ///
+    /// Receiver threads exit and close their sockets as soon as they notice
+    /// the shutdown, without waiting for another packet to arrive. Dropping the
+    /// receiver closes it as well.
+    pub fn close(&self) {
+        if self.closed.get() {
+            return;
+        }
+        self.closed.set(true);
+
+        // Wake up any receiver thread blocked on our full queue before unsubscribing
+        self.recvr.close();
 
-/// Iterator for an `SSDPReceiver`.
-pub struct SSDPIter<T> {
-    recv: SSDPReceiver<T>,
-}
+        let last_subscriber = match self.subscribers.upgrade() {
+            Some(subscribers) => subscribers.lock().map(|mut n| n.unsubscribe(self.id)).unwrap_or(true),
+            None => true,
+        };
 
-impl<T> SSDPIter<T> {
-    fn new(recv: SSDPReceiver<T>) -> SSDPIter<T> {
-        SSDPIter { recv: recv }
+        if last_subscriber {
+            self.handle.shutdown();
+        }
     }
-}
 
-impl<T> Iterator for SSDPIter<T> {
-    type Item = (T, SocketAddr);
+    /// Whether or not the deadline has elapsed, closing the receiver if it has.
+    fn deadline_elapsed(&self) -> bool {
+        match self.deadline {
+            Some(n) if Instant::now() >= n => {
+                self.close();
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.recv.recv().ok()
+                true
+            }
+            _ => false,
+        }
     }
-}
 
-/// A non-blocking SSDP message receiver.
-pub struct SSDPReceiver<T> {
-    recvr: Receiver<(T, SocketAddr)>
+    /// Time to wait for the next message given the deadline and idle timeout.
+    fn next_timeout(&self) -> Option<Duration> {
+        let opt_remaining = self.deadline.map(|n| n.saturating_duration_since(Instant::now()));
+
+        match (opt_remaining, self.idle_timeout) {
+            (Some(remaining), Some(idle)) => Some(::std::cmp::min(remaining, idle)),
+            (Some(remaining), None) => Some(remaining),
+            (None, opt_idle) => opt_idle,
+        }
+    }
+
+    /// Get a handle that can be used to shutdown this receiver.
+    pub fn handle(&self) -> ListenHandle {
+        self.handle.clone()
+    }
 }
 
 impl<T> SSDPReceiver<T>
-    where T: FromRawSSDP + Send + 'static
+    where T: HeaderRef
 {
-    /// Construct a receiver that receives bytes from a number of UdpSockets and
-    /// tries to construct an object T from them. If a duration is provided, the
-    /// channel will be shutdown after the specified duration.
+    /// Only yield the first of any messages that share the same key, so that
+    /// each logical device or service is seen once.
     ///
-    /// Due to implementation details, none of the UdpSockets should be bound to
-    /// the default route, 0.0.0.0, address.
-    pub fn new(socks: Vec<UdpSocket>, time: Option<Duration>) -> io::Result<SSDPReceiver<T>> {
-        let (send, recv) = mpsc::channel();
-
-        // Ensure `receive_packets` times out in the event the timeout packet is not received
-        for sock in socks.iter() {
-            try!(sock.set_read_timeout(time));
-        }
-
-        // Spawn Receiver Threads
-        spawn_receivers(socks, send);
-
-        Ok(SSDPReceiver {
-            recvr: recv
-        })
+    /// Messages without a USN header are always yielded. Passing None will stop
+    /// deduplicating and forget any messages seen so far.
+    pub fn set_dedup(&mut self, opt_key: Option<DedupKey>) {
+        self.dedup = opt_key.map(|key| {
+            Dedup {
+                key: match key {
+                    DedupKey::Usn => usn_key::<T>,
+                    DedupKey::UsnLocation => usn_location_key::<T>,
+                },
+                seen: RefCell::new(HashSet::new()),
+            }
+        });
     }
 }
 
-/// Spawn a number of receiver threads that will receive packets, forward the
-/// bytes on to T, and send successfully constructed objects through the sender.
-fn spawn_receivers<T>(socks: Vec<UdpSocket>, sender: Sender<(T, SocketAddr)>)
-    where T: FromRawSSDP + Send + 'static
+#[cfg(feature = "futures")]
+impl<T> SSDPReceiver<T>
+    where T: Send + 'static
 {
-    for sock in socks {
-        let pckt_recv = PacketReceiver::new(sock);
-        let sender = sender.clone();
+    /// Convert this receiver into a `Stream` of messages.
+    ///
+    /// A background thread is spawned which blocks on the receiver and forwards
+    /// messages on to the stream. The thread will exit after the receiver is
+    /// exhausted or shortly after the stream has been dropped, closing the receiver.
+    pub fn into_stream(self) -> SSDPReceiverStream<T> {
+        let (send, recv) = async_mpsc::unbounded();
+        let poll_interval = Duration::from_millis(STREAM_POLL_INTERVAL_MS);
 
         thread::spawn(move || {
-            receive_packets(pckt_recv, sender);
+            loop {
+                match self.recv_timeout(poll_interval) {
+                    Ok(msg) => {
+                        if send.unbounded_send(msg).is_err() {
+                            break;
+                        }
+                    }
+                    // Wake up periodically to check if the stream has been dropped
+                    Err(RecvTimeoutError::Timeout) if !send.is_closed() => (),
+                    Err(_) => break,
+                }
+            }
         });
+
+        SSDPReceiverStream { recv: recv }
     }
 }
 
-impl<T> SSDPReceiver<T> {
-    /// Non-blocking method that attempts to read a value from the receiver.
-    pub fn try_recv(&self) -> Result<(T, SocketAddr), TryRecvError> {
-        self.recvr.try_recv()
-    }
+/// Stream for an `SSDPReceiver`.
+#[cfg(feature = "futures")]
+pub struct SSDPReceiverStream<T> {
+    recv: UnboundedReceiver<(T, SocketAddr)>,
+}
 
-    /// Blocking method that reads a value from the receiver until one is available.
-    pub fn recv(&self) -> Result<(T, SocketAddr), RecvError> {
-        self.recvr.recv()
+#[cfg(feature = "futures")]
+impl<T> Stream for SSDPReceiverStream<T> {
+    type Item = (T, SocketAddr);
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.recv).poll_next(cx)
     }
 }
 
 impl<'a, T> IntoIterator for &'a SSDPReceiver<T> {
     type Item = (T, SocketAddr);
-    type IntoIter = Iter<'a, (T, SocketAddr)>;
+    type IntoIter = SSDPRefIter<'a, T>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.recvr.iter()
+        SSDPRefIter { recv: self }
     }
 }
 
 impl<'a, T> IntoIterator for &'a mut SSDPReceiver<T> {
     type Item = (T, SocketAddr);
-    type IntoIter = Iter<'a, (T, SocketAddr)>;
+    type IntoIter = SSDPRefIter<'a, T>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.recvr.iter()
+        SSDPRefIter { recv: self }
+    }
+}
+
+impl<T> Drop for SSDPReceiver<T> {
+    fn drop(&mut self) {
+        self.close();
     }
 }
 
@@ -119,38 +1258,702 @@ impl<T> IntoIterator for SSDPReceiver<T> {
     }
 }
 
-/// Receives bytes and attempts to construct a T which will be sent through the supplied channel.
+/// Receives bytes and attempts to construct a T which will be delivered to the supplied sink.
 ///
 /// This should almost always be run in it's own thread.
-fn receive_packets<T>(recv: PacketReceiver, send: Sender<(T, SocketAddr)>)
-    where T: FromRawSSDP + Send
+#[cfg(not(feature = "mio"))]
+fn receive_packets<T, S>(recv: PacketReceiver,
+                         local_addr: SocketAddr,
+                         deadline: Option<Instant>,
+                         handle: ListenHandle,
+                         quirks: Quirks,
+                         send: S)
+    where T: FromRawSSDP + Send,
+          S: Sink<T>
 {
+    let poll_interval = Duration::from_millis(SHUTDOWN_POLL_INTERVAL_MS);
+    let interface = net::interface_name(&local_addr.ip()).map(Arc::from);
+    let mut pckt_buf = vec![0u8; MAX_PCKT_LEN];
+
     // TODO: Add logging to this function. Maybe forward sender IP Address along
     // so that we can do some checks when we parse the http.
     loop {
+        if handle.is_shutdown() {
+            trace!("Receiver at {} was shutdown", recv);
+            return;
+        }
+
+        // Receiver thread for another socket delivered the last message
+        if send.is_hung_up() {
+            trace!("Receiver at {} was hung up on", recv);
+            return;
+        }
+
+        // Wake up periodically to check if we have been shutdown
+        let read_timeout = match deadline {
+            Some(n) => {
+                let now = Instant::now();
+                if now >= n {
+                    // We have waited for at least the desired timeout (or possibly longer)
+                    trace!("Receiver at {} timed out", recv);
+                    return;
+                }
+
+                ::std::cmp::min(n - now, poll_interval)
+            }
+            None => poll_interval,
+        };
+        if let Err(err) = recv.set_read_timeout(Some(read_timeout)) {
+            handle.report(ReceiveError::Socket(local_addr, err));
+            return;
+        }
+
         trace!("Waiting on packet at {}...", recv);
-        let (msg_bytes, addr) = match recv.recv_pckt() {
-            Ok((bytes, addr)) => (bytes, addr),
+        let (size, addr, opt_dst) = match recv.recv_pckt(&mut pckt_buf) {
+            Ok(n) => n,
             // Unix returns WouldBlock on timeout while Windows returns TimedOut
             Err(ref err) if err.kind() == io::ErrorKind::WouldBlock ||
                             err.kind() == io::ErrorKind::TimedOut => {
-                // We have waited for at least the desired timeout (or possibly longer)
-                trace!("Receiver at {} timed out", recv);
-                return;
+                continue;
             },
-            Err(_) => {
+            Err(err) => {
+                handle.report(ReceiveError::Socket(local_addr, err));
                 continue;
             }
         };
 
-        trace!("Received packet with {} bytes", msg_bytes.len());
+        trace!("Received packet with {} bytes", size);
 
-        // Unwrap Will Cause A Panic If Receiver Hung Up Which Is Desired
-        match T::raw_ssdp(&msg_bytes[..]) {
-            Ok(n) => send.send((n, addr)).unwrap(),
-            Err(_) => {
-                continue;
+        match parse_packet(&pckt_buf[..size], local_addr, &interface, opt_dst, quirks) {
+            Ok(n) => {
+                // Receiver hung up, nobody is listening anymore
+                if !send.deliver((n, addr)) {
+                    return;
+                }
+            }
+            Err(err) => handle.report_malformed(MalformedPacket::new(&pckt_buf[..size], addr, err)),
+        }
+    }
+}
+
+/// Polls all sockets for bytes and attempts to construct a T which will be
+/// delivered to the supplied sink.
+///
+/// This should almost always be run in it's own thread.
+#[cfg(feature = "mio")]
+fn poll_packets<T, S>(mut poll: mio::Poll,
+                      socks: Vec<mio::net::UdpSocket>,
+                      time: Option<Duration>,
+                      handle: ListenHandle,
+                      quirks: Quirks,
+                      send: S)
+    where T: FromRawSSDP + Send,
+          S: Sink<T>
+{
+    let deadline = time.map(|dur| Instant::now() + dur);
+    let mut events = Events::with_capacity(socks.len());
+    let mut pckt_buf = vec![0u8; MAX_PCKT_LEN];
+
+    // Local addresses only change if the sockets are rebound, look them up once
+    let mut local_info = Vec::with_capacity(socks.len());
+    for sock in &socks {
+        let opt_info = sock.local_addr()
+            .ok()
+            .map(|addr| (addr, net::interface_name(&addr.ip()).map(Arc::from)));
+        local_info.push(opt_info);
+    }
+
+    loop {
+        if handle.is_shutdown() {
+            trace!("Poll receiver was shutdown");
+            return;
+        }
+
+        let opt_timeout = match deadline {
+            Some(n) => {
+                let now = Instant::now();
+                if now >= n {
+                    trace!("Poll receiver timed out");
+                    return;
+                }
+
+                Some(n - now)
             }
+            None => None,
         };
+
+        trace!("Waiting on packets at {} sockets...", socks.len());
+        match poll.poll(&mut events, opt_timeout) {
+            Ok(()) => (),
+            Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => {
+                // None of the sockets will be read from anymore
+                for sock in &socks {
+                    if let Ok(local_addr) = sock.local_addr() {
+                        let sock_err = io::Error::new(err.kind(), err.to_string());
+
+                        handle.report(ReceiveError::Socket(local_addr, sock_err));
+                    }
+                }
+
+                return;
+            }
+        }
+
+        for event in events.iter() {
+            // Token past the end of our sockets belongs to the shutdown waker
+            let (sock, opt_info) = match socks.get(event.token().0) {
+                Some(n) => (n, &local_info[event.token().0]),
+                None => continue,
+            };
+
+            // Sockets are edge triggered, so we have to read until there is nothing left
+            loop {
+                let (size, addr, opt_dst) = match recv_poll_sock(sock, &mut pckt_buf) {
+                    Ok(n) => n,
+                    Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                    Err(err) => {
+                        if let Ok(local_addr) = sock.local_addr() {
+                            handle.report(ReceiveError::Socket(local_addr, err));
+                        }
+
+                        break;
+                    }
+                };
+
+                trace!("Received packet with {} bytes", size);
+
+                let msg_result = match *opt_info {
+                    Some((local_addr, ref interface)) => {
+                        parse_packet(&pckt_buf[..size], local_addr, interface, opt_dst, quirks)
+                    }
+                    None => T::raw_ssdp_with_quirks(&pckt_buf[..size], quirks),
+                };
+
+                match msg_result {
+                    Ok(n) => {
+                        // Receiver hung up, nobody is listening anymore
+                        if !send.deliver((n, addr)) {
+                            return;
+                        }
+                    }
+                    Err(err) => {
+                        handle.report_malformed(MalformedPacket::new(&pckt_buf[..size], addr, err))
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Receive a packet from a polled socket, along with its destination.
+#[cfg(all(feature = "mio", target_os = "linux"))]
+fn recv_poll_sock(sock: &mio::net::UdpSocket,
+                  buf: &mut [u8])
+                  -> io::Result<(usize, SocketAddr, Option<PacketDst>)> {
+    sock.try_io(|| packet::recv_fd_with_dst(sock.as_raw_fd(), buf))
+}
+
+/// Receive a packet from a polled socket.
+///
+/// Packet destinations are only reported on linux.
+#[cfg(all(feature = "mio", not(target_os = "linux")))]
+fn recv_poll_sock(sock: &mio::net::UdpSocket,
+                  buf: &mut [u8])
+                  -> io::Result<(usize, SocketAddr, Option<PacketDst>)> {
+    sock.recv_from(buf).map(|(size, addr)| (size, addr, None))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::net::UdpSocket;
+    use std::sync::mpsc::{self, RecvTimeoutError};
+    use std::thread;
+    use std::time::{Duration, SystemTime};
+
+    use header::HeaderRef;
+    use message::{Quirks, SearchResponse};
+    use queue::OverflowPolicy;
+    use super::{SSDPReceiver, ReceiveError, ReceiveHandlers, DedupKey, Filter, Verdict, spawn_filtered};
+
+    #[test]
+    fn positive_receive_response() {
+        let recv_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let send_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let recv_addr = recv_sock.local_addr().unwrap();
+
+        let receiver = SSDPReceiver::<SearchResponse>::new(vec![recv_sock], None).unwrap();
+        send_sock.send_to(b"HTTP/1.1 200 OK\r\n\r\n", recv_addr).unwrap();
+
+        let (_, src) = receiver.recv().unwrap();
+        assert_eq!(src, send_sock.local_addr().unwrap());
+    }
+
+    #[test]
+    fn positive_receive_info() {
+        let recv_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let send_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let recv_addr = recv_sock.local_addr().unwrap();
+
+        let receiver = SSDPReceiver::<SearchResponse>::new(vec![recv_sock], None).unwrap();
+        let before = SystemTime::now();
+        send_sock.send_to(b"HTTP/1.1 200 OK\r\n\r\n", recv_addr).unwrap();
+
+        let (response, _) = receiver.recv().unwrap();
+        let info = response.receive_info().unwrap();
+        assert_eq!(info.local_addr(), recv_addr);
+        assert!(info.received_at() >= before);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn positive_receive_info_wildcard() {
+        use std::net::SocketAddr;
+
+        use net;
+
+        let recv_sock = UdpSocket::bind("0.0.0.0:0").unwrap();
+        let send_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let recv_addr = SocketAddr::new("127.0.0.1".parse().unwrap(), recv_sock.local_addr().unwrap().port());
+
+        let receiver = SSDPReceiver::<SearchResponse>::new(vec![recv_sock], None).unwrap();
+        send_sock.send_to(b"HTTP/1.1 200 OK\r\n\r\n", recv_addr).unwrap();
+
+        let (response, _) = receiver.recv().unwrap();
+        let info = response.receive_info().unwrap();
+        assert_eq!(info.local_addr(), recv_addr);
+        assert_eq!(info.dst_addr(), Some(recv_addr.ip()));
+        assert_eq!(info.interface(), net::interface_name(&recv_addr.ip()).as_ref().map(|name| &name[..]));
+        assert!(info.interface_index().is_some());
+    }
+
+    #[test]
+    fn positive_receive_info_bytes() {
+        let recv_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let send_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let recv_addr = recv_sock.local_addr().unwrap();
+        let packet = b"HTTP/1.1 200 OK\r\nX-VENDOR: data;raw\r\n\r\n";
+
+        let receiver = SSDPReceiver::<SearchResponse>::new(vec![recv_sock], None).unwrap();
+        send_sock.send_to(packet, recv_addr).unwrap();
+
+        let (response, _) = receiver.recv().unwrap();
+        let info = response.receive_info().unwrap();
+        assert_eq!(info.bytes(), &packet[..]);
+
+        // Clones share the bytes instead of copying them
+        assert_eq!(info.clone().bytes().as_ptr(), info.bytes().as_ptr());
+    }
+
+    #[test]
+    fn positive_recv_timeout() {
+        let recv_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let send_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let recv_addr = recv_sock.local_addr().unwrap();
+
+        let receiver = SSDPReceiver::<SearchResponse>::new(vec![recv_sock], None).unwrap();
+        assert_eq!(receiver.recv_timeout(Duration::from_millis(10)).unwrap_err(),
+                   RecvTimeoutError::Timeout);
+
+        send_sock.send_to(b"HTTP/1.1 200 OK\r\n\r\n", recv_addr).unwrap();
+        assert!(receiver.recv_timeout(Duration::from_secs(1)).is_ok());
+    }
+
+    #[test]
+    fn negative_recv_timeout_idle_timeout() {
+        let recv_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+        let mut receiver = SSDPReceiver::<SearchResponse>::new(vec![recv_sock], None).unwrap();
+        receiver.set_idle_timeout(Some(Duration::from_millis(10)));
+
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(1)).unwrap_err(),
+                   RecvTimeoutError::Disconnected);
+    }
+
+    #[test]
+    fn positive_receiver_timeout() {
+        let recv_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+        let receiver = SSDPReceiver::<SearchResponse>::new(vec![recv_sock], Some(Duration::from_millis(10)))
+            .unwrap();
+
+        assert!(receiver.recv().is_err());
+    }
+
+    #[test]
+    fn positive_shutdown_receiver() {
+        let recv_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let send_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let recv_addr = recv_sock.local_addr().unwrap();
+
+        let receiver = SSDPReceiver::<SearchResponse>::new(vec![recv_sock], None).unwrap();
+        send_sock.send_to(b"HTTP/1.1 200 OK\r\n\r\n", recv_addr).unwrap();
+
+        let handle = receiver.handle();
+        handle.shutdown();
+
+        assert!(handle.is_shutdown());
+        assert!(receiver.recv().is_err());
+    }
+
+    #[test]
+    fn positive_shutdown_blocked_receiver() {
+        let recv_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+        let receiver = SSDPReceiver::<SearchResponse>::new(vec![recv_sock], None).unwrap();
+        let handle = receiver.handle();
+
+        let recv_thread = thread::spawn(move || receiver.into_iter().count());
+        handle.shutdown();
+
+        assert_eq!(recv_thread.join().unwrap(), 0);
+    }
+
+    #[test]
+    fn positive_receiver_deadline() {
+        let recv_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+        let mut receiver = SSDPReceiver::<SearchResponse>::new(vec![recv_sock], None).unwrap();
+        receiver.set_deadline(Some(Duration::from_millis(10)));
+
+        assert!(receiver.recv().is_err());
+        assert!(receiver.handle().is_shutdown());
+    }
+
+    #[test]
+    fn positive_receiver_idle_timeout() {
+        let recv_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let send_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let recv_addr = recv_sock.local_addr().unwrap();
+
+        let mut receiver = SSDPReceiver::<SearchResponse>::new(vec![recv_sock], None).unwrap();
+        receiver.set_idle_timeout(Some(Duration::from_millis(500)));
+        send_sock.send_to(b"HTTP/1.1 200 OK\r\n\r\n", recv_addr).unwrap();
+
+        assert!(receiver.recv().is_ok());
+        assert!(receiver.recv().is_err());
+        assert!(receiver.handle().is_shutdown());
+    }
+
+    #[test]
+    fn positive_subscribe_receiver() {
+        let recv_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let send_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let recv_addr = recv_sock.local_addr().unwrap();
+
+        let receiver = SSDPReceiver::<SearchResponse>::new(vec![recv_sock], None).unwrap();
+        let subscriber = receiver.subscribe();
+        send_sock.send_to(b"HTTP/1.1 200 OK\r\n\r\n", recv_addr).unwrap();
+
+        assert_eq!(receiver.recv().unwrap().1, send_sock.local_addr().unwrap());
+        assert_eq!(subscriber.recv().unwrap().1, send_sock.local_addr().unwrap());
+    }
+
+    #[test]
+    fn positive_subscriber_outlives_receiver() {
+        let recv_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let send_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let recv_addr = recv_sock.local_addr().unwrap();
+
+        let mut receiver = SSDPReceiver::<SearchResponse>::new(vec![recv_sock], None).unwrap();
+        let subscriber = receiver.subscribe();
+
+        receiver.set_deadline(Some(Duration::from_millis(0)));
+        assert!(receiver.recv().is_err());
+
+        send_sock.send_to(b"HTTP/1.1 200 OK\r\n\r\n", recv_addr).unwrap();
+        assert!(subscriber.recv().is_ok());
+        assert!(!subscriber.handle().is_shutdown());
+    }
+
+    #[test]
+    fn positive_bounded_receiver_drops_oldest() {
+        let recv_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let send_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let recv_addr = recv_sock.local_addr().unwrap();
+
+        let receiver = SSDPReceiver::<SearchResponse>::with_capacity(vec![recv_sock],
+                                                                     None,
+                                                                     1,
+                                                                     OverflowPolicy::DropOldest)
+            .unwrap();
+        send_sock.send_to(b"HTTP/1.1 200 OK\r\n\r\n", recv_addr).unwrap();
+        send_sock.send_to(b"HTTP/1.1 200 OK\r\nUSN: second\r\n\r\n", recv_addr).unwrap();
+
+        // Give the receiver thread time to queue up both packets
+        thread::sleep(Duration::from_millis(200));
+
+        assert!(receiver.recv().unwrap().0.get_raw("USN").is_some());
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn positive_subscribe_while_blocked() {
+        let recv_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let send_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let recv_addr = recv_sock.local_addr().unwrap();
+        let (send, recv) = mpsc::channel();
+
+        let receiver = SSDPReceiver::<SearchResponse>::with_capacity(vec![recv_sock],
+                                                                     None,
+                                                                     1,
+                                                                     OverflowPolicy::Block)
+            .unwrap();
+        send_sock.send_to(b"HTTP/1.1 200 OK\r\n\r\n", recv_addr).unwrap();
+        send_sock.send_to(b"HTTP/1.1 200 OK\r\n\r\n", recv_addr).unwrap();
+
+        // Give the receiver thread time to block on the full queue
+        thread::sleep(Duration::from_millis(200));
+
+        thread::spawn(move || {
+            let subscriber = receiver.subscribe();
+            receiver.close();
+
+            send.send(subscriber.handle().is_shutdown()).unwrap();
+        });
+
+        assert_eq!(recv.recv_timeout(Duration::from_secs(5)), Ok(false));
+    }
+
+    #[test]
+    fn negative_zero_capacity() {
+        let recv_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+        let result = SSDPReceiver::<SearchResponse>::with_capacity(vec![recv_sock],
+                                                                   None,
+                                                                   0,
+                                                                   OverflowPolicy::Block);
+
+        assert_eq!(result.err().unwrap().kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn positive_filtered_receiver_last() {
+        let recv_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let send_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let recv_addr = recv_sock.local_addr().unwrap();
+
+        let filter: Filter<SearchResponse> = Box::new(|response, _| {
+            match response.get_raw("USN") {
+                Some(_) => Verdict::Last,
+                None => Verdict::Drop,
+            }
+        });
+        let receiver = spawn_filtered(vec![recv_sock],
+                                      None,
+                                      None,
+                                      OverflowPolicy::Block,
+                                      Some(filter),
+                                      ReceiveHandlers::new(),
+                                      Quirks::default())
+            .unwrap();
+
+        send_sock.send_to(b"HTTP/1.1 200 OK\r\n\r\n", recv_addr).unwrap();
+        send_sock.send_to(b"HTTP/1.1 200 OK\r\nUSN: last\r\n\r\n", recv_addr).unwrap();
+        send_sock.send_to(b"HTTP/1.1 200 OK\r\nUSN: after\r\n\r\n", recv_addr).unwrap();
+
+        assert_eq!(receiver.recv().unwrap().0.get_raw("USN").unwrap()[0], b"last".to_vec());
+        assert!(receiver.recv().is_err());
+    }
+
+    #[test]
+    fn positive_dedup_receiver() {
+        let recv_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let send_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let recv_addr = recv_sock.local_addr().unwrap();
+
+        let mut receiver = SSDPReceiver::<SearchResponse>::new(vec![recv_sock], None).unwrap();
+        receiver.set_dedup(Some(DedupKey::Usn));
+
+        send_sock.send_to(b"HTTP/1.1 200 OK\r\nUSN: first\r\n\r\n", recv_addr).unwrap();
+        send_sock.send_to(b"HTTP/1.1 200 OK\r\nUSN: first\r\n\r\n", recv_addr).unwrap();
+        send_sock.send_to(b"HTTP/1.1 200 OK\r\nUSN: second\r\n\r\n", recv_addr).unwrap();
+
+        assert_eq!(receiver.recv().unwrap().0.get_raw("USN").unwrap()[0], b"first".to_vec());
+        assert_eq!(receiver.recv().unwrap().0.get_raw("USN").unwrap()[0], b"second".to_vec());
+    }
+
+    #[test]
+    fn positive_dedup_usn_location() {
+        let recv_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let send_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let recv_addr = recv_sock.local_addr().unwrap();
+
+        let mut receiver = SSDPReceiver::<SearchResponse>::new(vec![recv_sock], None).unwrap();
+        receiver.set_dedup(Some(DedupKey::UsnLocation));
+
+        send_sock.send_to(b"HTTP/1.1 200 OK\r\nUSN: first\r\nLOCATION: a\r\n\r\n", recv_addr).unwrap();
+        send_sock.send_to(b"HTTP/1.1 200 OK\r\nUSN: first\r\nLOCATION: a\r\n\r\n", recv_addr).unwrap();
+        send_sock.send_to(b"HTTP/1.1 200 OK\r\nUSN: first\r\nLOCATION: b\r\n\r\n", recv_addr).unwrap();
+
+        assert_eq!(receiver.recv().unwrap().0.get_raw("LOCATION").unwrap()[0], b"a".to_vec());
+        assert_eq!(receiver.recv().unwrap().0.get_raw("LOCATION").unwrap()[0], b"b".to_vec());
+    }
+
+    #[test]
+    fn positive_receiver_max_messages() {
+        let recv_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let send_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let recv_addr = recv_sock.local_addr().unwrap();
+
+        let mut receiver = SSDPReceiver::<SearchResponse>::new(vec![recv_sock], None).unwrap();
+        receiver.set_max_messages(Some(1));
+
+        send_sock.send_to(b"HTTP/1.1 200 OK\r\n\r\n", recv_addr).unwrap();
+        send_sock.send_to(b"HTTP/1.1 200 OK\r\n\r\n", recv_addr).unwrap();
+
+        assert!(receiver.recv().is_ok());
+        assert!(receiver.recv().is_err());
+        assert!(receiver.handle().is_shutdown());
+    }
+
+    #[test]
+    fn positive_receive_callback() {
+        let recv_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let send_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let recv_addr = recv_sock.local_addr().unwrap();
+        let (send, recv) = mpsc::channel();
+
+        let handle = super::spawn_callback_filtered(vec![recv_sock],
+                                                    None,
+                                                    None,
+                                                    ReceiveHandlers::new(),
+                                                    Quirks::default(),
+                                                    move |_: SearchResponse, src| send.send(src).unwrap())
+            .unwrap();
+        send_sock.send_to(b"HTTP/1.1 200 OK\r\n\r\n", recv_addr).unwrap();
+
+        assert_eq!(recv.recv().unwrap(), send_sock.local_addr().unwrap());
+        handle.shutdown();
+    }
+
+    #[test]
+    fn positive_report_error() {
+        let (send, recv) = mpsc::channel();
+        let handlers = ReceiveHandlers::new().on_error(move |err| send.send(err).unwrap());
+
+        let receiver = SSDPReceiver::<SearchResponse>::with_handlers(vec![], None, handlers).unwrap();
+        receiver.handle().report(ReceiveError::Socket("127.0.0.1:1900".parse().unwrap(),
+                                           io::Error::new(io::ErrorKind::Other, "test")));
+
+        match recv.recv_timeout(Duration::from_secs(1)).unwrap() {
+            ReceiveError::Socket(addr, _) => assert_eq!(addr, "127.0.0.1:1900".parse().unwrap()),
+        }
+    }
+
+    #[test]
+    fn positive_drop_shuts_down() {
+        let recv_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+        let receiver = SSDPReceiver::<SearchResponse>::new(vec![recv_sock], None).unwrap();
+        let subscriber = receiver.subscribe();
+        let handle = receiver.handle();
+
+        drop(receiver);
+        assert!(!handle.is_shutdown());
+
+        drop(subscriber);
+        assert!(handle.is_shutdown());
+    }
+
+    #[test]
+    fn positive_report_malformed() {
+        let recv_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let send_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let recv_addr = recv_sock.local_addr().unwrap();
+
+        let (send, recv) = mpsc::channel();
+        let handlers = ReceiveHandlers::new()
+            .on_malformed(move |packet| send.send((packet.bytes().to_vec(), packet.source())).unwrap());
+
+        // Packets waiting before the receiver threads spawn are reported as well
+        send_sock.send_to(b"NOT SSDP\r\n\r\n", recv_addr).unwrap();
+        let receiver = SSDPReceiver::<SearchResponse>::with_handlers(vec![recv_sock], None, handlers)
+            .unwrap();
+        let handle = receiver.handle();
+
+        let (bytes, src) = recv.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert_eq!(&bytes[..], &b"NOT SSDP\r\n\r\n"[..]);
+        assert_eq!(src, send_sock.local_addr().unwrap());
+        handle.shutdown();
+    }
+
+    #[test]
+    fn negative_strict_receiver_lenient_packet() {
+        let recv_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let send_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let recv_addr = recv_sock.local_addr().unwrap();
+
+        let (send, recv) = mpsc::channel();
+        let handlers = ReceiveHandlers::new()
+            .on_malformed(move |packet| send.send(packet.bytes().to_vec()).unwrap());
+        let receiver = spawn_filtered::<SearchResponse>(vec![recv_sock],
+                                                        None,
+                                                        None,
+                                                        OverflowPolicy::Block,
+                                                        None,
+                                                        handlers,
+                                                        Quirks::none())
+            .unwrap();
+
+        // Parses leniently, but ends its lines with a bare LF
+        send_sock.send_to(b"HTTP/1.1 200 OK\nEXT:\n\n", recv_addr).unwrap();
+
+        let bytes = recv.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert_eq!(&bytes[..], &b"HTTP/1.1 200 OK\nEXT:\n\n"[..]);
+        assert!(receiver.try_recv().is_err());
+        receiver.handle().shutdown();
+    }
+
+    #[cfg(feature = "futures")]
+    #[test]
+    fn positive_receiver_stream() {
+        use std::future;
+        use std::pin::Pin;
+
+        use futures_core::Stream;
+        use tokio::runtime::Builder;
+
+        let recv_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let send_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let recv_addr = recv_sock.local_addr().unwrap();
+
+        let receiver = SSDPReceiver::<SearchResponse>::new(vec![recv_sock], Some(Duration::from_millis(500)))
+            .unwrap();
+        send_sock.send_to(b"HTTP/1.1 200 OK\r\n\r\n", recv_addr).unwrap();
+        send_sock.send_to(b"HTTP/1.1 200 OK\r\n\r\n", recv_addr).unwrap();
+
+        let runtime = Builder::new_current_thread().build().unwrap();
+        let mut stream = receiver.into_stream();
+        let mut count = 0;
+        let mut next = || runtime.block_on(future::poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)));
+        while let Some((_, src)) = next() {
+            assert_eq!(src, send_sock.local_addr().unwrap());
+            count += 1;
+        }
+
+        assert_eq!(count, 2);
+    }
+
+    #[cfg(feature = "futures")]
+    #[test]
+    fn positive_drop_stream_shuts_down() {
+        let recv_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+        let receiver = SSDPReceiver::<SearchResponse>::new(vec![recv_sock], None).unwrap();
+        let handle = receiver.handle();
+
+        drop(receiver.into_stream());
+        thread::sleep(Duration::from_millis(500));
+
+        assert!(handle.is_shutdown());
+    }
+
+    #[test]
+    fn positive_no_sockets() {
+        let receiver = SSDPReceiver::<SearchResponse>::new(Vec::new(), None).unwrap();
+
+        assert!(receiver.recv().is_err());
     }
 }
\ No newline at end of file
@@ -0,0 +1,823 @@
+//! Answering of search requests for registered devices and services.
+
+use std::cmp;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use SSDPResult;
+use advertiser;
+use bootid::BootIdManager;
+use header::{HeaderRef, MX, ST, MX_HEADER_UDA11_MAX};
+use message::{self, DeviceAnnouncement, Listen, ListenOptions, NotifyMessage, SearchRequest,
+              SearchRequestListener, SearchResponse};
+use net::{self, LocalSubnet};
+use receiver::ListenHandle;
+
+/// Function used to send responses from an optional local address, swapped out when testing.
+type SendFn = Arc<Fn(&mut SearchResponse, Option<SocketAddr>, SocketAddr) -> SSDPResult<()> + Send + Sync>;
+
+/// Identifies an announcement registered with a `Responder`.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct ResponseId(usize);
+
+/// Maximum number of responses sent within a period of time.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct RateLimit {
+    max_responses: u32,
+    period: Duration,
+}
+
+impl RateLimit {
+    /// Construct a new RateLimit allowing at most max_responses per period.
+    pub fn new(max_responses: u32, period: Duration) -> RateLimit {
+        RateLimit {
+            max_responses: max_responses,
+            period: period,
+        }
+    }
+}
+
+/// Number of responses sent within the current period of a `RateLimit`.
+#[derive(Copy, Clone)]
+struct Window {
+    start: Instant,
+    count: u32,
+}
+
+impl Window {
+    fn new(now: Instant) -> Window {
+        Window {
+            start: now,
+            count: 0,
+        }
+    }
+
+    fn is_expired(&self, limit: &RateLimit, now: Instant) -> bool {
+        now >= self.start + limit.period
+    }
+
+    /// Number of responses that can still be sent at now, starting a new period
+    /// if the current one has passed.
+    fn remaining(&mut self, limit: &RateLimit, now: Instant) -> u32 {
+        if self.is_expired(limit, now) {
+            *self = Window::new(now);
+        }
+
+        limit.max_responses.saturating_sub(self.count)
+    }
+}
+
+struct Entry {
+    id: usize,
+    message: NotifyMessage,
+}
+
+/// Response waiting for its random delay to pass before being sent.
+struct Pending {
+    due: Instant,
+    response: SearchResponse,
+    local_addr: Option<SocketAddr>,
+    dst_addr: SocketAddr,
+}
+
+struct State {
+    entries: Vec<Entry>,
+    next_id: usize,
+    pending: Vec<Pending>,
+    max_mx: u8,
+    local_sources_only: bool,
+    strict_man: bool,
+    global_limit: Option<RateLimit>,
+    global_window: Window,
+    source_limit: Option<RateLimit>,
+    source_windows: HashMap<IpAddr, Window>,
+    boot_ids: Option<Arc<BootIdManager>>,
+    shutdown: bool,
+}
+
+impl State {
+    /// Number of the requested responses to the source address that the rate
+    /// limits allow to be sent at now, counting them as sent.
+    fn allow(&mut self, src_ip: IpAddr, requested: usize, now: Instant) -> usize {
+        let mut allowed = requested;
+
+        if let Some(limit) = self.global_limit {
+            allowed = cmp::min(allowed, self.global_window.remaining(&limit, now) as usize);
+        }
+        if let Some(limit) = self.source_limit {
+            // Forget sources whose period has passed, so spoofed addresses don't pile up
+            self.source_windows.retain(|_, window| !window.is_expired(&limit, now));
+
+            let window = self.source_windows.entry(src_ip).or_insert_with(|| Window::new(now));
+            allowed = cmp::min(allowed, window.remaining(&limit, now) as usize);
+            window.count += allowed as u32;
+        }
+        if self.global_limit.is_some() {
+            self.global_window.count += allowed as u32;
+        }
+
+        allowed
+    }
+}
+
+struct Shared {
+    state: Mutex<State>,
+    wakeup: Condvar,
+}
+
+impl Shared {
+    fn new() -> Shared {
+        Shared {
+            state: Mutex::new(State {
+                entries: Vec::new(),
+                next_id: 0,
+                pending: Vec::new(),
+                max_mx: MX_HEADER_UDA11_MAX,
+                local_sources_only: false,
+                strict_man: true,
+                global_limit: None,
+                global_window: Window::new(Instant::now()),
+                source_limit: None,
+                source_windows: HashMap::new(),
+                boot_ids: None,
+                shutdown: false,
+            }),
+            wakeup: Condvar::new(),
+        }
+    }
+
+    fn lock<'a>(&'a self) -> MutexGuard<'a, State> {
+        // Responses are sent without the lock held, recover from poisoning
+        match self.state.lock() {
+            Ok(n) => n,
+            Err(err) => err.into_inner(),
+        }
+    }
+
+    fn add(&self, message: NotifyMessage) -> ResponseId {
+        let mut state = self.lock();
+        let id = state.next_id;
+        state.next_id += 1;
+
+        state.entries.push(Entry {
+            id: id,
+            message: message,
+        });
+
+        ResponseId(id)
+    }
+
+    /// Build the responses to the search request from the registered announcements.
+    fn responses(&self, request: &SearchRequest) -> Vec<SearchResponse> {
+        let state = self.lock();
+        if !request.man_validity().is_acceptable(state.strict_man) {
+            debug!("Responder ignoring search request with invalid MAN header");
+            return Vec::new();
+        }
+
+        let search_target = match request.get::<ST>() {
+            Some(st) => st.clone(),
+            None => {
+                debug!("Responder ignoring search request without ST header");
+                return Vec::new();
+            }
+        };
+
+
+        let mut responses = Vec::new();
+        for entry in &state.entries {
+            match entry.message.respond_to(&search_target) {
+                Ok(Some(response)) => responses.push(response),
+                Ok(None) => (),
+                Err(err) => warn!("Responder failed to build response: {}", err),
+            }
+        }
+
+        responses
+    }
+
+    /// Queue the responses to the search request, to be sent to the source address
+    /// after a random delay within the MX wait bound of the request.
+    fn schedule(&self, request: &SearchRequest, src_addr: SocketAddr) {
+        let local_sources_only = self.lock().local_sources_only;
+        if local_sources_only && !is_local_source(&src_addr.ip()) {
+            debug!("Responder ignoring search request from non local source {}", src_addr);
+            return;
+        }
+
+        let mut responses = self.responses(request);
+        if responses.is_empty() {
+            return;
+        }
+
+        // Sockets bound to an unspecified address only know the interface that received
+        // the search where the platform reports packet destinations
+        let opt_local_addr = request.receive_info()
+            .map(|info| info.local_addr())
+            .filter(|addr| !addr.ip().is_unspecified());
+
+        let mut state = self.lock();
+        let now = Instant::now();
+
+        let allowed = state.allow(src_addr.ip(), responses.len(), now);
+        if allowed < responses.len() {
+            debug!("Responder rate limited {} of {} responses to {}",
+                   responses.len() - allowed,
+                   responses.len(),
+                   src_addr);
+            responses.truncate(allowed);
+        }
+
+        let due = now + response_delay(request.get::<MX>().as_ref(), state.max_mx);
+        for response in responses {
+            state.pending.push(Pending {
+                due: due,
+                response: response,
+                local_addr: opt_local_addr,
+                dst_addr: src_addr,
+            });
+        }
+
+        self.wakeup.notify_one();
+    }
+}
+
+/// Answers `M-SEARCH` requests multicast by control points with a unicast
+/// `SearchResponse` for every registered announcement matching the search target.
+///
+/// Searches for "ssdp:all", "upnp:rootdevice", uuids and device or service types
+/// are matched as defined by the standard, including searches for older versions.
+///
+/// Responses are sent from the local interface that received the search, so that
+/// the LOCATION header, which may use the `LOCATION_BIND_ADDR` placeholder, is
+/// reachable by the control point.
+///
+/// Responses are delayed by a random duration within the MX wait bound of the
+/// search, as required by the standard, and sent from a background thread so that
+/// devices on large networks do not all respond at once.
+///
+/// Stopping or dropping the Responder stops listening for search requests and
+/// discards any responses that have not been sent yet.
+pub struct Responder {
+    shared: Arc<Shared>,
+    handle: ListenHandle,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Responder {
+    /// Construct a new Responder listening for search requests on the standard
+    /// multicast address:port of all local network interfaces.
+    pub fn new() -> SSDPResult<Responder> {
+        Responder::with_port(message::UPNP_MULTICAST_PORT)
+    }
+
+    /// Construct a new Responder listening for search requests on the standard
+    /// multicast address but a custom port.
+    pub fn with_port(port: u16) -> SSDPResult<Responder> {
+        Responder::with_options(&ListenOptions::new().port(port))
+    }
+
+    /// Construct a new Responder listening for search requests as configured by the options.
+    pub fn with_options(options: &ListenOptions) -> SSDPResult<Responder> {
+        Responder::with_sender(options, Arc::new(send_response))
+    }
+
+    fn with_sender(options: &ListenOptions, send: SendFn) -> SSDPResult<Responder> {
+        let shared = Arc::new(Shared::new());
+
+        let listen_shared = shared.clone();
+        let schedule = move |request: SearchRequest, src_addr| listen_shared.schedule(&request, src_addr);
+        let handle = try!(SearchRequestListener::listen_with_options_callback(options, schedule));
+
+        let thread_shared = shared.clone();
+        let thread = thread::spawn(move || run_responder(thread_shared, send));
+
+        Ok(Responder {
+            shared: shared,
+            handle: handle,
+            thread: Some(thread),
+        })
+    }
+
+    /// Cap the MX wait bound of searches, in seconds, so that searches with a
+    /// large MX are not left waiting for a response for too long.
+    ///
+    /// Defaults to the 5 second maximum from the `UPnP` 1.1 standard.
+    pub fn set_max_mx(&self, max_mx: u8) {
+        self.shared.lock().max_mx = max_mx;
+    }
+
+    /// Ignore search requests whose source address is not on a subnet that a local
+    /// network interface is directly attached to, or for IPv6 is not link-local,
+    /// to prevent the device being abused for SSDP reflection attacks.
+    ///
+    /// Disabled by default.
+    pub fn set_local_sources_only(&self, enabled: bool) {
+        self.shared.lock().local_sources_only = enabled;
+    }
+
+    /// Only answer search requests whose MAN header is exactly `"ssdp:discover"`.
+    ///
+    /// Enabled by default. When disabled, the unquoted `ssdp:discover` that some
+    /// control points send is also accepted.
+    pub fn set_strict_man(&self, enabled: bool) {
+        self.shared.lock().strict_man = enabled;
+    }
+
+    /// Limit the number of responses sent to any one source address, so that a
+    /// control point flooding searches can not use the device as a traffic amplifier.
+    ///
+    /// Responses over the limit are dropped. Passing None removes the limit, which is the default.
+    pub fn set_source_rate_limit(&self, opt_limit: Option<RateLimit>) {
+        let mut state = self.shared.lock();
+
+        state.source_limit = opt_limit;
+        state.source_windows.clear();
+    }
+
+    /// Limit the number of responses sent to all source addresses combined.
+    ///
+    /// Responses over the limit are dropped. Passing None removes the limit, which is the default.
+    pub fn set_global_rate_limit(&self, opt_limit: Option<RateLimit>) {
+        let mut state = self.shared.lock();
+
+        state.global_limit = opt_limit;
+        state.global_window = Window::new(Instant::now());
+    }
+
+    /// Stamp the BOOTID.UPNP.ORG and CONFIGID.UPNP.ORG headers from the manager
+    /// onto every response sent from now on.
+    ///
+    /// Passing None will send the responses with whatever headers their announcements
+    /// were added with.
+    pub fn set_boot_id_manager(&self, opt_boot_ids: Option<Arc<BootIdManager>>) {
+        self.shared.lock().boot_ids = opt_boot_ids;
+    }
+
+    /// Register an `ssdp:alive` announcement to answer matching searches with.
+    ///
+    /// The message must carry the NT, USN, LOCATION, CACHE-CONTROL and SERVER
+    /// headers, which are copied into every response.
+    pub fn add(&self, message: NotifyMessage) -> SSDPResult<ResponseId> {
+        try!(message.respond_to(&ST::All));
+
+        Ok(self.shared.add(message))
+    }
+
+    /// Register every announcement required for the device, so that the device
+    /// answers searches with the same headers that an `Advertiser` announces.
+    pub fn add_device(&self, device: &DeviceAnnouncement) -> SSDPResult<Vec<ResponseId>> {
+        let messages = try!(device.alive_messages());
+
+        let mut ids = Vec::with_capacity(messages.len());
+        for message in messages {
+            match self.add(message) {
+                Ok(id) => ids.push(id),
+                Err(err) => {
+                    for id in ids {
+                        self.remove(id);
+                    }
+                    return Err(err);
+                }
+            }
+        }
+
+        Ok(ids)
+    }
+
+    /// Stop answering searches with the announcement.
+    ///
+    /// Returns false if the announcement was not registered.
+    pub fn remove(&self, id: ResponseId) -> bool {
+        let mut state = self.shared.lock();
+
+        match state.entries.iter().position(|entry| entry.id == id.0) {
+            Some(index) => {
+                state.entries.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Number of announcements currently registered.
+    pub fn len(&self) -> usize {
+        self.shared.lock().entries.len()
+    }
+
+    /// Whether or not no announcements are currently registered.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Stop listening for search requests and discard any responses that have
+    /// not been sent yet.
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        self.handle.shutdown();
+
+        self.shared.lock().shutdown = true;
+        self.shared.wakeup.notify_one();
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for Responder {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Whether or not the source address is link-local or on a directly attached subnet.
+fn is_local_source(ip: &IpAddr) -> bool {
+    if net::is_link_local_v6(ip) {
+        return true;
+    }
+
+    match net::local_subnets() {
+        Ok(subnets) => is_on_subnet(ip, &subnets),
+        Err(err) => {
+            warn!("Responder failed to find local subnets: {}", err);
+            false
+        }
+    }
+}
+
+fn is_on_subnet(ip: &IpAddr, subnets: &[LocalSubnet]) -> bool {
+    subnets.iter().any(|subnet| subnet.contains(ip))
+}
+
+/// Send the response from the local address that received the search, or from the
+/// local address that the operating system routes the destination address through.
+fn send_response(response: &mut SearchResponse,
+                 opt_local_addr: Option<SocketAddr>,
+                 dst_addr: SocketAddr)
+                 -> SSDPResult<()> {
+    let local_addr = match opt_local_addr {
+        Some(n) => n,
+        None => try!(net::route_local_addr(&dst_addr)),
+    };
+
+    response.unicast_from(local_addr, dst_addr)
+}
+
+/// Random duration to delay the response to a search by, within its MX wait bound
+/// capped at max_mx seconds.
+///
+/// Searches without an MX header, such as unicast searches, are responded to immediately.
+fn response_delay(opt_mx: Option<&MX>, max_mx: u8) -> Duration {
+    match opt_mx {
+        Some(&MX(n)) => {
+            advertiser::random_between(Duration::from_millis(0),
+                                       Duration::from_secs(cmp::min(n, max_mx) as u64))
+        }
+        None => Duration::from_millis(0),
+    }
+}
+
+/// Send responses as their delay passes until the responder is shutdown.
+fn run_responder(shared: Arc<Shared>, send: SendFn) {
+    let mut state = shared.lock();
+
+    loop {
+        if state.shutdown {
+            return;
+        }
+
+        let now = Instant::now();
+        let (due, pending) = state.pending.drain(..).partition::<Vec<_>, _>(|pending| pending.due <= now);
+        state.pending = pending;
+
+        if !due.is_empty() {
+            let opt_boot_ids = state.boot_ids.clone();
+
+            // Sending can take a while, don't block incoming searches while it happens
+            drop(state);
+            for mut pending in due {
+                if let Some(ref boot_ids) = opt_boot_ids {
+                    boot_ids.stamp(&mut pending.response);
+                }
+                if let Err(err) = send(&mut pending.response, pending.local_addr, pending.dst_addr) {
+                    warn!("Responder failed to send response to {}: {}", pending.dst_addr, err);
+                }
+            }
+
+            state = shared.lock();
+            continue;
+        }
+
+        let opt_next_due = state.pending.iter().map(|pending| pending.due).min();
+        state = match opt_next_due {
+            Some(next_due) => {
+                match shared.wakeup.wait_timeout(state, next_due - now) {
+                    Ok((n, _)) => n,
+                    Err(err) => err.into_inner().0,
+                }
+            }
+            None => {
+                match shared.wakeup.wait(state) {
+                    Ok(n) => n,
+                    Err(err) => err.into_inner(),
+                }
+            }
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{mpsc, Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+
+    use FieldMap;
+    use bootid::{BootIdManager, MemoryStorage};
+    use header::{BootID, ConfigID, HeaderRef, HeaderMut, MX, ST, USN};
+    use message::{DeviceAnnouncement, ListenOptions, SearchRequest, SearchResponse};
+    use receiver::{FromRawSSDP, ReceiveInfo};
+    use net::LocalSubnet;
+    use super::{RateLimit, Shared, SendFn, response_delay, run_responder};
+
+    fn device() -> DeviceAnnouncement {
+        DeviceAnnouncement::new("root", FieldMap::urn("schemas-upnp-org:device:MediaServer:1"))
+            .service(FieldMap::urn("schemas-upnp-org:service:ContentDirectory:2"))
+            .location("http://192.168.0.2:80/desc.xml")
+            .max_age(1800)
+            .server("Linux/3.0 UPnP/1.0 Test/1.0")
+    }
+
+    fn shared_with_device() -> Shared {
+        let shared = Shared::new();
+        for message in device().alive_messages().unwrap() {
+            shared.add(message);
+        }
+
+        shared
+    }
+
+    fn request(search_target: ST) -> SearchRequest {
+        SearchRequest::builder().search_target(search_target).mx(1).build().unwrap()
+    }
+
+    #[test]
+    fn positive_respond_all() {
+        let shared = shared_with_device();
+
+        assert_eq!(shared.responses(&request(ST::All)).len(), 4);
+    }
+
+    #[test]
+    fn positive_respond_root_device() {
+        let shared = shared_with_device();
+
+        let responses = shared.responses(&request(ST::Target(FieldMap::upnp("rootdevice"))));
+
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0].get::<USN>(),
+                   Some(USN(FieldMap::uuid("root"), Some(FieldMap::upnp("rootdevice")))));
+    }
+
+    #[test]
+    fn positive_respond_older_version() {
+        let shared = shared_with_device();
+        let searched = ST::Target(FieldMap::urn("schemas-upnp-org:service:ContentDirectory:1"));
+
+        let responses = shared.responses(&request(searched.clone()));
+
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0].get::<ST>(), Some(searched));
+    }
+
+    #[test]
+    fn negative_respond_missing_man() {
+        let shared = shared_with_device();
+
+        let mut request = SearchRequest::new();
+        request.set(ST::All);
+
+        assert!(shared.responses(&request).is_empty());
+    }
+
+    #[test]
+    fn positive_source_rate_limit() {
+        let shared = shared_with_device();
+        shared.lock().source_limit = Some(RateLimit::new(5, Duration::from_millis(100)));
+
+        let first = "192.168.0.3:50000".parse().unwrap();
+        let second = "192.168.0.4:50000".parse().unwrap();
+        shared.schedule(&request(ST::All), first);
+        shared.schedule(&request(ST::All), first);
+        shared.schedule(&request(ST::All), second);
+        assert_eq!(shared.lock().pending.len(), 9);
+
+        thread::sleep(Duration::from_millis(150));
+        shared.schedule(&request(ST::All), first);
+        assert_eq!(shared.lock().pending.len(), 13);
+    }
+
+    #[test]
+    fn positive_global_rate_limit() {
+        let shared = shared_with_device();
+        shared.lock().global_limit = Some(RateLimit::new(6, Duration::from_secs(60)));
+
+        shared.schedule(&request(ST::All), "192.168.0.3:50000".parse().unwrap());
+        shared.schedule(&request(ST::All), "192.168.0.4:50000".parse().unwrap());
+        shared.schedule(&request(ST::All), "192.168.0.5:50000".parse().unwrap());
+
+        assert_eq!(shared.lock().pending.len(), 6);
+    }
+
+    #[test]
+    fn positive_no_global_rate_limit_window_untouched() {
+        let shared = shared_with_device();
+        shared.lock().source_limit = Some(RateLimit::new(5, Duration::from_secs(60)));
+
+        shared.schedule(&request(ST::All), "192.168.0.3:50000".parse().unwrap());
+
+        assert_eq!(shared.lock().pending.len(), 4);
+        assert_eq!(shared.lock().global_window.count, 0);
+    }
+
+    #[test]
+    fn positive_lenient_man_accepts_unquoted() {
+        let shared = shared_with_device();
+        let mut unquoted = request(ST::All);
+        unquoted.set_raw("MAN", vec![b"ssdp:discover".to_vec()]);
+
+        assert!(shared.responses(&unquoted).is_empty());
+
+        shared.lock().strict_man = false;
+        assert_eq!(shared.responses(&unquoted).len(), 4);
+    }
+
+    #[test]
+    fn positive_local_sources_only() {
+        let subnet = LocalSubnet::new("192.168.0.2".parse().unwrap(), "255.255.255.0".parse().unwrap());
+
+        assert!(super::is_on_subnet(&"192.168.0.3".parse().unwrap(), &[subnet]));
+        assert!(!super::is_on_subnet(&"8.8.8.8".parse().unwrap(), &[subnet]));
+        assert!(super::is_local_source(&"fe80::1".parse().unwrap()));
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn positive_local_sources_only_loopback() {
+        assert!(super::is_local_source(&"127.0.0.1".parse().unwrap()));
+    }
+
+    #[test]
+    fn negative_local_sources_only_drops_remote_source() {
+        let shared = shared_with_device();
+        shared.lock().local_sources_only = true;
+
+        shared.schedule(&request(ST::All), "203.0.113.7:50000".parse().unwrap());
+
+        assert!(shared.lock().pending.is_empty());
+    }
+
+    #[test]
+    fn positive_response_delay_within_mx() {
+        for _ in 0..100 {
+            assert!(response_delay(Some(&MX(2)), 5) <= Duration::from_secs(2));
+            assert!(response_delay(Some(&MX(120)), 1) <= Duration::from_secs(1));
+        }
+        assert_eq!(response_delay(None, 5), Duration::from_millis(0));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn positive_respond_from_receiving_interface() {
+        use std::net::{SocketAddr, UdpSocket};
+        use std::sync::mpsc;
+
+        use super::Responder;
+
+        // Find a free port for the wildcard listener
+        let port = UdpSocket::bind("0.0.0.0:0").unwrap().local_addr().unwrap().port();
+        let (sent_send, sent_recv) = mpsc::channel();
+        let sent_send = Mutex::new(sent_send);
+
+        let send: SendFn = Arc::new(move |_: &mut SearchResponse, opt_local_addr, dst_addr| {
+            sent_send.lock().unwrap().send((opt_local_addr, dst_addr)).unwrap();
+            Ok(())
+        });
+        let responder = Responder::with_sender(&ListenOptions::new().port(port), send).unwrap();
+        responder.add_device(&device()).unwrap();
+
+        let search_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let search = format!("M-SEARCH * HTTP/1.1\r\nHOST: 127.0.0.1:{}\r\nMAN: \"ssdp:discover\"\r\n\
+                              ST: uuid:root\r\n\r\n",
+                             port);
+        search_sock.send_to(search.as_bytes(), ("127.0.0.1", port)).unwrap();
+
+        // Listener is bound to the wildcard address, yet the response goes out from loopback
+        let local_addr = SocketAddr::new("127.0.0.1".parse().unwrap(), port);
+        assert_eq!(sent_recv.recv_timeout(Duration::from_secs(5)).unwrap(),
+                   (Some(local_addr), search_sock.local_addr().unwrap()));
+    }
+
+    #[test]
+    fn positive_scheduled_response_sent_to_source() {
+        let shared = Arc::new(shared_with_device());
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let thread_sent = sent.clone();
+
+        let send: SendFn = Arc::new(move |response: &mut SearchResponse, opt_local_addr, dst_addr| {
+            thread_sent.lock().unwrap().push((response.get::<ST>(), opt_local_addr, dst_addr));
+            Ok(())
+        });
+        let thread_shared = shared.clone();
+        let thread = thread::spawn(move || run_responder(thread_shared, send));
+
+        let src_addr = "192.168.0.3:50000".parse().unwrap();
+        let mut received = request(ST::Target(FieldMap::uuid("root")));
+        received.set_receive_info(ReceiveInfo::new("192.168.0.2:1900".parse().unwrap(), None));
+        shared.schedule(&received, src_addr);
+        shared.schedule(&request(ST::Target(FieldMap::uuid("root"))), src_addr);
+        thread::sleep(Duration::from_millis(1300));
+
+        shared.lock().shutdown = true;
+        shared.wakeup.notify_one();
+        thread.join().unwrap();
+
+        let target = Some(ST::Target(FieldMap::uuid("root")));
+        let mut sent = sent.lock().unwrap().clone();
+        sent.sort_by_key(|&(_, opt_local_addr, _)| opt_local_addr.is_none());
+        assert_eq!(sent,
+                   vec![(target.clone(), Some("192.168.0.2:1900".parse().unwrap()), src_addr),
+                        (target, None, src_addr)]);
+    }
+
+    /// Run the responder on the shared state, sending every response to the channel.
+    fn spawn_stamped(shared: &Arc<Shared>) -> (mpsc::Receiver<SearchResponse>, thread::JoinHandle<()>) {
+        let (sent_send, sent_recv) = mpsc::channel();
+        let sent_send = Mutex::new(sent_send);
+        let send: SendFn = Arc::new(move |response: &mut SearchResponse, _, _| {
+            sent_send.lock().unwrap().send(response.clone()).unwrap();
+            Ok(())
+        });
+        let thread_shared = shared.clone();
+
+        (sent_recv, thread::spawn(move || run_responder(thread_shared, send)))
+    }
+
+    /// Schedule a response to a search for the root device, which is sent immediately
+    /// since the search has no MX header.
+    fn schedule_unicast(shared: &Shared) {
+        let unicast = SearchRequest::builder()
+            .search_target(ST::Target(FieldMap::uuid("root")))
+            .build()
+            .unwrap();
+
+        shared.schedule(&unicast, "192.168.0.3:50000".parse().unwrap());
+    }
+
+    fn stop_stamped(shared: &Shared, thread: thread::JoinHandle<()>) {
+        shared.lock().shutdown = true;
+        shared.wakeup.notify_one();
+        thread.join().unwrap();
+    }
+
+    #[test]
+    fn positive_stamp_boot_id() {
+        let shared = Arc::new(shared_with_device());
+        let boot_ids = Arc::new(BootIdManager::new(MemoryStorage::new(), 0).unwrap());
+        shared.lock().boot_ids = Some(boot_ids.clone());
+        let (sent, thread) = spawn_stamped(&shared);
+
+        schedule_unicast(&shared);
+        assert_eq!(sent.recv_timeout(Duration::from_secs(1)).unwrap().get::<BootID>(), Some(BootID(1)));
+
+        boot_ids.increment().unwrap();
+        schedule_unicast(&shared);
+        assert_eq!(sent.recv_timeout(Duration::from_secs(1)).unwrap().get::<BootID>(), Some(BootID(2)));
+
+        stop_stamped(&shared, thread);
+    }
+
+    #[test]
+    fn positive_stamp_config_id() {
+        let shared = Arc::new(shared_with_device());
+        let boot_ids = Arc::new(BootIdManager::new(MemoryStorage::new(), 7).unwrap());
+        shared.lock().boot_ids = Some(boot_ids.clone());
+        let (sent, thread) = spawn_stamped(&shared);
+
+        schedule_unicast(&shared);
+        assert_eq!(sent.recv_timeout(Duration::from_secs(1)).unwrap().get::<ConfigID>(), Some(ConfigID(7)));
+
+        boot_ids.set_config_id(8).unwrap();
+        schedule_unicast(&shared);
+        assert_eq!(sent.recv_timeout(Duration::from_secs(1)).unwrap().get::<ConfigID>(), Some(ConfigID(8)));
+
+        stop_stamped(&shared, thread);
+    }
+}
@@ -0,0 +1,301 @@
+//! Primitives for asynchronous SSDP message receiving.
+
+use std::io;
+use std::marker::PhantomData;
+use std::net::{UdpSocket, SocketAddr};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures_core::Stream;
+
+use net;
+use net::packet::{self, MAX_PCKT_LEN};
+use net::runtime::Runtime;
+use message::Quirks;
+use receiver::{self, FromRawSSDP, Filter, Verdict};
+
+/// An asynchronous SSDP message receiver.
+///
+/// Yields the same items as an `SSDPReceiver` but does not tie up any threads
+/// while waiting on responses, all sockets are driven by the reactor of the
+/// `Runtime` R.
+pub struct SSDPStream<T, R>
+    where R: Runtime
+{
+    socks: Vec<R::Socket>,
+    local_info: Vec<Option<(SocketAddr, Option<Arc<str>>)>>,
+    timeout: Option<R::Timer>,
+    filter: Option<Filter<T>>,
+    quirks: Quirks,
+    finished: bool,
+    buf: Vec<u8>,
+    _message: PhantomData<fn() -> T>,
+}
+
+impl<T, R> SSDPStream<T, R>
+    where T: FromRawSSDP,
+          R: Runtime
+{
+    /// Construct a stream that receives bytes from a number of UdpSockets and
+    /// tries to construct an object T from them. If a duration is provided, the
+    /// stream will end after the specified duration.
+    pub fn new(socks: Vec<UdpSocket>, time: Option<Duration>) -> io::Result<SSDPStream<T, R>> {
+        SSDPStream::with_filter(socks, time, None, Quirks::default())
+    }
+
+    /// Construct a stream like `SSDPStream::new` that only yields messages the
+    /// filter, if provided, delivers.
+    ///
+    /// Once the filter returns `Verdict::Last`, the stream ends after yielding
+    /// that message. Packets showing quirks that are not tolerated are dropped.
+    pub fn with_filter(socks: Vec<UdpSocket>,
+                       time: Option<Duration>,
+                       filter: Option<Filter<T>>,
+                       quirks: Quirks)
+                       -> io::Result<SSDPStream<T, R>> {
+        let mut async_socks = Vec::with_capacity(socks.len());
+        let mut local_info = Vec::with_capacity(socks.len());
+
+        for sock in socks {
+            if let Err(err) = packet::set_recv_dst(&sock) {
+                debug!("Packet destinations will not be reported at {:?}: {}", sock.local_addr(), err);
+            }
+
+            let opt_info = sock.local_addr()
+                .ok()
+                .map(|addr| (addr, net::interface_name(&addr.ip()).map(Arc::from)));
+            local_info.push(opt_info);
+            async_socks.push(try!(R::socket(sock)));
+        }
+
+        Ok(SSDPStream {
+            socks: async_socks,
+            local_info: local_info,
+            timeout: time.map(R::timer),
+            filter: filter,
+            quirks: quirks,
+            finished: false,
+            buf: vec![0u8; MAX_PCKT_LEN],
+            _message: PhantomData,
+        })
+    }
+}
+
+impl<T, R> Unpin for SSDPStream<T, R> where R: Runtime {}
+
+impl<T, R> Stream for SSDPStream<T, R>
+    where T: FromRawSSDP,
+          R: Runtime
+{
+    type Item = (T, SocketAddr);
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let stream = self.get_mut();
+
+        if let Some(ref mut timeout) = stream.timeout {
+            if R::poll_timer(timeout, cx).is_ready() {
+                trace!("Stream timed out");
+                return Poll::Ready(None);
+            }
+        }
+
+        if stream.finished || stream.socks.is_empty() {
+            return Poll::Ready(None);
+        }
+
+        let mut index = 0;
+        while index < stream.socks.len() {
+            match R::poll_recv_dst(&stream.socks[index], cx, &mut stream.buf[..]) {
+                Poll::Ready(Ok((size, addr, opt_dst))) => {
+                    trace!("Received packet with {} bytes", size);
+
+                    // Invalid messages are dropped, keep reading from the same socket
+                    let bytes = &stream.buf[..size];
+                    let opt_msg = match stream.local_info[index] {
+                        Some((local_addr, ref interface)) => {
+                            receiver::parse_packet(bytes, local_addr, interface, opt_dst, stream.quirks).ok()
+                        }
+                        None => T::raw_ssdp_with_quirks(bytes, stream.quirks).ok(),
+                    };
+
+                    let mut msg = match opt_msg {
+                        Some(n) => n,
+                        None => continue,
+                    };
+
+                    let verdict = match stream.filter {
+                        Some(ref mut filter) => filter(&mut msg, &addr),
+                        None => Verdict::Deliver,
+                    };
+
+                    match verdict {
+                        Verdict::Deliver => return Poll::Ready(Some((msg, addr))),
+                        Verdict::Drop => (),
+                        Verdict::Last => {
+                            stream.finished = true;
+
+                            return Poll::Ready(Some((msg, addr)));
+                        }
+                    }
+                }
+                // Socket errors are not fatal to the stream, but no waker was registered
+                // for the failed socket, so ask to be polled again before moving on
+                Poll::Ready(Err(err)) => {
+                    debug!("Stream failed to receive on socket {}: {}", index, err);
+
+                    cx.waker().wake_by_ref();
+                    index += 1;
+                }
+                Poll::Pending => index += 1,
+            }
+        }
+
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future;
+    use std::net::UdpSocket;
+    use std::pin::Pin;
+    use std::thread;
+    use std::time::Duration;
+
+    use futures_core::Stream;
+
+    use message::SearchResponse;
+    use super::SSDPStream;
+
+    /// Bind a receiving socket that already has a valid response queued up on it.
+    fn queued_response_socks() -> (UdpSocket, UdpSocket) {
+        let recv_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let send_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+        send_sock.send_to(b"HTTP/1.1 200 OK\r\n\r\n", recv_sock.local_addr().unwrap()).unwrap();
+
+        (recv_sock, send_sock)
+    }
+
+    /// Bind a receiving socket with a connection refused error queued up on it,
+    /// which can only be cleared by receiving from the socket.
+    fn refused_sock() -> (UdpSocket, thread::JoinHandle<()>) {
+        let peer_addr = UdpSocket::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let recv_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let recv_addr = recv_sock.local_addr().unwrap();
+
+        recv_sock.connect(peer_addr).unwrap();
+        recv_sock.send(b"").unwrap();
+        thread::sleep(Duration::from_millis(50));
+
+        // Deliver a valid response once the stream has run into the error
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(200));
+
+            let send_sock = UdpSocket::bind(peer_addr).unwrap();
+            send_sock.send_to(b"HTTP/1.1 200 OK\r\n\r\n", recv_addr).unwrap();
+        });
+
+        (recv_sock, handle)
+    }
+
+    #[cfg(feature = "tokio")]
+    mod tokio {
+        use tokio::runtime::{Builder, Runtime};
+
+        use net::runtime::Tokio;
+        use super::*;
+
+        fn runtime() -> Runtime {
+            Builder::new_current_thread().enable_all().build().unwrap()
+        }
+
+        #[test]
+        fn positive_receive_response() {
+            let runtime = runtime();
+            let (recv_sock, send_sock) = queued_response_socks();
+
+            let mut stream = {
+                let _guard = runtime.enter();
+                SSDPStream::<SearchResponse, Tokio>::new(vec![recv_sock], None).unwrap()
+            };
+            let next = runtime.block_on(future::poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)));
+
+            assert_eq!(next.unwrap().1, send_sock.local_addr().unwrap());
+        }
+
+        #[test]
+        fn positive_stream_timeout() {
+            let runtime = runtime();
+            let recv_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+            let mut stream = {
+                let _guard = runtime.enter();
+                SSDPStream::<SearchResponse, Tokio>::new(vec![recv_sock], Some(Duration::from_millis(10)))
+                    .unwrap()
+            };
+
+            assert!(runtime.block_on(future::poll_fn(|cx| Pin::new(&mut stream).poll_next(cx))).is_none());
+        }
+
+        #[test]
+        fn positive_receive_after_socket_error() {
+            let runtime = runtime();
+            let (recv_sock, handle) = refused_sock();
+
+            let mut stream = {
+                let _guard = runtime.enter();
+                SSDPStream::<SearchResponse, Tokio>::new(vec![recv_sock], Some(Duration::from_secs(5)))
+                    .unwrap()
+            };
+            let next = runtime.block_on(future::poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)));
+
+            handle.join().unwrap();
+            assert!(next.is_some());
+        }
+    }
+
+    #[cfg(feature = "async-io")]
+    mod async_io {
+        use async_io::block_on;
+
+        use net::runtime::AsyncIo;
+        use super::*;
+
+        #[test]
+        fn positive_receive_response() {
+            let (recv_sock, send_sock) = queued_response_socks();
+
+            let mut stream = SSDPStream::<SearchResponse, AsyncIo>::new(vec![recv_sock], None).unwrap();
+            let next = block_on(future::poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)));
+
+            assert_eq!(next.unwrap().1, send_sock.local_addr().unwrap());
+        }
+
+        #[test]
+        fn positive_stream_timeout() {
+            let recv_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+            let mut stream = SSDPStream::<SearchResponse, AsyncIo>::new(vec![recv_sock],
+                                                                         Some(Duration::from_millis(10)))
+                .unwrap();
+
+            assert!(block_on(future::poll_fn(|cx| Pin::new(&mut stream).poll_next(cx))).is_none());
+        }
+
+        #[test]
+        fn positive_receive_after_socket_error() {
+            let (recv_sock, handle) = refused_sock();
+
+            let mut stream = SSDPStream::<SearchResponse, AsyncIo>::new(vec![recv_sock],
+                                                                         Some(Duration::from_secs(5)))
+                .unwrap();
+            let next = block_on(future::poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)));
+
+            handle.join().unwrap();
+            assert!(next.is_some());
+        }
+    }
+}
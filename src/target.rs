@@ -0,0 +1,295 @@
+//! Typed representation of the targets that can be searched for.
+
+use std::fmt::{Display, Error, Formatter};
+use std::result::Result;
+use std::str::FromStr;
+
+use {SSDPError, FieldMap};
+use header::ST;
+
+/// Value of the "upnp" field that targets root devices.
+const ROOT_DEVICE_VALUE: &'static str = "rootdevice";
+/// Value of the search target that targets all devices and services.
+const ALL_VALUE: &'static str = "ssdp:all";
+
+/// Kind of URN field for devices.
+const DEVICE_KIND: &'static str = "device";
+/// Kind of URN field for services.
+const SERVICE_KIND: &'static str = "service";
+
+/// Domain name for the standard devices and services defined by the `UPnP` forum.
+const UPNP_DOMAIN: &'static str = "schemas-upnp-org";
+
+/// Enumerates the targets that devices and services respond to searches for.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum SearchTarget {
+    /// All devices and services, "ssdp:all".
+    All,
+    /// Root devices only, "upnp:rootdevice".
+    RootDevice,
+    /// A particular device, "uuid:{uuid}".
+    Uuid(String),
+    /// Any device of a type, "urn:{domain}:device:{device_type}:{version}".
+    DeviceUrn {
+        domain: String,
+        device_type: String,
+        version: u32,
+    },
+    /// Any service of a type, "urn:{domain}:service:{service_type}:{version}".
+    ServiceUrn {
+        domain: String,
+        service_type: String,
+        version: u32,
+    },
+}
+
+impl SearchTarget {
+    /// Target a standard `UPnP` device type, such as "MediaServer".
+    pub fn device<S: Into<String>>(device_type: S, version: u32) -> SearchTarget {
+        SearchTarget::DeviceUrn {
+            domain: UPNP_DOMAIN.to_owned(),
+            device_type: device_type.into(),
+            version: version,
+        }
+    }
+
+    /// Target a standard `UPnP` service type, such as "ContentDirectory".
+    pub fn service<S: Into<String>>(service_type: S, version: u32) -> SearchTarget {
+        SearchTarget::ServiceUrn {
+            domain: UPNP_DOMAIN.to_owned(),
+            service_type: service_type.into(),
+            version: version,
+        }
+    }
+
+    /// Convert an `ST` header into a SearchTarget.
+    ///
+    /// Returns None if the header does not hold a target defined by the standard.
+    pub fn from_st(st: &ST) -> Option<SearchTarget> {
+        match *st {
+            ST::All => Some(SearchTarget::All),
+            ST::Target(FieldMap::UPnP(ref n)) if n == ROOT_DEVICE_VALUE => Some(SearchTarget::RootDevice),
+            ST::Target(FieldMap::UUID(ref n)) => Some(SearchTarget::Uuid(n.clone())),
+            ST::Target(FieldMap::URN(ref n)) => parse_urn(n),
+            ST::Target(_) => None,
+        }
+    }
+
+    /// Check whether a device or service advertising the given target should be
+    /// considered a match for a search for this target.
+    ///
+    /// Versions of standard device and service types are backwards compatible, so
+    /// a search for version 1 is matched by an advertisement of version 2 or 3.
+    pub fn matches(&self, advertised: &SearchTarget) -> bool {
+        match (self, advertised) {
+            (&SearchTarget::All, _) => true,
+            (&SearchTarget::DeviceUrn { ref domain, ref device_type, version },
+             &SearchTarget::DeviceUrn { domain: ref adv_domain,
+                                      device_type: ref adv_type,
+                                      version: adv_version }) => {
+                domain == adv_domain && device_type == adv_type && adv_version >= version
+            }
+            (&SearchTarget::ServiceUrn { ref domain, ref service_type, version },
+             &SearchTarget::ServiceUrn { domain: ref adv_domain,
+                                       service_type: ref adv_type,
+                                       version: adv_version }) => {
+                domain == adv_domain && service_type == adv_type && adv_version >= version
+            }
+            (searched, advertised) => searched == advertised,
+        }
+    }
+
+    /// Convert this SearchTarget into the `FieldMap` it is represented by.
+    ///
+    /// Returns None for `SearchTarget::All` which is not a key value pair.
+    pub fn to_field_map(&self) -> Option<FieldMap> {
+        match *self {
+            SearchTarget::All => None,
+            SearchTarget::RootDevice => Some(FieldMap::upnp(ROOT_DEVICE_VALUE)),
+            SearchTarget::Uuid(ref n) => Some(FieldMap::uuid(n.as_str())),
+            SearchTarget::DeviceUrn { ref domain, ref device_type, version } => {
+                Some(FieldMap::urn(format!("{}:{}:{}:{}", domain, DEVICE_KIND, device_type, version)))
+            }
+            SearchTarget::ServiceUrn { ref domain, ref service_type, version } => {
+                Some(FieldMap::urn(format!("{}:{}:{}:{}", domain, SERVICE_KIND, service_type, version)))
+            }
+        }
+    }
+}
+
+/// Check whether a device or service advertising the advertised target should be
+/// considered a match for a search for the searched target.
+///
+/// Targets that are not defined by the standard only match when they are equal,
+/// or when searching for `ST::All`.
+pub fn target_matches(searched: &ST, advertised: &ST) -> bool {
+    if *searched == ST::All || searched == advertised {
+        return true;
+    }
+
+    match (SearchTarget::from_st(searched), SearchTarget::from_st(advertised)) {
+        (Some(searched), Some(advertised)) => searched.matches(&advertised),
+        _ => false,
+    }
+}
+
+/// Parse the value of a "urn" field into a device or service target.
+fn parse_urn(value: &str) -> Option<SearchTarget> {
+    let parts: Vec<&str> = value.split(':').collect();
+    if parts.len() != 4 || parts.iter().any(|part| part.is_empty()) {
+        return None;
+    }
+
+    let version = match u32::from_str(parts[3]) {
+        Ok(n) => n,
+        Err(_) => return None,
+    };
+
+    match parts[1] {
+        DEVICE_KIND => {
+            Some(SearchTarget::DeviceUrn {
+                domain: parts[0].to_owned(),
+                device_type: parts[2].to_owned(),
+                version: version,
+            })
+        }
+        SERVICE_KIND => {
+            Some(SearchTarget::ServiceUrn {
+                domain: parts[0].to_owned(),
+                service_type: parts[2].to_owned(),
+                version: version,
+            })
+        }
+        _ => None,
+    }
+}
+
+impl Display for SearchTarget {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        match self.to_field_map() {
+            Some(n) => Display::fmt(&n, f),
+            None => f.write_str(ALL_VALUE),
+        }
+    }
+}
+
+impl FromStr for SearchTarget {
+    type Err = SSDPError;
+
+    fn from_str(s: &str) -> Result<SearchTarget, SSDPError> {
+        let opt_target = if s == ALL_VALUE {
+            Some(SearchTarget::All)
+        } else {
+            FieldMap::new(s).and_then(|field| SearchTarget::from_st(&ST::Target(field)))
+        };
+
+        opt_target.ok_or(SSDPError::InvalidHeader("ST", "Not A Standard Search Target"))
+    }
+}
+
+impl From<SearchTarget> for ST {
+    fn from(target: SearchTarget) -> ST {
+        match target.to_field_map() {
+            Some(n) => ST::Target(n),
+            None => ST::All,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use FieldMap;
+    use header::ST;
+    use super::{target_matches, SearchTarget};
+
+    #[test]
+    fn positive_all() {
+        assert_eq!("ssdp:all".parse::<SearchTarget>().unwrap(), SearchTarget::All);
+        assert_eq!(SearchTarget::All.to_string(), "ssdp:all");
+    }
+
+    #[test]
+    fn positive_root_device() {
+        assert_eq!("upnp:rootdevice".parse::<SearchTarget>().unwrap(), SearchTarget::RootDevice);
+        assert_eq!(SearchTarget::RootDevice.to_string(), "upnp:rootdevice");
+    }
+
+    #[test]
+    fn positive_uuid() {
+        let target = "uuid:2fac1234-31f8-11b4-a222-08002b34c003".parse::<SearchTarget>().unwrap();
+
+        assert_eq!(target, SearchTarget::Uuid("2fac1234-31f8-11b4-a222-08002b34c003".to_owned()));
+    }
+
+    #[test]
+    fn positive_device_urn() {
+        let target = "urn:schemas-upnp-org:device:MediaServer:1".parse::<SearchTarget>().unwrap();
+
+        assert_eq!(target, SearchTarget::device("MediaServer", 1));
+        assert_eq!(target.to_string(), "urn:schemas-upnp-org:device:MediaServer:1");
+    }
+
+    #[test]
+    fn positive_service_urn() {
+        let target = "urn:schemas-upnp-org:service:ContentDirectory:2".parse::<SearchTarget>().unwrap();
+
+        assert_eq!(target, SearchTarget::service("ContentDirectory", 2));
+        assert_eq!(target.to_string(), "urn:schemas-upnp-org:service:ContentDirectory:2");
+    }
+
+    #[test]
+    fn positive_into_st() {
+        let st: ST = SearchTarget::device("MediaServer", 1).into();
+
+        assert_eq!(st, ST::Target(FieldMap::urn("schemas-upnp-org:device:MediaServer:1")));
+    }
+
+    #[test]
+    fn positive_matches_newer_version() {
+        let searched = SearchTarget::device("MediaServer", 1);
+
+        assert!(searched.matches(&SearchTarget::device("MediaServer", 1)));
+        assert!(searched.matches(&SearchTarget::device("MediaServer", 3)));
+    }
+
+    #[test]
+    fn positive_all_matches_any() {
+        assert!(SearchTarget::All.matches(&SearchTarget::service("ContentDirectory", 1)));
+        assert!(target_matches(&ST::All, &ST::Target(FieldMap::upnp("somedevice"))));
+    }
+
+    #[test]
+    fn positive_target_matches_st() {
+        let searched = ST::Target(FieldMap::urn("schemas-upnp-org:service:ContentDirectory:1"));
+        let advertised = ST::Target(FieldMap::urn("schemas-upnp-org:service:ContentDirectory:2"));
+
+        assert!(target_matches(&searched, &advertised));
+        assert!(!target_matches(&advertised, &searched));
+    }
+
+    #[test]
+    fn negative_matches_older_version() {
+        assert!(!SearchTarget::device("MediaServer", 2).matches(&SearchTarget::device("MediaServer", 1)));
+    }
+
+    #[test]
+    fn negative_matches_different_type() {
+        assert!(!SearchTarget::device("MediaServer", 1).matches(&SearchTarget::device("MediaRenderer", 1)));
+        assert!(!SearchTarget::device("MediaServer", 1).matches(&SearchTarget::service("MediaServer", 1)));
+    }
+
+    #[test]
+    fn negative_unknown_urn_kind() {
+        assert!("urn:schemas-upnp-org:thing:MediaServer:1".parse::<SearchTarget>().is_err());
+    }
+
+    #[test]
+    fn negative_invalid_version() {
+        assert!("urn:schemas-upnp-org:device:MediaServer:one".parse::<SearchTarget>().is_err());
+    }
+
+    #[test]
+    fn negative_unknown_upnp_value() {
+        assert!(SearchTarget::from_st(&ST::Target(FieldMap::upnp("somedevice"))).is_none());
+    }
+}